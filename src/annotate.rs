@@ -0,0 +1,436 @@
+use std::{
+    fmt,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+use crate::{
+    guid::Guid,
+    object::{
+        RcUnrealObject,
+        builtins::{ClassFlags, FunctionFlags, PropertyFlags},
+        internal::{fname::FName, object_ref::ObjectRef},
+    },
+    profile::StringEncoding,
+    reader::LinRead,
+};
+
+/// A single field read while deserializing an object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldAnnotation {
+    /// Stream position the field was read from
+    pub offset: u64,
+    pub len: u64,
+    pub name: &'static str,
+    /// The decoded value
+    pub value: String,
+}
+
+/// Every annotated field read while deserializing an object, along with the raw bytes they
+/// were read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectAnnotations {
+    pub full_name: String,
+    pub class_name: String,
+    pub serial_offset: u64,
+    /// Bytes read from the object's serial data, starting at `serial_offset`
+    pub data: Vec<u8>,
+    /// Fields in the order they finished being read. Fields which contain other fields (e.g.
+    /// a name read as part of a property tag) are recorded after the fields they contain.
+    pub fields: Vec<FieldAnnotation>,
+}
+
+impl ObjectAnnotations {
+    /// Bytes from `offset` to `offset + len`, or as many of them as were read.
+    fn bytes(&self, offset: u64, len: u64) -> &[u8] {
+        let start = offset.saturating_sub(self.serial_offset) as usize;
+        let end = (start + len as usize).min(self.data.len());
+
+        self.data.get(start..end).unwrap_or_default()
+    }
+}
+
+/// Renders an annotated hex dump. Bytes which no field covers are listed as `<unannotated>`.
+impl fmt::Display for ObjectAnnotations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Bytes shown on a single line before the rest are elided
+        const MAX_BYTES: usize = 16;
+
+        let line = |f: &mut fmt::Formatter<'_>, offset: u64, bytes: &[u8], label: &str| {
+            let mut hex = bytes
+                .iter()
+                .take(MAX_BYTES)
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            if bytes.len() > MAX_BYTES {
+                hex.push_str(" ..");
+            }
+
+            writeln!(f, "  {offset:08X}  {hex:<50} {label}")
+        };
+
+        writeln!(
+            f,
+            "{} ({}) @ {:#X}, {:#X} bytes",
+            self.full_name,
+            self.class_name,
+            self.serial_offset,
+            self.data.len()
+        )?;
+
+        let mut fields = self.fields.iter().collect::<Vec<_>>();
+        // Outer fields are listed before the fields they contain
+        fields.sort_by_key(|field| (field.offset, std::cmp::Reverse(field.len)));
+
+        let mut covered = self.serial_offset;
+        for field in fields {
+            if field.offset > covered {
+                let gap = field.offset - covered;
+                line(f, covered, self.bytes(covered, gap), "<unannotated>")?;
+            }
+
+            let label = format!("{} = {}", field.name, field.value);
+            line(f, field.offset, self.bytes(field.offset, field.len), &label)?;
+
+            covered = covered.max(field.offset + field.len);
+        }
+
+        let end = self.serial_offset + self.data.len() as u64;
+        if end > covered {
+            line(
+                f,
+                covered,
+                self.bytes(covered, end - covered),
+                "<unannotated>",
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How a field's decoded value is shown in its annotation.
+pub(crate) trait AnnotationValue {
+    fn annotation_value(&self) -> String;
+}
+
+macro_rules! debug_annotation_value {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl AnnotationValue for $ty {
+                fn annotation_value(&self) -> String {
+                    format!("{self:?}")
+                }
+            }
+        )*
+    };
+}
+
+debug_annotation_value!(
+    u8,
+    u16,
+    u32,
+    u64,
+    i32,
+    String,
+    Option<String>,
+    Vec<i32>,
+    FName,
+    Vec<FName>,
+    FunctionFlags,
+    PropertyFlags,
+    ClassFlags,
+    Guid,
+);
+
+impl AnnotationValue for Option<RcUnrealObject> {
+    fn annotation_value(&self) -> String {
+        let Some(obj) = self else {
+            return "None".to_owned();
+        };
+
+        // The object may be the one currently being deserialized
+        match obj.try_borrow() {
+            Ok(obj) => format!("{} ({})", obj.base_object().name(), obj.kind().as_str()),
+            Err(_) => "<object in use>".to_owned(),
+        }
+    }
+}
+
+impl AnnotationValue for ObjectRef {
+    fn annotation_value(&self) -> String {
+        format!("object index {}", self.raw_index())
+    }
+}
+
+/// Evaluates `$read` and, if the reader is annotating, records the bytes it consumed as the
+/// field `$name`. The value is shown using [`AnnotationValue`] unless a formatter is given.
+macro_rules! annotated {
+    ($reader:expr, $name:literal, $read:expr) => {
+        $crate::annotate::annotated!($reader, $name, $read, |value| {
+            $crate::annotate::AnnotationValue::annotation_value(value)
+        })
+    };
+    ($reader:expr, $name:literal, $read:expr, $format:expr) => {{
+        tracing::debug!($name);
+
+        let start = $crate::reader::LinRead::annotation_position(&*$reader);
+        let value = $read;
+        if let Some(start) = start {
+            let format: &dyn Fn(&_) -> String = &$format;
+            $crate::reader::LinRead::annotate_field(&mut *$reader, start, $name, format(&value));
+        }
+
+        value
+    }};
+}
+
+pub(crate) use annotated;
+
+/// Wraps a reader and records the fields read from each object's serial data.
+///
+/// Annotation is disabled until [`AnnotatingReader::set_enabled`] is called, in which case
+/// reads are passed straight through.
+pub struct AnnotatingReader<R> {
+    inner: R,
+    pos: u64,
+    enabled: bool,
+    /// Objects being deserialized. Loading an object can load others, so the innermost one
+    /// is last.
+    stack: Vec<ObjectAnnotations>,
+    finished: Vec<ObjectAnnotations>,
+}
+
+impl<R> AnnotatingReader<R> {
+    pub fn new(inner: R) -> Self {
+        AnnotatingReader {
+            inner,
+            pos: 0,
+            enabled: false,
+            stack: Vec::new(),
+            finished: Vec::new(),
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Takes the annotations of every object which finished deserializing, in the order they
+    /// finished.
+    pub fn take_annotations(&mut self) -> Vec<ObjectAnnotations> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Records annotations while `f` runs and returns the ones recorded, without disturbing
+    /// annotations already collected for the caller of [`AnnotatingReader::take_annotations`].
+    pub(crate) fn annotate_scoped<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> (T, Vec<ObjectAnnotations>) {
+        let enabled = std::mem::replace(&mut self.enabled, true);
+        let finished = std::mem::take(&mut self.finished);
+
+        let result = f(self);
+
+        self.enabled = enabled;
+        (result, std::mem::replace(&mut self.finished, finished))
+    }
+
+    /// Copies bytes read at `pos` into the object currently being deserialized.
+    fn record(&mut self, pos: u64, buf: &[u8]) {
+        let Some(object) = self.stack.last_mut() else {
+            return;
+        };
+        let Some(start) = pos.checked_sub(object.serial_offset) else {
+            return;
+        };
+
+        let start = start as usize;
+        let end = start + buf.len();
+        if object.data.len() < end {
+            object.data.resize(end, 0);
+        }
+        object.data[start..end].copy_from_slice(buf);
+    }
+}
+
+impl<R> Read for AnnotatingReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes_read = self.inner.read(buf)?;
+        self.record(self.pos, &buf[..bytes_read]);
+        self.pos += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<R> Seek for AnnotatingReader<R>
+where
+    R: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = self.inner.seek(pos)?;
+        Ok(self.pos)
+    }
+}
+
+impl<R> LinRead for AnnotatingReader<R>
+where
+    R: LinRead,
+{
+    fn begin_unverified(&mut self) {
+        self.inner.begin_unverified();
+    }
+
+    fn end_unverified(&mut self) {
+        self.inner.end_unverified();
+    }
+
+    fn known_len(&mut self) -> io::Result<Option<u64>> {
+        self.inner.known_len()
+    }
+
+    fn read_raw_unverified(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.inner.read_raw_unverified(buf)?;
+        self.record(self.pos, buf);
+        self.pos += buf.len() as u64;
+
+        Ok(())
+    }
+
+    fn supports_rereads(&self) -> bool {
+        self.inner.supports_rereads()
+    }
+
+    fn string_encoding(&self) -> StringEncoding {
+        self.inner.string_encoding()
+    }
+
+    fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.inner.set_string_encoding(encoding);
+    }
+
+    fn annotation_position(&self) -> Option<u64> {
+        (!self.stack.is_empty()).then_some(self.pos)
+    }
+
+    fn annotate_field(&mut self, offset: u64, name: &'static str, value: String) {
+        if let Some(object) = self.stack.last_mut() {
+            object.fields.push(FieldAnnotation {
+                offset,
+                len: self.pos.saturating_sub(offset),
+                name,
+                value,
+            });
+        }
+    }
+
+    fn begin_object_annotation(&mut self, full_name: &str, class_name: &str, serial_offset: u64) {
+        if !self.enabled {
+            return;
+        }
+
+        self.stack.push(ObjectAnnotations {
+            full_name: full_name.to_owned(),
+            class_name: class_name.to_owned(),
+            serial_offset,
+            data: Vec::new(),
+            fields: Vec::new(),
+        });
+    }
+
+    fn end_object_annotation(&mut self) {
+        // Annotation may have been enabled partway through loading an object
+        if let Some(object) = self.stack.pop() {
+            self.finished.push(object);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::{LittleEndian, ReadBytesExt};
+
+    use super::*;
+    use crate::reader::PackageReader;
+
+    #[test]
+    fn records_fields_per_object() {
+        let data = vec![0xAA, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0xBB];
+        let mut reader = AnnotatingReader::new(PackageReader::new(Cursor::new(data)));
+
+        // Nothing is recorded until annotation is enabled
+        reader.begin_object_annotation("Pkg.Ignored", "Object", 0);
+        assert_eq!(reader.annotation_position(), None);
+        reader.end_object_annotation();
+
+        reader.set_enabled(true);
+        reader.seek(SeekFrom::Start(1)).unwrap();
+        reader.begin_object_annotation("Pkg.Outer", "Object", 1);
+
+        let reader = &mut reader;
+        let a = annotated!(reader, "a", reader.read_u16::<LittleEndian>().unwrap());
+
+        // An object loaded partway through gets its own annotations
+        reader.seek(SeekFrom::Start(7)).unwrap();
+        reader.begin_object_annotation("Pkg.Inner", "Object", 7);
+        annotated!(reader, "inner", reader.read_u8().unwrap());
+        reader.end_object_annotation();
+        reader.seek(SeekFrom::Start(3)).unwrap();
+
+        let b = annotated!(
+            reader,
+            "b",
+            reader.read_u32::<LittleEndian>().unwrap(),
+            |b| format!("{b:#X}")
+        );
+        reader.end_object_annotation();
+
+        assert_eq!((a, b), (1, 2));
+
+        let annotations = reader.take_annotations();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].full_name, "Pkg.Inner");
+        assert_eq!(annotations[0].data, [0xBB]);
+
+        let outer = &annotations[1];
+        assert_eq!(outer.data, [0x01, 0x00, 0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            outer.fields,
+            [
+                FieldAnnotation {
+                    offset: 1,
+                    len: 2,
+                    name: "a",
+                    value: "1".to_owned(),
+                },
+                FieldAnnotation {
+                    offset: 3,
+                    len: 4,
+                    name: "b",
+                    value: "0x2".to_owned(),
+                },
+            ]
+        );
+
+        let dump = outer.to_string();
+        assert!(dump.starts_with("Pkg.Outer (Object) @ 0x1, 0x6 bytes\n"));
+        assert!(dump.contains("00000003  02 00 00 00"));
+        assert!(dump.contains("b = 0x2"));
+        assert!(!dump.contains("<unannotated>"));
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, ErrorKind, Read, Seek, SeekFrom},
+};
+
+use byteorder::ByteOrder;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    de::{Block, LinHeader, LinSummary, RawPackage, read_package},
+    profile::{FormatProfile, LinCompression},
+    reader::PackageReader,
+};
+
+/// Size of the chunks [`read_package_async`] fetches the package's tables in.
+const CHUNK_LEN: u64 = 0x10000;
+
+/// Reads a package's tables like [`read_package`], without blocking on IO.
+///
+/// Only the chunks of the file the tables are stored in are read, one at a time as parsing
+/// reaches them, so a large package's object data is never read.
+pub async fn read_package_async<E, R>(reader: &mut R) -> io::Result<RawPackage>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    E: ByteOrder,
+{
+    let len = reader.seek(SeekFrom::End(0)).await?;
+    let mut chunks = FetchedChunks {
+        chunks: BTreeMap::new(),
+        pos: 0,
+        len,
+        missing: None,
+    };
+
+    // Parsing starts over each time it reaches a chunk which hasn't been fetched yet. The
+    // tables are small next to the IO, so this costs little.
+    loop {
+        chunks.pos = 0;
+        let result = read_package::<E, _>(&mut PackageReader::new(&mut chunks));
+
+        let Some(chunk) = chunks.missing.take() else {
+            return result;
+        };
+        chunks.fetch(reader, chunk).await?;
+    }
+}
+
+/// Chunks of a file which have been read asynchronously, served to the synchronous parsers.
+/// Reading data which hasn't been fetched fails with [`ErrorKind::WouldBlock`] and records
+/// the chunk which is needed.
+struct FetchedChunks {
+    /// Keyed by chunk index
+    chunks: BTreeMap<u64, Vec<u8>>,
+    pos: u64,
+    len: u64,
+    missing: Option<u64>,
+}
+
+impl FetchedChunks {
+    async fn fetch<R>(&mut self, reader: &mut R, chunk: u64) -> io::Result<()>
+    where
+        R: AsyncRead + AsyncSeek + Unpin,
+    {
+        let start = chunk * CHUNK_LEN;
+        let mut data = vec![0; (self.len - start).min(CHUNK_LEN) as usize];
+        reader.seek(SeekFrom::Start(start)).await?;
+        reader.read_exact(&mut data).await?;
+        self.chunks.insert(chunk, data);
+
+        Ok(())
+    }
+}
+
+impl Read for FetchedChunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let chunk = self.pos / CHUNK_LEN;
+        let Some(data) = self.chunks.get(&chunk) else {
+            self.missing = Some(chunk);
+            return Err(io::Error::new(
+                ErrorKind::WouldBlock,
+                format!("chunk {chunk:#X} hasn't been fetched"),
+            ));
+        };
+
+        let offset = (self.pos % CHUNK_LEN) as usize;
+        let read = buf.len().min(data.len() - offset);
+        buf[..read].copy_from_slice(&data[offset..offset + read]);
+        self.pos += read as u64;
+
+        Ok(read)
+    }
+}
+
+impl Seek for FetchedChunks {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+
+        self.pos = pos.ok_or_else(|| {
+            io::Error::new(ErrorKind::InvalidInput, "seek to a negative position")
+        })?;
+
+        Ok(self.pos)
+    }
+}
+
+/// Decompresses a linear file whose block header layout is described by `profile` into
+/// `writer`, like [`decompress_linear_file_to_with_profile`], without blocking on IO.
+///
+/// One block is read, decompressed and written at a time, so a slow `writer` holds back
+/// reading rather than data piling up in memory.
+///
+/// [`decompress_linear_file_to_with_profile`]: crate::de::decompress_linear_file_to_with_profile
+pub async fn decompress_linear_file_async<E, R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    profile: &FormatProfile,
+) -> io::Result<LinSummary>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+    E: ByteOrder,
+{
+    if profile.quirks.lin_compression == LinCompression::None {
+        let uncompressed_len = tokio::io::copy(reader, writer).await?;
+
+        return Ok(LinSummary {
+            uncompressed_len,
+            ..Default::default()
+        });
+    }
+
+    let mut values = Vec::with_capacity(profile.quirks.lin_header_blocks as usize);
+    for _ in 0..profile.quirks.lin_header_blocks {
+        values.push(read_block_async::<E, _>(reader).await?.header_value()?);
+    }
+    let mut summary = LinSummary {
+        header: LinHeader::from_header_values(values),
+        ..Default::default()
+    };
+
+    let mut data = Vec::new();
+    loop {
+        let block = match read_block_async::<E, _>(reader).await {
+            Ok(block) => block,
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        };
+
+        data.clear();
+        let block_len = block.inflate_to(summary.uncompressed_len, &mut data)?;
+        writer.write_all(&data).await?;
+        summary.add_block(&block, block_len);
+    }
+    writer.flush().await?;
+
+    summary.check_uncompressed_len(profile)?;
+
+    Ok(summary)
+}
+
+async fn read_block_async<E, R>(reader: &mut R) -> io::Result<Block>
+where
+    R: AsyncRead + Unpin,
+    E: ByteOrder,
+{
+    let mut sizes = [0; 8];
+    reader.read_exact(&mut sizes).await?;
+    let uncompressed_len = E::read_u32(&sizes);
+    let compressed_len = E::read_u32(&sizes[4..]);
+
+    let mut compressed_data = vec![0; compressed_len as usize];
+    reader.read_exact(&mut compressed_data).await?;
+
+    Ok(Block {
+        uncompressed_len,
+        compressed_len,
+        compressed_data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Write},
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use byteorder::LittleEndian;
+    use flate2::{Compression, write::ZlibEncoder};
+
+    use super::*;
+    use crate::{
+        de::{decompress_linear_file_with_profile, read_package},
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    /// Runs `future`, whose IO is all in memory and so never has to wait.
+    fn run<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("in-memory IO shouldn't be pending"),
+        }
+    }
+
+    #[test]
+    fn reads_package_tables() {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        // A name long enough that the tables span several chunks
+        builder.name(&"x".repeat(CHUNK_LEN as usize));
+        let data = DataBuilder::field(0, 0).string("1").build();
+        builder.export(const_class, 0, "One", data);
+        let data = builder.build();
+
+        let package = run(read_package_async::<LittleEndian, _>(&mut Cursor::new(
+            &data,
+        )))
+        .unwrap();
+        let expected =
+            read_package::<LittleEndian, _>(&mut PackageReader::new(Cursor::new(&data))).unwrap();
+        assert_eq!(package.names, expected.names);
+        assert_eq!(package.imports, expected.imports);
+        assert_eq!(package.exports, expected.exports);
+
+        let err = run(read_package_async::<LittleEndian, _>(&mut Cursor::new(
+            &data[..data.len() / 2],
+        )))
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn decompresses_linear_files() {
+        let block = |uncompressed_len: u32, data: &[u8]| {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            let compressed = encoder.finish().unwrap();
+
+            let mut block = uncompressed_len.to_le_bytes().to_vec();
+            block.extend((compressed.len() as u32).to_le_bytes());
+            block.extend(compressed);
+            block
+        };
+
+        let mut file = Vec::new();
+        for value in [6u32, 0x20, 1, 2] {
+            file.extend(block(4, &value.to_le_bytes()));
+        }
+        file.extend(block(4, b"abcd"));
+        file.extend(block(2, b"ef"));
+
+        let profile = FormatProfile::splinter_cell();
+        let mut data = Vec::new();
+        let summary = run(decompress_linear_file_async::<LittleEndian, _, _>(
+            &mut file.as_slice(),
+            &mut data,
+            &profile,
+        ))
+        .unwrap();
+        assert_eq!(data, b"abcdef");
+        assert_eq!(
+            data,
+            decompress_linear_file_with_profile::<LittleEndian, _>(&mut file.as_slice(), &profile)
+                .unwrap()
+        );
+        assert_eq!(summary.header.uncompressed_size, 6);
+        assert_eq!((summary.block_count, summary.uncompressed_len), (2, 6));
+
+        // A block which decompresses to less than it declares
+        file.extend(block(3, b"g"));
+        let err = run(decompress_linear_file_async::<LittleEndian, _, _>(
+            &mut file.as_slice(),
+            &mut Vec::new(),
+            &profile,
+        ))
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
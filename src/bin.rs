@@ -1,119 +1,924 @@
 use std::{
-    io::{BufReader, BufWriter, Cursor},
-    path::PathBuf,
+    fmt::Write as _,
+    io::{BufReader, BufWriter, Cursor, Write as _},
+    path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use byteorder::LittleEndian;
-use clap::Parser;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use clap::{ArgAction, Args as ClapArgs, Parser, Subcommand, ValueEnum};
 use color_eyre::{
     Result,
     eyre::{Context, eyre},
 };
-use tracing::Level;
-use tracing_subscriber::fmt;
+use tracing::{Event, Level, Subscriber, debug, field::Field, info};
+use tracing_subscriber::{
+    field::Visit,
+    fmt::{
+        self, FmtContext, FormatEvent, FormatFields, FormattedFields, format::Writer,
+        writer::BoxMakeWriter,
+    },
+    registry::LookupSpan,
+};
 use unrealin::{
-    ExportedData,
+    Endianness, ExportedData, FileKind, FormatProfile, LocalizationTable, Manifest, Package,
     de::{self, LinearFileDecoder},
 };
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Where to extract files to. By default this will be the basename of the input file.
     /// For example, `common.lin` will extract to `common/`
     #[arg(short, long)]
     output: Option<PathBuf>,
 
     /// File to extract
-    common_lin: PathBuf,
+    #[arg(required = true)]
+    common_lin: Option<PathBuf>,
 
-    map_lin: PathBuf,
+    #[arg(required = true)]
+    map_lin: Option<PathBuf>,
+
+    /// Write a JSON report of how long each object took to load, and any problems found
+    /// loading it, to this file
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Profile file (JSON) describing the game's package format. By default the format is
+    /// detected from each package's version
+    #[arg(long, global = true)]
+    profile: Option<PathBuf>,
+
+    #[command(flatten)]
+    log: LogArgs,
 }
-fn main() -> Result<()> {
-    let mut args = Args::parse();
 
-    let subscriber = fmt().pretty().with_max_level(Level::TRACE).finish();
-    tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
+#[derive(ClapArgs, Debug)]
+struct LogArgs {
+    /// Log more detail. Repeat for more: -v logs info, -vv debug, and -vvv everything
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
 
-    let mut common_file = std::fs::File::open(&args.common_lin)
-        .wrap_err_with(|| format!("failed to open {:?}", &args.common_lin))?;
-    let mut common_mmap = unsafe { memmap2::Mmap::map(&common_file)? };
-    let mut raw_common_file = &common_mmap[..];
+    /// Only log errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
 
-    let mut map_file = std::fs::File::open(&args.map_lin)
-        .wrap_err_with(|| format!("failed to open {:?}", &args.map_lin))?;
-    let mut map_mmap = unsafe { memmap2::Mmap::map(&map_file)? };
-    let mut raw_map_file = &map_mmap[..];
+    /// How log lines are formatted
+    #[arg(long, value_enum, default_value_t = LogFormat::Pretty, global = true)]
+    log_format: LogFormat,
 
-    let mut output_dir = if let Some(output_dir) = args.output.take() {
-        output_dir
-    } else {
-        let Some(parent) = args.common_lin.parent() else {
-            return Err(eyre!("Input path {:?} has no parent", args.common_lin));
-        };
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+}
 
-        let Some(stem) = args.common_lin.file_stem() else {
-            return Err(eyre!("Input path {:?} has no file stem", args.common_lin));
-        };
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+enum LogFormat {
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Extract UnrealScript sources from a package's TextBuffer exports
+    Scripts {
+        /// Package file to read scripts from (.u)
+        file: PathBuf,
+
+        /// Directory to write `.uc` files to. By default this will be the basename of
+        /// the input file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write declaration stubs for the classes a package exports, recovered from their
+    /// compiled variables and functions
+    Headers {
+        /// Package file to read classes from (.u)
+        file: PathBuf,
+
+        /// Directory to write `.uc` files to. By default this will be the basename of
+        /// the input file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write each class's declaration, including its within class and config name, as
+        /// a `.json` file instead of a `.uc` stub
+        #[arg(long)]
+        json: bool,
+    },
+    /// List a package's exports along with their classes
+    Dump {
+        /// Package file to list exports from
+        file: PathBuf,
+
+        /// Only list exports matching this pattern, e.g. `Engine.*`, `*Weapon*`, or
+        /// `class=Function`
+        #[arg(short, long)]
+        filter: Option<String>,
+
+        /// Load each listed export and print an annotated hex dump of its data
+        #[arg(short, long)]
+        annotate: bool,
+    },
+    /// List what refers to an object in a package, such as its children, subclasses and
+    /// instances
+    Xref {
+        /// Package file to search
+        file: PathBuf,
+
+        /// Full name of the object, e.g. `Engine.Actor`
+        object: String,
+    },
+    /// Check a package's name, import, and export tables for corruption
+    Validate {
+        /// Package files to check
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Summarize the classes and sizes of a package's exports
+    Stats {
+        /// Package files to summarize
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Print the statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Hash each export of packages and each member of linear files, for checking which
+    /// exports a mod changed
+    Manifest {
+        /// Package and linear files to hash
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// JSON file to write the manifest to. By default it's written to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write each export's raw serialized data to its own file
+    Carve {
+        /// Package file to carve exports from
+        file: PathBuf,
+
+        /// Directory to write `<index>_<name>_<class>.bin` files to. By default this will be
+        /// the basename of the input file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a package's translatable strings to a PO file
+    Strings {
+        /// Package file to read strings from
+        file: PathBuf,
+
+        /// PO file to write, or the listing of constants with `--script`. By default it's
+        /// written to stdout.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// List the constants used by each script instead, such as strings, names and the
+        /// objects they reference
+        #[arg(long)]
+        script: bool,
+    },
+    /// Apply the translations in a PO file and write the localized package
+    Localize {
+        /// Package file to localize
+        file: PathBuf,
 
-        parent.join(stem)
+        /// PO file holding the translations, as written by `strings`
+        strings: PathBuf,
+
+        /// Where to write the localized package
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Decode linear files, checking every read against a recorded trace
+    Verify {
+        /// Linear files to decode, in the order the trace opened them (e.g. common.lin
+        /// followed by the map's .lin)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Trace of the reads made by the game while loading the files
+        #[arg(long)]
+        trace: PathBuf,
+
+        /// Number of divergences from the trace to print
+        #[arg(long, default_value_t = 10)]
+        max_divergences: usize,
+    },
+}
+
+/// Returns `output` if provided, otherwise the input path without its extension.
+fn output_dir_for(input: &Path, output: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(output_dir) = output {
+        return Ok(output_dir);
+    }
+
+    let Some(parent) = input.parent() else {
+        return Err(eyre!("Input path {:?} has no parent", input));
     };
 
+    let Some(stem) = input.file_stem() else {
+        return Err(eyre!("Input path {:?} has no file stem", input));
+    };
+
+    Ok(parent.join(stem))
+}
+
+/// Opens a package file, reading it with `profile` if one was given.
+fn open_package<E: ByteOrder>(
+    file: &Path,
+    profile: Option<&FormatProfile>,
+) -> Result<Package<E, BufReader<std::fs::File>>> {
+    let package = match profile {
+        Some(profile) => Package::open_file_with_profile(file, profile.clone()),
+        None => Package::open_file(file),
+    };
+
+    package.wrap_err_with(|| format!("failed to read package {file:?}"))
+}
+
+fn write_headers<E: ByteOrder>(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    json: bool,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let output_dir = output_dir_for(&file, output)?;
+
+    let mut package = open_package::<E>(&file, profile)?;
+    let headers = package
+        .class_headers()
+        .wrap_err("failed to generate class headers")?;
+
     std::fs::create_dir_all(&output_dir)
         .wrap_err_with(|| format!("failed to create output dir {:?}", &output_dir))?;
 
-    let output_path = output_dir.join("complete.bin");
-    let mut out_file = BufWriter::new(
-        std::fs::File::create(&output_path)
-            .wrap_err_with(|| format!("failed to create output file {output_path:?}"))?,
+    for header in headers {
+        let (extension, contents) = if json {
+            ("json", serde_json::to_string_pretty(&header)?)
+        } else {
+            ("uc", header.to_string())
+        };
+        let out_path = output_dir.join(format!("{}.{extension}", header.name));
+        println!("Writing {:?}", out_path);
+        std::fs::write(&out_path, contents)
+            .wrap_err_with(|| format!("failed to write {out_path:?}"))?;
+    }
+
+    Ok(())
+}
+
+fn extract_scripts<E: ByteOrder>(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let output_dir = output_dir_for(&file, output)?;
+
+    let mut package = open_package::<E>(&file, profile)?;
+    let sources = package
+        .extract_script_sources()
+        .wrap_err("failed to extract script sources")?;
+
+    std::fs::create_dir_all(&output_dir)
+        .wrap_err_with(|| format!("failed to create output dir {:?}", &output_dir))?;
+
+    for source in sources {
+        let out_path = output_dir.join(source.file_name());
+        println!("Writing {:?}", out_path);
+        std::fs::write(&out_path, source.text)
+            .wrap_err_with(|| format!("failed to write {out_path:?}"))?;
+    }
+
+    Ok(())
+}
+
+fn dump_exports<E: ByteOrder>(
+    file: PathBuf,
+    filter: Option<String>,
+    annotate: bool,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let mut package = open_package::<E>(&file, profile)?;
+    let contents = package.contents();
+    package.set_annotate_reads(annotate);
+
+    for index in contents.find_exports(filter.as_deref().unwrap_or("*")) {
+        let full_name = contents.export_full_name(index);
+        println!(
+            "{:>5} {} ({})",
+            index,
+            full_name,
+            contents.export_class_name(index)
+        );
+
+        if !annotate {
+            continue;
+        }
+
+        // Drop the package name to get the export's path within the package
+        let path = full_name
+            .split_once('.')
+            .map_or(full_name.as_str(), |(_, path)| path);
+        package
+            .load_object(path)
+            .wrap_err_with(|| format!("failed to load {full_name}"))?;
+
+        for annotations in package.take_annotations() {
+            println!("{annotations}");
+        }
+    }
+
+    Ok(())
+}
+
+fn print_referencers<E: ByteOrder>(
+    file: PathBuf,
+    object: String,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let package = open_package::<E>(&file, profile)?;
+
+    let referencers = package.referencers_of(&object);
+    if referencers.is_empty() {
+        println!("Nothing in {} refers to {object}", file.display());
+    }
+    for referencer in referencers {
+        println!("{} ({:?})", referencer.from, referencer.kind);
+    }
+
+    Ok(())
+}
+
+fn validate_packages<E: ByteOrder>(
+    files: Vec<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let mut found_problems = false;
+
+    for file in files {
+        let mut package = open_package::<E>(&file, profile)?;
+        let findings = package
+            .validate()
+            .wrap_err_with(|| format!("failed to validate {:?}", &file))?;
+
+        if findings.is_empty() {
+            println!("{}: OK", file.display());
+            continue;
+        }
+
+        found_problems = true;
+        for finding in findings {
+            println!("{}: {finding}", file.display());
+        }
+    }
+
+    if found_problems {
+        return Err(eyre!("one or more packages failed validation"));
+    }
+
+    Ok(())
+}
+
+fn print_stats<E: ByteOrder>(files: Vec<PathBuf>, json: bool) -> Result<()> {
+    // Only the tables are needed, so every file can be read at once
+    let packages = de::open_all::<E, _>(&files);
+
+    let mut all_stats = Vec::with_capacity(files.len());
+    for (file, package) in files.into_iter().zip(packages) {
+        let package = package.wrap_err_with(|| format!("failed to read package {file:?}"))?;
+        all_stats.push((file, package.stats()));
+    }
+
+    if json {
+        let by_file = all_stats
+            .iter()
+            .map(|(file, stats)| (file.display().to_string(), stats))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        println!("{}", serde_json::to_string_pretty(&by_file)?);
+
+        return Ok(());
+    }
+
+    for (file, stats) in all_stats {
+        println!("{}:\n{stats}", file.display());
+    }
+
+    Ok(())
+}
+
+fn write_manifest(
+    files: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    // Each file's kind and byte order are detected on their own
+    let manifest = Manifest::from_paths(&files, profile).wrap_err("failed to hash files")?;
+    let json = serde_json::to_string_pretty(&manifest)?;
+
+    match output {
+        Some(output) => {
+            std::fs::write(&output, json).wrap_err_with(|| format!("failed to write {output:?}"))?
+        }
+        None => println!("{json}"),
+    }
+
+    Ok(())
+}
+
+fn carve_exports<E: ByteOrder>(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let output_dir = output_dir_for(&file, output)?;
+
+    let mut package = open_package::<E>(&file, profile)?;
+    let written = package
+        .carve_exports(&output_dir)
+        .wrap_err_with(|| format!("failed to carve exports into {output_dir:?}"))?;
+
+    println!("Wrote {} exports to {:?}", written.len(), output_dir);
+
+    Ok(())
+}
+
+fn export_strings<E: ByteOrder>(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let mut package = open_package::<E>(&file, profile)?;
+    let table = package
+        .localization_table()
+        .wrap_err("failed to collect strings")?;
+
+    match output {
+        Some(output) => {
+            let out = std::fs::File::create(&output)
+                .wrap_err_with(|| format!("failed to create {output:?}"))?;
+            table.write_po(BufWriter::new(out))?;
+        }
+        None => table.write_po(std::io::stdout().lock())?,
+    }
+
+    Ok(())
+}
+
+fn list_script_constants<E: ByteOrder>(
+    file: PathBuf,
+    output: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let mut package = open_package::<E>(&file, profile)?;
+    let scripts = package
+        .script_constants()
+        .wrap_err("failed to collect script constants")?;
+
+    let mut listing = String::new();
+    for script in scripts {
+        writeln!(listing, "{}", script.owner)?;
+        for constant in script.constants {
+            writeln!(listing, "  {:#06X} {}", constant.offset, constant.value)?;
+        }
+    }
+
+    match output {
+        Some(output) => std::fs::write(&output, listing)
+            .wrap_err_with(|| format!("failed to write {output:?}"))?,
+        None => print!("{listing}"),
+    }
+
+    Ok(())
+}
+
+fn localize_package<E: ByteOrder>(
+    file: PathBuf,
+    strings: PathBuf,
+    output: PathBuf,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let po = std::fs::read_to_string(&strings)
+        .wrap_err_with(|| format!("failed to read {strings:?}"))?;
+    let table =
+        LocalizationTable::read_po(&po).wrap_err_with(|| format!("failed to parse {strings:?}"))?;
+
+    let mut package = open_package::<E>(&file, profile)?;
+    let mut out = BufWriter::new(
+        std::fs::File::create(&output).wrap_err_with(|| format!("failed to create {output:?}"))?,
     );
+    package
+        .write_localized(&table, &mut out)
+        .wrap_err("failed to write localized package")?;
+    out.flush()?;
 
-    let common_lin_data = if args
-        .common_lin
-        .extension()
-        .as_ref()
-        .map(|ext| ext.to_str().unwrap() == "lin")
-        .unwrap_or_default()
-    {
-        unrealin::de::decompress_linear_file::<LittleEndian, _>(&mut raw_common_file)?
-    } else {
-        raw_common_file.to_vec()
+    info!(
+        translated = table.translated().count(),
+        "Wrote {}",
+        output.display()
+    );
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    init_logging(&args.log)?;
+
+    let profile = args
+        .profile
+        .as_deref()
+        .map(FormatProfile::from_file)
+        .transpose()
+        .wrap_err("failed to load profile")?;
+
+    // Without a profile, the byte order is detected from the input
+    let endianness = match &profile {
+        Some(profile) => Some(profile.endianness),
+        None => detect_endianness(&args)?,
     };
 
-    let map_lin_data = if args
-        .common_lin
-        .extension()
-        .as_ref()
-        .map(|ext| ext.to_str().unwrap() == "lin")
-        .unwrap_or_default()
-    {
-        unrealin::de::decompress_linear_file::<LittleEndian, _>(&mut raw_map_file)?
-    } else {
-        raw_common_file.to_vec()
+    match endianness {
+        Some(Endianness::Big) => run::<BigEndian>(args, profile.as_ref()),
+        Some(Endianness::Little) | None => run::<LittleEndian>(args, profile.as_ref()),
+    }
+}
+
+/// Byte order of the first input file, if its kind is recognized.
+fn detect_endianness(args: &Args) -> Result<Option<Endianness>> {
+    let input = match &args.command {
+        Some(
+            Command::Scripts { file, .. }
+            | Command::Headers { file, .. }
+            | Command::Dump { file, .. }
+            | Command::Xref { file, .. }
+            | Command::Carve { file, .. }
+            | Command::Strings { file, .. }
+            | Command::Localize { file, .. },
+        ) => Some(file),
+        Some(
+            Command::Validate { files }
+            | Command::Stats { files, .. }
+            | Command::Verify { files, .. },
+        ) => files.first(),
+        // Detected for each file as it's hashed
+        Some(Command::Manifest { .. }) => None,
+        None => args.common_lin.as_ref(),
     };
+    let Some(input) = input else {
+        return Ok(None);
+    };
+
+    let mut file = std::fs::File::open(input)
+        .wrap_err_with(|| format!("failed to open {}", input.display()))?;
+    let kind = unrealin::detect(&mut file)
+        .wrap_err_with(|| format!("failed to read {}", input.display()))?;
+    debug!("{} is {kind:?}", input.display());
+
+    Ok(kind.endianness())
+}
+
+fn run<E: ByteOrder>(args: Args, profile: Option<&FormatProfile>) -> Result<()> {
+    match args.command {
+        Some(Command::Scripts { file, output }) => extract_scripts::<E>(file, output, profile),
+        Some(Command::Headers { file, output, json }) => {
+            write_headers::<E>(file, output, json, profile)
+        }
+        Some(Command::Dump {
+            file,
+            filter,
+            annotate,
+        }) => dump_exports::<E>(file, filter, annotate, profile),
+        Some(Command::Xref { file, object }) => print_referencers::<E>(file, object, profile),
+        Some(Command::Validate { files }) => validate_packages::<E>(files, profile),
+        Some(Command::Stats { files, json }) => print_stats::<E>(files, json),
+        Some(Command::Manifest { files, output }) => write_manifest(files, output, profile),
+        Some(Command::Carve { file, output }) => carve_exports::<E>(file, output, profile),
+        Some(Command::Strings {
+            file,
+            output,
+            script: false,
+        }) => export_strings::<E>(file, output, profile),
+        Some(Command::Strings {
+            file,
+            output,
+            script: true,
+        }) => list_script_constants::<E>(file, output, profile),
+        Some(Command::Localize {
+            file,
+            strings,
+            output,
+        }) => localize_package::<E>(file, strings, output, profile),
+        Some(Command::Verify {
+            files,
+            trace,
+            max_divergences,
+        }) => verify_lin::<E>(files, trace, max_divergences, profile),
+        None => decode_lin::<E>(
+            args.common_lin.expect("common_lin is required"),
+            args.map_lin.expect("map_lin is required"),
+            args.output,
+            args.report,
+            profile,
+        ),
+    }
+}
+
+fn init_logging(args: &LogArgs) -> Result<()> {
+    let level = match (args.quiet, args.verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+
+    let writer = match &args.log_file {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .wrap_err_with(|| format!("failed to create log file {path:?}"))?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
+    let builder = fmt::fmt()
+        .with_max_level(level)
+        .with_writer(writer)
+        .with_ansi(args.log_file.is_none());
+
+    let result = match args.log_format {
+        LogFormat::Pretty => tracing::subscriber::set_global_default(builder.pretty().finish()),
+        LogFormat::Json => {
+            tracing::subscriber::set_global_default(builder.event_format(JsonFormat).finish())
+        }
+    };
+    result.wrap_err("failed to install log subscriber")
+}
+
+/// Formats each event as a single line of JSON holding its level, target, fields, and the
+/// spans it occurred in.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        let metadata = event.metadata();
+
+        let mut fields = JsonFields::default();
+        event.record(&mut fields);
+
+        let spans = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let extensions = span.extensions();
+                let fields = extensions
+                    .get::<FormattedFields<N>>()
+                    .map(|fields| fields.fields.clone())
+                    .unwrap_or_default();
+
+                serde_json::json!({ "name": span.name(), "fields": fields })
+            })
+            .collect::<Vec<_>>();
 
-    std::io::copy(&mut common_lin_data.as_slice(), &mut out_file)
-        .wrap_err_with(|| format!("failed to copy data to output file {output_path:?}"))?;
+        let line = serde_json::json!({
+            "level": metadata.level().as_str(),
+            "target": metadata.target(),
+            "fields": fields.0,
+            "spans": spans,
+        });
 
+        writeln!(writer, "{line}")
+    }
+}
+
+#[derive(Default)]
+struct JsonFields(serde_json::Map<String, serde_json::Value>);
+
+impl Visit for JsonFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let mut formatted = String::new();
+        let _ = write!(formatted, "{value:?}");
+        self.0.insert(field.name().to_owned(), formatted.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_owned(), value.into());
+    }
+}
+
+/// Reads the export metadata and IO trace recorded from the game.
+fn read_trace(path: &Path) -> Result<ExportedData> {
     let reader = BufReader::new(
-        std::fs::File::open("/var/tmp/reads.json").expect("failed to open reads file"),
+        std::fs::File::open(path).wrap_err_with(|| format!("failed to open trace {path:?}"))?,
     );
 
-    let mut metadata: ExportedData = serde_json::from_reader(reader).expect("failed to parse read");
+    let mut metadata =
+        ExportedData::from_json_reader(reader).wrap_err("failed to parse export metadata")?;
     metadata.file_ptr_order.reverse();
     metadata
         .file_reads
         .iter_mut()
         .for_each(|(_k, v)| v.reverse());
 
-    let mut lin_decoder = LinearFileDecoder::<LittleEndian, _>::new_checked(
+    Ok(metadata)
+}
+
+fn verify_lin<E: ByteOrder>(
+    files: Vec<PathBuf>,
+    trace: PathBuf,
+    max_divergences: usize,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let default_profile = FormatProfile::splinter_cell();
+    let lin_profile = profile.unwrap_or(&default_profile);
+
+    let sources = files
+        .iter()
+        .map(|file| {
+            let data = std::fs::read(file).wrap_err_with(|| format!("failed to read {file:?}"))?;
+            let data = de::read_linear_file::<E, _>(&mut Cursor::new(data), lin_profile)
+                .wrap_err_with(|| format!("failed to read {file:?}"))?;
+
+            Ok(Cursor::new(data))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let metadata = read_trace(&trace)?;
+
+    let (mut lin_decoder, verification) =
+        LinearFileDecoder::<E, _>::new_verifying(sources, metadata);
+    if let Some(profile) = profile {
+        lin_decoder.set_format_profile(profile.clone());
+    }
+    let result = lin_decoder.decode_linear_file();
+
+    let divergences = verification.divergences();
+    let failed = result.is_err() || !verification.passed();
+    println!("{}", if failed { "FAIL" } else { "PASS" });
+    if let Err(e) = &result {
+        println!("Decoding stopped: {e}");
+    }
+    println!("{} divergences from the trace", divergences.len());
+    for divergence in divergences.iter().take(max_divergences) {
+        println!("  {divergence}");
+    }
+    if divergences.len() > max_divergences {
+        println!("  ... and {} more", divergences.len() - max_divergences);
+    }
+    println!(
+        "{} recorded operations were never made",
+        verification.remaining_ops()
+    );
+
+    if failed {
+        return Err(eyre!("decode diverged from trace {trace:?}"));
+    }
+
+    Ok(())
+}
+
+fn decode_lin<E: ByteOrder>(
+    common_lin: PathBuf,
+    map_lin: PathBuf,
+    output: Option<PathBuf>,
+    report: Option<PathBuf>,
+    profile: Option<&FormatProfile>,
+) -> Result<()> {
+    let default_profile = FormatProfile::splinter_cell();
+    let lin_profile = profile.unwrap_or(&default_profile);
+
+    let common_file = std::fs::File::open(&common_lin)
+        .wrap_err_with(|| format!("failed to open {:?}", &common_lin))?;
+    let common_mmap = unsafe { memmap2::Mmap::map(&common_file)? };
+    let mut raw_common_file = &common_mmap[..];
+
+    let map_file =
+        std::fs::File::open(&map_lin).wrap_err_with(|| format!("failed to open {:?}", &map_lin))?;
+    let map_mmap = unsafe { memmap2::Mmap::map(&map_file)? };
+    let raw_map_file = &map_mmap[..];
+
+    let output_dir = output_dir_for(&common_lin, output)?;
+
+    std::fs::create_dir_all(&output_dir)
+        .wrap_err_with(|| format!("failed to create output dir {:?}", &output_dir))?;
+
+    let output_path = output_dir.join("complete.bin");
+    let mut out_file = BufWriter::new(
+        std::fs::File::create(&output_path)
+            .wrap_err_with(|| format!("failed to create output file {output_path:?}"))?,
+    );
+
+    let common_kind = unrealin::detect(&mut Cursor::new(raw_common_file))?;
+    debug!("{common_lin:?} is {common_kind:?}");
+    let common_lin_data = if matches!(common_kind, FileKind::CompressedLinear(_)) {
+        let summary = unrealin::de::decompress_linear_file_to_with_profile::<E, _, _>(
+            &mut raw_common_file,
+            &mut out_file,
+            lin_profile,
+        )
+        .wrap_err_with(|| format!("failed to decompress {common_lin:?}"))?;
+        info!("Decompressed {common_lin:?}: {summary:#X?}");
+
+        out_file
+            .flush()
+            .wrap_err_with(|| format!("failed to write output file {output_path:?}"))?;
+        std::fs::read(&output_path)
+            .wrap_err_with(|| format!("failed to read back output file {output_path:?}"))?
+    } else {
+        out_file
+            .write_all(raw_common_file)
+            .wrap_err_with(|| format!("failed to copy data to output file {output_path:?}"))?;
+
+        raw_common_file.to_vec()
+    };
+
+    let map_lin_data = de::read_linear_file::<E, _>(&mut Cursor::new(raw_map_file), lin_profile)
+        .wrap_err_with(|| format!("failed to read {map_lin:?}"))?;
+
+    let metadata = read_trace(Path::new("/var/tmp/reads.json"))?;
+
+    let mut lin_decoder = LinearFileDecoder::<E, _>::new_checked(
         vec![Cursor::new(common_lin_data), Cursor::new(map_lin_data)],
         metadata,
     );
-    lin_decoder
+    if let Some(profile) = profile {
+        lin_decoder.set_format_profile(profile.clone());
+    }
+    lin_decoder.set_record_load_report(report.is_some());
+    let packages = lin_decoder
         .decode_linear_file()
         .expect("failed to decode lienar file");
 
+    for package in &packages {
+        println!(
+            "{}: {} exports, {} objects loaded",
+            package.name(),
+            package.package().exports.len(),
+            package.objects().len()
+        );
+    }
+
+    if let Some(report_path) = report {
+        let report = lin_decoder
+            .load_report()
+            .expect("load report recording was enabled");
+        std::fs::write(&report_path, report.to_json()?)
+            .wrap_err_with(|| format!("failed to write load report {report_path:?}"))?;
+
+        for object in report.slowest(5) {
+            info!(
+                elapsed_us = object.elapsed_us,
+                bytes = object.bytes_read,
+                "Slow load: {}",
+                object.full_name
+            );
+        }
+        info!(
+            warnings = report.with_warnings().count(),
+            "Wrote load report to {}",
+            report_path.display()
+        );
+    }
+
     // for (i, package) in linear_file.packages_mut().iter_mut().enumerate() {
     //     let out_path = output_dir.join(format!("{i}.bin"));
     //     println!("Rewriting {:?}", out_path);
@@ -0,0 +1,166 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use tracing::{Level, debug, span};
+
+use crate::de::ObjectExport;
+
+#[derive(Debug)]
+struct CachedExport {
+    offset: u64,
+    data: Vec<u8>,
+}
+
+/// In-memory copies of export data, read ahead of time so that deserializing objects doesn't
+/// seek back and forth through the underlying reader.
+#[derive(Debug, Default)]
+pub struct ExportCache {
+    /// Sorted by offset
+    exports: Vec<CachedExport>,
+    cached_bytes: usize,
+}
+
+impl ExportCache {
+    /// Reads the serial data of each export in `exports`. Exports are read in file order, and
+    /// reading stops once caching another export would exceed `max_bytes`.
+    pub fn build<R>(
+        reader: &mut R,
+        exports: &[ObjectExport],
+        max_bytes: Option<usize>,
+    ) -> io::Result<Self>
+    where
+        R: Read + Seek,
+    {
+        let span = span!(Level::DEBUG, "build_export_cache");
+        let _enter = span.enter();
+
+        let mut ranges = Vec::with_capacity(exports.len());
+        for (i, export) in exports.iter().enumerate() {
+            let range = export.serial_range(&i.to_string())?;
+            if !range.is_empty() {
+                ranges.push((range.start, (range.end - range.start) as usize));
+            }
+        }
+        ranges.sort_unstable();
+        ranges.dedup();
+
+        let saved_pos = reader.stream_position()?;
+
+        let mut cache = ExportCache::default();
+        for (offset, size) in ranges {
+            if max_bytes.is_some_and(|max| cache.cached_bytes + size > max) {
+                debug!("Export cache limit reached at offset {offset:#X}");
+                break;
+            }
+
+            // Overlapping exports are served from whichever range contains the position first
+            if cache.lookup(offset).is_some() {
+                continue;
+            }
+
+            let mut data = vec![0u8; size];
+            reader.seek(SeekFrom::Start(offset))?;
+            reader.read_exact(&mut data)?;
+
+            cache.cached_bytes += size;
+            cache.exports.push(CachedExport { offset, data });
+        }
+
+        reader.seek(SeekFrom::Start(saved_pos))?;
+
+        debug!(
+            "Cached {} exports ({:#X} bytes)",
+            cache.exports.len(),
+            cache.cached_bytes
+        );
+
+        Ok(cache)
+    }
+
+    /// Number of exports held in memory.
+    pub fn len(&self) -> usize {
+        self.exports.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty()
+    }
+
+    /// Total size of the cached export data.
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes
+    }
+
+    /// Returns the cached data from `pos` to the end of the export containing it.
+    pub(crate) fn lookup(&self, pos: u64) -> Option<&[u8]> {
+        let idx = self
+            .exports
+            .partition_point(|export| export.offset <= pos)
+            .checked_sub(1)?;
+        let export = &self.exports[idx];
+
+        let start = (pos - export.offset) as usize;
+        if start < export.data.len() {
+            Some(&export.data[start..])
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn export(serial_offset: i32, serial_size: i32) -> ObjectExport {
+        ObjectExport {
+            class_index: 0,
+            super_index: 0,
+            package_index: 0,
+            object_name: 0,
+            object_flags: 0,
+            serial_size,
+            serial_offset,
+        }
+    }
+
+    #[test]
+    fn caches_export_ranges() {
+        let data = (0..0x40u8).collect::<Vec<_>>();
+        let mut reader = Cursor::new(data);
+        reader.set_position(0x3);
+
+        let exports = [export(0x20, 0x10), export(0x8, 0x4), export(0, 0)];
+        let cache = ExportCache::build(&mut reader, &exports, None).unwrap();
+
+        assert_eq!(reader.position(), 0x3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.cached_bytes(), 0x14);
+        assert_eq!(cache.lookup(0x9), Some(&[0x9, 0xA, 0xB][..]));
+        assert_eq!(cache.lookup(0x2F), Some(&[0x2F][..]));
+        assert_eq!(cache.lookup(0xC), None);
+        assert_eq!(cache.lookup(0x7), None);
+    }
+
+    #[test]
+    fn respects_byte_limit() {
+        let mut reader = Cursor::new(vec![0u8; 0x40]);
+
+        let exports = [export(0, 0x10), export(0x10, 0x10), export(0x20, 0x10)];
+        let cache = ExportCache::build(&mut reader, &exports, Some(0x28)).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.lookup(0x20), None);
+    }
+
+    #[test]
+    fn rejects_negative_sizes() {
+        let mut reader = Cursor::new(vec![0u8; 0x40]);
+
+        let exports = [export(0, 0x10), export(0x10, -1)];
+        let err = ExportCache::build(&mut reader, &exports, None).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
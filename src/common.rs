@@ -1,4 +1,8 @@
-use std::collections::{HashMap, VecDeque};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Write},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -6,31 +10,258 @@ use crate::de::ObjectExport;
 
 pub fn normalize_index(index: i32) -> usize {
     match index {
-        i if i < 0 => (-index) as usize - 1,
+        i if i < 0 => index.unsigned_abs() as usize - 1,
         i if i > 0 => index as usize - 1,
         _ => 0,
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// A read of an export's data recorded while the game loaded a linear file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExportRead {
     pub export: ObjectExport,
+    /// Number of bytes read
     pub len: usize,
+    /// Whether the read should be skipped when replaying the recording
     pub ignore: bool,
+    /// Position in the export's data the read started at
     pub start_offset: u64,
 }
 
-#[derive(Deserialize)]
+impl ExportRead {
+    pub fn new(export: ObjectExport, start_offset: u64, len: usize) -> Self {
+        ExportRead {
+            export,
+            len,
+            ignore: false,
+            start_offset,
+        }
+    }
+}
+
+/// Metadata recorded while the game loaded a linear file, describing which packages and
+/// objects were loaded and every IO operation made against the file.
+///
+/// This is exchanged as JSON with the tools which record it. The layout is versioned by
+/// `schema_version`; documents without one are treated as version 1, the layout used before
+/// the version was recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ExportedData {
+    #[serde(default = "ExportedData::legacy_schema_version")]
+    pub schema_version: u32,
+    /// Names of the packages in the order they were loaded
     pub file_load_order: Vec<String>,
+    /// Export reads keyed by the address of the package file they were read from
     pub file_reads: HashMap<u32, Vec<ExportRead>>,
+    /// Package file addresses in the order they were first read from
     pub file_ptr_order: Vec<u32>,
+    /// Every seek and read made against the linear file, in order
     pub raw_io_ops: Vec<IoOp>,
+    /// Full names of the objects in the order they were loaded
     pub object_load_order: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl Default for ExportedData {
+    fn default() -> Self {
+        ExportedData {
+            schema_version: Self::SCHEMA_VERSION,
+            file_load_order: Vec::new(),
+            file_reads: HashMap::new(),
+            file_ptr_order: Vec::new(),
+            raw_io_ops: Vec::new(),
+            object_load_order: Vec::new(),
+        }
+    }
+}
+
+impl ExportedData {
+    /// Schema version written by this crate and the newest one it can read.
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    fn legacy_schema_version() -> u32 {
+        1
+    }
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses metadata from JSON, rejecting documents with a schema this crate can't read.
+    pub fn from_json_reader<R: Read>(reader: R) -> Result<Self, ExportedDataError> {
+        let data: ExportedData =
+            serde_json::from_reader(reader).map_err(ExportedDataError::Json)?;
+        data.validate()?;
+
+        Ok(data)
+    }
+
+    pub fn from_json_str(json: &str) -> Result<Self, ExportedDataError> {
+        Self::from_json_reader(json.as_bytes())
+    }
+
+    pub fn to_json_writer<W: Write>(&self, writer: W) -> Result<(), ExportedDataError> {
+        serde_json::to_writer(writer, self).map_err(ExportedDataError::Json)
+    }
+
+    pub fn to_json(&self) -> Result<String, ExportedDataError> {
+        serde_json::to_string(self).map_err(ExportedDataError::Json)
+    }
+
+    /// Checks that the metadata uses a supported schema and that its tables agree with
+    /// each other.
+    pub fn validate(&self) -> Result<(), ExportedDataError> {
+        if self.schema_version == 0 || self.schema_version > Self::SCHEMA_VERSION {
+            return Err(ExportedDataError::UnsupportedSchemaVersion(
+                self.schema_version,
+            ));
+        }
+
+        if let Some(file_ptr) = self
+            .file_ptr_order
+            .iter()
+            .find(|file_ptr| !self.file_reads.contains_key(file_ptr))
+        {
+            return Err(ExportedDataError::MissingFileReads(*file_ptr));
+        }
+
+        Ok(())
+    }
+
+    /// Records that the package `name` was loaded.
+    pub fn record_file_load(&mut self, name: impl Into<String>) {
+        self.file_load_order.push(name.into());
+    }
+
+    /// Records an export read from the package file at `file_ptr`.
+    pub fn record_export_read(&mut self, file_ptr: u32, read: ExportRead) {
+        let reads = self.file_reads.entry(file_ptr).or_insert_with(|| {
+            self.file_ptr_order.push(file_ptr);
+            Vec::new()
+        });
+        reads.push(read);
+    }
+
+    pub fn record_io_op(&mut self, op: IoOp) {
+        self.raw_io_ops.push(op);
+    }
+
+    /// Records that the object `full_name` was loaded.
+    pub fn record_object_load(&mut self, full_name: impl Into<String>) {
+        self.object_load_order.push(full_name.into());
+    }
+}
+
+/// An error reading or writing [`ExportedData`].
+#[derive(Debug)]
+pub enum ExportedDataError {
+    /// The document isn't valid JSON or doesn't match the schema
+    Json(serde_json::Error),
+    /// The document was written with a schema version this crate doesn't support
+    UnsupportedSchemaVersion(u32),
+    /// A file pointer in `file_ptr_order` has no entry in `file_reads`
+    MissingFileReads(u32),
+}
+
+impl fmt::Display for ExportedDataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportedDataError::Json(err) => write!(f, "invalid export metadata: {err}"),
+            ExportedDataError::UnsupportedSchemaVersion(version) => write!(
+                f,
+                "unsupported export metadata schema version {version} (supported: 1..={})",
+                ExportedData::SCHEMA_VERSION
+            ),
+            ExportedDataError::MissingFileReads(file_ptr) => {
+                write!(f, "file pointer {file_ptr:#X} has no recorded reads")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportedDataError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExportedDataError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// An IO operation made against a linear file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IoOp {
     Seek { to: u64, from: u64 },
     Read { len: u64 },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> ObjectExport {
+        ObjectExport {
+            class_index: -1,
+            super_index: 0,
+            package_index: 0,
+            object_name: 2,
+            object_flags: 0,
+            serial_size: 0x10,
+            serial_offset: 0x40,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut data = ExportedData::new();
+        data.record_file_load("Core");
+        data.record_export_read(0x1000, ExportRead::new(export(), 0, 4));
+        data.record_export_read(0x1000, ExportRead::new(export(), 4, 12));
+        data.record_io_op(IoOp::Seek { to: 0x40, from: 0 });
+        data.record_io_op(IoOp::Read { len: 0x10 });
+        data.record_object_load("Core.Object");
+
+        assert_eq!(data.file_ptr_order, [0x1000]);
+        assert_eq!(data.file_reads[&0x1000].len(), 2);
+
+        let json = data.to_json().unwrap();
+        assert_eq!(ExportedData::from_json_str(&json).unwrap(), data);
+    }
+
+    #[test]
+    fn reads_unversioned_documents() {
+        let json = r#"{
+            "file_load_order": [],
+            "file_reads": {},
+            "file_ptr_order": [],
+            "raw_io_ops": [{"Read": {"len": 4}}],
+            "object_load_order": ["Core.Object"]
+        }"#;
+
+        let data = ExportedData::from_json_str(json).unwrap();
+        assert_eq!(data.schema_version, 1);
+        assert_eq!(data.raw_io_ops, [IoOp::Read { len: 4 }]);
+    }
+
+    #[test]
+    fn rejects_mismatched_schemas() {
+        let mut data = ExportedData::new();
+        data.schema_version = ExportedData::SCHEMA_VERSION + 1;
+        let json = data.to_json().unwrap();
+        assert!(matches!(
+            ExportedData::from_json_str(&json),
+            Err(ExportedDataError::UnsupportedSchemaVersion(2))
+        ));
+
+        assert!(matches!(
+            ExportedData::from_json_str(r#"{"file_load_order": 5}"#),
+            Err(ExportedDataError::Json(_))
+        ));
+
+        let mut data = ExportedData::new();
+        data.file_ptr_order.push(0x2000);
+        assert!(matches!(
+            data.validate(),
+            Err(ExportedDataError::MissingFileReads(0x2000))
+        ));
+    }
+}
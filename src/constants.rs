@@ -0,0 +1,109 @@
+use std::fmt;
+
+use crate::{de::Linker, graph::object_path, object::internal::script::Const};
+
+/// A constant used by a script, with names and object references resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstantValue {
+    Int(i32),
+    Byte(u8),
+    Float(f32),
+    Bool(bool),
+    String(String),
+    Name(String),
+    /// Full name of the referenced object, or `None` for a null reference
+    Object(Option<String>),
+    Vector {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    Rotation {
+        pitch: i32,
+        yaw: i32,
+        roll: i32,
+    },
+    Range {
+        min: f32,
+        max: f32,
+    },
+    Pointer(i32),
+}
+
+impl ConstantValue {
+    /// Resolves the names and objects in `constant` through `linker`, which holds the script
+    /// it was read from.
+    pub(crate) fn resolve(constant: &Const, linker: &Linker) -> Self {
+        match constant {
+            Const::Int(value) => ConstantValue::Int(*value),
+            Const::IntByte(value) => ConstantValue::Int(i32::from(*value)),
+            Const::Byte(value) => ConstantValue::Byte(*value),
+            Const::Float(value) => ConstantValue::Float(*value),
+            Const::Bool(value) => ConstantValue::Bool(*value),
+            Const::String(value) | Const::UnicodeString(value) => {
+                ConstantValue::String(value.clone())
+            }
+            Const::Object(object) => ConstantValue::Object(
+                (object.raw_index != 0)
+                    .then(|| object_path(&linker.name, &linker.package, object.raw_index))
+                    .flatten(),
+            ),
+            Const::Name(name) => ConstantValue::Name(name.resolve(linker).to_string()),
+            Const::Rotation { pitch, yaw, roll } => ConstantValue::Rotation {
+                pitch: *pitch,
+                yaw: *yaw,
+                roll: *roll,
+            },
+            Const::Vector { x, y, z } => ConstantValue::Vector {
+                x: *x,
+                y: *y,
+                z: *z,
+            },
+            Const::Range { min, max } => ConstantValue::Range {
+                min: *min,
+                max: *max,
+            },
+            Const::Pointer(value) => ConstantValue::Pointer(*value),
+        }
+    }
+}
+
+impl fmt::Display for ConstantValue {
+    /// Writes the constant as an UnrealScript literal, e.g. `"Hello"`, `'Name'` or
+    /// `vect(1,2,3)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantValue::Int(value) => write!(f, "{value}"),
+            ConstantValue::Byte(value) => write!(f, "{value}"),
+            ConstantValue::Float(value) => write!(f, "{value}"),
+            ConstantValue::Bool(value) => write!(f, "{value}"),
+            ConstantValue::String(value) => write!(f, "{value:?}"),
+            ConstantValue::Name(value) => write!(f, "'{value}'"),
+            ConstantValue::Object(Some(path)) => f.write_str(path),
+            ConstantValue::Object(None) => f.write_str("None"),
+            ConstantValue::Vector { x, y, z } => write!(f, "vect({x},{y},{z})"),
+            ConstantValue::Rotation { pitch, yaw, roll } => {
+                write!(f, "rot({pitch},{yaw},{roll})")
+            }
+            ConstantValue::Range { min, max } => write!(f, "rng({min},{max})"),
+            ConstantValue::Pointer(value) => write!(f, "{value:#X}"),
+        }
+    }
+}
+
+/// A constant along with where it's used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptConstant {
+    /// Code offset of the statement using the constant
+    pub offset: usize,
+    pub value: ConstantValue,
+}
+
+/// The constants used by the script of one function, state or class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptConstants {
+    /// Full name of the object owning the script
+    pub owner: String,
+    /// Constants in the order they appear in the script
+    pub constants: Vec<ScriptConstant>,
+}
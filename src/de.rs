@@ -1,25 +1,48 @@
 use std::{
-    cell::RefCell,
-    collections::{HashMap, VecDeque},
-    io::{BufRead, Cursor, ErrorKind, Read, Seek, SeekFrom},
+    cell::{Ref, RefCell},
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write},
+    iter,
     marker::PhantomData,
     rc::{Rc, Weak},
 };
 
 use crate::{
+    object::internal::object_ref::ReferencePolicy,
+    object::internal::{natives::NativeFunctionTable, probes::ProbeNameTable},
     object::{
-        DeserializeUnrealObject, ObjectFlags, RcUnrealObject, UObjectKind, UnrealObject,
-        builtins::*,
+        DeserializeUnrealObject, ObjectFlags, RcUnrealObject, TypedObject, UObjectKind,
+        UnrealObject, builtins::*,
     },
-    reader::{CheckedLinReader, LinRead, LinReader, UnrealReadExt},
-    runtime::UnrealRuntime,
+    reader::{
+        CheckedLinReader, Divergence, DivergenceLog, LinRead, LinReader, PackageReader,
+        UnrealReadExt,
+    },
+    runtime::{LoadOptions, SerialSizeMismatch, UnloadStats, UnrealRuntime},
+    snapshot::Snapshot,
+    stats::LinkerUsage,
+    warnings::LoadWarning,
 };
 use byteorder::{ByteOrder, ReadBytesExt};
 use flate2::read::ZlibDecoder;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::io;
+use std::ops::Range;
+use std::path::Path;
+use tracing::debug;
 
 use crate::common::normalize_index;
+use crate::detect::{FileKind, detect};
+use crate::graph::{DependencyGraph, Referencer};
+use crate::guid::{Guid, read_guid};
+use crate::hierarchy::ClassHierarchy;
+use crate::observer::LoadObserver;
+use crate::pattern::ExportPattern;
+use crate::plan::Plan;
+use crate::postload::PostLoadHook;
+use crate::profile::{FormatProfile, HeaderExtensionLayout, LinCompression};
+use crate::report::LoadReport;
 use crate::{
     LIN_FILE_TABLE_TAG, PKG_TAG,
     common::{ExportRead, ExportedData, IoOp},
@@ -44,44 +67,121 @@ impl ExportIndex {
 
         ExportIndex(normalize_index(idx))
     }
+
+    /// Zero-based position of this export in the export table.
+    pub fn index(&self) -> usize {
+        self.0
+    }
 }
 
 pub(crate) type WeakLinker = Weak<RefCell<Linker>>;
 pub(crate) type RcLinker = Rc<RefCell<Linker>>;
 
 pub(crate) struct Linker {
-    pub objects: HashMap<ExportIndex, RcUnrealObject>,
+    /// Loaded objects, ordered by export index
+    pub objects: BTreeMap<ExportIndex, RcUnrealObject>,
     pub name: String,
     pub package: RawPackage,
+    /// Layout decisions for this package's serialized objects
+    pub profile: FormatProfile,
 }
 
 impl Linker {
-    pub fn new(name: String, package: RawPackage) -> Linker {
+    /// Constructs a new linker. All names in the package's name table are interned
+    /// through `name_pool` so that names shared between packages are only stored once.
+    ///
+    /// If `profile` is `None`, the format profile is detected from the package's version.
+    pub fn new(
+        name: String,
+        mut package: RawPackage,
+        name_pool: &mut NamePool,
+        profile: Option<FormatProfile>,
+    ) -> Linker {
+        for name in &mut package.names {
+            name.name = name_pool.intern(&name.name);
+        }
+
+        let raw_version = package.header.version;
+        let profile = match profile {
+            Some(profile) => profile.with_raw_version(raw_version),
+            None => FormatProfile::detect(raw_version),
+        };
+
         Linker {
             objects: Default::default(),
             name,
             package,
+            profile,
         }
     }
 
-    pub fn version(&self) -> u16 {
-        (self.package.header.version & 0xFFFF) as u16
+    /// Returns a shared reference to the name at `index` in this linker's name table.
+    pub fn name(&self, index: i32) -> Rc<str> {
+        Rc::clone(&self.package.names[index as usize].name)
     }
 
-    pub fn licensee_version(&self) -> u16 {
-        ((self.package.header.version & 0xFFFF_0000) >> 16) as u16
+    /// Finds the export named `name`, ignoring ASCII case as the engine does. Returns `None`
+    /// if several exports share the name, such as objects with the same name in different
+    /// groups; use [`Linker::find_export_by_path`] or [`Linker::find_exports_by_name`] for
+    /// those.
+    pub fn find_export_by_name(&self, name: &str) -> Option<(ExportIndex, &ObjectExport)> {
+        let mut matches = self.find_exports_by_name(name);
+        let found = matches.next()?;
+        if matches.next().is_some() {
+            debug!("Several exports are named {name}");
+            return None;
+        }
+
+        Some(found)
     }
 
-    pub fn find_export_by_name(&self, name: &str) -> Option<(ExportIndex, &ObjectExport)> {
-        let index = self
-            .package
+    /// Finds every export named `name`, ignoring ASCII case, in export table order.
+    pub fn find_exports_by_name(
+        &self,
+        name: &str,
+    ) -> impl Iterator<Item = (ExportIndex, &ObjectExport)> {
+        self.package
             .exports
             .iter()
-            .position(|export| export.object_name(self) == name)?;
+            .enumerate()
+            .filter(move |(_, export)| export.object_name(self).eq_ignore_ascii_case(name))
+            .map(|(index, export)| (ExportIndex(index), export))
+    }
+
+    /// Finds the export at `path` within this package, e.g. `Group.Object`. Unlike
+    /// [`Linker::find_export_by_name`], objects with the same name in different groups are
+    /// told apart. Segments are compared ignoring ASCII case.
+    pub fn find_export_by_path(&self, path: &str) -> Option<(ExportIndex, &ObjectExport)> {
+        let index = self.package.exports.iter().position(|export| {
+            let mut segments = path.rsplit('.');
+            let mut matches = |name: &str| {
+                segments
+                    .next()
+                    .is_some_and(|segment| segment.eq_ignore_ascii_case(name))
+            };
+
+            matches(export.object_name(self))
+                && self
+                    .package
+                    .export_outers(export.package_index)
+                    .all(|outer| matches(outer.object_name(self)))
+                && segments.next().is_none()
+        })?;
 
         Some((ExportIndex(index), &self.package.exports[index]))
     }
 
+    /// Finds every export whose full name and class match `pattern`, in export table order.
+    pub fn find_exports_matching(&self, pattern: &ExportPattern) -> Vec<ExportIndex> {
+        self.package
+            .exports
+            .iter()
+            .enumerate()
+            .filter(|(_, export)| pattern.matches(&export.full_name(self), export.class_name(self)))
+            .map(|(index, _)| ExportIndex(index))
+            .collect()
+    }
+
     pub fn find_import_by_index(&self, index: ImportIndex) -> Option<&Import> {
         self.package.imports.get(index.0)
     }
@@ -91,10 +191,51 @@ impl Linker {
     }
 }
 
-struct Block {
-    uncompressed_len: u32,
-    compressed_len: u32,
-    compressed_data: Vec<u8>,
+pub(crate) struct Block {
+    pub uncompressed_len: u32,
+    pub compressed_len: u32,
+    pub compressed_data: Vec<u8>,
+}
+
+impl Block {
+    /// The `u32` held by one of the header blocks at the start of a linear file.
+    pub fn header_value(&self) -> io::Result<u32> {
+        let mut bytes = Vec::with_capacity(4);
+        ZlibDecoder::new(self.compressed_data.as_slice()).read_to_end(&mut bytes)?;
+
+        let bytes: [u8; 4] = bytes.try_into().map_err(|bytes: Vec<u8>| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "linear file header block holds {:#X} bytes, expected 4",
+                    bytes.len()
+                ),
+            )
+        })?;
+
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Decompresses the block's data into `writer`, checking it holds as many bytes as the
+    /// block declares. `block_start` is where the data starts in the decompressed file.
+    pub fn inflate_to<W: Write>(&self, block_start: u64, writer: &mut W) -> io::Result<u64> {
+        let block_len = io::copy(
+            &mut ZlibDecoder::new(self.compressed_data.as_slice()),
+            writer,
+        )?;
+
+        if block_len != self.uncompressed_len as u64 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "linear file block at {block_start:#X} decompressed to {block_len:#X} bytes, expected {:#X}",
+                    self.uncompressed_len
+                ),
+            ));
+        }
+
+        Ok(block_len)
+    }
 }
 
 fn read_block<E, R>(reader: &mut R) -> io::Result<Block>
@@ -114,14 +255,28 @@ where
     })
 }
 
-#[derive(Debug)]
-pub(crate) struct FileEntry {
+/// An entry in a linear file's file table. See [`read_linear_file_table`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
     pub name: String,
+    /// Where the file's data starts in the decompressed linear file
     pub offset: u32,
     pub len: u32,
     pub unk: u32,
 }
 
+impl FileEntry {
+    /// Name of the package stored in this file, i.e. the file name without its directory or
+    /// extension.
+    pub fn package_name(&self) -> &str {
+        let file_name = self.name.rsplit(['/', '\\']).next().unwrap_or(&self.name);
+
+        file_name
+            .split_once('.')
+            .map_or(file_name, |(stem, _)| stem)
+    }
+}
+
 fn read_file_entry<E, R>(reader: &mut R) -> io::Result<FileEntry>
 where
     R: LinRead,
@@ -142,7 +297,7 @@ where
     Ok(entry)
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PackageHeader {
     pub version: u32,
     pub flags: u32,
@@ -152,33 +307,107 @@ pub struct PackageHeader {
     pub export_offset: u32,
     pub import_count: u32,
     pub import_offset: u32,
+    /// First field of the licensee header extension. See [`PackageHeader::extension`].
     pub unk: u32,
+    /// Bytes of the licensee header extension following `unk`, serialized with a packed
+    /// length. See [`PackageHeader::extension`].
     pub unknown_data: Vec<u8>,
-    pub guid_a: u32,
-    pub guid_b: u32,
-    pub guid_c: u32,
-    pub guid_d: u32,
+    pub guid: Guid,
     pub generations: Vec<GenerationInfo>,
 }
 
-#[derive(Debug)]
+/// A package header's licensee extension, decoded according to a profile's
+/// [`HeaderExtensionLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderExtension {
+    SplinterCell {
+        /// Constant written by the game's script compiler
+        tag: u32,
+        /// Name of the package the header was saved for, e.g. `Engine`
+        package_name: String,
+    },
+    /// The extension of a profile whose layout isn't known, as stored in the header
+    Raw { unk: u32, data: Vec<u8> },
+}
+
+impl PackageHeader {
+    /// Decodes the licensee header extension with `profile`'s layout.
+    pub fn extension(&self, profile: &FormatProfile) -> io::Result<HeaderExtension> {
+        match profile.quirks.header_extension {
+            HeaderExtensionLayout::Raw => Ok(HeaderExtension::Raw {
+                unk: self.unk,
+                data: self.unknown_data.clone(),
+            }),
+            HeaderExtensionLayout::SplinterCell => {
+                // The bytes are a string's, with its null terminator
+                let package_name = match self.unknown_data.split_last() {
+                    None => String::new(),
+                    Some((0, name)) => profile.quirks.string_encoding.decode(name.to_vec())?,
+                    Some(_) => {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "header extension package name is not null-terminated",
+                        ));
+                    }
+                };
+
+                Ok(HeaderExtension::SplinterCell {
+                    tag: self.unk,
+                    package_name,
+                })
+            }
+        }
+    }
+
+    /// Replaces the licensee header extension, which is stored as it's serialized.
+    pub fn set_extension(&mut self, extension: &HeaderExtension) {
+        match extension {
+            HeaderExtension::SplinterCell { tag, package_name } => {
+                self.unk = *tag;
+                self.unknown_data = package_name.as_bytes().to_vec();
+                if !package_name.is_empty() {
+                    self.unknown_data.push(0);
+                }
+            }
+            HeaderExtension::Raw { unk, data } => {
+                self.unk = *unk;
+                self.unknown_data = data.clone();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Name {
-    pub name: String,
+    pub name: Rc<str>,
     pub flags: u32,
 }
 
-fn read_name<E, R>(reader: &mut R) -> io::Result<Name>
-where
-    R: LinRead,
-    E: ByteOrder,
-{
-    Ok(Name {
-        name: reader.read_string()?,
-        flags: reader.read_u32::<E>()?,
-    })
+/// Interned name strings shared between all linkers in a runtime.
+#[derive(Debug, Default)]
+pub(crate) struct NamePool {
+    names: HashSet<Rc<str>>,
+}
+
+impl NamePool {
+    /// Returns the pooled copy of `name`, inserting it if this is the first time it's been seen.
+    pub fn intern(&mut self, name: &str) -> Rc<str> {
+        if let Some(existing) = self.names.get(name) {
+            return Rc::clone(existing);
+        }
+
+        let name: Rc<str> = Rc::from(name);
+        self.names.insert(Rc::clone(&name));
+
+        name
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Import {
     pub class_package: i32,
     pub class_name: i32,
@@ -190,33 +419,32 @@ impl Import {
     pub fn class_name<'p>(&self, package: &'p Linker) -> &'p str {
         package.package.names[self.class_name as usize]
             .name
-            .as_str()
+            .as_ref()
     }
 
     pub fn object_name<'p>(&self, package: &'p Linker) -> &'p str {
         package.package.names[self.object_name as usize]
             .name
-            .as_str()
+            .as_ref()
     }
 
+    /// The import's full name, e.g. `Engine.Actor` or `Package.Group.Object`, built by
+    /// walking its outer imports up to the package it lives in.
     pub fn full_name<'p>(&self, linker: &'p Linker) -> String {
-        let package_name = &linker.package.names[self.class_package as usize];
-        format!("{}.{}", &package_name.name, self.object_name(linker))
+        self.path(&linker.package)
     }
 
-    // pub fn full_name(&self, package: &RawPackage<'_>) -> String {
-    //     format!(
-    //         "{} {}.{}",
-    //         package.names[self.class_name as usize].name,
-    //         package.names[self.class_package as usize].name,
-    //         package.names[self.object_name as usize].name
-    //     )
-    // }
+    pub(crate) fn path(&self, package: &RawPackage) -> String {
+        let mut parts = vec![package.names[self.object_name as usize].name.as_ref()];
+        parts.extend(
+            package
+                .import_outers(self.package_index)
+                .map(|import| package.names[import.object_name as usize].name.as_ref()),
+        );
 
-    // pub fn resolve_export<'i>(&self, container: &'i RawPackage<'_>) -> &'i ObjectExport<'i> {
-    //     let normalized_index = normalize_index(self.package_index);
-    //     &container.exports[normalized_index]
-    // }
+        parts.reverse();
+        parts.join(".")
+    }
 }
 
 fn read_import<E, R>(reader: &mut R) -> io::Result<Import>
@@ -240,7 +468,7 @@ where
     })
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct ObjectExport {
     pub class_index: i32,
     pub super_index: i32,
@@ -268,13 +496,56 @@ impl ObjectExport {
     pub fn serial_size(&self) -> usize {
         self.serial_size as usize
     }
+
+    /// Where the export's data is stored. `full_name` names the export in the error returned
+    /// for a range which is negative or overflows.
+    pub(crate) fn serial_range(&self, full_name: &str) -> io::Result<Range<u64>> {
+        let range = u64::try_from(self.serial_offset)
+            .ok()
+            .zip(u64::try_from(self.serial_size).ok())
+            .and_then(|(offset, size)| Some(offset..offset.checked_add(size)?));
+        let Some(range) = range else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "export {full_name} has an invalid serial range (offset {:#X}, size {:#X})",
+                    self.serial_offset, self.serial_size
+                ),
+            ));
+        };
+
+        Ok(range)
+    }
+
+    /// Like [`ObjectExport::serial_range`], but also checked against the length of
+    /// `reader`'s stream when it's known.
+    pub(crate) fn checked_serial_range<R: LinRead>(
+        &self,
+        full_name: &str,
+        reader: &mut R,
+    ) -> io::Result<Range<u64>> {
+        let range = self.serial_range(full_name)?;
+        if let Some(len) = reader.known_len()?
+            && range.end > len
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "export {full_name}'s data ({:#X}..{:#X}) extends past the end of the package ({len:#X})",
+                    range.start, range.end
+                ),
+            ));
+        }
+
+        Ok(range)
+    }
 }
 
 impl ObjectExport {
     pub fn object_name<'p>(&self, linker: &'p Linker) -> &'p str {
         linker.package.names[self.object_name as usize]
             .name
-            .as_str()
+            .as_ref()
     }
 
     pub fn class_name<'p>(&self, linker: &'p Linker) -> &'p str {
@@ -288,16 +559,31 @@ impl ObjectExport {
         if index < 0 {
             header.names[header.imports[normalize_index(index)].object_name as usize]
                 .name
-                .as_str()
+                .as_ref()
         } else {
             header.names[header.exports[normalize_index(index)].object_name as usize]
                 .name
-                .as_str()
+                .as_ref()
         }
     }
 
+    /// The export's full name, e.g. `Package.Group.Object`, built by walking its outer
+    /// exports up to the package.
     pub fn full_name<'p>(&self, linker: &'p Linker) -> String {
-        format!("{}.{}", &linker.name, self.object_name(linker))
+        self.path(&linker.name, &linker.package)
+    }
+
+    pub(crate) fn path(&self, package_name: &str, package: &RawPackage) -> String {
+        let mut parts = vec![package.names[self.object_name as usize].name.as_ref()];
+        parts.extend(
+            package
+                .export_outers(self.package_index)
+                .map(|export| package.names[export.object_name as usize].name.as_ref()),
+        );
+        parts.push(package_name);
+
+        parts.reverse();
+        parts.join(".")
     }
 }
 
@@ -335,8 +621,9 @@ where
     })
 }
 
-#[derive(Debug)]
-pub(crate) struct GenerationInfo {
+/// Export and name counts recorded each time a package was saved.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GenerationInfo {
     pub export_count: u32,
     pub name_count: u32,
 }
@@ -373,19 +660,45 @@ where
     Ok(file_table)
 }
 
-fn read_package_header<E, R>(reader: &mut R) -> io::Result<PackageHeader>
+/// Reads the header and file table at the start of a decompressed linear file. See
+/// [`LinBlockIndex::read_file_table`] to read it without decompressing the whole file.
+pub fn read_linear_file_table<E, R>(reader: &mut R) -> io::Result<Vec<FileEntry>>
+where
+    R: LinRead,
+    E: ByteOrder,
+{
+    let _unk = reader.read_u32::<E>()?;
+    let _name = reader.read_string()?;
+
+    let tag = reader.read_u32::<E>()?;
+    if tag != LIN_FILE_TABLE_TAG {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("expected the linear file table tag, found {tag:#X}"),
+        ));
+    }
+
+    read_file_table::<E, _>(reader)
+}
+
+pub(crate) fn read_package_header<E, R>(reader: &mut R) -> io::Result<PackageHeader>
 where
     R: LinRead,
     E: ByteOrder,
 {
     let tag = reader.read_u32::<E>()?;
-    assert_eq!(tag, PKG_TAG, "Invalid linker tag");
+    if tag != PKG_TAG {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid package tag {tag:#010X}"),
+        ));
+    }
 
     let version = reader.read_u32::<E>()?;
-    println!("Version: {:#X}", version);
+    debug!("Version: {:#X}", version);
     let flags = reader.read_u32::<E>()?;
     let name_count = reader.read_u32::<E>()?;
-    println!("name_count: {:#X}", name_count);
+    debug!("Name count: {:#X}", name_count);
     let name_offset = reader.read_u32::<E>()?;
     let export_count = reader.read_u32::<E>()?;
     let export_offset = reader.read_u32::<E>()?;
@@ -393,14 +706,11 @@ where
     let import_offset = reader.read_u32::<E>()?;
 
     let unk = reader.read_u32::<E>()?;
-    println!("Unknown value: {:#X}", unk);
+    debug!("Unknown value: {:#X}", unk);
 
     let unknown_data = reader.read_array()?;
 
-    let guid_a = reader.read_u32::<E>()?;
-    let guid_b = reader.read_u32::<E>()?;
-    let guid_c = reader.read_u32::<E>()?;
-    let guid_d = reader.read_u32::<E>()?;
+    let guid = read_guid::<E, _>(reader)?;
 
     let generation_count = reader.read_u32::<E>()? as usize;
     let mut generations = Vec::with_capacity(generation_count);
@@ -419,15 +729,12 @@ where
         import_offset,
         unk,
         unknown_data,
-        guid_a,
-        guid_b,
-        guid_c,
-        guid_d,
+        guid,
         generations,
     })
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RawPackage {
     pub header: PackageHeader,
     pub names: Vec<Name>,
@@ -435,7 +742,194 @@ pub struct RawPackage {
     pub exports: Vec<ObjectExport>,
 }
 
+impl RawPackage {
+    /// The chain of imports enclosing an import whose outer is `package_index`, innermost
+    /// first. The last import yielded is the package the object lives in.
+    pub(crate) fn import_outers(&self, package_index: i32) -> impl Iterator<Item = &Import> {
+        let mut current = package_index;
+        iter::from_fn(move || {
+            if current >= 0 {
+                return None;
+            }
+
+            let import = self.imports.get(normalize_index(current))?;
+            current = import.package_index;
+            Some(import)
+        })
+        // Bail out of cycles in a corrupt table
+        .take(self.imports.len())
+    }
+
+    /// The chain of exports enclosing an export whose outer is `package_index`, innermost
+    /// first. The package itself has no export, so the chain ends at a top-level group.
+    pub(crate) fn export_outers(&self, package_index: i32) -> impl Iterator<Item = &ObjectExport> {
+        let mut current = package_index;
+        iter::from_fn(move || {
+            if current <= 0 {
+                return None;
+            }
+
+            let export = self.exports.get(normalize_index(current))?;
+            current = export.package_index;
+            Some(export)
+        })
+        .take(self.exports.len())
+    }
+
+    /// Streams each export's serial data from `reader`, the package the tables were read
+    /// from, in table order. No objects are loaded and only the export being visited is
+    /// read, so memory use doesn't grow with the size of the package.
+    pub fn stream_exports<R: LinRead>(&self, reader: R) -> ExportStream<'_, R> {
+        ExportStream {
+            package: self,
+            reader,
+            next: 0,
+        }
+    }
+}
+
+/// Visits a package's exports one at a time, as returned by [`RawPackage::stream_exports`].
+///
+/// Each [`StreamedExport`] borrows the stream's reader, so this is walked with
+/// [`ExportStream::next_export`] rather than as an [`Iterator`].
+pub struct ExportStream<'p, R> {
+    package: &'p RawPackage,
+    reader: R,
+    next: usize,
+}
+
+/// An export visited by an [`ExportStream`].
+pub struct StreamedExport<'s, R> {
+    pub index: usize,
+    pub export: &'s ObjectExport,
+    /// The export's serial data. Reads stop at the end of the export.
+    pub data: io::Take<&'s mut R>,
+}
+
+impl<R: LinRead> ExportStream<'_, R> {
+    /// Seeks to the next export's data. Returns `None` once every export has been visited,
+    /// or an error if the export's serial range is invalid, in which case the stream moves
+    /// on to the export after it.
+    pub fn next_export(&mut self) -> Option<io::Result<StreamedExport<'_, R>>> {
+        let index = self.next;
+        let export = self.package.exports.get(index)?;
+        self.next += 1;
+
+        let name = self
+            .package
+            .names
+            .get(export.object_name as usize)
+            .map_or("<invalid name>", |name| name.name.as_ref());
+        let range = match export.checked_serial_range(name, &mut self.reader) {
+            Ok(range) => range,
+            Err(err) => return Some(Err(err)),
+        };
+        if let Err(err) = self.reader.seek(SeekFrom::Start(range.start)) {
+            return Some(Err(err));
+        }
+
+        Some(Ok(StreamedExport {
+            index,
+            export,
+            data: (&mut self.reader).take(range.end - range.start),
+        }))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
 pub fn read_package<E, R>(reader: &mut R) -> io::Result<RawPackage>
+where
+    R: LinRead,
+    E: ByteOrder,
+{
+    read_package_tables::<E, _>(reader).map(RawPackage::from)
+}
+
+/// Reads the tables of each package in `readers`, in parallel if the `parallel` feature is
+/// enabled. Results are in the same order as `readers`.
+pub fn read_packages<E, R>(readers: Vec<R>) -> Vec<io::Result<RawPackage>>
+where
+    E: ByteOrder,
+    R: Read + Seek + Send,
+{
+    let read = |reader: R| read_package_tables::<E, _>(&mut PackageReader::new(reader));
+
+    #[cfg(feature = "parallel")]
+    let tables: Vec<_> = {
+        use rayon::prelude::*;
+
+        readers.into_par_iter().map(read).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let tables: Vec<_> = readers.into_iter().map(read).collect();
+
+    // Names are shared through `Rc`s, so they can only be made once back on this thread
+    tables
+        .into_iter()
+        .map(|tables| tables.map(RawPackage::from))
+        .collect()
+}
+
+/// Opens each package file in `paths` and reads its tables. See [`read_packages`].
+pub fn open_all<E, P>(paths: &[P]) -> Vec<io::Result<RawPackage>>
+where
+    E: ByteOrder,
+    P: AsRef<Path>,
+{
+    let mut readers = Vec::with_capacity(paths.len());
+    // The error opening each path, if there was one
+    let mut open_errors = Vec::with_capacity(paths.len());
+    for path in paths {
+        match File::open(path) {
+            Ok(file) => {
+                readers.push(BufReader::new(file));
+                open_errors.push(None);
+            }
+            Err(err) => open_errors.push(Some(err)),
+        }
+    }
+
+    let mut packages = read_packages::<E, _>(readers).into_iter();
+    open_errors
+        .into_iter()
+        .map(|err| match err {
+            Some(err) => Err(err),
+            None => packages.next().expect("one package per opened file"),
+        })
+        .collect()
+}
+
+/// A package's tables before its names are made shareable. Unlike [`RawPackage`], this can be
+/// sent between threads.
+struct PackageTables {
+    header: PackageHeader,
+    names: Vec<(String, u32)>,
+    imports: Vec<Import>,
+    exports: Vec<ObjectExport>,
+}
+
+impl From<PackageTables> for RawPackage {
+    fn from(tables: PackageTables) -> Self {
+        RawPackage {
+            header: tables.header,
+            names: tables
+                .names
+                .into_iter()
+                .map(|(name, flags)| Name {
+                    name: Rc::from(name),
+                    flags,
+                })
+                .collect(),
+            imports: tables.imports,
+            exports: tables.exports,
+        }
+    }
+}
+
+fn read_package_tables<E, R>(reader: &mut R) -> io::Result<PackageTables>
 where
     R: LinRead,
     E: ByteOrder,
@@ -446,7 +940,7 @@ where
 
     let mut names = Vec::with_capacity(header.name_count as usize);
     for _ in 0..header.name_count as usize {
-        names.push(read_name::<E, _>(reader)?);
+        names.push((reader.read_string()?, reader.read_u32::<E>()?));
     }
 
     reader.seek(SeekFrom::Start(header.import_offset as u64))?;
@@ -461,7 +955,7 @@ where
         exports.push(read_export::<E, _>(reader)?);
     }
 
-    Ok(RawPackage {
+    Ok(PackageTables {
         header,
         names,
         imports,
@@ -474,55 +968,118 @@ where
     R: Read,
     E: ByteOrder,
 {
-    let mut out_data = Vec::new();
+    decompress_linear_file_with_profile::<E, _>(reader, &FormatProfile::splinter_cell())
+}
 
-    // Read the first data block to get the decompressed size
-    let uncompressed_data_size = {
-        let block = read_block::<E, _>(reader).expect("failed to read block");
-        let mut reader = ZlibDecoder::new(block.compressed_data.as_slice());
-        let mut bytes = [0u8; 4];
-        let mut cursor = Cursor::new(bytes.as_mut_slice());
-        std::io::copy(&mut reader, &mut cursor).expect("failed to read zlib data ");
+/// Sizes stored in the compressed blocks at the start of a linear file. Blocks beyond the
+/// number the format profile declares are `None`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LinHeader {
+    pub uncompressed_size: u32,
+    pub compressed_size: Option<u32>,
+    pub unk1: Option<u32>,
+    pub unk2: Option<u32>,
+}
 
-        u32::from_le_bytes(bytes)
-    };
+/// Reads a single compressed block which holds one `u32`.
+fn read_header_block<E, R>(reader: &mut R) -> io::Result<u32>
+where
+    R: Read,
+    E: ByteOrder,
+{
+    read_block::<E, _>(reader)?.header_value()
+}
 
-    out_data.reserve(uncompressed_data_size as usize);
+/// Reads the header blocks at the start of a linear file.
+pub fn read_linear_file_header<E, R>(
+    reader: &mut R,
+    profile: &FormatProfile,
+) -> io::Result<LinHeader>
+where
+    R: Read,
+    E: ByteOrder,
+{
+    let values = (0..profile.quirks.lin_header_blocks)
+        .map(|_| read_header_block::<E, _>(reader))
+        .collect::<io::Result<Vec<_>>>()?;
 
-    let compressed_data_size = {
-        let block = read_block::<E, _>(reader).expect("failed to read block");
-        let mut reader = ZlibDecoder::new(block.compressed_data.as_slice());
-        let mut bytes = [0u8; 4];
-        let mut cursor = Cursor::new(bytes.as_mut_slice());
-        std::io::copy(&mut reader, &mut cursor).expect("failed to read zlib data");
+    Ok(LinHeader::from_header_values(values))
+}
 
-        u32::from_le_bytes(bytes)
-    };
+impl LinHeader {
+    /// The header made of the values held by each header block, in file order. Values
+    /// beyond the fields a header has are dropped.
+    pub(crate) fn from_header_values(values: impl IntoIterator<Item = u32>) -> Self {
+        let mut slots = [None; 4];
+        for (slot, value) in slots.iter_mut().zip(values) {
+            *slot = Some(value);
+        }
 
-    let unk1 = {
-        let block = read_block::<E, _>(reader).expect("failed to read block");
-        let mut reader = ZlibDecoder::new(block.compressed_data.as_slice());
-        let mut bytes = [0u8; 4];
-        let mut cursor = Cursor::new(bytes.as_mut_slice());
-        std::io::copy(&mut reader, &mut cursor).expect("failed to read zlib data");
+        let [uncompressed_size, compressed_size, unk1, unk2] = slots;
+        let header = LinHeader {
+            uncompressed_size: uncompressed_size.unwrap_or_default(),
+            compressed_size,
+            unk1,
+            unk2,
+        };
+        debug!("{header:#X?}");
 
-        u32::from_le_bytes(bytes)
-    };
+        header
+    }
+}
 
-    let unk2 = {
-        let block = read_block::<E, _>(reader).expect("failed to read block");
-        let mut reader = ZlibDecoder::new(block.compressed_data.as_slice());
-        let mut bytes = [0u8; 4];
-        let mut cursor = Cursor::new(bytes.as_mut_slice());
-        std::io::copy(&mut reader, &mut cursor).expect("failed to read zlib data");
+/// What was read while decompressing a linear file.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LinSummary {
+    pub header: LinHeader,
+    /// Number of data blocks following the header blocks
+    pub block_count: usize,
+    /// Sum of the compressed sizes declared by each data block
+    pub compressed_len: u64,
+    /// Number of bytes the data blocks decompressed to
+    pub uncompressed_len: u64,
+}
 
-        u32::from_le_bytes(bytes)
-    };
+/// Decompresses a Splinter Cell linear file into `writer`, one block at a time.
+pub fn decompress_linear_file_to<E, R, W>(reader: &mut R, writer: &mut W) -> io::Result<LinSummary>
+where
+    R: Read,
+    W: Write,
+    E: ByteOrder,
+{
+    decompress_linear_file_to_with_profile::<E, _, _>(
+        reader,
+        writer,
+        &FormatProfile::splinter_cell(),
+    )
+}
 
-    println!("uncompressed_data_size: {uncompressed_data_size:#X}");
-    println!("compressed_data_size: {compressed_data_size:#X}");
-    println!("unk1: {unk1:#X}");
-    println!("unk2: {unk2:#X}");
+/// Decompresses a linear file whose block header layout is described by `profile` into
+/// `writer`, one block at a time. The data is verified against the sizes declared by the
+/// header and by each block.
+pub fn decompress_linear_file_to_with_profile<E, R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    profile: &FormatProfile,
+) -> io::Result<LinSummary>
+where
+    R: Read,
+    W: Write,
+    E: ByteOrder,
+{
+    if profile.quirks.lin_compression == LinCompression::None {
+        let uncompressed_len = io::copy(reader, writer)?;
+
+        return Ok(LinSummary {
+            uncompressed_len,
+            ..Default::default()
+        });
+    }
+
+    let mut summary = LinSummary {
+        header: read_linear_file_header::<E, _>(reader, profile)?,
+        ..Default::default()
+    };
 
     // Read until EOF
     loop {
@@ -536,12 +1093,358 @@ where
                 return Err(e);
             }
         };
-        let mut reader = ZlibDecoder::new(block.compressed_data.as_slice());
 
-        std::io::copy(&mut reader, &mut out_data).expect("failed to read zlib data");
+        let block_len = block.inflate_to(summary.uncompressed_len, writer)?;
+        summary.add_block(&block, block_len);
     }
 
-    Ok(out_data)
+    summary.check_uncompressed_len(profile)?;
+    debug!("{summary:#X?}");
+
+    Ok(summary)
+}
+
+impl LinSummary {
+    pub(crate) fn add_block(&mut self, block: &Block, block_len: u64) {
+        self.block_count += 1;
+        self.compressed_len += block.compressed_len as u64;
+        self.uncompressed_len += block_len;
+    }
+
+    /// Checks the data decompressed to the size declared by the header, if `profile` has
+    /// one.
+    pub(crate) fn check_uncompressed_len(&self, profile: &FormatProfile) -> io::Result<()> {
+        if self.uncompressed_len != self.header.uncompressed_size as u64
+            && profile.quirks.lin_header_blocks > 0
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "linear file decompressed to {:#X} bytes, header declares {:#X}",
+                    self.uncompressed_len, self.header.uncompressed_size
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Decompresses a linear file whose block header layout is described by `profile`.
+pub fn decompress_linear_file_with_profile<E, R>(
+    reader: &mut R,
+    profile: &FormatProfile,
+) -> io::Result<Vec<u8>>
+where
+    R: Read,
+    E: ByteOrder,
+{
+    decompress_linear_file_with_header::<E, _>(reader, profile).map(|(_header, data)| data)
+}
+
+/// Decompresses a linear file and returns its header alongside the data. The data is
+/// verified against the sizes declared by the header and by each block.
+pub fn decompress_linear_file_with_header<E, R>(
+    reader: &mut R,
+    profile: &FormatProfile,
+) -> io::Result<(LinHeader, Vec<u8>)>
+where
+    R: Read,
+    E: ByteOrder,
+{
+    let mut out_data = Vec::new();
+    let summary =
+        decompress_linear_file_to_with_profile::<E, _, _>(reader, &mut out_data, profile)?;
+
+    Ok((summary.header, out_data))
+}
+
+/// Reads a linear file, decompressing it first if it's compressed. Which it is is
+/// [`detect`]ed from its leading bytes, so this accepts linear files before or after they've
+/// been through [`decompress_linear_file_with_profile`].
+pub fn read_linear_file<E, R>(reader: &mut R, profile: &FormatProfile) -> io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+    E: ByteOrder,
+{
+    match detect(reader)? {
+        FileKind::CompressedLinear(_) => {
+            decompress_linear_file_with_profile::<E, _>(reader, profile)
+        }
+        FileKind::Linear(_) => {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+
+            Ok(data)
+        }
+        kind => Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("expected a linear file, found {kind:?}"),
+        )),
+    }
+}
+
+/// Where one data block of a compressed linear file is stored and where its data ends up
+/// once decompressed. See [`LinBlockIndex`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LinBlock {
+    /// Position of the block, including its sizes, in the compressed file
+    pub compressed_offset: u64,
+    pub compressed_len: u32,
+    /// Position of the block's data in the decompressed file
+    pub uncompressed_offset: u64,
+    pub uncompressed_len: u32,
+}
+
+/// The position of every data block of a compressed linear file, used to decompress only
+/// the blocks covering part of it, such as a single file from its file table, rather than
+/// the whole file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinBlockIndex {
+    pub header: LinHeader,
+    /// In file order, which is also decompressed order
+    pub blocks: Vec<LinBlock>,
+}
+
+impl LinBlockIndex {
+    /// Indexes the compressed linear file in `reader`, whose block header layout is described
+    /// by `profile`. Only the sizes of each block are read; their data is skipped over.
+    pub fn read<E, R>(reader: &mut R, profile: &FormatProfile) -> io::Result<Self>
+    where
+        R: Read + Seek,
+        E: ByteOrder,
+    {
+        if profile.quirks.lin_compression == LinCompression::None {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "linear files which aren't compressed have no blocks to index",
+            ));
+        }
+
+        let header = read_linear_file_header::<E, _>(reader, profile)?;
+        let mut offset = reader.stream_position()?;
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut blocks = Vec::new();
+        let mut uncompressed_offset = 0;
+        while offset < file_len {
+            let uncompressed_len = reader.read_u32::<E>()?;
+            let compressed_len = reader.read_u32::<E>()?;
+            let data_end = offset + 8 + compressed_len as u64;
+            if data_end > file_len {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "linear file block at {offset:#X} holds {compressed_len:#X} bytes, past the end of the file ({file_len:#X})"
+                    ),
+                ));
+            }
+
+            blocks.push(LinBlock {
+                compressed_offset: offset,
+                compressed_len,
+                uncompressed_offset,
+                uncompressed_len,
+            });
+            uncompressed_offset += uncompressed_len as u64;
+            offset = reader.seek(SeekFrom::Start(data_end))?;
+        }
+
+        Ok(LinBlockIndex { header, blocks })
+    }
+
+    /// Length of the linear file once decompressed.
+    pub fn uncompressed_len(&self) -> u64 {
+        self.blocks.last().map_or(0, |block| {
+            block.uncompressed_offset + block.uncompressed_len as u64
+        })
+    }
+
+    /// Decompresses the bytes at `range` of the decompressed file, reading only the blocks
+    /// which cover it from `reader`, the file this index was read from.
+    pub fn read_range<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        range: Range<u64>,
+    ) -> io::Result<Vec<u8>> {
+        if range.start > range.end || range.end > self.uncompressed_len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "range {:#X}..{:#X} is outside of the decompressed linear file ({:#X})",
+                    range.start,
+                    range.end,
+                    self.uncompressed_len()
+                ),
+            ));
+        }
+
+        let first = self.blocks.partition_point(|block| {
+            block.uncompressed_offset + block.uncompressed_len as u64 <= range.start
+        });
+
+        let mut data = Vec::with_capacity((range.end - range.start) as usize);
+        for block in &self.blocks[first..] {
+            if block.uncompressed_offset >= range.end {
+                break;
+            }
+
+            let block_data = decompress_indexed_block(reader, block)?;
+            let start = range.start.saturating_sub(block.uncompressed_offset) as usize;
+            let end = (range.end - block.uncompressed_offset).min(block_data.len() as u64);
+            data.extend_from_slice(&block_data[start..end as usize]);
+        }
+
+        Ok(data)
+    }
+
+    /// Reads the file table, decompressing only as many blocks from the start of the file as
+    /// it takes up.
+    pub fn read_file_table<E, R>(&self, reader: &mut R) -> io::Result<Vec<FileEntry>>
+    where
+        R: Read + Seek,
+        E: ByteOrder,
+    {
+        let mut data = Vec::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            data.extend(decompress_indexed_block(reader, block)?);
+
+            let mut table_reader = PackageReader::new(io::Cursor::new(data.as_slice()));
+            match read_linear_file_table::<E, _>(&mut table_reader) {
+                // The table may continue into the next block
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof && i + 1 < self.blocks.len() => {
+                }
+                result => return result,
+            }
+        }
+
+        Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "linear file has no data blocks",
+        ))
+    }
+
+    /// Decompresses the data of the file table entry `entry`.
+    pub fn read_file<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        entry: &FileEntry,
+    ) -> io::Result<Vec<u8>> {
+        let start = entry.offset as u64;
+        self.read_range(reader, start..start + entry.len as u64)
+    }
+}
+
+/// Decompresses the block described by `block`, checking it holds as many bytes as it
+/// declares.
+fn decompress_indexed_block<R: Read + Seek>(
+    reader: &mut R,
+    block: &LinBlock,
+) -> io::Result<Vec<u8>> {
+    reader.seek(SeekFrom::Start(block.compressed_offset + 8))?;
+    let mut compressed_data = vec![0; block.compressed_len as usize];
+    reader.read_exact(&mut compressed_data)?;
+
+    let mut data = Vec::with_capacity(block.uncompressed_len as usize);
+    Block {
+        uncompressed_len: block.uncompressed_len,
+        compressed_len: block.compressed_len,
+        compressed_data,
+    }
+    .inflate_to(block.uncompressed_offset, &mut data)?;
+
+    Ok(data)
+}
+
+/// A loaded package's tables and objects. Packages decoded from a linear file are
+/// reconstructed from the data the linear file recorded for them.
+#[derive(Clone)]
+pub struct VirtualPackage {
+    pub(crate) linker: RcLinker,
+}
+
+impl VirtualPackage {
+    pub fn name(&self) -> String {
+        self.linker.borrow().name.clone()
+    }
+
+    /// The package's header, name, import, and export tables.
+    pub fn package(&self) -> Ref<'_, RawPackage> {
+        Ref::map(self.linker.borrow(), |linker| &linker.package)
+    }
+
+    pub fn format_profile(&self) -> FormatProfile {
+        self.linker.borrow().profile.clone()
+    }
+
+    /// Objects loaded from this package's exports along with their full names, in export
+    /// table order. Exports which were never loaded are not included.
+    pub fn objects(&self) -> Vec<(String, RcUnrealObject)> {
+        let linker = self.linker.borrow();
+
+        linker
+            .objects
+            .iter()
+            .map(|(index, obj)| {
+                let full_name = linker.package.exports[index.index()].full_name(&linker);
+                (full_name, Rc::clone(obj))
+            })
+            .collect()
+    }
+
+    /// Declarations of every loaded property, in export table order.
+    pub fn properties(&self) -> Vec<PropertyInfo> {
+        let linker = self.linker.borrow();
+
+        linker
+            .objects
+            .values()
+            .filter_map(|obj| {
+                let obj = obj.borrow();
+                let property = obj
+                    .parent_of_kind(UObjectKind::Property)?
+                    .as_any()
+                    .downcast_ref::<Property>()?;
+
+                Some(property.info())
+            })
+            .collect()
+    }
+
+    /// Indices into the export table of every export matching `pattern`. See
+    /// [`ExportPattern`] for the pattern syntax.
+    pub fn find_exports(&self, pattern: &str) -> Vec<usize> {
+        self.linker
+            .borrow()
+            .find_exports_matching(&ExportPattern::new(pattern))
+            .into_iter()
+            .map(|index| index.index())
+            .collect()
+    }
+
+    /// Full name of the export at `index` in the export table.
+    pub fn export_full_name(&self, index: usize) -> String {
+        let linker = self.linker.borrow();
+        linker.package.exports[index].full_name(&linker)
+    }
+
+    /// Class name of the export at `index` in the export table.
+    pub fn export_class_name(&self, index: usize) -> String {
+        let linker = self.linker.borrow();
+        linker.package.exports[index].class_name(&linker).to_owned()
+    }
+
+    /// Returns the loaded export at `path` within the package, e.g. `Group.Object`, or the
+    /// only loaded export named `path` if there's no export at that path.
+    pub fn object(&self, path: &str) -> Option<RcUnrealObject> {
+        let linker = self.linker.borrow();
+        let (index, _) = linker
+            .find_export_by_path(path)
+            .or_else(|| linker.find_export_by_name(path))?;
+
+        linker.objects.get(&index).map(Rc::clone)
+    }
 }
 
 pub struct LinearFileDecoder<E, R> {
@@ -549,6 +1452,9 @@ pub struct LinearFileDecoder<E, R> {
     metadata: ExportedData,
     file_table: Vec<FileEntry>,
     runtime: UnrealRuntime,
+    /// Load order used by [`LinearFileDecoder::load_objects`], and how many of its objects
+    /// have been loaded so far
+    plan: Option<(Plan, usize)>,
     _endian: PhantomData<E>,
 }
 
@@ -561,11 +1467,18 @@ where
         Self {
             sources: VecDeque::from_iter(sources.into_iter().map(LinReader::new)),
             runtime: UnrealRuntime {
-                linkers: HashMap::with_capacity(metadata.file_load_order.len()),
+                linkers: Default::default(),
+                name_pool: Default::default(),
                 objects_full_loading: Default::default(),
+                reference_policy: Default::default(),
+                format_profile: None,
+                intrinsic_objects: Default::default(),
+                observers: Vec::new(),
+                ..Default::default()
             },
             file_table: Vec::new(),
             metadata,
+            plan: None,
             _endian: PhantomData,
         }
     }
@@ -585,14 +1498,67 @@ where
                     .map(|reader| CheckedLinReader::new(reader, Rc::clone(&io_ops))),
             ),
             runtime: UnrealRuntime {
-                linkers: HashMap::with_capacity(metadata.file_load_order.len()),
+                linkers: Default::default(),
+                name_pool: Default::default(),
                 objects_full_loading: Default::default(),
+                reference_policy: Default::default(),
+                format_profile: None,
+                intrinsic_objects: Default::default(),
+                observers: Vec::new(),
+                ..Default::default()
             },
             file_table: Vec::new(),
             metadata,
+            plan: None,
             _endian: PhantomData,
         }
     }
+
+    /// Like [`Self::new_checked`], but divergences from the recorded IO are collected in the
+    /// returned [`TraceVerification`] instead of failing the decode.
+    pub fn new_verifying(sources: Vec<R>, metadata: ExportedData) -> (Self, TraceVerification) {
+        let mut decoder = Self::new_checked(sources, metadata);
+        let divergences = DivergenceLog::default();
+        let io_ops = decoder
+            .sources
+            .front()
+            .map(|source| source.io_ops())
+            .unwrap_or_default();
+        for source in &mut decoder.sources {
+            source.set_divergence_log(Some(Rc::clone(&divergences)));
+        }
+
+        (
+            decoder,
+            TraceVerification {
+                io_ops,
+                divergences,
+            },
+        )
+    }
+}
+
+/// Results of decoding with [`LinearFileDecoder::new_verifying`].
+pub struct TraceVerification {
+    io_ops: Rc<RefCell<VecDeque<IoOp>>>,
+    divergences: DivergenceLog,
+}
+
+impl TraceVerification {
+    /// IO operations which didn't match the trace, in the order they were made.
+    pub fn divergences(&self) -> Ref<'_, [Divergence]> {
+        Ref::map(self.divergences.borrow(), Vec::as_slice)
+    }
+
+    /// Number of recorded operations which were never made.
+    pub fn remaining_ops(&self) -> usize {
+        self.io_ops.borrow().len()
+    }
+
+    /// Whether the decode matched the trace exactly.
+    pub fn passed(&self) -> bool {
+        self.divergences.borrow().is_empty() && self.remaining_ops() == 0
+    }
 }
 
 impl<E, R> LinearFileDecoder<E, R>
@@ -604,52 +1570,1007 @@ where
         self.sources.front_mut().expect("no file reader available?")
     }
 
-    pub fn decode_linear_file(&mut self) -> io::Result<()> {
-        self.read_lin_header()?;
+    /// Replaces the table used to name native functions called from script code.
+    pub fn set_native_functions(&mut self, table: NativeFunctionTable) {
+        self.runtime.native_functions = table;
+    }
+
+    /// Replaces the table used to name the probe functions in states' probe and ignore masks.
+    pub fn set_probe_names(&mut self, table: ProbeNameTable) {
+        self.runtime.probe_names = table;
+    }
 
-        for object in &self.metadata.object_load_order {
-            let reader = self.sources.front_mut().expect("no file reader available?");
-            println!("Loading {object}");
-            self.runtime.load_object_by_full_name::<E, _>(
-                object,
-                crate::runtime::LoadKind::Load,
-                reader,
-            )?;
-            panic!("first object loaded!");
+    /// Limits how much is loaded when decoding objects.
+    ///
+    /// Linear files are read front to back, so options which leave data unread (load limits
+    /// and [`LoadOptions::skip_script_bodies`]) are rejected unless every source can be
+    /// reread.
+    pub fn set_load_options(&mut self, options: LoadOptions) -> io::Result<()> {
+        if options.skips_data() && self.sources.iter().any(|source| !source.supports_rereads()) {
+            return Err(io::Error::new(
+                ErrorKind::Unsupported,
+                "load limits and skipping script bodies require a reader which supports rereads",
+            ));
         }
 
+        self.runtime.load_options = options;
+
         Ok(())
     }
 
+    /// Exports whose data didn't match their serial size. Only populated when loading with
+    /// [`LoadOptions::lenient`] set.
+    pub fn serial_size_mismatches(&self) -> &[SerialSizeMismatch] {
+        &self.runtime.serial_size_mismatches
+    }
+
+    /// Conditions noticed while loading which didn't stop the load, oldest first.
+    pub fn warnings(&self) -> &[LoadWarning] {
+        self.runtime.warnings.records()
+    }
+
+    /// Removes and returns the warnings recorded so far.
+    pub fn take_warnings(&mut self) -> Vec<LoadWarning> {
+        self.runtime.warnings.take()
+    }
+
+    /// Calls `callback` with each warning as it's recorded. Warnings are still collected.
+    pub fn set_warning_callback(&mut self, callback: impl FnMut(&LoadWarning) + 'static) {
+        self.runtime.warnings.set_callback(Some(Box::new(callback)));
+    }
+
+    /// Registers a hook which is notified as packages and objects are loaded.
+    pub fn add_observer(&mut self, observer: Box<dyn LoadObserver>) {
+        self.runtime.add_observer(observer);
+    }
+
+    /// Sets whether the time spent deserializing each object is recorded for
+    /// [`LoadReport`]s. Enabling recording restarts it.
+    pub fn set_record_load_report(&mut self, record: bool) {
+        self.runtime.set_record_load_report(record);
+    }
+
+    /// Builds a report of every object deserialized since recording was enabled, or `None`
+    /// if it isn't.
+    pub fn load_report(&self) -> Option<LoadReport> {
+        self.runtime.load_report()
+    }
+
+    /// Registers a hook which is run on objects of `kind`, or a subclass of it, once
+    /// they've been loaded.
+    pub fn add_post_load_hook(&mut self, kind: UObjectKind, hook: Box<dyn PostLoadHook>) {
+        self.runtime.add_post_load_hook(kind, hook);
+    }
+
+    /// Builds a dependency graph of every package loaded so far.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        self.runtime.dependency_graph()
+    }
+
+    /// What refers to the object or package named `full_name` among the packages and objects
+    /// loaded so far.
+    pub fn referencers_of(&self, full_name: &str) -> Vec<Referencer> {
+        self.runtime.referencers_of(full_name)
+    }
+
+    /// Builds the hierarchy of the classes exported by every package loaded so far.
+    pub fn class_hierarchy(&self) -> ClassHierarchy {
+        self.runtime.class_hierarchy()
+    }
+
+    /// Object counts and estimated memory usage of every package loaded so far.
+    pub fn linker_usage(&self) -> Vec<LinkerUsage> {
+        self.runtime.linker_usage()
+    }
+
+    /// The loaded class named `full_name`, e.g. `Engine.Actor`.
+    pub fn get_class(&self, full_name: &str) -> Option<TypedObject<Class>> {
+        self.runtime.get_class(full_name)
+    }
+
+    /// The loaded function named `full_name`, e.g. `Engine.Actor.Spawn`.
+    pub fn get_function(&self, full_name: &str) -> Option<TypedObject<Function>> {
+        self.runtime.get_function(full_name)
+    }
+
+    /// The loaded struct named `full_name`. Functions, states and classes are structs too.
+    pub fn get_struct(&self, full_name: &str) -> Option<TypedObject<Struct>> {
+        self.runtime.get_struct(full_name)
+    }
+
+    /// Every loaded object along with the name of the package it was loaded from and its
+    /// index in that package's export table.
+    pub fn iter_objects(&self) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects()
+    }
+
+    /// Every loaded object which is a `kind`, including kinds derived from it.
+    pub fn iter_objects_of_kind(
+        &self,
+        kind: UObjectKind,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects_of_kind(kind)
+    }
+
+    /// Every loaded object which has all of `flags` set.
+    pub fn iter_objects_with_flags(
+        &self,
+        flags: ObjectFlags,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects_with_flags(flags)
+    }
+
+    /// Copies the loaded object graph into a [`Snapshot`] which can be shared between
+    /// threads.
+    pub fn freeze(&self) -> Snapshot {
+        self.runtime.freeze()
+    }
+
+    /// Unloads the package `name` and the objects loaded from it. Unloaded objects are reset
+    /// to break reference cycles between them, so any handles to them which are still held
+    /// no longer hold data. Packages which another loaded package imports from can't be
+    /// unloaded.
+    pub fn unload_package(&mut self, name: &str) -> io::Result<UnloadStats> {
+        self.runtime.unload_package(name)
+    }
+
+    /// Unloads every package loaded so far.
+    pub fn clear(&mut self) -> UnloadStats {
+        self.runtime.clear()
+    }
+
+    /// Sets whether object references are loaded as soon as they're encountered or on demand.
+    pub fn set_reference_policy(&mut self, policy: ReferencePolicy) {
+        self.runtime.reference_policy = policy;
+    }
+
+    /// Overrides format detection: all packages will be read using `profile`. The profile's
+    /// native function table, if it has one, replaces the current table, and its string
+    /// encoding applies to the linear file's header as well.
+    pub fn set_format_profile(&mut self, profile: FormatProfile) {
+        if let Some(natives) = &profile.native_functions {
+            self.runtime.native_functions = natives.clone();
+        }
+        for source in &mut self.sources {
+            source.set_string_encoding(profile.quirks.string_encoding);
+        }
+        self.runtime.format_profile = Some(profile);
+    }
+
+    /// Loads every object in the linear file's load [`Plan`] and returns the packages they
+    /// were loaded from. Continues from where [`LinearFileDecoder::load_objects`] stopped if
+    /// it's been called; objects it skipped stay unloaded.
+    pub fn decode_linear_file(&mut self) -> io::Result<Vec<VirtualPackage>> {
+        self.next_planned_objects(|_| true, |_| false)?;
+
+        Ok(self.packages())
+    }
+
+    /// Loads the objects named by `full_names` (e.g. `Common.PlayerController`) along with
+    /// their dependencies, and returns them in the same order.
+    ///
+    /// Linear files are streamed, so the data of objects which come before the requested
+    /// ones in the load [`Plan`] is still read through. Objects the requested ones don't
+    /// depend on are skipped over rather than deserialized, and can't be loaded afterwards.
+    /// Everything after the last requested object is left unread. Later calls pick up where
+    /// the previous one stopped, so objects can be loaded incrementally.
+    ///
+    /// Requesting an object which isn't left in the plan is a `NotFound` error, reported
+    /// before anything else is read.
+    pub fn load_objects(&mut self, full_names: &[&str]) -> io::Result<Vec<RcUnrealObject>> {
+        self.make_plan()?;
+
+        let (plan, next) = self.plan.as_ref().expect("plan was just made");
+        let remaining = plan.objects[*next..]
+            .iter()
+            .map(|object| object.to_ascii_lowercase())
+            .collect::<HashSet<_>>();
+        for name in full_names {
+            if self.runtime.loaded_object(name).is_none()
+                && !remaining.contains(&name.to_ascii_lowercase())
+            {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("{name} is not left to load in the linear file"),
+                ));
+            }
+        }
+
+        // Dependencies are only known once the tables of the requested objects' packages have
+        // been read. Until then, everything before the requested objects is loaded.
+        let packages_loaded = full_names.iter().all(|name| {
+            let package = name.split_once('.').map_or(*name, |(package, _)| package);
+            self.runtime.linker(package).is_some()
+        });
+        let needed = packages_loaded.then(|| {
+            self.runtime
+                .dependency_graph()
+                .reachable_ignoring_case(full_names.iter().copied())
+        });
+
+        self.next_planned_objects(
+            |object| {
+                needed
+                    .as_ref()
+                    .is_none_or(|needed| needed.contains(&object.to_ascii_lowercase()))
+            },
+            |runtime| {
+                full_names
+                    .iter()
+                    .all(|name| runtime.loaded_object(name).is_some())
+            },
+        )?;
+
+        full_names
+            .iter()
+            .map(|name| {
+                self.runtime.loaded_object(name).ok_or_else(|| {
+                    io::Error::new(
+                        ErrorKind::NotFound,
+                        format!("{name} is not in the linear file"),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Reads the header and makes the load plan, if that hasn't been done yet.
+    fn make_plan(&mut self) -> io::Result<()> {
+        if self.plan.is_none() {
+            self.read_lin_header()?;
+            self.plan = Some((self.plan()?, 0));
+        }
+
+        Ok(())
+    }
+
+    /// Goes through the load plan, continuing from the last object, until `done` returns true
+    /// or the plan is finished. Objects `load` returns true for are loaded, and the data of
+    /// the others is skipped.
+    fn next_planned_objects(
+        &mut self,
+        load: impl Fn(&str) -> bool,
+        done: impl Fn(&UnrealRuntime) -> bool,
+    ) -> io::Result<()> {
+        self.make_plan()?;
+
+        let (plan, mut next) = self.plan.take().expect("plan was just made");
+        let reader = self.sources.front_mut().expect("no file reader available?");
+        let result = self.runtime.load_batch(|runtime| {
+            while next < plan.objects.len() && !done(runtime) {
+                let object = &plan.objects[next];
+                if load(object) {
+                    debug!("Loading {object}");
+                    runtime.load_object_by_full_name::<E, _>(
+                        object,
+                        crate::runtime::LoadKind::Load,
+                        reader,
+                    )?;
+                } else {
+                    runtime.skip_object_by_full_name::<E, _>(object, reader)?;
+                }
+                next += 1;
+            }
+
+            Ok(())
+        });
+        self.plan = Some((plan, next));
+
+        result
+    }
+
+    /// The order objects are loaded in. This is the load order recorded in the metadata if
+    /// there is one. Otherwise it's derived from the packages' tables, which are read in file
+    /// table order.
+    pub fn plan(&mut self) -> io::Result<Plan> {
+        if !self.metadata.object_load_order.is_empty() {
+            return Ok(Plan::from_exported_data(&self.metadata));
+        }
+
+        let package_names = self
+            .file_table
+            .iter()
+            .map(|entry| entry.package_name().to_owned())
+            .collect::<Vec<_>>();
+
+        let mut linkers = Vec::with_capacity(package_names.len());
+        for name in package_names {
+            let linker = match self.runtime.linker(&name) {
+                Some(linker) => linker,
+                None => {
+                    debug!("Reading tables of {name} to plan the load order");
+
+                    let reader = self.sources.front_mut().expect("no file reader available?");
+                    self.runtime.load_linker::<E, _>(name.clone(), reader)?;
+                    self.runtime.linker(&name).expect("linker was just loaded")
+                }
+            };
+
+            linkers.push(linker);
+        }
+
+        let linkers = linkers
+            .iter()
+            .map(|linker| linker.borrow())
+            .collect::<Vec<_>>();
+
+        Ok(Plan::derive(
+            linkers
+                .iter()
+                .map(|linker| (linker.name.as_str(), &linker.package)),
+        ))
+    }
+
+    /// Loads each object in `plan`. Packages are loaded as objects are first needed from
+    /// them.
+    pub fn execute_plan(&mut self, plan: &Plan) -> io::Result<()> {
+        let reader = self.sources.front_mut().expect("no file reader available?");
+        self.runtime.load_batch(|runtime| {
+            for object in &plan.objects {
+                debug!("Loading {object}");
+                runtime.load_object_by_full_name::<E, _>(
+                    object,
+                    crate::runtime::LoadKind::Load,
+                    reader,
+                )?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Packages which have been loaded from the linear file so far, in load order.
+    pub fn packages(&self) -> Vec<VirtualPackage> {
+        self.runtime
+            .linkers_in_load_order()
+            .map(|linker| VirtualPackage { linker })
+            .collect()
+    }
+
+    /// Returns the loaded package named `name`.
+    pub fn package(&self, name: &str) -> Option<VirtualPackage> {
+        self.runtime
+            .linker(name)
+            .map(|linker| VirtualPackage { linker })
+    }
+
     pub fn read_lin_header(&mut self) -> io::Result<()> {
         let has_file_table = !self.file_table.is_empty();
 
-        let mut reader = self.reader();
+        let file_table = self.reader().unverified_scope(|reader| {
+            // There's only one file table, so later headers stop before it
+            if has_file_table {
+                let _unk = reader.read_u32::<E>()?;
+                let name = reader.read_string()?;
+                debug!("Linear file header for {name}");
+
+                return Ok(None);
+            }
+
+            let file_table = read_linear_file_table::<E, _>(reader)?;
+            debug!("File table length: {:#X}", file_table.len());
+            debug!("{file_table:#X?}");
+
+            Ok(Some(file_table))
+        })?;
+
+        if let Some(file_table) = file_table {
+            self.runtime.package_lens = file_table
+                .iter()
+                .map(|entry| {
+                    (
+                        entry.package_name().to_ascii_lowercase(),
+                        u64::from(entry.len),
+                    )
+                })
+                .collect();
+            self.file_table = file_table;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_pool_shares_names() {
+        let mut pool = NamePool::default();
+
+        let a = pool.intern("Object");
+        let b = pool.intern("Object");
+        let c = pool.intern("Class");
+
+        assert!(Rc::ptr_eq(&a, &b));
+        assert!(!Rc::ptr_eq(&a, &c));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn file_entries_name_their_package() {
+        let entry = |name: &str| FileEntry {
+            name: name.to_owned(),
+            offset: 0,
+            len: 0,
+            unk: 0,
+        };
+
+        assert_eq!(entry("..\\System\\Engine.u").package_name(), "Engine");
+        assert_eq!(entry("Maps/Tbilisi.unr").package_name(), "Tbilisi");
+        assert_eq!(entry("Core").package_name(), "Core");
+    }
+
+    #[test]
+    fn full_names_follow_outer_chain() {
+        let names = [
+            "None", "Core", "Package", "Engine", "Actor", "Group", "Sound", "Other",
+        ]
+        .into_iter()
+        .map(|name| Name {
+            name: Rc::from(name),
+            flags: 0,
+        })
+        .collect::<Vec<_>>();
+
+        let export = |package_index, object_name| ObjectExport {
+            class_index: 0,
+            super_index: 0,
+            package_index,
+            object_name,
+            object_flags: 0,
+            serial_size: 0,
+            serial_offset: 0,
+        };
+
+        let package = RawPackage {
+            header: PackageHeader {
+                version: 0,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: 4,
+                export_offset: 0,
+                import_count: 2,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: Vec::new(),
+            },
+            names,
+            imports: vec![
+                // Engine
+                Import {
+                    class_package: 1,
+                    class_name: 2,
+                    package_index: 0,
+                    object_name: 3,
+                },
+                // Engine.Actor, whose class lives in Core
+                Import {
+                    class_package: 1,
+                    class_name: 2,
+                    package_index: -1,
+                    object_name: 4,
+                },
+            ],
+            exports: vec![
+                // MyPackage.Group
+                export(0, 5),
+                // MyPackage.Group.Sound
+                export(1, 6),
+                // MyPackage.Other
+                export(0, 7),
+                // MyPackage.Other.Sound
+                export(3, 6),
+            ],
+        };
+
+        let mut pool = NamePool::default();
+        let linker = Linker::new("MyPackage".to_owned(), package, &mut pool, None);
+
+        let imports = &linker.package.imports;
+        assert_eq!(imports[0].full_name(&linker), "Engine");
+        assert_eq!(imports[1].full_name(&linker), "Engine.Actor");
+
+        let exports = &linker.package.exports;
+        assert_eq!(exports[1].full_name(&linker), "MyPackage.Group.Sound");
+        assert_eq!(exports[3].full_name(&linker), "MyPackage.Other.Sound");
+
+        let index = |path| linker.find_export_by_path(path).map(|(index, _)| index);
+        assert_eq!(index("Group.Sound"), Some(ExportIndex(1)));
+        assert_eq!(index("Other.Sound"), Some(ExportIndex(3)));
+        assert_eq!(index("Other"), Some(ExportIndex(2)));
+        assert_eq!(index("other.SOUND"), Some(ExportIndex(3)));
+        assert_eq!(index("Sound"), None);
+        assert_eq!(index("MyPackage.Other.Sound"), None);
+
+        assert_eq!(
+            linker.find_export_by_name("group").map(|(index, _)| index),
+            Some(ExportIndex(0))
+        );
+        assert!(
+            linker.find_export_by_name("sound").is_none(),
+            "ambiguous names aren't resolved"
+        );
+        assert_eq!(
+            linker
+                .find_exports_by_name("sound")
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>(),
+            [ExportIndex(1), ExportIndex(3)]
+        );
+
+        let matching = |pattern| linker.find_exports_matching(&ExportPattern::new(pattern));
+        assert_eq!(matching("*.sound"), [ExportIndex(1), ExportIndex(3)]);
+        assert_eq!(matching("MyPackage.Other:*"), [ExportIndex(3)]);
+        assert_eq!(
+            matching("class=Class *Group*"),
+            [ExportIndex(0), ExportIndex(1)]
+        );
+        assert!(matching("class=Function").is_empty());
+    }
+
+    #[test]
+    fn reads_many_packages() {
+        use crate::test_support::{DataBuilder, PackageBuilder};
+        use byteorder::LittleEndian;
+        use std::io::Cursor;
+
+        let package = |exports: &[&str]| {
+            let mut builder = PackageBuilder::new();
+            let constant = builder.core_class("Const");
+            for name in exports {
+                let data = DataBuilder::field(0, 0).string("1").build();
+                builder.export(constant, 0, name, data);
+            }
+
+            Cursor::new(builder.build())
+        };
+
+        let packages = read_packages::<LittleEndian, _>(vec![
+            package(&["One"]),
+            Cursor::new(b"not a package".to_vec()),
+            package(&["One", "Two"]),
+        ]);
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[0].as_ref().unwrap().exports.len(), 1);
+        assert!(packages[1].is_err());
+        assert_eq!(packages[2].as_ref().unwrap().exports.len(), 2);
+    }
+
+    fn compressed_block(uncompressed_len: u32, data: &[u8]) -> Vec<u8> {
+        use flate2::{Compression, write::ZlibEncoder};
+        use std::io::Write;
 
-        reader.set_reading_linker_header(true);
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
 
-        let unk = reader.read_u32::<E>()?;
-        let name = reader.read_string()?;
-        println!("{}", name);
+        let mut block = Vec::new();
+        block.extend_from_slice(&uncompressed_len.to_le_bytes());
+        block.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        block.extend_from_slice(&compressed);
 
-        // There's only one file table, so we shouldn't read this.
-        if has_file_table {
-            reader.set_reading_linker_header(false);
-            return Ok(());
+        block
+    }
+
+    fn linear_file(declared_size: u32, blocks: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut file = Vec::new();
+        for value in [declared_size, 0x20, 1, 2] {
+            file.extend(compressed_block(4, &value.to_le_bytes()));
         }
+        for (uncompressed_len, data) in blocks {
+            file.extend(compressed_block(*uncompressed_len, data));
+        }
+
+        file
+    }
 
-        let tag = reader.read_u32::<E>()?;
-        assert_eq!(tag, LIN_FILE_TABLE_TAG, "LIN file table tag mismatch");
+    #[test]
+    fn decompresses_selected_files() {
+        use crate::ser::write_var_string;
+        use byteorder::LittleEndian;
+
+        let mut data = Vec::new();
+        data.extend(0u32.to_le_bytes());
+        write_var_string(&mut data, "Maps\\Test.lin").unwrap();
+        data.extend(LIN_FILE_TABLE_TAG.to_le_bytes());
+        data.extend([0; 0x10]);
+        data.push(2);
+        let files: [(&str, &[u8]); 2] = [("First.u", b"first!"), ("Second.u", b"2nd!")];
+        // Each entry is a name, its length and null terminator, then offset, length and unk
+        let mut offset = data.len()
+            + files
+                .iter()
+                .map(|(name, _)| name.len() + 2 + 12)
+                .sum::<usize>();
+        for (name, contents) in files {
+            write_var_string(&mut data, name).unwrap();
+            data.extend((offset as u32).to_le_bytes());
+            data.extend((contents.len() as u32).to_le_bytes());
+            data.extend(0u32.to_le_bytes());
+            offset += contents.len();
+        }
+        for (_, contents) in files {
+            data.extend(contents);
+        }
 
-        let file_table = Some(read_file_table::<E, _>(reader).expect("failed to read file table"));
-        println!(
-            "File table length: {:#X}",
-            file_table.as_ref().map(|t| t.len()).unwrap_or_default()
+        // Small blocks, so the table and files each span several of them
+        let chunks = data.chunks(7).collect::<Vec<_>>();
+        let blocks = chunks
+            .iter()
+            .map(|chunk| (chunk.len() as u32, *chunk))
+            .collect::<Vec<_>>();
+        let mut file = linear_file(data.len() as u32, &blocks);
+
+        let profile = FormatProfile::splinter_cell();
+        let mut reader = io::Cursor::new(file.clone());
+        let index = LinBlockIndex::read::<LittleEndian, _>(&mut reader, &profile).unwrap();
+        assert_eq!(index.blocks.len(), chunks.len());
+        assert_eq!(index.uncompressed_len(), data.len() as u64);
+
+        let table = index
+            .read_file_table::<LittleEndian, _>(&mut reader)
+            .unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[1].name, "Second.u");
+        assert_eq!(index.read_file(&mut reader, &table[0]).unwrap(), b"first!");
+        assert_eq!(index.read_file(&mut reader, &table[1]).unwrap(), b"2nd!");
+
+        // Blocks outside of the file being read aren't decompressed
+        let last = index.blocks.last().unwrap();
+        let garbage = last.compressed_offset as usize + 8;
+        file[garbage..garbage + last.compressed_len as usize].fill(0xFF);
+        let mut reader = io::Cursor::new(file);
+        assert_eq!(index.read_file(&mut reader, &table[0]).unwrap(), b"first!");
+        assert!(index.read_file(&mut reader, &table[1]).is_err());
+
+        let past_end = index.uncompressed_len()..index.uncompressed_len() + 1;
+        let err = index.read_range(&mut reader, past_end).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn decompress_verifies_sizes() {
+        use byteorder::LittleEndian;
+
+        let profile = FormatProfile::splinter_cell();
+
+        let file = linear_file(6, &[(4, b"abcd"), (2, b"ef")]);
+        let (header, data) =
+            decompress_linear_file_with_header::<LittleEndian, _>(&mut file.as_slice(), &profile)
+                .unwrap();
+        assert_eq!(data, b"abcdef");
+        assert_eq!(
+            header,
+            LinHeader {
+                uncompressed_size: 6,
+                compressed_size: Some(0x20),
+                unk1: Some(1),
+                unk2: Some(2),
+            }
         );
-        println!("{file_table:#X?}");
 
-        reader.set_reading_linker_header(false);
+        let mut streamed = Vec::new();
+        let summary = decompress_linear_file_to_with_profile::<LittleEndian, _, _>(
+            &mut file.as_slice(),
+            &mut streamed,
+            &profile,
+        )
+        .unwrap();
+        assert_eq!(streamed, data);
+        assert_eq!(summary.header, header);
+        assert_eq!((summary.block_count, summary.uncompressed_len), (2, 6));
+        assert!(summary.compressed_len > 0);
+
+        let mut uncompressed = profile.clone();
+        uncompressed.quirks.lin_compression = LinCompression::None;
+        let mut streamed = Vec::new();
+        let summary = decompress_linear_file_to_with_profile::<LittleEndian, _, _>(
+            &mut b"raw data".as_slice(),
+            &mut streamed,
+            &uncompressed,
+        )
+        .unwrap();
+        assert_eq!(streamed, b"raw data");
+        assert_eq!((summary.block_count, summary.uncompressed_len), (0, 8));
+
+        let file = linear_file(8, &[(4, b"abcd"), (2, b"ef")]);
+        let err =
+            decompress_linear_file_with_header::<LittleEndian, _>(&mut file.as_slice(), &profile)
+                .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let file = linear_file(6, &[(5, b"abcd"), (2, b"ef")]);
+        let err =
+            decompress_linear_file_with_header::<LittleEndian, _>(&mut file.as_slice(), &profile)
+                .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
 
-        Ok(())
+    /// A linear file holding one package, whose export data is streamed in export order.
+    fn single_package_linear_file(package: &[u8]) -> Vec<u8> {
+        use crate::ser::write_var_string;
+
+        let mut lin = Vec::new();
+        lin.extend(0u32.to_le_bytes());
+        write_var_string(&mut lin, "Maps\\Test.lin").unwrap();
+        lin.extend(LIN_FILE_TABLE_TAG.to_le_bytes());
+        lin.extend([0; 0x10]);
+        // One file table entry
+        lin.push(1);
+        write_var_string(&mut lin, "Test.u").unwrap();
+        let offset = lin.len() as u32 + 12;
+        lin.extend(offset.to_le_bytes());
+        lin.extend((package.len() as u32).to_le_bytes());
+        lin.extend(0u32.to_le_bytes());
+
+        lin.extend(package);
+        lin
+    }
+
+    #[test]
+    fn notifies_observers_in_load_order() {
+        use crate::observer::{LinkerEvent, LoadObserver, ObjEvent};
+        use crate::test_support::{DataBuilder, PackageBuilder};
+        use byteorder::LittleEndian;
+
+        struct Recorder(Rc<RefCell<Vec<String>>>);
+
+        impl LoadObserver for Recorder {
+            fn on_object_constructed(&mut self, event: &ObjEvent) -> io::Result<()> {
+                assert_eq!(event.serial_size, None);
+                self.0
+                    .borrow_mut()
+                    .push(format!("constructed {}", event.full_name));
+                Ok(())
+            }
+
+            fn on_object_deserialized(&mut self, event: &ObjEvent) -> io::Result<()> {
+                self.0.borrow_mut().push(format!(
+                    "deserialized {} ({} {:?})",
+                    event.full_name, event.export_index, event.serial_size
+                ));
+                Ok(())
+            }
+
+            fn on_linker_loaded(&mut self, event: &LinkerEvent) -> io::Result<()> {
+                self.0.borrow_mut().push(format!(
+                    "linker {} ({} exports)",
+                    event.name, event.export_count
+                ));
+                Ok(())
+            }
+        }
+
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let one = DataBuilder::field(0, 0).string("1").build();
+        let one_len = one.len();
+        builder.export(const_class, 0, "One", one);
+        let two = DataBuilder::field(0, 0).string("22").build();
+        let two_len = two.len();
+        builder.export(const_class, 0, "Two", two);
+        let lin = single_package_linear_file(&builder.build());
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut decoder = LinearFileDecoder::<LittleEndian, _>::new(
+            vec![io::Cursor::new(lin)],
+            ExportedData::default(),
+        );
+        decoder.add_observer(Box::new(Recorder(Rc::clone(&events))));
+        decoder.decode_linear_file().unwrap();
+
+        assert_eq!(
+            *events.borrow(),
+            [
+                "linker Test (2 exports)".to_owned(),
+                "constructed Test.One".to_owned(),
+                format!("deserialized Test.One (0 Some({one_len}))"),
+                "constructed Test.Two".to_owned(),
+                format!("deserialized Test.Two (1 Some({two_len}))"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_file_table_tags() {
+        use crate::test_support::PackageBuilder;
+        use byteorder::LittleEndian;
+
+        let mut lin = single_package_linear_file(&PackageBuilder::new().build());
+        let tag = LIN_FILE_TABLE_TAG.to_le_bytes();
+        let tag_offset = lin.windows(4).position(|bytes| bytes == tag).unwrap();
+        lin[tag_offset] ^= 0xFF;
+
+        let mut decoder = LinearFileDecoder::<LittleEndian, _>::new(
+            vec![io::Cursor::new(lin)],
+            ExportedData::default(),
+        );
+        let err = decoder.read_lin_header().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn checks_exports_against_file_table_lengths() {
+        use crate::test_support::{DataBuilder, PackageBuilder};
+        use byteorder::LittleEndian;
+
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("1").build();
+        builder.export(const_class, 0, "One", data);
+        let package = builder.build();
+        let lin = single_package_linear_file(&package);
+
+        let (mut decoder, _) = LinearFileDecoder::<LittleEndian, _>::new_verifying(
+            vec![io::Cursor::new(lin.clone())],
+            ExportedData::default(),
+        );
+        decoder.load_objects(&["Test.One"]).unwrap();
+
+        // A file table entry which ends before the export's data does
+        let mut truncated = lin;
+        let len_offset = truncated.len() - package.len() - 8;
+        truncated[len_offset..len_offset + 4].copy_from_slice(&8u32.to_le_bytes());
+        let (mut decoder, _) = LinearFileDecoder::<LittleEndian, _>::new_verifying(
+            vec![io::Cursor::new(truncated.clone())],
+            ExportedData::default(),
+        );
+        let err = decoder.load_objects(&["Test.One"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("past the end of the package"));
+
+        // File table entries are matched to packages regardless of case
+        let name_offset = truncated
+            .windows(6)
+            .position(|window| window == b"Test.u")
+            .unwrap();
+        truncated[name_offset..name_offset + 4].copy_from_slice(b"TEST");
+        let mut decoder = LinearFileDecoder::<LittleEndian, _>::new(
+            vec![io::Cursor::new(truncated)],
+            ExportedData::default(),
+        );
+        decoder.read_lin_header().unwrap();
+        assert_eq!(decoder.runtime.package_len("Test"), Some(8));
+        assert_eq!(decoder.runtime.package_len("test"), Some(8));
+    }
+
+    #[test]
+    fn loads_objects_incrementally() {
+        use crate::test_support::{DataBuilder, PackageBuilder};
+        use byteorder::LittleEndian;
+
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        for (name, value) in [("First", "1"), ("Second", "2"), ("Third", "3")] {
+            builder.export(
+                const_class,
+                0,
+                name,
+                DataBuilder::field(0, 0).string(value).build(),
+            );
+        }
+        let lin = single_package_linear_file(&builder.build());
+
+        let mut decoder = LinearFileDecoder::<LittleEndian, _>::new(
+            vec![io::Cursor::new(lin)],
+            ExportedData::default(),
+        );
+        let objects = decoder.load_objects(&["Test.Second"]).unwrap();
+        assert_eq!(objects[0].borrow().base_object().name(), "Second");
+
+        // Second doesn't depend on First, so First's data was skipped rather than loaded
+        let loaded = |decoder: &LinearFileDecoder<_, _>| {
+            let mut names = decoder.packages()[0]
+                .objects()
+                .into_iter()
+                .filter(|(_, obj)| !obj.borrow().base_object().needs_load())
+                .map(|(_, obj)| obj.borrow().base_object().name().to_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(loaded(&decoder), ["Second"]);
+
+        // Already loaded objects don't read any further
+        decoder.load_objects(&["test.second"]).unwrap();
+        assert_eq!(loaded(&decoder), ["Second"]);
+
+        // Skipped objects and objects which aren't in the file fail without reading on
+        for name in ["Test.First", "Test.Missing"] {
+            let err = decoder.load_objects(&[name]).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+            assert_eq!(loaded(&decoder), ["Second"]);
+        }
+
+        let third = decoder.load_objects(&["Test.Third"]).unwrap().remove(0);
+        let third = third.borrow();
+        assert_eq!(third.as_any().downcast_ref::<Const>().unwrap().value, "3");
+    }
+
+    #[test]
+    fn rejects_load_options_which_skip_data() {
+        use crate::runtime::LoadOptions;
+        use crate::test_support::PackageBuilder;
+        use byteorder::LittleEndian;
+
+        let lin = single_package_linear_file(&PackageBuilder::new().build());
+        let mut decoder = LinearFileDecoder::<LittleEndian, _>::new(
+            vec![io::Cursor::new(lin)],
+            ExportedData::default(),
+        );
+
+        for options in [
+            LoadOptions {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+            LoadOptions {
+                max_objects: Some(1),
+                ..Default::default()
+            },
+            LoadOptions {
+                max_bytes: Some(1),
+                ..Default::default()
+            },
+            LoadOptions {
+                skip_script_bodies: true,
+                ..Default::default()
+            },
+        ] {
+            let err = decoder.set_load_options(options).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::Unsupported);
+        }
+
+        // Options which only change how data is interpreted are fine
+        decoder
+            .set_load_options(LoadOptions {
+                lenient: true,
+                skip_script_debug_info: true,
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn streams_export_data() {
+        use crate::reader::PackageReader;
+        use crate::test_support::{DataBuilder, PackageBuilder};
+        use byteorder::LittleEndian;
+
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let first = DataBuilder::field(0, 0).string("1").build();
+        let second = DataBuilder::field(0, 0).string("22").build();
+        builder.export(const_class, 0, "First", first.clone());
+        builder.export(const_class, 0, "Second", second.clone());
+        builder.export(const_class, 0, "Stripped", Vec::new());
+        let data = builder.build();
+
+        let mut reader = PackageReader::new(io::Cursor::new(data.as_slice()));
+        let package = read_package::<LittleEndian, _>(&mut reader).unwrap();
+
+        let mut stream = package.stream_exports(reader);
+        let mut seen = Vec::new();
+        while let Some(export) = stream.next_export() {
+            let mut export = export.unwrap();
+            let mut bytes = Vec::new();
+            export.data.read_to_end(&mut bytes).unwrap();
+            seen.push((export.index, bytes));
+        }
+        assert_eq!(seen, vec![(0, first), (1, second), (2, Vec::new())]);
+
+        // Bad ranges are reported without ending the stream
+        let mut package = package;
+        package.exports[0].serial_size = data.len() as i32;
+        let mut stream =
+            package.stream_exports(PackageReader::new(io::Cursor::new(data.as_slice())));
+        let err = stream.next_export().unwrap().err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(stream.next_export().unwrap().unwrap().index, 1);
     }
 }
@@ -0,0 +1,198 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::{LIN_FILE_TABLE_TAG, PKG_TAG, packed::read_packed_int, profile::Endianness};
+
+/// Number of leading bytes inspected by [`detect`]. Enough for a linear file's header,
+/// which holds a file name.
+const SNIFF_LEN: usize = 512;
+
+/// What kind of file some input holds, as classified by [`detect`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FileKind {
+    /// A standalone package starting with the package tag.
+    Package(Endianness),
+    /// A linear file made of zlib compressed blocks.
+    CompressedLinear(Endianness),
+    /// A decompressed linear file, starting with its header and file table.
+    Linear(Endianness),
+    Unknown,
+}
+
+impl FileKind {
+    /// Byte order of the file, if its kind was recognized.
+    pub fn endianness(&self) -> Option<Endianness> {
+        match self {
+            FileKind::Package(endianness)
+            | FileKind::CompressedLinear(endianness)
+            | FileKind::Linear(endianness) => Some(*endianness),
+            FileKind::Unknown => None,
+        }
+    }
+}
+
+/// The [`Endianness`] matching the byte order type `E`.
+pub(crate) fn endianness_of<E: ByteOrder>() -> Endianness {
+    if E::read_u16(&[0, 1]) == 1 {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    }
+}
+
+/// Classifies `reader` by its leading bytes. The reader is left at the position it started
+/// at.
+pub fn detect<R: Read + Seek>(reader: &mut R) -> io::Result<FileKind> {
+    let start = reader.stream_position()?;
+    let remaining = reader.seek(SeekFrom::End(0))? - start;
+    reader.seek(SeekFrom::Start(start))?;
+
+    let mut data = Vec::with_capacity(SNIFF_LEN);
+    reader
+        .by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut data)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    Ok(detect_bytes(&data, remaining))
+}
+
+/// Classifies a file from its first bytes. `len` is the length of the whole file.
+fn detect_bytes(data: &[u8], len: u64) -> FileKind {
+    let byte_orders = [
+        (
+            Endianness::Little,
+            is_tag::<LittleEndian> as fn(&[u8], u32) -> bool,
+        ),
+        (Endianness::Big, is_tag::<BigEndian>),
+    ];
+
+    for (endianness, is_tag) in byte_orders {
+        if is_tag(data, PKG_TAG) {
+            return FileKind::Package(endianness);
+        }
+    }
+
+    if let Some(tag_offset) = linear_header_len(data) {
+        for (endianness, is_tag) in byte_orders {
+            if is_tag(&data[tag_offset..], LIN_FILE_TABLE_TAG) {
+                return FileKind::Linear(endianness);
+            }
+        }
+    }
+
+    if is_compressed_block::<LittleEndian>(data, len) {
+        return FileKind::CompressedLinear(Endianness::Little);
+    }
+    if is_compressed_block::<BigEndian>(data, len) {
+        return FileKind::CompressedLinear(Endianness::Big);
+    }
+
+    FileKind::Unknown
+}
+
+fn is_tag<E: ByteOrder>(data: &[u8], tag: u32) -> bool {
+    data.len() >= 4 && E::read_u32(data) == tag
+}
+
+/// Length of a linear file's header before its file table tag: a `u32` followed by the
+/// file's name. `None` if `data` doesn't start with one.
+fn linear_header_len(data: &[u8]) -> Option<usize> {
+    let mut rest = data.get(4..)?;
+    let name_len = usize::try_from(read_packed_int(&mut rest).ok()?).ok()?;
+    let name = rest.get(..name_len)?;
+
+    // Names are null terminated and printable
+    let (0, text) = name.split_last()? else {
+        return None;
+    };
+    if !text.iter().all(|c| c.is_ascii_graphic() || *c == b' ') {
+        return None;
+    }
+
+    Some(data.len() - rest.len() + name_len)
+}
+
+/// Whether `data` starts with a compressed block: its uncompressed and compressed sizes,
+/// then zlib data which fits in the file.
+fn is_compressed_block<E: ByteOrder>(data: &[u8], len: u64) -> bool {
+    let Some(header) = data.get(..10) else {
+        return false;
+    };
+
+    let uncompressed_len = E::read_u32(header);
+    let compressed_len = E::read_u32(&header[4..]) as u64;
+    let (cmf, flg) = (header[8], header[9]);
+
+    // Deflate with a window of at most 32K, and a header checksum
+    let is_zlib = cmf & 0x0F == 8 && cmf >> 4 <= 7 && u16::from_be_bytes([cmf, flg]) % 31 == 0;
+
+    is_zlib && uncompressed_len > 0 && compressed_len > 0 && compressed_len <= len - 8
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use byteorder::WriteBytesExt;
+    use flate2::{Compression, write::ZlibEncoder};
+
+    use super::*;
+    use crate::ser::write_var_string;
+
+    #[test]
+    fn detects_file_kinds() {
+        assert_eq!(endianness_of::<LittleEndian>(), Endianness::Little);
+        assert_eq!(endianness_of::<BigEndian>(), Endianness::Big);
+
+        let detect_data = |data: &[u8]| detect(&mut Cursor::new(data)).unwrap();
+
+        assert_eq!(
+            detect_data(&PKG_TAG.to_le_bytes()),
+            FileKind::Package(Endianness::Little)
+        );
+        assert_eq!(
+            detect_data(&PKG_TAG.to_be_bytes()),
+            FileKind::Package(Endianness::Big)
+        );
+
+        let mut linear = Vec::new();
+        linear.write_u32::<BigEndian>(1).unwrap();
+        write_var_string(&mut linear, "Maps\\common.lin").unwrap();
+        linear.write_u32::<BigEndian>(LIN_FILE_TABLE_TAG).unwrap();
+        assert_eq!(detect_data(&linear), FileKind::Linear(Endianness::Big));
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0; 64]).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut block = Vec::new();
+        block.write_u32::<LittleEndian>(64).unwrap();
+        block
+            .write_u32::<LittleEndian>(compressed.len() as u32)
+            .unwrap();
+        block.extend(&compressed);
+        assert_eq!(
+            detect_data(&block),
+            FileKind::CompressedLinear(Endianness::Little)
+        );
+
+        // A block claiming more data than the file holds
+        block.truncate(block.len() - 1);
+        assert_eq!(detect_data(&block), FileKind::Unknown);
+        assert_eq!(detect_data(b"not a package"), FileKind::Unknown);
+        assert_eq!(detect_data(&[]), FileKind::Unknown);
+    }
+
+    #[test]
+    fn restores_reader_position() {
+        let mut reader = Cursor::new([0, 0, 0xC1, 0x83, 0x2A, 0x9E]);
+        reader.set_position(2);
+
+        assert_eq!(
+            detect(&mut reader).unwrap(),
+            FileKind::Package(Endianness::Little)
+        );
+        assert_eq!(reader.position(), 2);
+    }
+}
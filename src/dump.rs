@@ -0,0 +1,365 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, Cursor},
+    path::Path,
+};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::Serialize;
+
+use crate::{
+    common::ExportedData,
+    de::{LinearFileDecoder, read_linear_file},
+    detect::{FileKind, detect},
+    headers::{ClassHeader, FunctionDecl, VariableDecl, declared_within_and_config},
+    object::{
+        RcUnrealObject, TypedObject, UObjectKind, UnrealObject,
+        builtins::{
+            ByteProperty, Class, ClassProperty, DelegateProperty, Field, FixedArrayProperty,
+            Function, MapProperty, ObjectProperty, Property, PropertyFlags, StructProperty,
+        },
+    },
+    package::Package,
+    profile::{Endianness, FormatProfile},
+};
+
+/// An owned summary of a class, as returned by [`load_class`]. It doesn't borrow from the
+/// package or runtime it was loaded from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassDump {
+    /// Name of the package exporting the class
+    pub package: String,
+    /// The class's declaration, recovered from its compiled fields
+    pub header: ClassHeader,
+}
+
+impl ClassDump {
+    /// The class's full name, e.g. `Engine.Actor`.
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.package, self.header.name)
+    }
+}
+
+impl fmt::Display for ClassDump {
+    /// Writes the class as an UnrealScript declaration stub.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.header.fmt(f)
+    }
+}
+
+/// Loads the class named `full_name` (e.g. `Echelon.EPlayerController`) from the file at
+/// `path` and summarizes it.
+///
+/// The file may be a linear file, compressed or not, or a standalone package. Its kind and
+/// byte order are detected from its leading bytes. Linear files are read with the
+/// [`FormatProfile::splinter_cell`] profile; see [`load_classes_with_profile`] for others.
+pub fn load_class(path: &Path, full_name: &str) -> io::Result<ClassDump> {
+    let mut classes = load_classes(path, &[full_name])?;
+
+    Ok(classes.pop().expect("one class was requested"))
+}
+
+/// Like [`load_class`], but loads several classes from the same file. The classes are
+/// returned in the order they're named.
+pub fn load_classes(path: &Path, full_names: &[&str]) -> io::Result<Vec<ClassDump>> {
+    load_classes_inner(path, full_names, None)
+}
+
+/// Like [`load_classes`], but reads the file with `profile` instead of the default for its
+/// kind.
+pub fn load_classes_with_profile(
+    path: &Path,
+    full_names: &[&str],
+    profile: &FormatProfile,
+) -> io::Result<Vec<ClassDump>> {
+    load_classes_inner(path, full_names, Some(profile))
+}
+
+fn load_classes_inner(
+    path: &Path,
+    full_names: &[&str],
+    profile: Option<&FormatProfile>,
+) -> io::Result<Vec<ClassDump>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let kind = detect(&mut reader)?;
+
+    match kind {
+        FileKind::Package(Endianness::Little) => {
+            package_classes::<LittleEndian>(path, reader, full_names, profile)
+        }
+        FileKind::Package(Endianness::Big) => {
+            package_classes::<BigEndian>(path, reader, full_names, profile)
+        }
+        FileKind::Linear(Endianness::Little) | FileKind::CompressedLinear(Endianness::Little) => {
+            linear_file_classes::<LittleEndian>(reader, full_names, profile)
+        }
+        FileKind::Linear(Endianness::Big) | FileKind::CompressedLinear(Endianness::Big) => {
+            linear_file_classes::<BigEndian>(reader, full_names, profile)
+        }
+        FileKind::Unknown => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a package or linear file", path.display()),
+        )),
+    }
+}
+
+/// Recovers the requested classes from a standalone package, whose name is taken from its
+/// file name.
+fn package_classes<E: ByteOrder>(
+    path: &Path,
+    reader: BufReader<File>,
+    full_names: &[&str],
+    profile: Option<&FormatProfile>,
+) -> io::Result<Vec<ClassDump>> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut package = match profile {
+        Some(profile) => Package::<E, _>::open_with_profile(name.clone(), reader, profile.clone())?,
+        None => Package::<E, _>::open(name.clone(), reader)?,
+    };
+
+    full_names
+        .iter()
+        .map(|full_name| {
+            let header = full_name
+                .split_once('.')
+                .filter(|(package_name, _)| package_name.eq_ignore_ascii_case(&name))
+                .map(|(_, path)| package.class_header(path))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| not_found(full_name))?;
+
+            Ok(ClassDump {
+                package: name.clone(),
+                header,
+            })
+        })
+        .collect()
+}
+
+/// Streams the requested classes out of a linear file. The load order is derived from the
+/// packages' tables, since there's no recorded trace to follow.
+fn linear_file_classes<E: ByteOrder>(
+    mut reader: BufReader<File>,
+    full_names: &[&str],
+    profile: Option<&FormatProfile>,
+) -> io::Result<Vec<ClassDump>> {
+    let lin_profile = profile
+        .cloned()
+        .unwrap_or_else(FormatProfile::splinter_cell);
+    let data = read_linear_file::<E, _>(&mut reader, &lin_profile)?;
+
+    let mut decoder =
+        LinearFileDecoder::<E, _>::new(vec![Cursor::new(data)], ExportedData::default());
+    if let Some(profile) = profile {
+        decoder.set_format_profile(profile.clone());
+    }
+    decoder.load_objects(full_names)?;
+
+    full_names
+        .iter()
+        .map(|full_name| {
+            let class = decoder
+                .get_class(full_name)
+                .ok_or_else(|| not_found(full_name))?;
+            let package = full_name
+                .split_once('.')
+                .map(|(package, _)| package.to_owned())
+                .unwrap_or_default();
+
+            Ok(ClassDump {
+                package,
+                header: loaded_class_header(&class),
+            })
+        })
+        .collect()
+}
+
+fn not_found(full_name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{full_name} is not a class in the file"),
+    )
+}
+
+/// Builds the declaration of a class which has been loaded by the runtime.
+fn loaded_class_header(class: &TypedObject<Class>) -> ClassHeader {
+    let object = class.object().borrow();
+    let super_class = object
+        .parent_of_kind(UObjectKind::Field)
+        .and_then(|field| field.as_any().downcast_ref::<Field>())
+        .and_then(Field::super_field)
+        .map(|super_class| super_class.borrow().base_object().name().to_owned());
+    let class = class.borrow();
+    let (within, config_name) = declared_within_and_config(&class);
+
+    ClassHeader {
+        name: object.base_object().name().to_owned(),
+        super_class,
+        within,
+        config_name,
+        variables: class
+            .properties()
+            .map(|property| loaded_variable_decl(&property))
+            .collect(),
+        functions: class
+            .functions()
+            .map(|function| loaded_function_decl(&function))
+            .collect(),
+    }
+}
+
+fn loaded_variable_decl(property: &TypedObject<Property>) -> VariableDecl {
+    let info = property.borrow().info();
+
+    VariableDecl {
+        name: info.name,
+        type_name: loaded_property_type(&*property.object().borrow()),
+        category: info.category,
+        flags: info.flags,
+        array_dim: info.array_dim,
+    }
+}
+
+fn loaded_function_decl(function: &TypedObject<Function>) -> FunctionDecl {
+    let name = function.object().borrow().base_object().name().to_owned();
+    let function = function.borrow();
+
+    let mut decl = FunctionDecl {
+        name,
+        flags: function.function_flags,
+        native_index: function.inative(),
+        return_type: None,
+        params: Vec::new(),
+    };
+    for param in function.parameters() {
+        let variable = loaded_variable_decl(&param);
+        if variable.flags.contains(PropertyFlags::RETURN_PARM) {
+            decl.return_type = Some(variable.type_name);
+        } else {
+            decl.params.push(variable);
+        }
+    }
+
+    decl
+}
+
+/// The UnrealScript type of a loaded property. Types naming objects which weren't loaded
+/// fall back to their base type.
+fn loaded_property_type(property: &dyn UnrealObject) -> String {
+    let any = property.as_any();
+    let name_of = |object: &RcUnrealObject| object.borrow().base_object().name().to_owned();
+
+    match property.kind() {
+        UObjectKind::ByteProperty => any
+            .downcast_ref::<ByteProperty>()
+            .and_then(|property| property.eenum.as_ref())
+            .map(name_of)
+            .unwrap_or_else(|| "byte".to_owned()),
+        UObjectKind::IntProperty => "int".to_owned(),
+        UObjectKind::BoolProperty => "bool".to_owned(),
+        UObjectKind::FloatProperty => "float".to_owned(),
+        UObjectKind::NameProperty => "name".to_owned(),
+        UObjectKind::StrProperty => "string".to_owned(),
+        UObjectKind::PointerProperty => "pointer".to_owned(),
+        UObjectKind::ObjectProperty => any
+            .downcast_ref::<ObjectProperty>()
+            .and_then(|property| property.property_class.object_name())
+            .unwrap_or_else(|| "Object".to_owned()),
+        UObjectKind::ClassProperty => format!(
+            "class<{}>",
+            any.downcast_ref::<ClassProperty>()
+                .and_then(|property| property.meta_class.object_name())
+                .unwrap_or_else(|| "Object".to_owned())
+        ),
+        UObjectKind::StructProperty => any
+            .downcast_ref::<StructProperty>()
+            .and_then(|property| property.struct_obj.as_ref())
+            .map(name_of)
+            .unwrap_or_else(|| "struct".to_owned()),
+        UObjectKind::DelegateProperty => format!(
+            "delegate<{}>",
+            any.downcast_ref::<DelegateProperty>()
+                .and_then(|property| property.function.object_name())
+                .unwrap_or_else(|| "None".to_owned())
+        ),
+        UObjectKind::MapProperty => {
+            let (key, value) = any
+                .downcast_ref::<MapProperty>()
+                .map(|property| (property.key.as_ref(), property.value.as_ref()))
+                .unwrap_or_default();
+            let inner = |inner: Option<&RcUnrealObject>| {
+                inner
+                    .map(|inner| loaded_property_type(&*inner.borrow()))
+                    .unwrap_or_else(|| "Object".to_owned())
+            };
+
+            format!("map<{}, {}>", inner(key), inner(value))
+        }
+        UObjectKind::FixedArrayProperty => {
+            let (inner, count) = any
+                .downcast_ref::<FixedArrayProperty>()
+                .map(|property| (property.inner.as_ref(), property.count))
+                .unwrap_or_default();
+            let inner = inner
+                .map(|inner| loaded_property_type(&*inner.borrow()))
+                .unwrap_or_else(|| "Object".to_owned());
+
+            format!("{inner}[{count}]")
+        }
+        kind => kind.as_str().to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{DataBuilder, PackageBuilder};
+
+    #[test]
+    fn loads_a_class_from_a_package_file() {
+        let mut builder = PackageBuilder::new();
+        let class = builder.core_class("Class");
+        let int_property = builder.core_class("IntProperty");
+        let class_name = builder.name("MyActor");
+
+        let data = DataBuilder::default()
+            // super_field, next
+            .packed(0)
+            .packed(0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(2)
+            .packed(class_name)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // probe_mask, ignore_mask, label_table_offset, state_flags
+            .u64(0)
+            .u64(u64::MAX)
+            .u16(0xFFFF)
+            .u32(0)
+            .ue2_class_declaration(0, 0, 0)
+            .build();
+        let my_actor = builder.export(class, 0, "MyActor", data);
+        let data = DataBuilder::ue2_property(0, 0, 0).build();
+        builder.export(int_property, my_actor, "Count", data);
+
+        let dir = std::env::temp_dir().join(format!("unrealin-dump-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Test.u");
+        std::fs::write(&path, builder.build()).unwrap();
+
+        let class = load_class(&path, "test.MyActor");
+        let missing = load_class(&path, "Other.MyActor");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let class = class.unwrap();
+        assert_eq!(class.full_name(), "Test.MyActor");
+        assert_eq!(class.to_string(), "class MyActor;\n\nvar int Count;\n");
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
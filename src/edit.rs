@@ -0,0 +1,1503 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io::{self, Read, Seek, Write},
+    ops::Range,
+    rc::Rc,
+};
+
+use byteorder::ByteOrder;
+use tracing::{Level, debug, span};
+
+use crate::{
+    common::normalize_index,
+    de::{GenerationInfo, Import, Linker, Name, ObjectExport},
+    object::{
+        ObjectFlags,
+        internal::{
+            fname::FName,
+            script::{self, Expr, ScriptIndices, Statement},
+        },
+    },
+    package::Package,
+    packed::{read_packed_int, write_packed_int},
+    ser::{LayoutMode, serialize_unreal_package},
+};
+
+/// A new script for [`PackageEditor::replace_function_script`], either as expressions or
+/// already serialized.
+#[derive(Debug, Clone)]
+pub enum ScriptPatch {
+    Exprs(Vec<Expr>),
+    Bytes(Vec<u8>),
+}
+
+impl From<Vec<Expr>> for ScriptPatch {
+    fn from(exprs: Vec<Expr>) -> Self {
+        ScriptPatch::Exprs(exprs)
+    }
+}
+
+impl From<Vec<u8>> for ScriptPatch {
+    fn from(bytes: Vec<u8>) -> Self {
+        ScriptPatch::Bytes(bytes)
+    }
+}
+
+/// How [`PackageEditor::write`] records the save in the package's generations, which the
+/// engine compares against the counts in the header.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GenerationPolicy {
+    /// Replace the counts of the last generation with the saved package's
+    #[default]
+    UpdateLast,
+    /// Add a generation for the saved package, as the engine does each time it saves one
+    Append,
+    /// Keep the package's generations, only lowering counts past the end of the saved tables
+    Preserve,
+}
+
+/// Where a struct's script size and script are stored, relative to the start of its export's
+/// data.
+#[derive(Debug, Clone)]
+pub(crate) struct ScriptRanges {
+    pub(crate) script_size: Range<usize>,
+    pub(crate) script: Range<usize>,
+}
+
+/// Where an export's data refers to names and objects, relative to the start of the data.
+#[derive(Debug, Default)]
+pub(crate) struct DataReferences {
+    /// Single packed name indices
+    pub(crate) names: Vec<Range<usize>>,
+    /// Packed arrays of name indices, starting with their length
+    pub(crate) name_arrays: Vec<Range<usize>>,
+    /// Packed raw object indices
+    pub(crate) objects: Vec<Range<usize>>,
+    /// The export's script and its decoded statements, if it's a struct
+    pub(crate) script: Option<(Range<usize>, Vec<Statement>)>,
+}
+
+/// An import added with [`PackageEditor::add_import`]. Names are resolved against the edited
+/// name table when the package is written.
+struct NewImport {
+    class_package: String,
+    class_name: String,
+    /// Raw index of the import's outer, or 0 if it's a package
+    outer: i32,
+    object_name: String,
+    /// Full name, e.g. `Engine.Actor`
+    path: String,
+}
+
+/// An export added with [`PackageEditor::add_export`].
+struct NewExport {
+    class: i32,
+    /// Raw index of the export's outer, or 0 if it's at the top of the package
+    outer: i32,
+    /// Raw index of the struct the export inherits from, if it's a struct
+    super_index: i32,
+    object_name: String,
+    flags: ObjectFlags,
+    data: Vec<u8>,
+    /// Path within the package, e.g. `MyClass.MaxCount`
+    path: String,
+}
+
+/// An export being copied from another package by [`PackageEditor::merge_export`].
+struct MergedExport {
+    /// Index of the export in the package it's copied from
+    index: usize,
+    export: ObjectExport,
+    data: Vec<u8>,
+    references: DataReferences,
+}
+
+/// A replacement script and where it goes in its export's data.
+struct ScriptEdit {
+    ranges: ScriptRanges,
+    script: Vec<u8>,
+    /// Size of the script once loaded
+    memory_size: u32,
+}
+
+/// Flags given to appended names, matching the names the engine saves.
+const APPENDED_NAME_FLAGS: ObjectFlags = ObjectFlags::TAG_EXP
+    .union(ObjectFlags::LOAD_FOR_CLIENT)
+    .union(ObjectFlags::LOAD_FOR_SERVER)
+    .union(ObjectFlags::LOAD_FOR_EDIT);
+
+/// Changes to a package which are applied when it's written out. Created with
+/// [`Package::edit`].
+pub struct PackageEditor<'p, E, R> {
+    package: &'p mut Package<E, R>,
+    /// The edited name table. Each entry keeps the index it had in the package, or `None` if
+    /// it was appended.
+    names: Vec<(Option<i32>, Name)>,
+    /// Imports added after the package's own
+    imports: Vec<NewImport>,
+    /// Exports added after the package's own
+    exports: Vec<NewExport>,
+    /// Replaced scripts, keyed by export index
+    scripts: BTreeMap<usize, ScriptEdit>,
+    generation_policy: GenerationPolicy,
+}
+
+impl<'p, E, R> PackageEditor<'p, E, R>
+where
+    E: ByteOrder,
+    R: Read + Seek,
+{
+    pub(crate) fn new(package: &'p mut Package<E, R>) -> Self {
+        let names = package
+            .contents()
+            .linker
+            .borrow()
+            .package
+            .names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (Some(i as i32), name.clone()))
+            .collect();
+
+        PackageEditor {
+            package,
+            names,
+            imports: Vec::new(),
+            exports: Vec::new(),
+            scripts: BTreeMap::new(),
+            generation_policy: GenerationPolicy::default(),
+        }
+    }
+
+    /// Sets how the written package's generations are updated. Defaults to
+    /// [`GenerationPolicy::UpdateLast`].
+    pub fn set_generation_policy(&mut self, policy: GenerationPolicy) {
+        self.generation_policy = policy;
+    }
+
+    /// Index of `name` in the edited name table, ignoring ASCII case as the engine does.
+    pub fn find_name(&self, name: &str) -> Option<i32> {
+        self.names
+            .iter()
+            .position(|(_, existing)| existing.name.eq_ignore_ascii_case(name))
+            .map(|index| index as i32)
+    }
+
+    /// Adds `name` to the end of the name table and returns its index. If the table already
+    /// has the name, the existing index is returned instead.
+    pub fn append_name(&mut self, name: &str) -> i32 {
+        if let Some(index) = self.find_name(name) {
+            return index;
+        }
+
+        debug!("Appending name {name:?}");
+        self.names.push((
+            None,
+            Name {
+                name: Rc::from(name),
+                flags: APPENDED_NAME_FLAGS.bits(),
+            },
+        ));
+
+        self.names.len() as i32 - 1
+    }
+
+    /// Renames the name at `index`. The name keeps its index, so everything which refers to
+    /// it refers to the new name, e.g. renaming a class's name renames the class.
+    pub fn rename_name(&mut self, index: i32, name: &str) -> io::Result<()> {
+        let position = self.name_position(index)?;
+        // Names are looked up ignoring case, so a rename must stay distinct from the rest
+        if self
+            .find_name(name)
+            .is_some_and(|existing| existing as usize != position)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("renaming name {index} to {name:?} collides with an existing name"),
+            ));
+        }
+
+        debug!("Renaming name {index} to {name:?}");
+        self.names[position].1.name = Rc::from(name);
+
+        Ok(())
+    }
+
+    /// Removes the name at `index`. The names after it move down one index, and the tables
+    /// and export data which refer to them are rewritten to match when the package is
+    /// written.
+    ///
+    /// Rewriting export data requires reading each export, which isn't supported yet for
+    /// classes or objects with tagged properties. Writing fails if the package has any, or if
+    /// anything still refers to the removed name.
+    pub fn remove_name(&mut self, index: i32) -> io::Result<()> {
+        let position = self.name_position(index)?;
+        if position == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the None name can't be removed",
+            ));
+        }
+
+        debug!("Removing name {index}");
+        self.names.remove(position);
+
+        Ok(())
+    }
+
+    fn name_position(&self, index: i32) -> io::Result<usize> {
+        usize::try_from(index)
+            .ok()
+            .filter(|&index| index < self.names.len())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("name index {index} out of bounds"),
+                )
+            })
+    }
+
+    /// Adds an import of the object at `path`, e.g. `Engine.Actor.Spawn`, whose class is
+    /// `class_package.class_name`, and returns its raw object index. The index can be used in
+    /// export fields or as a script operand, such as the function of a final function call.
+    ///
+    /// Names are appended as needed. If the package already imports the object, its existing
+    /// index is returned. A missing outer package is imported too, but other outers must
+    /// already be imported, since their class isn't known.
+    pub fn add_import(
+        &mut self,
+        class_package: &str,
+        class_name: &str,
+        path: &str,
+    ) -> io::Result<i32> {
+        if let Some(index) = self.find_import(class_name, path) {
+            return Ok(index);
+        }
+
+        let (outer, object_name) = match path.rsplit_once('.') {
+            Some((outer_path, object_name)) => {
+                let outer = match self.find_import_path(outer_path) {
+                    Some(outer) => outer,
+                    None if !outer_path.contains('.') => {
+                        self.add_import("Core", "Package", outer_path)?
+                    }
+                    None => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("can't import {path}: {outer_path} isn't imported"),
+                        ));
+                    }
+                };
+
+                (outer, object_name)
+            }
+            None => (0, path),
+        };
+
+        for name in [class_package, class_name, object_name] {
+            self.append_name(name);
+        }
+
+        debug!("Importing {class_package}.{class_name} {path}");
+        self.imports.push(NewImport {
+            class_package: class_package.to_owned(),
+            class_name: class_name.to_owned(),
+            outer,
+            object_name: object_name.to_owned(),
+            path: path.to_owned(),
+        });
+
+        Ok(-(self.import_count() as i32))
+    }
+
+    fn import_count(&self) -> usize {
+        self.package
+            .contents()
+            .linker
+            .borrow()
+            .package
+            .imports
+            .len()
+            + self.imports.len()
+    }
+
+    /// Raw index of the import at `path` with the class `class_name`.
+    fn find_import(&self, class_name: &str, path: &str) -> Option<i32> {
+        self.imports_by_path(path)
+            .into_iter()
+            .find(|(_, class)| class.eq_ignore_ascii_case(class_name))
+            .map(|(index, _)| index)
+    }
+
+    /// Raw index of the first import at `path`, whatever its class.
+    fn find_import_path(&self, path: &str) -> Option<i32> {
+        self.imports_by_path(path).first().map(|(index, _)| *index)
+    }
+
+    /// Raw indices and class names of the imports at `path`, ignoring ASCII case.
+    fn imports_by_path(&self, path: &str) -> Vec<(i32, String)> {
+        let linker = self.package.contents().linker;
+        let linker = linker.borrow();
+
+        let existing = linker.package.imports.iter().map(|import| {
+            (
+                import.full_name(&linker),
+                import.class_name(&linker).to_owned(),
+            )
+        });
+        let added = self
+            .imports
+            .iter()
+            .map(|import| (import.path.clone(), import.class_name.clone()));
+
+        existing
+            .chain(added)
+            .enumerate()
+            .filter(|(_, (import_path, _))| import_path.eq_ignore_ascii_case(path))
+            .map(|(i, (_, class_name))| (-(i as i32) - 1, class_name))
+            .collect()
+    }
+
+    /// Adds a new export named `name` whose class is the raw object index `class` and returns
+    /// its raw index. `outer` is the raw index of the export it's contained in, or 0 to add it
+    /// to the top of the package.
+    ///
+    /// `data` is written out as the export's serialized data, so names within it must be
+    /// indices into the edited name table and object references must be raw indices, such as
+    /// those returned by [`add_import`](Self::add_import).
+    pub fn add_export(
+        &mut self,
+        class: i32,
+        outer: i32,
+        name: &str,
+        flags: ObjectFlags,
+        data: Vec<u8>,
+    ) -> io::Result<i32> {
+        let export_count = self.export_count();
+        let import_count = self.import_count();
+        if class > export_count as i32 || class < -(import_count as i32) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("class index {class} out of bounds"),
+            ));
+        }
+
+        let path = match outer {
+            0 => name.to_owned(),
+            1.. if outer as usize <= export_count => {
+                format!("{}.{name}", self.export_path(outer as usize - 1))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("outer {outer} isn't an export"),
+                ));
+            }
+        };
+
+        let exists = self
+            .package
+            .contents()
+            .linker
+            .borrow()
+            .find_export_by_path(&path)
+            .is_some()
+            || self
+                .exports
+                .iter()
+                .any(|export| export.path.eq_ignore_ascii_case(&path));
+        if exists {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the package already has an export named {path}"),
+            ));
+        }
+
+        self.append_name(name);
+
+        debug!("Adding export {path}: {:#X} bytes", data.len());
+        self.exports.push(NewExport {
+            class,
+            outer,
+            super_index: 0,
+            object_name: name.to_owned(),
+            flags,
+            data,
+            path,
+        });
+
+        Ok(self.export_count() as i32)
+    }
+
+    fn export_count(&self) -> usize {
+        self.package
+            .contents()
+            .linker
+            .borrow()
+            .package
+            .exports
+            .len()
+            + self.exports.len()
+    }
+
+    /// Path within the package of the export at `index`, whether it's the package's own or
+    /// added.
+    fn export_path(&self, index: usize) -> String {
+        let linker = self.package.contents().linker;
+        let linker = linker.borrow();
+
+        match linker.package.exports.get(index) {
+            Some(export) => {
+                let full_name = export.full_name(&linker);
+                // Drop the package's name
+                match full_name.split_once('.') {
+                    Some((_, path)) => path.to_owned(),
+                    None => full_name,
+                }
+            }
+            None => self.exports[index - linker.package.exports.len()]
+                .path
+                .clone(),
+        }
+    }
+
+    /// Raw index of the export at `path`, whether it's the package's own or added.
+    fn find_export_path(&self, path: &str) -> Option<i32> {
+        let existing = self
+            .package
+            .contents()
+            .linker
+            .borrow()
+            .find_export_by_path(path)
+            .map(|(index, _)| index.index() as i32 + 1);
+
+        existing.or_else(|| {
+            let existing_count = self.export_count() - self.exports.len();
+            self.exports
+                .iter()
+                .position(|export| export.path.eq_ignore_ascii_case(path))
+                .map(|index| (existing_count + index) as i32 + 1)
+        })
+    }
+
+    /// Copies the export at `path` (e.g. `MyFunction`) in `source` into this package and
+    /// returns its raw index here.
+    ///
+    /// Every export it refers to, whether as its class, outer, or super, from its data, or
+    /// from its script, is copied along with it unless this package already has an export
+    /// at the same path, in which case references are pointed at that instead. Names and
+    /// imports are appended as needed, and every index in the copied data is rewritten to
+    /// refer to this package's tables.
+    ///
+    /// Like [`remove_name`](Self::remove_name), this requires reading each copied export,
+    /// so classes and objects with tagged properties can't be merged yet.
+    pub fn merge_export<S: Read + Seek>(
+        &mut self,
+        source: &mut Package<E, S>,
+        path: &str,
+    ) -> io::Result<i32> {
+        let span = span!(Level::DEBUG, "merge_export");
+        let _enter = span.enter();
+
+        let source_linker = source.contents().linker;
+        let source_path = |index: usize| {
+            let linker = source_linker.borrow();
+            let full_name = linker.package.exports[index].full_name(&linker);
+            // Drop the package's name
+            match full_name.split_once('.') {
+                Some((_, path)) => path.to_owned(),
+                None => full_name,
+            }
+        };
+
+        let root = source_linker
+            .borrow()
+            .find_export_by_path(path)
+            .map(|(index, _)| index.index());
+        let Some(root) = root else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no export named {path}"),
+            ));
+        };
+        if self.find_export_path(&source_path(root)).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("the package already has an export named {path}"),
+            ));
+        }
+
+        // New raw index of each object the copied exports refer to, keyed by its raw index in
+        // `source`
+        let mut objects = HashMap::from([(0, 0)]);
+        // Source names and imports the copied exports refer to
+        let mut source_names = BTreeSet::new();
+        let mut source_imports = BTreeSet::new();
+
+        let mut copies = Vec::new();
+        let mut seen = HashSet::from([root]);
+        let mut pending = vec![root];
+        while let Some(index) = pending.pop() {
+            let export = source_linker.borrow().package.exports[index].clone();
+            let (data, references) = if export.serial_size() == 0 {
+                (Vec::new(), DataReferences::default())
+            } else {
+                (source.export_data(index)?, source.data_references(index)?)
+            };
+
+            let mut referenced = vec![export.class_index, export.super_index, export.package_index];
+            source_names.insert(export.object_name);
+            for range in &references.objects {
+                referenced.push(read_packed_int(&mut &data[range.clone()])?);
+            }
+            for range in &references.names {
+                source_names.insert(read_packed_int(&mut &data[range.clone()])?);
+            }
+            for range in &references.name_arrays {
+                let mut array = &data[range.clone()];
+                for _ in 0..read_packed_int(&mut array)? {
+                    source_names.insert(read_packed_int(&mut array)?);
+                }
+            }
+            if let Some((_, statements)) = &references.script {
+                if statements
+                    .iter()
+                    .any(|statement| matches!(statement.expr, Expr::Unknown { .. }))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!(
+                            "the script of {} isn't fully decoded, so it can't be merged",
+                            source_path(index)
+                        ),
+                    ));
+                }
+
+                // Encoding the script visits every name and object it refers to
+                let script_names = RefCell::new(Vec::new());
+                let script_objects = RefCell::new(Vec::new());
+                script::serialize_exprs_remapped::<E, _>(
+                    &mut io::sink(),
+                    statements.iter().map(|statement| &statement.expr),
+                    ScriptIndices {
+                        name: &|name: FName| {
+                            script_names.borrow_mut().push(name.index());
+                            Ok(name.index())
+                        },
+                        object: &|index| {
+                            script_objects.borrow_mut().push(index);
+                            Ok(index)
+                        },
+                    },
+                )?;
+                source_names.extend(script_names.into_inner());
+                referenced.extend(script_objects.into_inner());
+            }
+
+            for object in referenced {
+                if object < 0 {
+                    source_imports.insert(object);
+                } else if object > 0 && seen.insert(normalize_index(object)) {
+                    let dependency = normalize_index(object);
+                    if dependency >= source_linker.borrow().package.exports.len() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "{} refers to object {object}, which is out of bounds",
+                                source_path(index)
+                            ),
+                        ));
+                    }
+
+                    match self.find_export_path(&source_path(dependency)) {
+                        Some(existing) => {
+                            objects.insert(object, existing);
+                        }
+                        None => pending.push(dependency),
+                    }
+                }
+            }
+
+            copies.push(MergedExport {
+                index,
+                export,
+                data,
+                references,
+            });
+        }
+
+        // Each export's class and outer must be added before it
+        let mut ordered = Vec::with_capacity(copies.len());
+        while !copies.is_empty() {
+            let waiting = copies
+                .iter()
+                .map(|copy| copy.index as i32 + 1)
+                .collect::<HashSet<_>>();
+            let before = copies.len();
+            let (ready, rest) = copies.into_iter().partition::<Vec<_>, _>(|copy| {
+                !waiting.contains(&copy.export.class_index)
+                    && !waiting.contains(&copy.export.package_index)
+            });
+            ordered.extend(ready);
+            copies = rest;
+
+            if copies.len() == before {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("exports depending on {path} contain each other"),
+                ));
+            }
+        }
+
+        let first_index = self.export_count() as i32 + 1;
+        for (i, copy) in ordered.iter().enumerate() {
+            objects.insert(copy.index as i32 + 1, first_index + i as i32);
+        }
+
+        let mut names = HashMap::new();
+        {
+            let linker = source_linker.borrow();
+            for import in source_imports {
+                let index = self.merge_import(&linker, import)?;
+                objects.insert(import, index);
+            }
+            for index in source_names {
+                let Some(name) = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| linker.package.names.get(index))
+                else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("name index {index} out of bounds"),
+                    ));
+                };
+                names.insert(index, self.append_name(&name.name));
+            }
+        }
+
+        let name = |index: i32| {
+            names.get(&index).copied().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("name index {index} wasn't merged"),
+                )
+            })
+        };
+        let object = |index: i32| {
+            objects.get(&index).copied().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("object index {index} wasn't merged"),
+                )
+            })
+        };
+
+        for copy in ordered {
+            let MergedExport {
+                index,
+                export,
+                mut data,
+                references,
+            } = copy;
+
+            let mut splices = Vec::new();
+            for range in references.names {
+                let mut encoded = Vec::new();
+                write_packed_int(
+                    &mut encoded,
+                    name(read_packed_int(&mut &data[range.clone()])?)?,
+                )?;
+                splices.push((range, encoded));
+            }
+            for range in references.objects {
+                let mut encoded = Vec::new();
+                write_packed_int(
+                    &mut encoded,
+                    object(read_packed_int(&mut &data[range.clone()])?)?,
+                )?;
+                splices.push((range, encoded));
+            }
+            for range in references.name_arrays {
+                let mut array = &data[range.clone()];
+                let len = read_packed_int(&mut array)?;
+
+                let mut encoded = Vec::new();
+                write_packed_int(&mut encoded, len)?;
+                for _ in 0..len {
+                    write_packed_int(&mut encoded, name(read_packed_int(&mut array)?)?)?;
+                }
+                splices.push((range, encoded));
+            }
+            if let Some((range, statements)) = references.script {
+                let mut encoded = Vec::new();
+                script::serialize_exprs_remapped::<E, _>(
+                    &mut encoded,
+                    statements.iter().map(|statement| &statement.expr),
+                    ScriptIndices {
+                        name: &|index: FName| name(index.index()),
+                        object: &object,
+                    },
+                )?;
+                splices.push((range, encoded));
+            }
+
+            // Later ranges are replaced first so that earlier ones stay put
+            splices.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+            for (range, encoded) in splices {
+                data.splice(range, encoded);
+            }
+
+            let object_name = source_linker.borrow().name(export.object_name);
+            let new_index = self.add_export(
+                object(export.class_index)?,
+                object(export.package_index)?,
+                &object_name,
+                ObjectFlags::from_bits_retain(export.object_flags),
+                data,
+            )?;
+            debug_assert_eq!(Some(&new_index), objects.get(&(index as i32 + 1)));
+
+            self.exports
+                .last_mut()
+                .expect("export was just added")
+                .super_index = object(export.super_index)?;
+        }
+
+        object(root as i32 + 1)
+    }
+
+    /// Imports the object `source` imports at `raw_index`, along with its outers, and returns
+    /// its raw index in this package.
+    fn merge_import(&mut self, source: &Linker, raw_index: i32) -> io::Result<i32> {
+        let Some(import) = source.package.imports.get(normalize_index(raw_index)) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("import index {raw_index} out of bounds"),
+            ));
+        };
+        match import.package_index {
+            0 => {}
+            outer @ ..0 => {
+                self.merge_import(source, outer)?;
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "{} is imported from within an export",
+                        import.full_name(source)
+                    ),
+                ));
+            }
+        }
+
+        self.add_import(
+            &source.name(import.class_package),
+            import.class_name(source),
+            &import.full_name(source),
+        )
+    }
+
+    /// Replaces the body of the function at `path` (e.g. `MyClass.Tick`) with `script`.
+    ///
+    /// The function's script size is updated to match, and the export's size and the offsets
+    /// of the exports after it are fixed up when the package is written. Code offsets within
+    /// `script`, such as jump targets, must already be correct for the new script, and names
+    /// must be indices into the edited name table.
+    pub fn replace_function_script(
+        &mut self,
+        path: &str,
+        script: impl Into<ScriptPatch>,
+    ) -> io::Result<()> {
+        let span = span!(Level::DEBUG, "replace_function_script");
+        let _enter = span.enter();
+
+        let index = self
+            .package
+            .contents()
+            .linker
+            .borrow()
+            .find_export_by_path(path)
+            .map(|(index, _)| index.index());
+        let Some(index) = index else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no export named {path}"),
+            ));
+        };
+
+        let script = match script.into() {
+            ScriptPatch::Exprs(exprs) => {
+                let mut bytes = Vec::new();
+                script::serialize_exprs::<E, _>(&mut bytes, &exprs)?;
+                bytes
+            }
+            ScriptPatch::Bytes(bytes) => bytes,
+        };
+
+        let ranges = self.package.function_script_ranges(index)?;
+        let memory_size = self.package.script_memory_size(&script)?;
+        debug!(
+            "Replacing script of {path}: {:#X} serialized bytes, {memory_size:#X} in memory",
+            script.len()
+        );
+
+        self.scripts.insert(
+            index,
+            ScriptEdit {
+                ranges,
+                script,
+                memory_size,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Writes the package with every edit applied.
+    pub fn write<W: Write>(self, writer: W) -> io::Result<()> {
+        let span = span!(Level::DEBUG, "write_edited_package");
+        let _enter = span.enter();
+
+        let mut package = self.package.contents().linker.borrow().package.clone();
+
+        // New index of each of the package's names, or `None` if it was removed
+        let mut name_map = vec![None; package.names.len()];
+        for (new_index, (old_index, _)) in self.names.iter().enumerate() {
+            if let Some(old_index) = old_index {
+                name_map[*old_index as usize] = Some(new_index as i32);
+            }
+        }
+        let names_moved = name_map
+            .iter()
+            .enumerate()
+            .any(|(i, new_index)| *new_index != Some(i as i32));
+        let remap = |index: i32, user: &dyn Fn() -> String| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| name_map.get(index).copied().flatten())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} refers to removed name {index}", user()),
+                    )
+                })
+        };
+
+        if names_moved {
+            for (i, import) in package.imports.iter_mut().enumerate() {
+                let user = || format!("import {i}");
+                import.class_package = remap(import.class_package, &user)?;
+                import.class_name = remap(import.class_name, &user)?;
+                import.object_name = remap(import.object_name, &user)?;
+            }
+            for (i, export) in package.exports.iter_mut().enumerate() {
+                export.object_name = remap(export.object_name, &|| format!("export {i}"))?;
+            }
+        }
+
+        for import in &self.imports {
+            let name = |name: &str| {
+                self.find_name(name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("import of {} refers to removed name {name:?}", import.path),
+                    )
+                })
+            };
+
+            package.imports.push(Import {
+                class_package: name(&import.class_package)?,
+                class_name: name(&import.class_name)?,
+                package_index: import.outer,
+                object_name: name(&import.object_name)?,
+            });
+        }
+
+        let existing_exports = package.exports.len();
+        for export in &self.exports {
+            let object_name = self.find_name(&export.object_name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("export {} refers to a removed name", export.path),
+                )
+            })?;
+
+            package.exports.push(ObjectExport {
+                class_index: export.class,
+                super_index: export.super_index,
+                package_index: export.outer,
+                object_name,
+                object_flags: export.flags.bits(),
+                // Laid out by the serializer
+                serial_size: 0,
+                serial_offset: 0,
+            });
+        }
+
+        package.names = self.names.iter().map(|(_, name)| name.clone()).collect();
+        let header = &mut package.header;
+        header.import_count = package.imports.len() as u32;
+        header.export_count = package.exports.len() as u32;
+        header.name_count = package.names.len() as u32;
+        for generation in &mut header.generations {
+            generation.export_count = generation.export_count.min(header.export_count);
+            generation.name_count = generation.name_count.min(header.name_count);
+        }
+        let saved = GenerationInfo {
+            export_count: header.export_count,
+            name_count: header.name_count,
+        };
+        match (self.generation_policy, header.generations.last_mut()) {
+            (GenerationPolicy::UpdateLast, Some(generation)) => *generation = saved,
+            (GenerationPolicy::UpdateLast, None) | (GenerationPolicy::Append, _) => {
+                header.generations.push(saved)
+            }
+            (GenerationPolicy::Preserve, _) => {}
+        }
+
+        let mut export_data = Vec::with_capacity(package.exports.len());
+        for (i, export) in package.exports[..existing_exports].iter().enumerate() {
+            if export.serial_size() == 0 {
+                export_data.push(Vec::new());
+                continue;
+            }
+
+            let mut data = self.package.export_data(i)?;
+
+            // Each replaced range paired with its new contents
+            let mut splices = Vec::new();
+            if let Some(edit) = self.scripts.get(&i) {
+                let mut memory_size = vec![0; 4];
+                E::write_u32(&mut memory_size, edit.memory_size);
+
+                splices.push((edit.ranges.script_size.clone(), memory_size));
+                splices.push((edit.ranges.script.clone(), edit.script.clone()));
+            }
+
+            if names_moved {
+                let user = || format!("export {i}");
+                let references = self.package.data_references(i)?;
+
+                for range in references.names {
+                    let index = read_packed_int(&mut &data[range.clone()])?;
+
+                    let mut encoded = Vec::new();
+                    write_packed_int(&mut encoded, remap(index, &user)?)?;
+                    splices.push((range, encoded));
+                }
+
+                for range in references.name_arrays {
+                    let mut array = &data[range.clone()];
+                    let len = read_packed_int(&mut array)?;
+
+                    let mut encoded = Vec::new();
+                    write_packed_int(&mut encoded, len)?;
+                    for _ in 0..len {
+                        write_packed_int(
+                            &mut encoded,
+                            remap(read_packed_int(&mut array)?, &user)?,
+                        )?;
+                    }
+                    splices.push((range, encoded));
+                }
+
+                // A replaced script already uses the edited name table
+                if let Some((range, statements)) = references.script
+                    && !self.scripts.contains_key(&i)
+                {
+                    if statements
+                        .iter()
+                        .any(|statement| matches!(statement.expr, Expr::Unknown { .. }))
+                    {
+                        return Err(io::Error::new(
+                            io::ErrorKind::Unsupported,
+                            format!(
+                                "the script of export {i} isn't fully decoded, so its names can't be rewritten"
+                            ),
+                        ));
+                    }
+
+                    let mut encoded = Vec::new();
+                    script::serialize_exprs_remapped::<E, _>(
+                        &mut encoded,
+                        statements.iter().map(|statement| &statement.expr),
+                        ScriptIndices {
+                            name: &|name: FName| remap(name.index(), &user),
+                            object: &|index| Ok(index),
+                        },
+                    )?;
+                    splices.push((range, encoded));
+                }
+            }
+
+            // Later ranges are replaced first so that earlier ones stay put
+            splices.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+            for (range, encoded) in splices {
+                data.splice(range, encoded);
+            }
+
+            export_data.push(vec![(export.serial_offset(), data)]);
+        }
+        // Added exports were never read from anywhere, so their data has no source offset
+        export_data.extend(
+            self.exports
+                .iter()
+                .map(|export| vec![(0, export.data.clone())]),
+        );
+
+        serialize_unreal_package::<E, _>(writer, &mut package, &export_data, LayoutMode::Compact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        object::{
+            UObjectKind,
+            builtins::{Const as ConstObject, Enum, Field, Struct},
+            internal::script::{Callee, Const},
+        },
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    #[test]
+    fn replaces_function_scripts() {
+        let mut builder = PackageBuilder::new();
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Tick");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(0)
+            .packed(name)
+            .u32(0)
+            .u32(0)
+            .u32(3)
+            // Return(Nothing), EndOfScript
+            .u8(0x04)
+            .u8(0x0B)
+            .u8(0x47)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Tick", data);
+
+        // Follows the function, so it moves when the script grows
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+
+        let mut editor = package.edit();
+        let err = editor
+            .replace_function_script("MaxCount", Vec::<u8>::new())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let script = vec![
+            Expr::Return(Box::new(Expr::Const(Const::Int(7)))),
+            Expr::EndOfScript,
+        ];
+        editor.replace_function_script("Tick", script).unwrap();
+
+        let mut patched = Vec::new();
+        editor.write(&mut patched).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(patched)).unwrap();
+        let contents = package.load_objects().unwrap();
+        assert!(package.serial_size_mismatches().is_empty());
+
+        let tick = contents.object("Tick").unwrap();
+        let tick = tick.borrow();
+        let tick = tick
+            .parent_of_kind(UObjectKind::Struct)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Struct>()
+            .unwrap();
+        assert_eq!(
+            tick.script_bytes(),
+            [0x04, 0x1D, 0x07, 0x00, 0x00, 0x00, 0x47]
+        );
+        assert!(matches!(
+            &tick.script()[0].expr,
+            Expr::Return(value) if matches!(**value, Expr::Const(Const::Int(7)))
+        ));
+
+        let constant = contents.object("MaxCount").unwrap();
+        let constant = constant.borrow();
+        assert_eq!(
+            constant
+                .as_any()
+                .downcast_ref::<ConstObject>()
+                .unwrap()
+                .value,
+            "42"
+        );
+    }
+
+    #[test]
+    fn edits_name_table() {
+        let mut builder = PackageBuilder::new();
+        let unused = builder.name("Unused");
+
+        let enum_class = builder.core_class("Enum");
+        let values = [builder.name("RED"), builder.name("GREEN")];
+        let data = DataBuilder::field(0, 0)
+            .packed(values.len() as i32)
+            .packed(values[0])
+            .packed(values[1])
+            .build();
+        let colors = builder.export(enum_class, 0, "EColor", data);
+
+        let byte_property = builder.core_class("ByteProperty");
+        let category = builder.name("Display");
+        let data = DataBuilder::ue2_property(0, 0, category)
+            .packed(colors)
+            .build();
+        builder.export(byte_property, 0, "Color", data);
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Jump");
+        let data = DataBuilder::field(0, 0)
+            .packed(0)
+            .packed(0)
+            .packed(name)
+            .u32(0)
+            .u32(0)
+            // script_size: Return(NameConst(Jump)), EndOfScript
+            .u32(7)
+            .u8(0x04)
+            .u8(0x21)
+            .packed(name)
+            .u8(0x47)
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Jump", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+
+        let mut editor = package.edit();
+        assert_eq!(editor.append_name("display"), category);
+        let appended = editor.append_name("Appended");
+        assert_eq!(
+            editor.rename_name(appended, "RED").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        editor.rename_name(values[1], "BLUE").unwrap();
+        editor.remove_name(unused).unwrap();
+        assert_eq!(editor.find_name("Jump"), Some(name - 1));
+
+        let mut edited = Vec::new();
+        editor.write(&mut edited).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(edited)).unwrap();
+        let contents = package.load_objects().unwrap();
+
+        let colors = contents.object("EColor").unwrap();
+        let colors = colors.borrow();
+        let names = colors.as_any().downcast_ref::<Enum>().unwrap().names();
+        assert_eq!(
+            names.iter().map(|name| &**name).collect::<Vec<_>>(),
+            ["RED", "BLUE"]
+        );
+        assert_eq!(
+            contents.properties()[0].category.as_deref(),
+            Some("Display")
+        );
+
+        let jump = contents.object("Jump").unwrap();
+        let jump = jump.borrow();
+        let jump = jump
+            .parent_of_kind(UObjectKind::Struct)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Struct>()
+            .unwrap();
+        let Expr::Return(value) = &jump.script()[0].expr else {
+            panic!("expected Return, got {:?}", jump.script()[0].expr);
+        };
+        assert!(matches!(**value, Expr::Const(Const::Name(jump)) if jump.index() == name - 1));
+
+        // Names which are still used can't be removed
+        let mut editor = package.edit();
+        editor.remove_name(name - 1).unwrap();
+        let err = editor.write(&mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn adds_imports() {
+        let mut builder = PackageBuilder::new();
+        let function = builder.core_class("Function");
+        builder.export(function, 0, "Tick", DataBuilder::field(0, 0).build());
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+
+        let mut editor = package.edit();
+        assert_eq!(
+            editor.add_import("Core", "Class", "Core.Function").unwrap(),
+            function,
+            "existing imports are reused"
+        );
+        assert_eq!(
+            editor
+                .add_import("Engine", "Function", "Engine.Actor.Spawn")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // Engine is imported as a package along with Actor
+        let actor = editor.add_import("Core", "Class", "Engine.Actor").unwrap();
+        let spawn = editor
+            .add_import("Core", "Function", "Engine.Actor.Spawn")
+            .unwrap();
+        assert_eq!(editor.find_import_path("Engine"), Some(actor + 1));
+        assert_eq!(spawn, actor - 1);
+        assert_eq!(
+            editor
+                .add_import("Core", "Function", "engine.actor.spawn")
+                .unwrap(),
+            spawn
+        );
+
+        let mut edited = Vec::new();
+        editor.write(&mut edited).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(edited)).unwrap();
+        let editor = package.edit();
+        assert_eq!(editor.find_import("Package", "Engine"), Some(actor + 1));
+        assert_eq!(editor.find_import("Class", "Engine.Actor"), Some(actor));
+        assert_eq!(
+            editor.find_import("Function", "Engine.Actor.Spawn"),
+            Some(spawn)
+        );
+    }
+
+    #[test]
+    fn adds_exports() {
+        let mut builder = PackageBuilder::new();
+        let enum_class = builder.core_class("Enum");
+        let data = DataBuilder::field(0, 0).packed(0).build();
+        let colors = builder.export(enum_class, 0, "EColor", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+
+        let mut editor = package.edit();
+        let constant = editor.add_import("Core", "Class", "Core.Const").unwrap();
+        let data = DataBuilder::field(0, 0).string("42").build();
+        assert_eq!(
+            editor
+                .add_export(
+                    constant,
+                    colors + 1,
+                    "MaxCount",
+                    ObjectFlags::PUBLIC,
+                    data.clone()
+                )
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+        let max_count = editor
+            .add_export(
+                constant,
+                colors,
+                "MaxCount",
+                ObjectFlags::PUBLIC,
+                data.clone(),
+            )
+            .unwrap();
+        assert_eq!(max_count, colors + 1);
+        assert_eq!(
+            editor
+                .add_export(constant, colors, "maxcount", ObjectFlags::PUBLIC, data)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let mut edited = Vec::new();
+        editor.write(&mut edited).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(edited)).unwrap();
+        let linker = package.contents().linker;
+        let generation = *linker.borrow().package.header.generations.last().unwrap();
+        assert_eq!(generation.export_count, 2);
+        assert_eq!(
+            generation.name_count as usize,
+            linker.borrow().package.names.len()
+        );
+
+        let contents = package.load_objects().unwrap();
+        let constant = contents.object("MaxCount").unwrap();
+        let constant = constant.borrow();
+        assert_eq!(
+            constant
+                .as_any()
+                .downcast_ref::<ConstObject>()
+                .unwrap()
+                .value,
+            "42"
+        );
+    }
+
+    #[test]
+    fn merges_exports_with_dependencies() {
+        let function_data = |next: i32, friendly_name: i32, script_size: u32, script: &[u8]| {
+            let mut data = DataBuilder::field(0, next)
+                // script_text, children, friendly_name, line, text_pos, script_size
+                .packed(0)
+                .packed(0)
+                .packed(friendly_name)
+                .u32(0)
+                .u32(0)
+                .u32(script_size);
+            for &byte in script {
+                data = data.u8(byte);
+            }
+
+            // inative, operator_precedence, function_flags
+            data.u16(0).u8(0).u32(0x2).build()
+        };
+
+        let mut source = PackageBuilder::new();
+        let function = source.core_class("Function");
+        let helper_name = source.name("Helper");
+        let tick_name = source.name("Tick");
+        // Return(Nothing), EndOfScript
+        let helper = source.export(
+            function,
+            0,
+            "Helper",
+            function_data(0, helper_name, 3, &[0x04, 0x0B, 0x47]),
+        );
+        // FinalFunction(Helper), EndFunctionParms, Return(Nothing), EndOfScript
+        let tick_script = [0x1C, helper as u8, 0x16, 0x04, 0x0B, 0x47];
+        source.export(
+            function,
+            0,
+            "Tick",
+            function_data(helper, tick_name, 9, &tick_script),
+        );
+        let mut source =
+            Package::<LittleEndian, _>::open("Source", Cursor::new(source.build())).unwrap();
+
+        // Different tables from the source's, so every index has to be rewritten
+        let mut builder = PackageBuilder::new();
+        builder.name("Unrelated");
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+
+        let mut editor = package.edit();
+        assert_eq!(
+            editor
+                .merge_export(&mut source, "Missing")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+        let tick = editor.merge_export(&mut source, "Tick").unwrap();
+        assert_eq!(tick, 2);
+        assert_eq!(
+            editor
+                .merge_export(&mut source, "Helper")
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        let mut merged = Vec::new();
+        editor.write(&mut merged).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(merged)).unwrap();
+        let contents = package.load_objects().unwrap();
+        assert!(package.serial_size_mismatches().is_empty());
+
+        let tick = contents.object("Tick").unwrap();
+        let tick = tick.borrow();
+        let helper = contents.object("Helper").unwrap();
+        let next = tick
+            .parent_of_kind(UObjectKind::Field)
+            .and_then(|field| field.as_any().downcast_ref::<Field>())
+            .and_then(Field::next)
+            .unwrap();
+        assert!(Rc::ptr_eq(&next, &helper));
+
+        let tick = tick
+            .parent_of_kind(UObjectKind::Struct)
+            .unwrap()
+            .as_any()
+            .downcast_ref::<Struct>()
+            .unwrap();
+        assert_eq!(tick.friendly_name(), Some("Tick"));
+        assert!(matches!(
+            &tick.script()[0].expr,
+            Expr::Call { func: Callee::Final(function), .. }
+                if function.object.as_ref().is_some_and(|function| Rc::ptr_eq(function, &helper))
+        ));
+    }
+
+    #[test]
+    fn records_saves_in_generations() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("1").build();
+        builder.export(constant, 0, "One", data);
+        let data = builder.build();
+
+        let generations = |policy| {
+            let mut package =
+                Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+            let mut editor = package.edit();
+            editor.append_name("Added");
+            editor.set_generation_policy(policy);
+
+            let mut edited = Vec::new();
+            editor.write(&mut edited).unwrap();
+
+            let package = Package::<LittleEndian, _>::open("Test", Cursor::new(edited)).unwrap();
+            let linker = package.contents().linker;
+            let linker = linker.borrow();
+            let names = linker.package.names.len() as u32;
+            let generations = linker
+                .package
+                .header
+                .generations
+                .iter()
+                .map(|generation| (generation.export_count, generation.name_count))
+                .collect::<Vec<_>>();
+            (names, generations)
+        };
+
+        let (names, updated) = generations(GenerationPolicy::UpdateLast);
+        assert_eq!(updated, [(1, names)]);
+
+        let (_, appended) = generations(GenerationPolicy::Append);
+        assert_eq!(appended, [(1, names - 1), (1, names)]);
+
+        let (_, preserved) = generations(GenerationPolicy::Preserve);
+        assert_eq!(preserved, [(1, names - 1)]);
+    }
+}
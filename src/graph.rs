@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::{
+    common::normalize_index,
+    de::{Linker, RawPackage},
+    object::{
+        PropertyValue, UObjectKind,
+        builtins::{ObjectProperty, Struct},
+        internal::script::{self, Const},
+    },
+};
+
+/// Why one node depends on another.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum DependencyKind {
+    /// A package imports an object from another package.
+    Import,
+    /// An object is an instance of the target class.
+    Class,
+    /// A struct inherits from the target struct.
+    Super,
+    /// An object is contained in the target object.
+    Outer,
+    /// An object property refers to the target class.
+    PropertyReference,
+    /// An object's property value is the target object.
+    ValueReference,
+    /// A struct's script uses the target object as a constant.
+    ScriptReference,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Dependency {
+    pub to: String,
+    pub kind: DependencyKind,
+}
+
+/// A node which depends on another, as returned by [`DependencyGraph::dependents`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Referencer {
+    pub from: String,
+    pub kind: DependencyKind,
+}
+
+/// A directed graph of package-level and object-level dependencies.
+///
+/// Packages are identified by their name, objects by their full path (`Package.Outer.Name`).
+#[derive(Debug, Default, Clone)]
+pub struct DependencyGraph {
+    edges: BTreeMap<String, BTreeSet<Dependency>>,
+}
+
+impl DependencyGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_edge(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        kind: DependencyKind,
+    ) {
+        let to = to.into();
+        self.edges.entry(to.clone()).or_default();
+        self.edges
+            .entry(from.into())
+            .or_default()
+            .insert(Dependency { to, kind });
+    }
+
+    /// Adds the dependencies described by a package's import and export tables.
+    pub fn add_package(&mut self, package_name: &str, package: &RawPackage) {
+        self.edges.entry(package_name.to_owned()).or_default();
+
+        // Edges to objects whose indices are out of bounds are left out
+        for import in &package.imports {
+            let import_path = import.path(package);
+
+            // Package-level dependency on the outermost package of the import
+            let root = import_path.split('.').next().unwrap_or(&import_path);
+            if root != package_name {
+                self.add_edge(package_name, root, DependencyKind::Import);
+            }
+
+            if import.package_index < 0
+                && let Some(outer) = import_path_of_index(package, import.package_index)
+            {
+                self.add_edge(import_path.clone(), outer, DependencyKind::Outer);
+            }
+        }
+
+        for export in &package.exports {
+            let export_path = export.path(package_name, package);
+
+            if export.class_index != 0
+                && let Some(class) = object_path(package_name, package, export.class_index)
+            {
+                self.add_edge(export_path.clone(), class, DependencyKind::Class);
+            }
+
+            if export.super_index != 0
+                && let Some(super_path) = object_path(package_name, package, export.super_index)
+            {
+                self.add_edge(export_path.clone(), super_path, DependencyKind::Super);
+            }
+
+            let outer = if export.package_index == 0 {
+                Some(package_name.to_owned())
+            } else {
+                object_path(package_name, package, export.package_index)
+            };
+            if let Some(outer) = outer {
+                self.add_edge(export_path, outer, DependencyKind::Outer);
+            }
+        }
+    }
+
+    /// Adds the dependencies of a linker's package along with any references held by
+    /// objects which have already been loaded.
+    pub(crate) fn add_linker(&mut self, linker: &Linker) {
+        let package_name = linker.name.as_str();
+        let package = &linker.package;
+
+        self.add_package(package_name, package);
+
+        for (export_index, obj) in &linker.objects {
+            let obj = obj.borrow();
+            let Some(from) = export_path(package_name, package, export_index.index()) else {
+                continue;
+            };
+
+            for (_, value) in obj.base_object().properties() {
+                if let PropertyValue::Object(Some(target)) = value {
+                    let to = target.borrow().base_object().full_name();
+                    self.add_edge(from.clone(), to, DependencyKind::ValueReference);
+                }
+            }
+
+            if let Some(ustruct) = obj
+                .parent_of_kind(UObjectKind::Struct)
+                .and_then(|ustruct| ustruct.as_any().downcast_ref::<Struct>())
+            {
+                let statements = ustruct.script().iter().map(|statement| &statement.expr);
+                for constant in script::constants(statements) {
+                    if let Const::Object(object) = constant
+                        && object.raw_index != 0
+                        && let Some(to) = object_path(package_name, package, object.raw_index)
+                    {
+                        self.add_edge(from.clone(), to, DependencyKind::ScriptReference);
+                    }
+                }
+            }
+
+            let Some(property) = obj
+                .parent_of_kind(UObjectKind::ObjectProperty)
+                .and_then(|property| property.as_any().downcast_ref::<ObjectProperty>())
+            else {
+                continue;
+            };
+
+            let raw_index = property.property_class.raw_index();
+            if raw_index == 0 {
+                continue;
+            }
+
+            let Some(to) = object_path(package_name, package, raw_index) else {
+                continue;
+            };
+            self.add_edge(from, to, DependencyKind::PropertyReference);
+        }
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &str> {
+        self.edges.keys().map(String::as_str)
+    }
+
+    pub fn dependencies(&self, node: &str) -> impl Iterator<Item = &Dependency> {
+        self.edges.get(node).into_iter().flatten()
+    }
+
+    /// Every node reachable from `roots`, including the roots themselves, lowercased. Nodes
+    /// are matched ignoring ASCII case, since paths in import tables don't necessarily use
+    /// the same case as the exports they refer to.
+    pub(crate) fn reachable_ignoring_case<'a>(
+        &self,
+        roots: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeSet<String> {
+        let mut edges = BTreeMap::<String, Vec<String>>::new();
+        for (from, deps) in &self.edges {
+            edges
+                .entry(from.to_ascii_lowercase())
+                .or_default()
+                .extend(deps.iter().map(|dep| dep.to.to_ascii_lowercase()));
+        }
+
+        let mut reachable = BTreeSet::new();
+        let mut pending = roots
+            .into_iter()
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>();
+        while let Some(node) = pending.pop() {
+            if let Some(deps) = edges.get(&node) {
+                pending.extend(deps.iter().filter(|dep| !reachable.contains(*dep)).cloned());
+            }
+            reachable.insert(node);
+        }
+
+        reachable
+    }
+
+    /// The nodes which depend on `node`, ordered by name. This is the reverse of
+    /// [`DependencyGraph::dependencies`].
+    pub fn dependents(&self, node: &str) -> Vec<Referencer> {
+        self.edges
+            .iter()
+            .flat_map(|(from, deps)| {
+                deps.iter()
+                    .filter(|dep| dep.to == node)
+                    .map(|dep| Referencer {
+                        from: from.clone(),
+                        kind: dep.kind,
+                    })
+            })
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph dependencies {\n");
+        for (from, deps) in &self.edges {
+            out.push_str(&format!("    {from:?};\n"));
+            for dep in deps {
+                out.push_str(&format!(
+                    "    {from:?} -> {:?} [label={:?}];\n",
+                    dep.to,
+                    format!("{:?}", dep.kind)
+                ));
+            }
+        }
+        out.push_str("}\n");
+
+        out
+    }
+
+    /// Renders the graph as a JSON adjacency list mapping each node to its dependencies.
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.edges)
+    }
+}
+
+/// Full path of the object referenced by a raw object index, or `None` if the index is out
+/// of bounds.
+pub(crate) fn object_path(
+    package_name: &str,
+    package: &RawPackage,
+    raw_index: i32,
+) -> Option<String> {
+    if raw_index < 0 {
+        import_path_of_index(package, raw_index)
+    } else {
+        export_path(package_name, package, normalize_index(raw_index))
+    }
+}
+
+fn import_path_of_index(package: &RawPackage, raw_index: i32) -> Option<String> {
+    import_path(package, normalize_index(raw_index))
+}
+
+fn import_path(package: &RawPackage, index: usize) -> Option<String> {
+    Some(package.imports.get(index)?.path(package))
+}
+
+fn export_path(package_name: &str, package: &RawPackage, index: usize) -> Option<String> {
+    Some(package.exports.get(index)?.path(package_name, package))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::de::{Import, Name, ObjectExport, PackageHeader};
+
+    fn test_package() -> RawPackage {
+        let names = [
+            "None", "Core", "Package", "Class", "Object", "Actor", "MyActor", "Engine",
+        ]
+        .into_iter()
+        .map(|name| Name {
+            name: Rc::from(name),
+            flags: 0,
+        })
+        .collect::<Vec<_>>();
+
+        RawPackage {
+            header: PackageHeader {
+                version: 0,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: 1,
+                export_offset: 0,
+                import_count: 4,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: Vec::new(),
+            },
+            names,
+            imports: vec![
+                // Core
+                Import {
+                    class_package: 1,
+                    class_name: 2,
+                    package_index: 0,
+                    object_name: 1,
+                },
+                // Core.Class
+                Import {
+                    class_package: 1,
+                    class_name: 3,
+                    package_index: -1,
+                    object_name: 3,
+                },
+                // Engine
+                Import {
+                    class_package: 1,
+                    class_name: 2,
+                    package_index: 0,
+                    object_name: 7,
+                },
+                // Engine.Actor
+                Import {
+                    class_package: 1,
+                    class_name: 3,
+                    package_index: -3,
+                    object_name: 5,
+                },
+            ],
+            exports: vec![ObjectExport {
+                class_index: -2,
+                super_index: -4,
+                package_index: 0,
+                object_name: 6,
+                object_flags: 0,
+                serial_size: 0,
+                serial_offset: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn package_dependencies() {
+        let mut graph = DependencyGraph::new();
+        graph.add_package("MyPackage", &test_package());
+
+        let deps = graph.dependencies("MyPackage").collect::<Vec<_>>();
+        assert!(deps.contains(&&Dependency {
+            to: "Core".to_owned(),
+            kind: DependencyKind::Import,
+        }));
+        assert!(deps.contains(&&Dependency {
+            to: "Engine".to_owned(),
+            kind: DependencyKind::Import,
+        }));
+
+        let actor_deps = graph.dependencies("MyPackage.MyActor").collect::<Vec<_>>();
+        assert_eq!(
+            actor_deps,
+            vec![
+                &Dependency {
+                    to: "Core.Class".to_owned(),
+                    kind: DependencyKind::Class,
+                },
+                &Dependency {
+                    to: "Engine.Actor".to_owned(),
+                    kind: DependencyKind::Super,
+                },
+                &Dependency {
+                    to: "MyPackage".to_owned(),
+                    kind: DependencyKind::Outer,
+                },
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("\"MyPackage.MyActor\" -> \"Engine.Actor\" [label=\"Super\"];"));
+    }
+
+    #[test]
+    fn package_dependents() {
+        let mut graph = DependencyGraph::new();
+        graph.add_package("MyPackage", &test_package());
+
+        assert_eq!(
+            graph.dependents("Engine.Actor"),
+            vec![Referencer {
+                from: "MyPackage.MyActor".to_owned(),
+                kind: DependencyKind::Super,
+            }]
+        );
+        assert_eq!(
+            graph.dependents("Engine"),
+            vec![
+                Referencer {
+                    from: "Engine.Actor".to_owned(),
+                    kind: DependencyKind::Outer,
+                },
+                Referencer {
+                    from: "MyPackage".to_owned(),
+                    kind: DependencyKind::Import,
+                },
+            ]
+        );
+        assert!(graph.dependents("MyPackage.MyActor").is_empty());
+    }
+
+    #[test]
+    fn skips_out_of_bounds_indices() {
+        let mut package = test_package();
+        package.exports[0].class_index = -99;
+        package.exports[0].super_index = i32::MIN;
+
+        let mut graph = DependencyGraph::new();
+        graph.add_package("MyPackage", &package);
+
+        assert_eq!(
+            graph.dependencies("MyPackage.MyActor").collect::<Vec<_>>(),
+            vec![&Dependency {
+                to: "MyPackage".to_owned(),
+                kind: DependencyKind::Outer,
+            }]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{fmt, io::Read, str::FromStr};
+
+use byteorder::{ByteOrder, ReadBytesExt};
+
+/// A 128-bit globally unique identifier as stored in package headers.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Guid {
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+    pub d: u32,
+}
+
+impl Guid {
+    pub const fn new(a: u32, b: u32, c: u32, d: u32) -> Self {
+        Guid { a, b, c, d }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Guid::default()
+    }
+}
+
+pub(crate) fn read_guid<E, R>(reader: &mut R) -> std::io::Result<Guid>
+where
+    R: Read,
+    E: ByteOrder,
+{
+    Ok(Guid {
+        a: reader.read_u32::<E>()?,
+        b: reader.read_u32::<E>()?,
+        c: reader.read_u32::<E>()?,
+        d: reader.read_u32::<E>()?,
+    })
+}
+
+impl fmt::Display for Guid {
+    /// Formats the GUID in the standard `XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX` form.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:08X}-{:04X}-{:04X}-{:04X}-{:04X}{:08X}",
+            self.a,
+            self.b >> 16,
+            self.b & 0xFFFF,
+            self.c >> 16,
+            self.c & 0xFFFF,
+            self.d
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseGuidError(String);
+
+impl fmt::Display for ParseGuidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid GUID {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseGuidError {}
+
+impl FromStr for Guid {
+    type Err = ParseGuidError;
+
+    /// Parses either the dashed form produced by [`Display`](fmt::Display) or Unreal's
+    /// undashed 32-character form. Surrounding braces are accepted.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseGuidError(s.to_owned());
+
+        let trimmed = s.trim();
+        let trimmed = trimmed
+            .strip_prefix('{')
+            .and_then(|t| t.strip_suffix('}'))
+            .unwrap_or(trimmed);
+
+        let hex: String = trimmed.chars().filter(|c| *c != '-').collect();
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+
+        let part = |i: usize| u32::from_str_radix(&hex[i * 8..(i + 1) * 8], 16).map_err(|_| err());
+
+        Ok(Guid {
+            a: part(0)?,
+            b: part(1)?,
+            c: part(2)?,
+            d: part(3)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_roundtrip() {
+        let guid = Guid::new(0x0123_4567, 0x89AB_CDEF, 0xDEAD_BEEF, 0x1234_5678);
+        let formatted = guid.to_string();
+
+        assert_eq!(formatted, "01234567-89AB-CDEF-DEAD-BEEF12345678");
+        assert_eq!(formatted.parse::<Guid>().unwrap(), guid);
+    }
+
+    #[test]
+    fn parse_undashed_and_braced() {
+        let guid = Guid::new(0x0123_4567, 0x89AB_CDEF, 0xDEAD_BEEF, 0x1234_5678);
+
+        assert_eq!(
+            "0123456789ABCDEFDEADBEEF12345678".parse::<Guid>().unwrap(),
+            guid
+        );
+        assert_eq!(
+            "{01234567-89ab-cdef-dead-beef12345678}"
+                .parse::<Guid>()
+                .unwrap(),
+            guid
+        );
+        assert!("not-a-guid".parse::<Guid>().is_err());
+    }
+}
@@ -0,0 +1,211 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::object::builtins::{Class, ClassFlags, FunctionFlags, PropertyFlags};
+
+/// Declaration of a class recovered from its compiled fields, written out as an UnrealScript
+/// stub by its [`Display`](fmt::Display) impl.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassHeader {
+    pub name: String,
+    /// Name of the class this one extends, if any
+    pub super_class: Option<String>,
+    /// Class that objects of this class must be created within, if it's not `Object`
+    pub within: Option<String>,
+    /// Base name of the .ini file the class's config is read from, for classes declared
+    /// `config`
+    pub config_name: Option<String>,
+    /// Variables declared directly in the class, in declaration order
+    pub variables: Vec<VariableDecl>,
+    /// Functions declared directly in the class, in declaration order
+    pub functions: Vec<FunctionDecl>,
+}
+
+/// The `within` class and config name `class` is declared with, as stored in
+/// [`ClassHeader::within`] and [`ClassHeader::config_name`].
+pub(crate) fn declared_within_and_config(class: &Class) -> (Option<String>, Option<String>) {
+    let within = class
+        .within_class()
+        .filter(|within| !within.eq_ignore_ascii_case("Object"));
+    let config_name = class
+        .class_flags()
+        .contains(ClassFlags::CONFIG)
+        .then(|| class.config_name())
+        .flatten();
+
+    (within, config_name)
+}
+
+/// A variable or function parameter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariableDecl {
+    pub name: String,
+    /// UnrealScript type, e.g. `int`, `class<Actor>` or `EPhysics`
+    pub type_name: String,
+    /// Editor category, if the variable has one
+    pub category: Option<String>,
+    pub flags: PropertyFlags,
+    /// Number of elements in a static array; 1 for other variables
+    pub array_dim: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionDecl {
+    pub name: String,
+    pub flags: FunctionFlags,
+    /// Index in the native function table, or zero if the function isn't bound to one
+    pub native_index: u16,
+    pub return_type: Option<String>,
+    pub params: Vec<VariableDecl>,
+}
+
+/// Keywords for the property flags which appear in declarations, in the order the compiler
+/// accepts them.
+const VARIABLE_KEYWORDS: [(PropertyFlags, &str); 11] = [
+    (PropertyFlags::CONST, "const"),
+    (PropertyFlags::INPUT, "input"),
+    (PropertyFlags::CONFIG, "config"),
+    (PropertyFlags::GLOBAL_CONFIG, "globalconfig"),
+    (PropertyFlags::LOCALIZED, "localized"),
+    (PropertyFlags::TRAVEL, "travel"),
+    (PropertyFlags::TRANSIENT, "transient"),
+    (PropertyFlags::NATIVE, "native"),
+    (PropertyFlags::EDIT_CONST, "editconst"),
+    (PropertyFlags::EXPORT_OBJECT, "export"),
+    (PropertyFlags::EDIT_INLINE, "editinline"),
+];
+
+const PARAM_KEYWORDS: [(PropertyFlags, &str); 4] = [
+    (PropertyFlags::OPTIONAL_PARM, "optional"),
+    (PropertyFlags::OUT_PARM, "out"),
+    (PropertyFlags::COERCE_PARM, "coerce"),
+    (PropertyFlags::SKIP_PARM, "skip"),
+];
+
+const FUNCTION_KEYWORDS: [(FunctionFlags, &str); 8] = [
+    (FunctionFlags::PRIVATE, "private"),
+    (FunctionFlags::PROTECTED, "protected"),
+    (FunctionFlags::STATIC, "static"),
+    (FunctionFlags::FINAL, "final"),
+    (FunctionFlags::SIMULATED, "simulated"),
+    (FunctionFlags::SINGULAR, "singular"),
+    (FunctionFlags::LATENT, "latent"),
+    (FunctionFlags::ITERATOR, "iterator"),
+];
+
+impl VariableDecl {
+    fn write_type_and_name(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.type_name, self.name)?;
+        if self.array_dim > 1 {
+            write!(f, "[{}]", self.array_dim)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for VariableDecl {
+    /// Writes the variable as a class variable declaration, e.g. `var(Movement) float Speed;`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("var")?;
+        if self.flags.contains(PropertyFlags::EDIT) {
+            write!(f, "({})", self.category.as_deref().unwrap_or_default())?;
+        }
+
+        for (flag, keyword) in VARIABLE_KEYWORDS {
+            if self.flags.contains(flag) {
+                write!(f, " {keyword}")?;
+            }
+        }
+
+        f.write_str(" ")?;
+        self.write_type_and_name(f)?;
+        f.write_str(";")
+    }
+}
+
+impl fmt::Display for FunctionDecl {
+    /// Writes the function's prototype, e.g. `native(256) final latent function Sleep(float
+    /// Seconds);`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keywords = Vec::new();
+        if self.flags.contains(FunctionFlags::NATIVE) {
+            keywords.push(match self.native_index {
+                0 => "native".to_owned(),
+                index => format!("native({index})"),
+            });
+        }
+        for (flag, keyword) in FUNCTION_KEYWORDS {
+            if self.flags.contains(flag) {
+                keywords.push(keyword.to_owned());
+            }
+        }
+        keywords.push(
+            if self.flags.contains(FunctionFlags::DELEGATE) {
+                "delegate"
+            } else if self.flags.contains(FunctionFlags::PRE_OPERATOR) {
+                "preoperator"
+            } else if self.flags.contains(FunctionFlags::OPERATOR) {
+                "operator"
+            } else if self.flags.contains(FunctionFlags::EVENT) {
+                "event"
+            } else {
+                "function"
+            }
+            .to_owned(),
+        );
+
+        write!(f, "{}", keywords.join(" "))?;
+        if let Some(return_type) = &self.return_type {
+            write!(f, " {return_type}")?;
+        }
+        write!(f, " {}(", self.name)?;
+
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            for (flag, keyword) in PARAM_KEYWORDS {
+                if param.flags.contains(flag) {
+                    write!(f, "{keyword} ")?;
+                }
+            }
+            param.write_type_and_name(f)?;
+        }
+
+        f.write_str(");")
+    }
+}
+
+impl fmt::Display for ClassHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "class {}", self.name)?;
+        if let Some(super_class) = &self.super_class {
+            write!(f, " extends {super_class}")?;
+        }
+        if let Some(within) = &self.within {
+            write!(f, " within {within}")?;
+        }
+        if let Some(config_name) = &self.config_name {
+            write!(f, " config({config_name})")?;
+        }
+        writeln!(f, ";")?;
+
+        if !self.variables.is_empty() {
+            writeln!(f)?;
+            for variable in &self.variables {
+                writeln!(f, "{variable}")?;
+            }
+        }
+
+        if !self.functions.is_empty() {
+            writeln!(f)?;
+            for function in &self.functions {
+                writeln!(f, "{function}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
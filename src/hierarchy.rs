@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use crate::{de::RawPackage, graph::object_path};
+
+/// A class exported by a loaded package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassInfo {
+    pub name: String,
+    pub package: String,
+    /// Full name of the class this one extends, e.g. `Engine.Actor`
+    pub super_class: Option<String>,
+}
+
+impl ClassInfo {
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.package, self.name)
+    }
+}
+
+/// Inheritance between the classes exported by a set of packages, built from their export
+/// tables so that it doesn't depend on classes being deserialized.
+///
+/// Classes are looked up either by full name (`Engine.Actor`) or by name alone (`Actor`),
+/// ignoring ASCII case. Super classes imported from packages which weren't added are named
+/// by [`ClassInfo::super_class`] but have no entry of their own.
+#[derive(Debug, Default, Clone)]
+pub struct ClassHierarchy {
+    /// Keyed by lowercase full name
+    classes: BTreeMap<String, ClassInfo>,
+}
+
+impl ClassHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the classes exported by `package`.
+    pub fn add_package(&mut self, package_name: &str, package: &RawPackage) {
+        for export in &package.exports {
+            // Class exports either leave their class as the implicit `Class` or import it
+            let is_class = export.class_index == 0
+                || object_path(package_name, package, export.class_index)
+                    .is_some_and(|class| class.eq_ignore_ascii_case("Core.Class"));
+            if !is_class || export.package_index != 0 {
+                continue;
+            }
+
+            let class = ClassInfo {
+                name: package.names[export.object_name as usize].name.to_string(),
+                package: package_name.to_owned(),
+                super_class: (export.super_index != 0)
+                    .then(|| object_path(package_name, package, export.super_index))
+                    .flatten(),
+            };
+            self.classes
+                .insert(class.full_name().to_ascii_lowercase(), class);
+        }
+    }
+
+    /// Every class, ordered by full name.
+    pub fn classes(&self) -> impl Iterator<Item = &ClassInfo> {
+        self.classes.values()
+    }
+
+    /// The class named `name`. When only a name is given and several packages export a
+    /// class with that name, the first by full name is returned.
+    pub fn class(&self, name: &str) -> Option<&ClassInfo> {
+        if name.contains('.') {
+            return self.classes.get(&name.to_ascii_lowercase());
+        }
+
+        self.classes
+            .values()
+            .find(|class| class.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The classes `name` inherits from, starting with its direct super class. Stops at the
+    /// first super class which isn't part of the hierarchy.
+    pub fn super_classes(&self, name: &str) -> Vec<&ClassInfo> {
+        let mut supers = Vec::new();
+        let mut visited = HashSet::new();
+
+        let mut current = self.class(name);
+        while let Some(class) = current {
+            // Corrupt packages could make a class its own ancestor
+            if !visited.insert(class.full_name().to_ascii_lowercase()) {
+                break;
+            }
+
+            current = class
+                .super_class
+                .as_deref()
+                .and_then(|super_class| self.class(super_class));
+            supers.extend(current);
+        }
+
+        supers
+    }
+
+    /// Whether `class` is `ancestor` or inherits from it. `ancestor` may be a class which
+    /// isn't part of the hierarchy, as long as it's named as the super class of one which is.
+    pub fn is_subclass_of(&self, class: &str, ancestor: &str) -> bool {
+        let Some(class) = self.class(class) else {
+            return false;
+        };
+
+        let ancestor = self
+            .class(ancestor)
+            .map(ClassInfo::full_name)
+            .unwrap_or_else(|| ancestor.to_owned());
+        let matches = |full_name: &str| {
+            if ancestor.contains('.') {
+                full_name.eq_ignore_ascii_case(&ancestor)
+            } else {
+                full_name
+                    .rsplit('.')
+                    .next()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&ancestor))
+            }
+        };
+
+        std::iter::once(class)
+            .chain(self.super_classes(&class.full_name()))
+            .any(|class| {
+                matches(&class.full_name()) || class.super_class.as_deref().is_some_and(matches)
+            })
+    }
+
+    /// Classes which directly extend `name`.
+    pub fn children(&self, name: &str) -> Vec<&ClassInfo> {
+        let Some(parent) = self.class(name).map(ClassInfo::full_name) else {
+            return Vec::new();
+        };
+
+        self.classes()
+            .filter(|class| {
+                class
+                    .super_class
+                    .as_deref()
+                    .is_some_and(|super_class| super_class.eq_ignore_ascii_case(&parent))
+            })
+            .collect()
+    }
+
+    /// Every class which inherits from `name`, directly or not. `name` itself isn't included.
+    pub fn subclasses_of(&self, name: &str) -> Vec<&ClassInfo> {
+        let target = self.class(name).map(ClassInfo::full_name);
+
+        self.classes()
+            .filter(|class| Some(class.full_name()) != target)
+            .filter(|class| self.is_subclass_of(&class.full_name(), name))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{DataBuilder, PackageBuilder};
+
+    fn hierarchy() -> ClassHierarchy {
+        // Engine's classes extend Core.Object, which isn't loaded
+        let mut engine = PackageBuilder::new();
+        let class = engine.core_class("Class");
+        let core = engine.import("Core", "Package", 0, "Core");
+        let object = engine.import("Core", "Class", core, "Object");
+        let actor = engine.export(class, 0, "Actor", DataBuilder::object().build());
+        let pawn = engine.export(class, 0, "Pawn", DataBuilder::object().build());
+        let controller = engine.export(class, 0, "Controller", DataBuilder::object().build());
+        let texture = engine.core_class("Texture");
+        engine.export(texture, 0, "Logo", DataBuilder::object().build());
+        let mut engine = engine.raw_package();
+        engine.exports[(actor - 1) as usize].super_index = object;
+        engine.exports[(pawn - 1) as usize].super_index = actor;
+        engine.exports[(controller - 1) as usize].super_index = actor;
+
+        let mut game = PackageBuilder::new();
+        let class = game.core_class("Class");
+        let engine_import = game.import("Core", "Package", 0, "Engine");
+        let pawn = game.import("Core", "Class", engine_import, "Pawn");
+        let my_pawn = game.export(class, 0, "MyPawn", DataBuilder::object().build());
+        let mut game = game.raw_package();
+        game.exports[(my_pawn - 1) as usize].super_index = pawn;
+
+        let mut hierarchy = ClassHierarchy::new();
+        hierarchy.add_package("Engine", &engine);
+        hierarchy.add_package("Game", &game);
+        hierarchy
+    }
+
+    #[test]
+    fn queries_inheritance_across_packages() {
+        let hierarchy = hierarchy();
+
+        let names = |classes: Vec<&ClassInfo>| {
+            classes
+                .into_iter()
+                .map(ClassInfo::full_name)
+                .collect::<Vec<_>>()
+        };
+
+        // Logo is a texture, not a class
+        assert_eq!(hierarchy.classes().count(), 4);
+        assert_eq!(
+            hierarchy.class("mypawn").unwrap().super_class.as_deref(),
+            Some("Engine.Pawn")
+        );
+
+        assert_eq!(
+            names(hierarchy.super_classes("Game.MyPawn")),
+            ["Engine.Pawn", "Engine.Actor"]
+        );
+        assert!(hierarchy.is_subclass_of("MyPawn", "Actor"));
+        assert!(hierarchy.is_subclass_of("MyPawn", "MyPawn"));
+        assert!(hierarchy.is_subclass_of("MyPawn", "Core.Object"));
+        assert!(!hierarchy.is_subclass_of("Controller", "Pawn"));
+        assert!(!hierarchy.is_subclass_of("Missing", "Actor"));
+
+        assert_eq!(
+            names(hierarchy.children("Actor")),
+            ["Engine.Controller", "Engine.Pawn"]
+        );
+        assert_eq!(
+            names(hierarchy.subclasses_of("Engine.Actor")),
+            ["Engine.Controller", "Engine.Pawn", "Game.MyPawn"]
+        );
+        assert_eq!(hierarchy.subclasses_of("Object").len(), 4);
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, Read, Seek},
+    path::{Path, PathBuf},
+};
+
+use byteorder::ByteOrder;
+use tracing::{debug, warn};
+
+use crate::{
+    de::{GenerationInfo, PackageHeader, read_package_header},
+    detect::{FileKind, detect, endianness_of},
+    guid::Guid,
+    reader::PackageReader,
+};
+
+/// A package file found by a [`PackageIndexer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedPackage {
+    /// Name of the package, taken from its file name
+    pub name: String,
+    pub path: PathBuf,
+    pub guid: Guid,
+    pub version: u32,
+    /// Export and name counts each time the package was saved, oldest first
+    pub generations: Vec<GenerationInfo>,
+}
+
+/// Map of the packages in a game install, built by reading only their headers, for finding
+/// the file an import's package lives in.
+///
+/// Installs commonly hold several files with the same package name, such as patched copies
+/// or mods, so each name maps to every file found with it. Names are matched ignoring ASCII
+/// case.
+#[derive(Debug, Default)]
+pub struct PackageIndexer {
+    /// Files keyed by their lowercased package name, in the order they were found
+    packages: BTreeMap<String, Vec<IndexedPackage>>,
+}
+
+impl PackageIndexer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes every package under `dir`, recursing into subdirectories. Files which aren't
+    /// `E` endian packages are skipped, as are packages whose header can't be read.
+    pub fn scan<E: ByteOrder>(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let mut indexer = Self::new();
+        indexer.scan_dir::<E>(dir.as_ref())?;
+
+        Ok(indexer)
+    }
+
+    fn scan_dir<E: ByteOrder>(&mut self, dir: &Path) -> io::Result<()> {
+        // Sorted so that copies of a package are indexed in the same order on every platform
+        let mut entries = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for path in entries {
+            if path.is_dir() {
+                self.scan_dir::<E>(&path)?;
+                continue;
+            }
+
+            let result = File::open(&path)
+                .and_then(|file| self.add::<E, _>(path.clone(), BufReader::new(file)));
+            if let Err(err) = result {
+                warn!("Skipping {path:?}: {err}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Indexes the package read from `reader` as the file at `path`. Returns whether it was
+    /// an `E` endian package.
+    pub fn add<E, R>(&mut self, path: PathBuf, mut reader: R) -> io::Result<bool>
+    where
+        E: ByteOrder,
+        R: Read + Seek,
+    {
+        if detect(&mut reader)? != FileKind::Package(endianness_of::<E>()) {
+            debug!("{path:?} is not a package");
+            return Ok(false);
+        }
+
+        let header = read_package_header::<E, _>(&mut PackageReader::new(reader))?;
+        self.add_header(path, &header);
+
+        Ok(true)
+    }
+
+    fn add_header(&mut self, path: PathBuf, header: &PackageHeader) {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        debug!("Indexed {name} at {path:?}");
+
+        self.packages
+            .entry(name.to_ascii_lowercase())
+            .or_default()
+            .push(IndexedPackage {
+                name,
+                path,
+                guid: header.guid,
+                version: header.version,
+                generations: header.generations.clone(),
+            });
+    }
+
+    /// Every file found for the package `name`.
+    pub fn packages(&self, name: &str) -> &[IndexedPackage] {
+        self.packages
+            .get(&name.to_ascii_lowercase())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The file for the package `name`. Returns `None` if several files have the name; use
+    /// [`PackageIndexer::find_by_guid`] to pick between them.
+    pub fn find(&self, name: &str) -> Option<&IndexedPackage> {
+        match self.packages(name) {
+            [package] => Some(package),
+            [] => None,
+            packages => {
+                debug!("{} files hold a package named {name}", packages.len());
+                None
+            }
+        }
+    }
+
+    /// The file for the package `name` whose GUID is `guid`.
+    pub fn find_by_guid(&self, name: &str, guid: Guid) -> Option<&IndexedPackage> {
+        self.packages(name)
+            .iter()
+            .find(|package| package.guid == guid)
+    }
+
+    /// Every indexed file, ordered by package name.
+    pub fn iter(&self) -> impl Iterator<Item = &IndexedPackage> {
+        self.packages.values().flatten()
+    }
+
+    /// Number of indexed files.
+    pub fn len(&self) -> usize {
+        self.packages.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::test_support::PackageBuilder;
+
+    #[test]
+    fn indexes_copies_of_a_package() {
+        let mut builder = PackageBuilder::new();
+        let class = builder.core_class("Class");
+        builder.export(class, 0, "Actor", Vec::new());
+        let data = builder.build();
+
+        let mut indexer = PackageIndexer::new();
+        let path = PathBuf::from("System/Engine.u");
+        assert!(
+            indexer
+                .add::<LittleEndian, _>(path.clone(), Cursor::new(&data))
+                .unwrap()
+        );
+        assert!(
+            !indexer
+                .add::<LittleEndian, _>("System/Engine.int".into(), Cursor::new(b"[Public]"))
+                .unwrap()
+        );
+
+        let engine = indexer.find("engine").unwrap().clone();
+        assert_eq!(engine.name, "Engine");
+        assert_eq!(engine.path, path);
+        assert_eq!(engine.generations.len(), 1);
+
+        // A patched copy elsewhere in the install
+        let mut header =
+            read_package_header::<LittleEndian, _>(&mut PackageReader::new(Cursor::new(&data)))
+                .unwrap();
+        header.guid = Guid::new(1, 2, 3, 4);
+        indexer.add_header("Patch/Engine.u".into(), &header);
+
+        assert_eq!(indexer.len(), 2);
+        assert!(indexer.find("Engine").is_none());
+        assert_eq!(indexer.find_by_guid("Engine", engine.guid), Some(&engine));
+        assert_eq!(
+            indexer
+                .find_by_guid("Engine", header.guid)
+                .map(|package| package.path.as_path()),
+            Some(Path::new("Patch/Engine.u"))
+        );
+    }
+}
@@ -1,12 +1,93 @@
 pub mod de;
-// pub mod ser;
+pub mod ser;
 
+pub(crate) mod annotate;
+#[cfg(feature = "tokio")]
+pub(crate) mod async_io;
+pub(crate) mod cache;
 pub(crate) mod common;
+pub(crate) mod constants;
+pub(crate) mod detect;
+pub(crate) mod dump;
+pub(crate) mod edit;
+pub(crate) mod graph;
+pub(crate) mod guid;
+pub(crate) mod headers;
+pub(crate) mod hierarchy;
+pub(crate) mod indexer;
+pub(crate) mod localize;
+pub(crate) mod manifest;
 pub(crate) mod object;
+pub(crate) mod observer;
+pub(crate) mod package;
+pub(crate) mod packed;
+pub(crate) mod pattern;
+pub(crate) mod plan;
+pub(crate) mod postload;
+pub(crate) mod profile;
 pub(crate) mod reader;
+pub(crate) mod report;
 pub(crate) mod runtime;
+pub(crate) mod snapshot;
+pub(crate) mod stats;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub(crate) mod validate;
+pub(crate) mod warnings;
 
 pub(crate) const PKG_TAG: u32 = 0x9e2a83c1;
 pub(crate) const LIN_FILE_TABLE_TAG: u32 = 0x9FE3C5A3;
 
-pub use common::ExportedData;
+pub use annotate::{AnnotatingReader, FieldAnnotation, ObjectAnnotations};
+#[cfg(feature = "tokio")]
+pub use async_io::{decompress_linear_file_async, read_package_async};
+pub use cache::ExportCache;
+pub use common::{ExportRead, ExportedData, ExportedDataError, IoOp};
+pub use constants::{ConstantValue, ScriptConstant, ScriptConstants};
+pub use detect::{FileKind, detect};
+pub use dump::{ClassDump, load_class, load_classes, load_classes_with_profile};
+pub use edit::{GenerationPolicy, PackageEditor, ScriptPatch};
+pub use graph::{Dependency, DependencyGraph, DependencyKind, Referencer};
+pub use guid::{Guid, ParseGuidError};
+pub use headers::{ClassHeader, FunctionDecl, VariableDecl};
+pub use hierarchy::{ClassHierarchy, ClassInfo};
+pub use indexer::{IndexedPackage, PackageIndexer};
+pub use localize::{LocalizationTable, LocalizedString, StringKind};
+pub use manifest::{Change, ChangeKind, EntryChange, EntryHash, FileManifest, Manifest};
+pub use object::builtins::{
+    Class, ClassDependency, ClassFlags, Function, FunctionFlags, Property, PropertyFlags,
+    PropertyInfo, Struct, TextBuffer, TextData,
+};
+pub use object::internal::class_ref::ClassRef;
+pub use object::internal::fname::FName;
+pub use object::internal::natives::{
+    NativeFunction, NativeFunctionTable, NativeKind, ParseNativeTableError,
+};
+pub use object::internal::object_ref::ReferencePolicy;
+pub use object::internal::probes::ProbeNameTable;
+pub use object::internal::script::cfg::{BasicBlock, ControlFlowGraph, UnresolvedJump};
+pub use object::internal::script::{
+    Callee, Const, DebugInfo, Expr, ExprToken, Label, LetKind, ScriptObject, Statement, constants,
+    serialize_expr, serialize_exprs,
+};
+pub use object::{
+    BuiltinObject, ObjectFlags, PropertyValue, RcUnrealObject, TypedObject, UObjectKind,
+    WeakUnrealObject,
+};
+pub use observer::{LinkerEvent, LoadObserver, ObjEvent};
+pub use package::{Package, ScriptSource};
+pub use packed::{read_packed_int, write_packed_int};
+pub use pattern::ExportPattern;
+pub use plan::Plan;
+pub use postload::PostLoadHook;
+pub use profile::{
+    ArrayDimWidth, Endianness, FormatProfile, HeaderExtensionLayout, LinCompression, Quirks,
+    ScriptSizeModel, StringEncoding,
+};
+pub use reader::{BufferingReader, Divergence};
+pub use report::{LoadReport, ObjectLoadReport};
+pub use runtime::{LoadOptions, SerialSizeMismatch, UnloadStats};
+pub use snapshot::{FrozenData, FrozenObject, ObjectId, Snapshot};
+pub use stats::{ClassStats, ExportSize, LinkerUsage, PackageStats};
+pub use validate::{PackageTable, ValidationFinding};
+pub use warnings::{LoadWarning, WarningCallback, WarningKind, Warnings};
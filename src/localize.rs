@@ -0,0 +1,315 @@
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// Where a translatable string is stored in a package.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum StringKind {
+    /// An entry in the name table. Keyed by its index, which is kept when it's renamed.
+    Name,
+    /// The value of a `Const` export. Keyed by the export's full name.
+    Const,
+    /// The text held by a `TextBuffer` export. Keyed by the export's full name.
+    TextBuffer,
+}
+
+impl StringKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StringKind::Name => "name",
+            StringKind::Const => "const",
+            StringKind::TextBuffer => "text_buffer",
+        }
+    }
+}
+
+impl fmt::Display for StringKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for StringKind {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(StringKind::Name),
+            "const" => Ok(StringKind::Const),
+            "text_buffer" => Ok(StringKind::TextBuffer),
+            other => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown string kind {other:?}"),
+            )),
+        }
+    }
+}
+
+/// A single translatable string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalizedString {
+    pub kind: StringKind,
+    pub key: String,
+    /// The string as it's stored in the package
+    pub source: String,
+    /// The replacement for `source`. Empty if the string hasn't been translated.
+    pub translation: String,
+}
+
+/// Every translatable string in a package, in a form which can be written out for
+/// translators and read back in.
+///
+/// Tables are written in the gettext PO format. Each string is one message whose context is
+/// `<kind>:<key>`, so `msgid` holds the source string and `msgstr` the translation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalizationTable {
+    pub entries: Vec<LocalizedString>,
+}
+
+impl LocalizationTable {
+    /// The entry for the string of `kind` at `key`.
+    pub fn get(&self, kind: StringKind, key: &str) -> Option<&LocalizedString> {
+        self.entries
+            .iter()
+            .find(|entry| entry.kind == kind && entry.key == key)
+    }
+
+    /// Entries which have a translation.
+    pub fn translated(&self) -> impl Iterator<Item = &LocalizedString> {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.translation.is_empty())
+    }
+
+    pub fn write_po<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                writeln!(writer)?;
+            }
+
+            writeln!(writer, "#. {} {}", entry.kind, entry.key)?;
+            write_po_field(
+                &mut writer,
+                "msgctxt",
+                &format!("{}:{}", entry.kind, entry.key),
+            )?;
+            write_po_field(&mut writer, "msgid", &entry.source)?;
+            write_po_field(&mut writer, "msgstr", &entry.translation)?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a table written by [`LocalizationTable::write_po`]. Comments are ignored, and
+    /// strings may be split across several quoted lines.
+    pub fn read_po(po: &str) -> io::Result<Self> {
+        let invalid = |line: usize, message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {message}", line + 1),
+            )
+        };
+
+        let mut table = LocalizationTable::default();
+        let mut message = PoMessage::default();
+        // The field which continuation lines are appended to
+        let mut current = None;
+
+        for (line_num, line) in po.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (field, quoted) = match line.split_once(char::is_whitespace) {
+                Some((field @ ("msgctxt" | "msgid" | "msgstr"), quoted)) => {
+                    (Some(field), quoted.trim())
+                }
+                _ if line.starts_with('"') => (None, line),
+                _ => return Err(invalid(line_num, format!("unexpected line {line:?}"))),
+            };
+            let value = unescape_po(quoted).map_err(|e| invalid(line_num, e))?;
+
+            let Some(field) = field else {
+                let Some(current) = current else {
+                    return Err(invalid(line_num, "string outside of a message".to_owned()));
+                };
+                message
+                    .field_mut(current)
+                    .get_or_insert_default()
+                    .push_str(&value);
+                continue;
+            };
+
+            // A new context starts the next message
+            if field == "msgctxt" && message.msgctxt.is_some() {
+                table
+                    .entries
+                    .push(message.finish().map_err(|e| invalid(line_num, e))?);
+                message = PoMessage::default();
+            }
+            if message.field_mut(field).is_some() {
+                return Err(invalid(line_num, format!("duplicate {field}")));
+            }
+
+            *message.field_mut(field) = Some(value);
+            current = Some(field);
+        }
+
+        if message.msgctxt.is_some() {
+            table.entries.push(
+                message
+                    .finish()
+                    .map_err(|e| invalid(po.lines().count(), e))?,
+            );
+        }
+
+        Ok(table)
+    }
+}
+
+/// A message's fields as they're read.
+#[derive(Default)]
+struct PoMessage {
+    msgctxt: Option<String>,
+    msgid: Option<String>,
+    msgstr: Option<String>,
+}
+
+impl PoMessage {
+    fn field_mut(&mut self, field: &str) -> &mut Option<String> {
+        match field {
+            "msgctxt" => &mut self.msgctxt,
+            "msgid" => &mut self.msgid,
+            _ => &mut self.msgstr,
+        }
+    }
+
+    fn finish(self) -> Result<LocalizedString, String> {
+        let context = self.msgctxt.unwrap_or_default();
+        let Some((kind, key)) = context.split_once(':') else {
+            return Err(format!("message context {context:?} has no key"));
+        };
+
+        Ok(LocalizedString {
+            kind: kind.parse().map_err(|e: io::Error| e.to_string())?,
+            key: key.to_owned(),
+            source: self
+                .msgid
+                .ok_or_else(|| format!("message {context:?} has no msgid"))?,
+            translation: self.msgstr.unwrap_or_default(),
+        })
+    }
+}
+
+/// Writes `value` as a quoted PO string. Multi-line values are split after each newline so
+/// they're easier to edit.
+fn write_po_field<W: Write>(writer: &mut W, field: &str, value: &str) -> io::Result<()> {
+    let lines = value.split_inclusive('\n').collect::<Vec<_>>();
+    if lines.len() <= 1 {
+        return writeln!(writer, "{field} \"{}\"", escape_po(value));
+    }
+
+    writeln!(writer, "{field} \"\"")?;
+    for line in lines {
+        writeln!(writer, "\"{}\"", escape_po(line))?;
+    }
+
+    Ok(())
+}
+
+fn escape_po(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape_po(quoted: &str) -> Result<String, String> {
+    let Some(inner) = quoted
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'))
+    else {
+        return Err(format!("expected a quoted string, found {quoted:?}"));
+    };
+
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('\\') => value.push('\\'),
+            Some('"') => value.push('"'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            other => {
+                return Err(format!(
+                    "invalid escape sequence \\{}",
+                    other.unwrap_or(' ')
+                ));
+            }
+        }
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn po_round_trips() {
+        let table = LocalizationTable {
+            entries: vec![
+                LocalizedString {
+                    kind: StringKind::Name,
+                    key: "3".to_owned(),
+                    source: "Pawn".to_owned(),
+                    translation: String::new(),
+                },
+                LocalizedString {
+                    kind: StringKind::TextBuffer,
+                    key: "Engine.Pawn.ScriptText".to_owned(),
+                    source: "class Pawn\n\textends \"Actor\";\n".to_owned(),
+                    translation: "C:\\Pawn".to_owned(),
+                },
+            ],
+        };
+
+        let mut po = Vec::new();
+        table.write_po(&mut po).unwrap();
+        let po = String::from_utf8(po).unwrap();
+        assert!(po.contains("msgctxt \"text_buffer:Engine.Pawn.ScriptText\""));
+        assert!(po.contains("\"\\textends \\\"Actor\\\";\\n\""));
+
+        assert_eq!(LocalizationTable::read_po(&po).unwrap(), table);
+    }
+
+    #[test]
+    fn rejects_malformed_po() {
+        let err = LocalizationTable::read_po("msgctxt \"name:1\"\nmsgid \"None\nmsgstr \"\"")
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2:"));
+
+        let err = LocalizationTable::read_po("msgctxt \"bogus:1\"\nmsgid \"x\"").unwrap_err();
+        assert!(err.to_string().contains("unknown string kind"));
+    }
+}
@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, Write},
+    path::Path,
+};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    de::{read_linear_file, read_linear_file_table, read_package},
+    detect::{FileKind, detect},
+    profile::{Endianness, FormatProfile},
+    reader::PackageReader,
+};
+
+/// Content hashes of a set of package and linear files, used to tell which exports a mod
+/// has changed. See [`Manifest::verify`].
+///
+/// Hashes are CRC-32s: they catch modified data, not deliberate collisions.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// In the order the files were given
+    pub files: Vec<FileManifest>,
+}
+
+/// Hashes of a single file. See [`FileManifest::from_path`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileManifest {
+    /// Path of the file, as given when the manifest was made
+    pub path: String,
+    pub size: u64,
+    pub crc32: u32,
+    /// Each export of a package, named by its full path, in export table order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exports: Vec<EntryHash>,
+    /// Each file listed in a linear file's file table, in table order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<EntryHash>,
+}
+
+/// The hash of an export or linear file member.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryHash {
+    pub name: String,
+    pub size: u64,
+    pub crc32: u32,
+}
+
+/// A difference between a [`Manifest`] and the files it was made from, as returned by
+/// [`Manifest::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    /// Path of the file, as recorded in the manifest
+    pub file: String,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// The file no longer exists.
+    FileMissing,
+    /// The file changed, but none of its exports or members did, such as when only its
+    /// name or import tables were edited.
+    FileModified,
+    /// An export, named by its full path, changed.
+    Export(String, EntryChange),
+    /// A file in a linear file's file table changed.
+    Member(String, EntryChange),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (entry, name, change) = match &self.kind {
+            ChangeKind::FileMissing => return write!(f, "{}: missing", self.file),
+            ChangeKind::FileModified => return write!(f, "{}: modified", self.file),
+            ChangeKind::Export(name, change) => ("export", name, change),
+            ChangeKind::Member(name, change) => ("member", name, change),
+        };
+        let change = match change {
+            EntryChange::Added => "added",
+            EntryChange::Removed => "removed",
+            EntryChange::Modified => "modified",
+        };
+
+        write!(f, "{}: {entry} {name} {change}", self.file)
+    }
+}
+
+impl Manifest {
+    /// Hashes each file in `paths`. Linear files are read with `profile`, or
+    /// [`FormatProfile::splinter_cell`] if it's `None`.
+    pub fn from_paths<P: AsRef<Path>>(
+        paths: &[P],
+        profile: Option<&FormatProfile>,
+    ) -> io::Result<Self> {
+        let files = paths
+            .iter()
+            .map(|path| FileManifest::from_path(path.as_ref(), profile))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Manifest { files })
+    }
+
+    /// Hashes the files this manifest lists again and reports how they've changed since.
+    pub fn verify(&self, profile: Option<&FormatProfile>) -> io::Result<Vec<Change>> {
+        let mut current = Manifest::default();
+        for file in &self.files {
+            match FileManifest::from_path(Path::new(&file.path), profile) {
+                Ok(file) => current.files.push(file),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(self.diff(&current))
+    }
+
+    /// How the files in `current`, a later manifest of the same files, differ from this
+    /// one. Files are matched by path, and files which are only in `current` are ignored.
+    pub fn diff(&self, current: &Manifest) -> Vec<Change> {
+        let mut changes = Vec::new();
+        for file in &self.files {
+            let change = |kind| Change {
+                file: file.path.clone(),
+                kind,
+            };
+
+            let Some(now) = current.files.iter().find(|now| now.path == file.path) else {
+                changes.push(change(ChangeKind::FileMissing));
+                continue;
+            };
+            if (file.size, file.crc32) == (now.size, now.crc32) {
+                continue;
+            }
+
+            let before = changes.len();
+            for (name, entry_change) in diff_entries(&file.exports, &now.exports) {
+                changes.push(change(ChangeKind::Export(name, entry_change)));
+            }
+            for (name, entry_change) in diff_entries(&file.members, &now.members) {
+                changes.push(change(ChangeKind::Member(name, entry_change)));
+            }
+            if changes.len() == before {
+                changes.push(change(ChangeKind::FileModified));
+            }
+        }
+
+        changes
+    }
+}
+
+/// The entries removed from or modified in `before`, in its order, followed by those added
+/// in `after`, in its order.
+fn diff_entries(before: &[EntryHash], after: &[EntryHash]) -> Vec<(String, EntryChange)> {
+    let by_name = |entries: &[EntryHash]| {
+        entries
+            .iter()
+            .map(|entry| (entry.name.clone(), (entry.size, entry.crc32)))
+            .collect::<HashMap<_, _>>()
+    };
+    let before_by_name = by_name(before);
+    let after_by_name = by_name(after);
+
+    let mut changes = Vec::new();
+    for entry in before {
+        match after_by_name.get(&entry.name) {
+            None => changes.push((entry.name.clone(), EntryChange::Removed)),
+            Some(hash) if *hash != (entry.size, entry.crc32) => {
+                changes.push((entry.name.clone(), EntryChange::Modified))
+            }
+            Some(_) => {}
+        }
+    }
+    for entry in after {
+        if !before_by_name.contains_key(&entry.name) {
+            changes.push((entry.name.clone(), EntryChange::Added));
+        }
+    }
+
+    changes
+}
+
+impl FileManifest {
+    /// Hashes the file at `path`, along with each export if it's a package or each member
+    /// if it's a linear file. Its kind and byte order are detected from its leading bytes,
+    /// and files of other kinds are only hashed as a whole.
+    ///
+    /// Exports are streamed one at a time, so large packages aren't held in memory. Linear
+    /// files are decompressed in full.
+    pub fn from_path(path: &Path, profile: Option<&FormatProfile>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (size, crc32) = hash_reader(&mut reader)?;
+        reader.rewind()?;
+
+        let mut manifest = FileManifest {
+            path: path.display().to_string(),
+            size,
+            crc32,
+            exports: Vec::new(),
+            members: Vec::new(),
+        };
+
+        let package_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match detect(&mut reader)? {
+            FileKind::Package(Endianness::Little) => {
+                manifest.exports = hash_exports::<LittleEndian>(&package_name, reader)?;
+            }
+            FileKind::Package(Endianness::Big) => {
+                manifest.exports = hash_exports::<BigEndian>(&package_name, reader)?;
+            }
+            FileKind::Linear(Endianness::Little)
+            | FileKind::CompressedLinear(Endianness::Little) => {
+                manifest.members = hash_members::<LittleEndian>(reader, profile)?;
+            }
+            FileKind::Linear(Endianness::Big) | FileKind::CompressedLinear(Endianness::Big) => {
+                manifest.members = hash_members::<BigEndian>(reader, profile)?;
+            }
+            FileKind::Unknown => {}
+        }
+
+        Ok(manifest)
+    }
+}
+
+/// Hashes a package's exports, named by their full path within `package_name`.
+fn hash_exports<E: ByteOrder>(
+    package_name: &str,
+    reader: BufReader<File>,
+) -> io::Result<Vec<EntryHash>> {
+    let mut reader = PackageReader::new(reader);
+    let package = read_package::<E, _>(&mut reader)?;
+
+    let mut hashes = Vec::with_capacity(package.exports.len());
+    let mut stream = package.stream_exports(reader);
+    while let Some(export) = stream.next_export() {
+        let mut export = export?;
+        let (size, crc32) = hash_reader(&mut export.data)?;
+        hashes.push(EntryHash {
+            name: export.export.path(package_name, &package),
+            size,
+            crc32,
+        });
+    }
+
+    Ok(hashes)
+}
+
+/// Hashes the data each entry of a linear file's file table points to.
+fn hash_members<E: ByteOrder>(
+    mut reader: BufReader<File>,
+    profile: Option<&FormatProfile>,
+) -> io::Result<Vec<EntryHash>> {
+    let profile = profile
+        .cloned()
+        .unwrap_or_else(FormatProfile::splinter_cell);
+    let data = read_linear_file::<E, _>(&mut reader, &profile)?;
+    let file_table =
+        read_linear_file_table::<E, _>(&mut PackageReader::new(Cursor::new(data.as_slice())))?;
+
+    file_table
+        .into_iter()
+        .map(|entry| {
+            let start = entry.offset as usize;
+            let bytes = data
+                .get(start..start + entry.len as usize)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "file table entry {} ({:#X}..{:#X}) extends past the end of the linear file ({:#X})",
+                            entry.name,
+                            entry.offset,
+                            start + entry.len as usize,
+                            data.len()
+                        ),
+                    )
+                })?;
+
+            Ok(EntryHash {
+                name: entry.name,
+                size: bytes.len() as u64,
+                crc32: crc32fast::hash(bytes),
+            })
+        })
+        .collect()
+}
+
+/// A sink which hashes everything written to it.
+struct HashWriter(crc32fast::Hasher);
+
+impl Write for HashWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The number of bytes left in `reader` and their CRC-32.
+fn hash_reader(reader: &mut impl Read) -> io::Result<(u64, u32)> {
+    let mut writer = HashWriter(crc32fast::Hasher::new());
+    let size = io::copy(reader, &mut writer)?;
+
+    Ok((size, writer.0.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        LIN_FILE_TABLE_TAG,
+        ser::write_var_string,
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    fn test_package(second: &str) -> Vec<u8> {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let group = builder.export(0, 0, "Consts", Vec::new());
+        for (name, value) in [("First", "1"), ("Second", second)] {
+            let data = DataBuilder::field(0, 0).string(value).build();
+            builder.export(const_class, group, name, data);
+        }
+
+        builder.build()
+    }
+
+    /// A decompressed linear file whose only member is `package`.
+    fn test_linear_file(package: &[u8]) -> Vec<u8> {
+        let mut lin = Vec::new();
+        lin.extend(0u32.to_le_bytes());
+        write_var_string(&mut lin, "Maps\\Test.lin").unwrap();
+        lin.extend(LIN_FILE_TABLE_TAG.to_le_bytes());
+        lin.extend([0; 0x10]);
+        lin.push(1);
+        write_var_string(&mut lin, "Test.u").unwrap();
+
+        // The member starts right after its entry's offset, length, and unknown field
+        let offset = lin.len() + 12;
+        lin.extend((offset as u32).to_le_bytes());
+        lin.extend((package.len() as u32).to_le_bytes());
+        lin.extend(0u32.to_le_bytes());
+        lin.extend(package);
+
+        lin
+    }
+
+    #[test]
+    fn detects_modified_exports_and_members() {
+        let dir = std::env::temp_dir().join(format!("unrealin-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let package_path = dir.join("Test.u");
+        let lin_path = dir.join("Test.lin");
+        let missing_path = dir.join("Missing.u");
+        std::fs::write(&package_path, test_package("2")).unwrap();
+        std::fs::write(&lin_path, test_linear_file(&test_package("2"))).unwrap();
+        std::fs::write(&missing_path, test_package("2")).unwrap();
+
+        let manifest = Manifest::from_paths(&[&package_path, &lin_path, &missing_path], None);
+        let unchanged = manifest.as_ref().unwrap().verify(None);
+
+        std::fs::write(&package_path, test_package("3")).unwrap();
+        std::fs::write(&lin_path, test_linear_file(&test_package("3"))).unwrap();
+        std::fs::remove_file(&missing_path).unwrap();
+        let changes = manifest.as_ref().unwrap().verify(None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let manifest = manifest.unwrap();
+        let exports = manifest.files[0]
+            .exports
+            .iter()
+            .map(|export| export.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            exports,
+            ["Test.Consts", "Test.Consts.First", "Test.Consts.Second"]
+        );
+        assert_eq!(manifest.files[1].members[0].name, "Test.u");
+        assert!(unchanged.unwrap().is_empty());
+
+        let changes = changes
+            .unwrap()
+            .iter()
+            .map(|change| change.to_string().replace(&dir.display().to_string(), ""))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                "/Test.u: export Test.Consts.Second modified",
+                "/Test.lin: member Test.u modified",
+                "/Missing.u: missing",
+            ]
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(serde_json::from_str::<Manifest>(&json).unwrap(), manifest);
+    }
+}
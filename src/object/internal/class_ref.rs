@@ -0,0 +1,82 @@
+use std::{fmt, iter, rc::Rc};
+
+use crate::object::{RcUnrealObject, UObjectKind, builtins::Field};
+
+/// The class an object is an instance of.
+///
+/// An object's [`UObjectKind`] is only ever one of the builtin classes this crate knows how to
+/// deserialize, so instances of script classes such as `Engine.Actor` are loaded as the
+/// nearest builtin. This instead refers to the loaded `Class` object itself, so the object's
+/// real class and everything it extends can be checked.
+#[derive(Debug, Clone)]
+pub struct ClassRef(RcUnrealObject);
+
+impl ClassRef {
+    pub fn new(class: RcUnrealObject) -> Self {
+        ClassRef(class)
+    }
+
+    /// The loaded `Class` object.
+    pub fn object(&self) -> &RcUnrealObject {
+        &self.0
+    }
+
+    pub fn name(&self) -> Rc<str> {
+        Rc::clone(&self.0.borrow().base_object().name)
+    }
+
+    /// The class this one extends, or `None` if it extends nothing or its super class
+    /// hasn't been linked.
+    pub fn super_class(&self) -> Option<ClassRef> {
+        let class = self.0.borrow();
+        let field = class
+            .parent_of_kind(UObjectKind::Field)?
+            .as_any()
+            .downcast_ref::<Field>()?;
+
+        field.super_field().map(ClassRef)
+    }
+
+    /// This class followed by each class it extends, most derived first.
+    pub fn hierarchy(&self) -> impl Iterator<Item = ClassRef> {
+        iter::successors(Some(self.clone()), ClassRef::super_class)
+    }
+
+    /// The class's full name, e.g. `Engine.Actor`.
+    pub fn full_name(&self) -> String {
+        self.0.borrow().base_object().full_name()
+    }
+
+    /// Whether this class is `name` or extends it, ignoring ASCII case. `name` may be
+    /// qualified with its package, e.g. `Engine.Actor`, to tell apart classes which share a
+    /// name.
+    pub fn is_child_of(&self, name: &str) -> bool {
+        let qualified = name.contains('.');
+        self.hierarchy().any(|class| {
+            if qualified {
+                class.full_name().eq_ignore_ascii_case(name)
+            } else {
+                class.name().eq_ignore_ascii_case(name)
+            }
+        })
+    }
+
+    /// Whether this class is `class` or extends it.
+    pub fn is_child_of_class(&self, class: &ClassRef) -> bool {
+        self.hierarchy().any(|ancestor| ancestor == *class)
+    }
+}
+
+impl PartialEq for ClassRef {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ClassRef {}
+
+impl fmt::Display for ClassRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name())
+    }
+}
@@ -1,4 +1,8 @@
+use std::rc::Rc;
+
 use crate::{
+    annotate::annotated,
+    de::Linker,
     object::{DeserializeUnrealObject, NAME_NONE},
     reader::UnrealReadExt,
 };
@@ -11,9 +15,19 @@ impl FName {
         FName(idx)
     }
 
+    /// Index of this name in its package's name table.
+    pub fn index(&self) -> i32 {
+        self.0
+    }
+
     pub fn is_none(&self) -> bool {
         self.0 as usize == NAME_NONE
     }
+
+    /// Resolves this name against the linker's interned name table.
+    pub(crate) fn resolve(&self, linker: &Linker) -> Rc<str> {
+        linker.name(self.0)
+    }
 }
 
 impl DeserializeUnrealObject for FName {
@@ -27,7 +41,7 @@ impl DeserializeUnrealObject for FName {
         E: byteorder::ByteOrder,
         R: crate::reader::LinRead,
     {
-        *self = FName::from_raw(reader.read_packed_int()?);
+        *self = annotated!(reader, "name", FName::from_raw(reader.read_packed_int()?));
 
         Ok(())
     }
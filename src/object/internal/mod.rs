@@ -1,3 +1,7 @@
+pub mod class_ref;
 pub mod fname;
+pub mod natives;
+pub mod object_ref;
+pub mod probes;
 pub mod property;
 pub mod script;
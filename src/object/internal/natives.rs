@@ -0,0 +1,359 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt, str::FromStr};
+
+/// How a native function is invoked from script.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NativeKind {
+    Function,
+    /// Binary operator
+    Operator,
+    /// Unary operator placed before its operand
+    PreOperator,
+    /// Unary operator placed after its operand
+    PostOperator,
+}
+
+impl NativeKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            NativeKind::Function => "function",
+            NativeKind::Operator => "operator",
+            NativeKind::PreOperator => "preoperator",
+            NativeKind::PostOperator => "postoperator",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeFunction {
+    pub name: String,
+    pub kind: NativeKind,
+}
+
+/// Maps the index of a native function (as used by script bytecode and `Function::inative`)
+/// to its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeFunctionTable {
+    functions: BTreeMap<u16, NativeFunction>,
+}
+
+/// Natives declared in the stock UE2 `Object` and `Actor` classes.
+const UE2_NATIVES: &[(u16, &str, NativeKind)] = {
+    use NativeKind::*;
+
+    &[
+        // Object
+        (112, "$", Operator),
+        (113, "GotoState", Function),
+        (114, "==", Operator),
+        (115, "<", Operator),
+        (116, ">", Operator),
+        (117, "Enable", Function),
+        (118, "Disable", Function),
+        (119, "!=", Operator),
+        (120, "<=", Operator),
+        (121, ">=", Operator),
+        (122, "==", Operator),
+        (123, "!=", Operator),
+        (124, "~=", Operator),
+        (125, "Len", Function),
+        (126, "InStr", Function),
+        (127, "Mid", Function),
+        (128, "Left", Function),
+        (129, "!", PreOperator),
+        (130, "&&", Operator),
+        (131, "^^", Operator),
+        (132, "||", Operator),
+        (133, "*=", Operator),
+        (134, "/=", Operator),
+        (135, "+=", Operator),
+        (136, "-=", Operator),
+        (137, "++", PreOperator),
+        (138, "--", PreOperator),
+        (139, "++", PostOperator),
+        (140, "--", PostOperator),
+        (141, "~", PreOperator),
+        (142, "==", Operator),
+        (143, "-", PreOperator),
+        (144, "*", Operator),
+        (145, "/", Operator),
+        (146, "+", Operator),
+        (147, "-", Operator),
+        (148, "<<", Operator),
+        (149, ">>", Operator),
+        (150, "<", Operator),
+        (151, ">", Operator),
+        (152, "<=", Operator),
+        (153, ">=", Operator),
+        (154, "==", Operator),
+        (155, "!=", Operator),
+        (156, "&", Operator),
+        (157, "^", Operator),
+        (158, "|", Operator),
+        (159, "*=", Operator),
+        (160, "/=", Operator),
+        (161, "+=", Operator),
+        (162, "-=", Operator),
+        (163, "++", PreOperator),
+        (164, "--", PreOperator),
+        (165, "++", PostOperator),
+        (166, "--", PostOperator),
+        (167, "Rand", Function),
+        (168, "@", Operator),
+        (169, "-", PreOperator),
+        (170, "**", Operator),
+        (171, "*", Operator),
+        (172, "/", Operator),
+        (173, "%", Operator),
+        (174, "+", Operator),
+        (175, "-", Operator),
+        (176, "<", Operator),
+        (177, ">", Operator),
+        (178, "<=", Operator),
+        (179, ">=", Operator),
+        (180, "==", Operator),
+        (181, "!=", Operator),
+        (182, "*=", Operator),
+        (183, "/=", Operator),
+        (184, "+=", Operator),
+        (185, "-=", Operator),
+        (186, "Abs", Function),
+        (187, "Sin", Function),
+        (188, "Cos", Function),
+        (189, "Tan", Function),
+        (190, "Atan", Function),
+        (191, "Exp", Function),
+        (192, "Loge", Function),
+        (193, "Sqrt", Function),
+        (194, "Square", Function),
+        (195, "FRand", Function),
+        (196, ">>>", Operator),
+        (203, "!=", Operator),
+        (210, "~=", Operator),
+        (211, "-", PreOperator),
+        (212, "*", Operator),
+        (213, "*", Operator),
+        (214, "/", Operator),
+        (215, "+", Operator),
+        (216, "-", Operator),
+        (217, "==", Operator),
+        (218, "!=", Operator),
+        (219, "Dot", Operator),
+        (220, "Cross", Operator),
+        (221, "*=", Operator),
+        (222, "/=", Operator),
+        (223, "+=", Operator),
+        (224, "-=", Operator),
+        (225, "VSize", Function),
+        (226, "Normal", Function),
+        (227, "Invert", Function),
+        (229, "GetAxes", Function),
+        (230, "GetUnAxes", Function),
+        (231, "Log", Function),
+        (232, "Warn", Function),
+        (234, "Right", Function),
+        (235, "Caps", Function),
+        (236, "Chr", Function),
+        (237, "Asc", Function),
+        (242, "==", Operator),
+        (243, "!=", Operator),
+        (244, "FMin", Function),
+        (245, "FMax", Function),
+        (246, "FClamp", Function),
+        (247, "Lerp", Function),
+        (249, "Min", Function),
+        (250, "Max", Function),
+        (251, "Clamp", Function),
+        (252, "VRand", Function),
+        (254, "==", Operator),
+        (255, "!=", Operator),
+        (258, "ClassIsChildOf", Function),
+        (275, "<<", Operator),
+        (276, ">>", Operator),
+        (281, "IsInState", Function),
+        (284, "GetStateName", Function),
+        (287, "*", Operator),
+        (288, "*", Operator),
+        (289, "/", Operator),
+        (290, "*=", Operator),
+        (291, "/=", Operator),
+        (296, "*", Operator),
+        (316, "+", Operator),
+        (317, "-", Operator),
+        (318, "+=", Operator),
+        (319, "-=", Operator),
+        (320, "RotRand", Function),
+        (536, "SaveConfig", Function),
+        // Actor
+        (256, "Sleep", Function),
+        (261, "FinishAnim", Function),
+        (262, "SetCollision", Function),
+        (266, "Move", Function),
+        (267, "SetLocation", Function),
+        (272, "SetOwner", Function),
+        (277, "Trace", Function),
+        (278, "Spawn", Function),
+        (279, "Destroy", Function),
+        (283, "SetCollisionSize", Function),
+        (298, "SetBase", Function),
+        (299, "SetRotation", Function),
+    ]
+};
+
+impl Default for NativeFunctionTable {
+    fn default() -> Self {
+        Self::ue2()
+    }
+}
+
+impl NativeFunctionTable {
+    /// A table with no entries.
+    pub fn empty() -> Self {
+        NativeFunctionTable {
+            functions: BTreeMap::new(),
+        }
+    }
+
+    /// The natives declared by the stock UE2 `Object` and `Actor` classes.
+    pub fn ue2() -> Self {
+        let mut table = Self::empty();
+        for (index, name, kind) in UE2_NATIVES {
+            table.insert(*index, *name, *kind);
+        }
+
+        table
+    }
+
+    /// Adds or replaces the native at `index`.
+    pub fn insert(&mut self, index: u16, name: impl Into<String>, kind: NativeKind) {
+        self.functions.insert(
+            index,
+            NativeFunction {
+                name: name.into(),
+                kind,
+            },
+        );
+    }
+
+    /// Adds every entry in `other`, replacing any existing entries with the same index.
+    pub fn extend(&mut self, other: NativeFunctionTable) {
+        self.functions.extend(other.functions);
+    }
+
+    pub fn get(&self, index: u16) -> Option<&NativeFunction> {
+        self.functions.get(&index)
+    }
+
+    /// Returns the name of the native at `index`, or a placeholder such as `Native_300` if
+    /// it isn't in the table.
+    pub fn display_name(&self, index: u16) -> Cow<'_, str> {
+        match self.get(index) {
+            Some(function) => Cow::Borrowed(function.name.as_str()),
+            None => Cow::Owned(format!("Native_{index}")),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseNativeTableError {
+    line: usize,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseNativeTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseNativeTableError {}
+
+/// Parses a game-specific table. Each non-empty line is `<index> <kind> <name>` where `kind`
+/// is one of `function`, `operator`, `preoperator` or `postoperator`. Everything after a `#`
+/// is ignored.
+impl FromStr for NativeFunctionTable {
+    type Err = ParseNativeTableError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut table = Self::empty();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let err = |reason| ParseNativeTableError {
+                line: i + 1,
+                reason,
+            };
+
+            let mut parts = line.split_whitespace();
+            let index = parts
+                .next()
+                .and_then(|index| index.parse::<u16>().ok())
+                .ok_or_else(|| err("invalid native index"))?;
+            let kind = match parts.next() {
+                Some(kind) if kind == NativeKind::Function.as_str() => NativeKind::Function,
+                Some(kind) if kind == NativeKind::Operator.as_str() => NativeKind::Operator,
+                Some(kind) if kind == NativeKind::PreOperator.as_str() => NativeKind::PreOperator,
+                Some(kind) if kind == NativeKind::PostOperator.as_str() => NativeKind::PostOperator,
+                _ => return Err(err("invalid native kind")),
+            };
+            let name = parts.next().ok_or_else(|| err("missing native name"))?;
+            if parts.next().is_some() {
+                return Err(err("unexpected trailing data"));
+            }
+
+            table.insert(index, name, kind);
+        }
+
+        Ok(table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_table_has_stock_natives() {
+        let table = NativeFunctionTable::default();
+
+        assert_eq!(table.display_name(231), "Log");
+        assert_eq!(table.display_name(187), "Sin");
+        assert_eq!(
+            table.get(129).map(|f| f.kind),
+            Some(NativeKind::PreOperator)
+        );
+        assert_eq!(table.display_name(4000), "Native_4000");
+    }
+
+    #[test]
+    fn parse_game_table() {
+        let mut table = NativeFunctionTable::ue2();
+        table.extend(
+            "# Splinter Cell\n1000 function PlaySound\n\n231 function DebugLog # override\n"
+                .parse()
+                .unwrap(),
+        );
+
+        assert_eq!(table.display_name(1000), "PlaySound");
+        assert_eq!(table.display_name(231), "DebugLog");
+
+        assert_eq!(
+            "12 method Foo".parse::<NativeFunctionTable>(),
+            Err(ParseNativeTableError {
+                line: 1,
+                reason: "invalid native kind"
+            })
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::io;
+
+use byteorder::ByteOrder;
+use tracing::{Level, span, trace};
+
+use crate::{
+    common::normalize_index,
+    de::{RcLinker, WeakLinker},
+    object::RcUnrealObject,
+    reader::LinRead,
+    runtime::{LoadKind, UnrealRuntime},
+};
+
+/// How object references read from a serialized stream should be resolved.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ReferencePolicy {
+    /// Load the referenced object as soon as the reference is read.
+    #[default]
+    Eager,
+    /// Only record the raw index. The object is loaded when [`ObjectRef::resolve`] is called.
+    Lazy,
+}
+
+/// A reference to another object which may not have been loaded yet.
+#[derive(Debug, Default)]
+pub struct ObjectRef {
+    raw_index: i32,
+    linker: WeakLinker,
+    resolved: Option<RcUnrealObject>,
+}
+
+impl ObjectRef {
+    pub fn new(raw_index: i32, linker: &RcLinker) -> Self {
+        ObjectRef {
+            raw_index,
+            linker: std::rc::Rc::downgrade(linker),
+            resolved: None,
+        }
+    }
+
+    /// The raw encoded index of the referenced object. Negative values are imports,
+    /// positive values are exports.
+    pub fn raw_index(&self) -> i32 {
+        self.raw_index
+    }
+
+    pub fn is_null(&self) -> bool {
+        self.raw_index == 0
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        self.is_null() || self.resolved.is_some()
+    }
+
+    /// Returns the referenced object if it has already been resolved.
+    pub fn get(&self) -> Option<&RcUnrealObject> {
+        self.resolved.as_ref()
+    }
+
+    /// Name of the referenced object. References which haven't been resolved are named from
+    /// their linker's tables.
+    pub(crate) fn object_name(&self) -> Option<String> {
+        if let Some(object) = &self.resolved {
+            return Some(object.borrow().base_object().name().to_owned());
+        }
+
+        let linker = self.linker.upgrade()?;
+        let linker = linker.borrow();
+        let name = match self.raw_index {
+            0 => return None,
+            1.. => linker
+                .package
+                .exports
+                .get(normalize_index(self.raw_index))?
+                .object_name(&linker),
+            _ => linker
+                .package
+                .imports
+                .get(normalize_index(self.raw_index))?
+                .object_name(&linker),
+        };
+
+        Some(name.to_owned())
+    }
+
+    /// Loads the referenced object if it has not been loaded yet.
+    pub fn resolve<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        reader: &mut R,
+    ) -> io::Result<Option<RcUnrealObject>>
+    where
+        E: ByteOrder,
+        R: LinRead,
+    {
+        if self.is_resolved() {
+            return Ok(self.resolved.clone());
+        }
+
+        let span = span!(
+            Level::DEBUG,
+            "resolve_object_ref",
+            raw_index = self.raw_index
+        );
+        let _enter = span.enter();
+
+        let Some(linker) = self.linker.upgrade() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "the package object reference {:#X} was read from has been unloaded",
+                    self.raw_index
+                ),
+            ));
+        };
+
+        trace!("Resolving object reference {:#X}", self.raw_index);
+
+        self.resolved = runtime.load_object_by_raw_index::<E, _>(
+            self.raw_index,
+            &linker,
+            LoadKind::Create,
+            reader,
+        )?;
+
+        Ok(self.resolved.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        reader::PackageReader,
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    #[test]
+    fn resolves_lazily() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("1").build();
+        let one = builder.export(constant, 0, "One", data);
+
+        let mut reader = PackageReader::new(Cursor::new(builder.build()));
+        let mut runtime = UnrealRuntime {
+            standalone: true,
+            ..Default::default()
+        };
+        runtime
+            .load_linker::<LittleEndian, _>("Test".to_owned(), &mut reader)
+            .unwrap();
+        let linker = runtime.linker("Test").unwrap();
+
+        let mut reference = ObjectRef::new(one, &linker);
+        assert!(!reference.is_resolved());
+        assert!(reference.get().is_none());
+        assert_eq!(reference.object_name().as_deref(), Some("One"));
+
+        let object = reference
+            .resolve::<LittleEndian, _>(&mut runtime, &mut reader)
+            .unwrap()
+            .unwrap();
+        assert_eq!(object.borrow().base_object().name(), "One");
+        assert!(reference.is_resolved());
+        assert!(std::rc::Rc::ptr_eq(reference.get().unwrap(), &object));
+
+        // References into a package which has since been unloaded can't be resolved
+        let mut reference = ObjectRef::new(one, &linker);
+        drop(linker);
+        runtime.unload_package("Test").unwrap();
+        let err = reference
+            .resolve::<LittleEndian, _>(&mut runtime, &mut reader)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
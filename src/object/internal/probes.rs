@@ -0,0 +1,125 @@
+use std::borrow::Cow;
+
+/// Probe functions declared by the stock engine, in the order of their names from
+/// `NAME_PROBEMIN`. Bit `n` of a state's probe or ignore mask refers to the `n`th.
+const STOCK_PROBES: [&str; 64] = [
+    "Spawned",
+    "Destroyed",
+    "GainedChild",
+    "LostChild",
+    "Probe4",
+    "Probe5",
+    "Trigger",
+    "UnTrigger",
+    "Timer",
+    "HitWall",
+    "Falling",
+    "Landed",
+    "ZoneChange",
+    "Touch",
+    "UnTouch",
+    "Bump",
+    "BeginState",
+    "EndState",
+    "BaseChange",
+    "Attach",
+    "Detach",
+    "ActorEntered",
+    "ActorLeaving",
+    "KillCredit",
+    "AnimEnd",
+    "EndedRotation",
+    "InterpolateEnd",
+    "EncroachingOn",
+    "EncroachedBy",
+    "FootZoneChange",
+    "HeadZoneChange",
+    "PainTimer",
+    "SpeechTimer",
+    "MayFall",
+    "Probe34",
+    "Die",
+    "Tick",
+    "PlayerTick",
+    "Expired",
+    "Probe39",
+    "SeePlayer",
+    "EnemyNotVisible",
+    "HearNoise",
+    "UpdateEyeHeight",
+    "SeeMonster",
+    "SeeFriend",
+    "SpecialHandling",
+    "BotDesireability",
+    "Probe48",
+    "Probe49",
+    "Probe50",
+    "Probe51",
+    "Probe52",
+    "Probe53",
+    "Probe54",
+    "Probe55",
+    "Probe56",
+    "Probe57",
+    "Probe58",
+    "Probe59",
+    "Probe60",
+    "Probe61",
+    "Probe62",
+    "Probe63",
+];
+
+/// Maps the bits of a state's probe and ignore masks to the names of the probe functions
+/// they stand for.
+///
+/// Games commonly rename unused probes, so a game-specific table can be built with
+/// [`ProbeNameTable::new`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeNameTable {
+    names: Vec<String>,
+}
+
+impl ProbeNameTable {
+    /// Names bit `n` after the `n`th entry of `names`. Entries past the 64th are ignored.
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Self {
+        ProbeNameTable {
+            names: names.into_iter().take(64).map(Into::into).collect(),
+        }
+    }
+
+    /// The probes declared by the stock engine.
+    pub fn stock() -> Self {
+        Self::new(STOCK_PROBES)
+    }
+
+    /// Returns the name of the probe at `bit`, or a placeholder such as `Probe40` if it
+    /// isn't in the table.
+    pub fn name(&self, bit: u32) -> Cow<'_, str> {
+        match self.names.get(bit as usize) {
+            Some(name) => Cow::Borrowed(name.as_str()),
+            None => Cow::Owned(format!("Probe{bit}")),
+        }
+    }
+
+    /// The bit standing for the probe named `name`, ignoring ASCII case.
+    pub fn bit(&self, name: &str) -> Option<u32> {
+        self.names
+            .iter()
+            .position(|probe| probe.eq_ignore_ascii_case(name))
+            .map(|bit| bit as u32)
+    }
+
+    /// Names of the probes whose bits are set in `mask`, in bit order.
+    pub fn decode(&self, mask: u64) -> Vec<Cow<'_, str>> {
+        (0..u64::BITS)
+            .filter(|bit| mask & (1 << bit) != 0)
+            .map(|bit| self.name(bit))
+            .collect()
+    }
+}
+
+impl Default for ProbeNameTable {
+    fn default() -> Self {
+        Self::stock()
+    }
+}
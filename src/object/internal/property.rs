@@ -4,11 +4,30 @@ use std::rc::Rc;
 use tracing::{Level, debug, span, trace};
 
 use crate::de::{Linker, RcLinker};
-use crate::object::DeserializeUnrealObject;
 use crate::object::internal::fname::FName;
+use crate::object::{DeserializeUnrealObject, RcUnrealObject};
 use crate::reader::{LinRead, UnrealReadExt};
 use crate::runtime::UnrealRuntime;
 
+/// The value of a tagged property, such as an actor's default `DrawScale`.
+#[derive(Debug, Clone)]
+pub enum PropertyValue {
+    Byte(u8),
+    Int(i32),
+    Bool(bool),
+    Float(f32),
+    Object(Option<RcUnrealObject>),
+    Name(Rc<str>),
+    Str(String),
+    /// A struct value as it was serialized, along with the struct's name
+    Struct {
+        name: Rc<str>,
+        data: Vec<u8>,
+    },
+    /// A dynamic array as it was serialized
+    Array(Vec<u8>),
+}
+
 #[derive(Default)]
 pub struct PropertyTag {
     pub name: FName,
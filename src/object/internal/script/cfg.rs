@@ -0,0 +1,306 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use super::{Expr, Label, Statement};
+
+/// A run of statements which is only entered at its first statement and only left after its
+/// last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Code offset of the block's first statement
+    pub offset: usize,
+    /// Indices of this block's statements in the decoded script
+    pub statements: Range<usize>,
+    /// Indices of the blocks control may flow to after this one
+    pub successors: Vec<usize>,
+}
+
+/// A jump whose target is not the start of a statement.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnresolvedJump {
+    /// Index of the statement containing the jump
+    pub statement: usize,
+    pub target: usize,
+}
+
+/// The control flow graph of a decoded script.
+///
+/// Only statement-level control flow is modelled. Short-circuiting within an expression
+/// (`Skip`, `Context` null checks) never leaves the statement it occurs in, and `GotoLabel`
+/// targets are only known at runtime so those statements have no successors.
+#[derive(Debug, Default, Clone)]
+pub struct ControlFlowGraph {
+    blocks: Vec<BasicBlock>,
+    back_edges: Vec<(usize, usize)>,
+    unresolved: Vec<UnresolvedJump>,
+}
+
+/// How control leaves a statement.
+enum Flow {
+    /// Continues to the next statement
+    Next,
+    /// Continues to the next statement or branches to the target
+    Branch(usize),
+    /// Always jumps to the target
+    Jump(usize),
+    /// Leaves the script
+    Exit,
+}
+
+impl ControlFlowGraph {
+    pub fn build(statements: &[Statement]) -> Self {
+        let flows = statement_flows(statements);
+
+        let offsets = statements.iter().map(|s| s.offset).collect::<Vec<_>>();
+        let statement_at = |offset: usize| offsets.binary_search(&offset).ok();
+
+        let mut cfg = ControlFlowGraph::default();
+
+        // Find the statements which begin a block: the entry point, jump targets, labels,
+        // and anything after a statement which transfers control.
+        let mut leaders = BTreeSet::new();
+        if !statements.is_empty() {
+            leaders.insert(0);
+        }
+
+        for (i, (statement, flow)) in statements.iter().zip(&flows).enumerate() {
+            let target = match flow {
+                Flow::Next => None,
+                Flow::Branch(target) | Flow::Jump(target) => Some(*target),
+                Flow::Exit => None,
+            };
+
+            if !matches!(flow, Flow::Next) && i + 1 < statements.len() {
+                leaders.insert(i + 1);
+            }
+
+            if let Some(target) = target {
+                match statement_at(target) {
+                    Some(target) => {
+                        leaders.insert(target);
+                    }
+                    None => cfg.unresolved.push(UnresolvedJump {
+                        statement: i,
+                        target,
+                    }),
+                }
+            }
+
+            if let Expr::LabelTable(labels) = &statement.expr {
+                for Label { offset, .. } in labels {
+                    if let Some(target) = statement_at(*offset as usize) {
+                        leaders.insert(target);
+                    }
+                }
+            }
+        }
+
+        let leaders = leaders.into_iter().collect::<Vec<_>>();
+        let block_of =
+            |statement: usize| leaders.partition_point(|&leader| leader <= statement) - 1;
+
+        for (block, &start) in leaders.iter().enumerate() {
+            let end = leaders.get(block + 1).copied().unwrap_or(statements.len());
+            let last = end - 1;
+
+            let mut successors = Vec::new();
+            let fallthrough = (end < statements.len()).then(|| block + 1);
+            let target = |target: &usize| statement_at(*target).map(block_of);
+
+            match &flows[last] {
+                Flow::Next => successors.extend(fallthrough),
+                Flow::Branch(branch) => {
+                    successors.extend(fallthrough);
+                    successors.extend(target(branch));
+                }
+                Flow::Jump(jump) => successors.extend(target(jump)),
+                Flow::Exit => {}
+            }
+            successors.dedup();
+
+            cfg.blocks.push(BasicBlock {
+                offset: statements[start].offset,
+                statements: start..end,
+                successors,
+            });
+        }
+
+        cfg.back_edges = cfg.find_back_edges();
+
+        cfg
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Returns the block which starts at the code offset `offset`.
+    pub fn block_at(&self, offset: usize) -> Option<&BasicBlock> {
+        self.blocks
+            .binary_search_by_key(&offset, |block| block.offset)
+            .ok()
+            .map(|index| &self.blocks[index])
+    }
+
+    /// Indices of the blocks which may flow into `block`.
+    pub fn predecessors(&self, block: usize) -> Vec<usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.successors.contains(&block))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Edges `(from, to)` which jump back to a block that is still being executed, i.e. the
+    /// bottom of a loop jumping back to its head.
+    pub fn back_edges(&self) -> &[(usize, usize)] {
+        &self.back_edges
+    }
+
+    /// Jumps whose targets didn't land on a statement boundary.
+    pub fn unresolved_jumps(&self) -> &[UnresolvedJump] {
+        &self.unresolved
+    }
+
+    /// Finds retreating edges with a depth-first walk from the entry block.
+    fn find_back_edges(&self) -> Vec<(usize, usize)> {
+        #[derive(Copy, Clone, PartialEq, Eq)]
+        enum State {
+            Unvisited,
+            OnStack,
+            Done,
+        }
+
+        let mut back_edges = Vec::new();
+        if self.blocks.is_empty() {
+            return back_edges;
+        }
+
+        let mut state = vec![State::Unvisited; self.blocks.len()];
+        // (block, index of the next successor to visit)
+        let mut stack = vec![(0, 0)];
+        state[0] = State::OnStack;
+
+        while let Some((block, next)) = stack.last_mut() {
+            let block = *block;
+            let Some(&successor) = self.blocks[block].successors.get(*next) else {
+                state[block] = State::Done;
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+
+            match state[successor] {
+                State::Unvisited => {
+                    state[successor] = State::OnStack;
+                    stack.push((successor, 0));
+                }
+                State::OnStack => back_edges.push((block, successor)),
+                State::Done => {}
+            }
+        }
+
+        back_edges
+    }
+}
+
+/// Determines how control leaves each statement.
+fn statement_flows(statements: &[Statement]) -> Vec<Flow> {
+    // Offsets of the first statement in the body of each enclosing iterator
+    let mut iterators = Vec::new();
+
+    statements
+        .iter()
+        .enumerate()
+        .map(|(i, statement)| match &statement.expr {
+            Expr::Jump { offset } => Flow::Jump(*offset as usize),
+            Expr::JumpIfNot { offset, .. } => Flow::Branch(*offset as usize),
+            // A non-matching case jumps to the next case
+            Expr::Case {
+                offset,
+                value: Some(_),
+            } => Flow::Branch(*offset as usize),
+            Expr::Iterator { end_offset, .. } => {
+                if let Some(body) = statements.get(i + 1) {
+                    iterators.push(body.offset);
+                }
+                Flow::Branch(*end_offset as usize)
+            }
+            Expr::IteratorNext => match iterators.last() {
+                Some(body) => Flow::Branch(*body),
+                None => Flow::Next,
+            },
+            Expr::IteratorPop => {
+                iterators.pop();
+                Flow::Next
+            }
+            Expr::Return(_)
+            | Expr::Stop
+            | Expr::EndOfScript
+            | Expr::GotoLabel(_)
+            | Expr::Unknown { .. } => Flow::Exit,
+            _ => Flow::Next,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::internal::script::Const;
+
+    fn statement(offset: usize, expr: Expr) -> Statement {
+        Statement { offset, expr }
+    }
+
+    #[test]
+    fn while_loop() {
+        // while (true) {} return;
+        let statements = [
+            statement(
+                0,
+                Expr::JumpIfNot {
+                    offset: 11,
+                    condition: Box::new(Expr::Const(Const::Bool(true))),
+                },
+            ),
+            statement(5, Expr::Nothing),
+            statement(8, Expr::Jump { offset: 0 }),
+            statement(11, Expr::Return(Box::new(Expr::Nothing))),
+        ];
+
+        let cfg = ControlFlowGraph::build(&statements);
+
+        let blocks = cfg.blocks();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].statements, 0..1);
+        assert_eq!(blocks[0].successors, [1, 2]);
+        assert_eq!(blocks[1].statements, 1..3);
+        assert_eq!(blocks[1].successors, [0]);
+        assert!(blocks[2].successors.is_empty());
+
+        assert_eq!(cfg.block_at(11), Some(&blocks[2]));
+        assert_eq!(cfg.predecessors(0), [1]);
+        assert_eq!(cfg.back_edges(), [(1, 0)]);
+        assert!(cfg.unresolved_jumps().is_empty());
+    }
+
+    #[test]
+    fn reports_unresolved_jumps() {
+        let statements = [
+            statement(0, Expr::Jump { offset: 2 }),
+            statement(3, Expr::EndOfScript),
+        ];
+
+        let cfg = ControlFlowGraph::build(&statements);
+
+        assert_eq!(
+            cfg.unresolved_jumps(),
+            [UnresolvedJump {
+                statement: 0,
+                target: 2
+            }]
+        );
+        assert!(cfg.blocks()[0].successors.is_empty());
+    }
+}
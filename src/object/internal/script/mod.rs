@@ -0,0 +1,1930 @@
+pub mod cfg;
+
+use std::{
+    fmt,
+    io::{self, Cursor, Seek, SeekFrom, Write},
+};
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use tracing::{Level, debug, span, trace, warn};
+
+use crate::{
+    de::RcLinker,
+    object::{RcUnrealObject, internal::fname::FName},
+    packed::write_packed_int,
+    reader::{LinRead, PackageReader, UnrealReadExt},
+    runtime::{LoadKind, UnrealRuntime},
+};
+
+/// Version of the debug info emitted by script compilers with debugging support.
+const DEBUG_INFO_VERSION: i32 = 100;
+
+/// Size of a debug info token and its operands, both serialized and in memory.
+const DEBUG_INFO_SIZE: usize = 14;
+
+/// Source position recorded by script compilers with debugging support.
+///
+/// Debug info is emitted as its own statement and after every function call. Trailing debug
+/// info of [`DEBUG_INFO_VERSION`] is attached to the [`Expr::Call`] it follows; anything else
+/// is kept as a standalone [`Expr::DebugInfo`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DebugInfo {
+    pub version: i32,
+    /// Line of the source code the bytecode was compiled from
+    pub line: i32,
+    /// Position within the line
+    pub pos: i32,
+    /// What the debugger is told is happening, e.g. entering a function or a new statement
+    pub opcode: u8,
+}
+
+/// An object referenced by a script.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptObject {
+    /// The serialized object index. Negative values are imports, positive values are exports.
+    pub raw_index: i32,
+    /// The referenced object. Always `None` if script bodies are skipped.
+    pub object: Option<RcUnrealObject>,
+}
+
+/// A top-level expression in a script along with its code offset.
+#[derive(Clone, Debug)]
+pub struct Statement {
+    /// Offset of this statement's first token in the in-memory script
+    pub offset: usize,
+    pub expr: Expr,
+}
+
+/// A token this crate doesn't know how to decode, e.g. one added by a licensee.
+#[derive(Debug)]
+struct UnknownToken {
+    opcode: u8,
+    /// Offset of the token in the in-memory script
+    offset: usize,
+}
+
+impl fmt::Display for UnknownToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown script token {:#04X} at offset {:#X}",
+            self.opcode, self.offset
+        )
+    }
+}
+
+impl std::error::Error for UnknownToken {}
+
+/// Reads the statements of a script which is `script_size` bytes long in memory.
+///
+/// If an unknown token is encountered, the rest of the script is kept undecoded as a final
+/// [`Expr::Unknown`] statement. That data is never read past `serial_end`, where the data of
+/// the export holding the script ends, when it's known.
+pub fn deserialize_script<E, R>(
+    runtime: &mut UnrealRuntime,
+    linker: &RcLinker,
+    reader: &mut R,
+    script_size: usize,
+    serial_end: Option<u64>,
+) -> std::io::Result<Vec<Statement>>
+where
+    E: byteorder::ByteOrder,
+    R: LinRead,
+{
+    let mut statements = Vec::new();
+    let mut bytes_read = 0;
+
+    while bytes_read < script_size {
+        debug!("Bytes read: {bytes_read:#X} / {script_size:#X}");
+
+        let offset = bytes_read;
+        let expr =
+            match deserialize_expr::<E, _>(runtime, linker, reader, &mut bytes_read, script_size) {
+                Ok(expr) => expr,
+                Err(err) => {
+                    let Some(unknown) = err
+                        .get_ref()
+                        .and_then(|inner| inner.downcast_ref::<UnknownToken>())
+                    else {
+                        return Err(err);
+                    };
+                    warn!("{unknown}; leaving the rest of the script undecoded");
+
+                    let offset = unknown.offset;
+                    let opcode = unknown.opcode;
+
+                    // The size is measured in memory, where object references are wider than
+                    // they're serialized, so the rest of the script can be shorter on disk
+                    let mut raw_len = script_size.saturating_sub(bytes_read);
+                    if let Some(serial_end) = serial_end {
+                        let remaining = serial_end.saturating_sub(reader.stream_position()?);
+                        raw_len = raw_len.min(remaining as usize);
+                    }
+                    let mut raw_bytes = vec![0u8; raw_len];
+                    reader.read_raw_unverified(&mut raw_bytes)?;
+                    bytes_read = script_size;
+
+                    statements.push(Statement {
+                        offset,
+                        expr: Expr::Unknown { opcode, raw_bytes },
+                    });
+                    break;
+                }
+            };
+        if let Expr::DebugInfo(info) = expr
+            && let Some(previous) = statements.last_mut()
+            && attach_debug_info(runtime, &mut previous.expr, info)
+        {
+            continue;
+        }
+        statements.push(Statement { offset, expr });
+    }
+
+    if bytes_read != script_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "script decoded to {bytes_read:#X} bytes, expected {script_size:#X}. The format profile's script size model may be wrong"
+            ),
+        ));
+    }
+
+    Ok(statements)
+}
+
+/// Size of a serialized script once it's loaded, which is what a struct's `script_size`
+/// holds. Object references and names are sized with `linker`'s script size model.
+pub(crate) fn script_memory_size<E>(
+    runtime: &mut UnrealRuntime,
+    linker: &RcLinker,
+    script: &[u8],
+) -> io::Result<usize>
+where
+    E: byteorder::ByteOrder,
+{
+    // Only the size is needed, so don't load anything the script refers to
+    let skip_script_bodies = std::mem::replace(&mut runtime.load_options.skip_script_bodies, true);
+
+    let mut reader = PackageReader::new(Cursor::new(script));
+    let mut bytes_read = 0;
+    let mut result = Ok(());
+    while result.is_ok() && (reader.stream_position()? as usize) < script.len() {
+        result =
+            deserialize_expr::<E, _>(runtime, linker, &mut reader, &mut bytes_read, usize::MAX)
+                .map(drop);
+    }
+
+    runtime.load_options.skip_script_bodies = skip_script_bodies;
+    result.map(|()| bytes_read)
+}
+
+pub fn deserialize_expr<E, R>(
+    runtime: &mut UnrealRuntime,
+    linker: &RcLinker,
+    reader: &mut R,
+    bytes_read: &mut usize,
+    script_size: usize,
+) -> std::io::Result<Expr>
+where
+    E: byteorder::ByteOrder,
+    R: LinRead,
+{
+    let span = span!(Level::DEBUG, "deserialize_expr");
+    let _enter = span.enter();
+
+    let sizes = linker.borrow().profile.quirks.script_sizes;
+
+    let token_value = reader.read_u8()?;
+    *bytes_read += 1;
+
+    macro_rules! expr {
+        () => {
+            Box::new(deserialize_expr::<E, _>(
+                runtime,
+                linker,
+                reader,
+                bytes_read,
+                script_size,
+            )?)
+        };
+    }
+
+    macro_rules! call {
+        ($func:expr) => {{
+            let func = $func;
+
+            trace!("Reading function params");
+            let mut args: Vec<Expr> = Vec::new();
+            loop {
+                match deserialize_expr::<E, _>(runtime, linker, reader, bytes_read, script_size)? {
+                    Expr::EndFunctionParms => break,
+                    Expr::DebugInfo(info)
+                        if args
+                            .last_mut()
+                            .is_some_and(|arg| attach_debug_info(runtime, arg, info)) => {}
+                    arg => args.push(arg),
+                }
+            }
+
+            let debug_info =
+                read_trailing_debug_info::<E, _>(runtime, reader, bytes_read, script_size)?;
+
+            Expr::Call {
+                func,
+                args,
+                debug_info,
+            }
+        }};
+    }
+
+    // These do not map directly to a token
+    if token_value >= ExprToken::ExtendedNative as u8 {
+        debug!("Token implies native");
+
+        // Extended natives store the high bits of the index in the token
+        let native_index = if token_value < ExprToken::FirstNative as u8 {
+            trace!("Reading extra byte for ExtendedNative");
+
+            let low = reader.read_u8()?;
+            *bytes_read += 1;
+
+            (((token_value - ExprToken::ExtendedNative as u8) as u16) << 8) | low as u16
+        } else {
+            token_value as u16
+        };
+
+        debug!(
+            "Native function: {}",
+            runtime.native_functions.display_name(native_index)
+        );
+
+        return Ok(call!(Callee::Native(native_index)));
+    }
+
+    let Ok(token) = ExprToken::try_from(token_value) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            UnknownToken {
+                opcode: token_value,
+                offset: *bytes_read - 1,
+            },
+        ));
+    };
+
+    debug!("Token is: {:?}", token);
+
+    macro_rules! read_object {
+        () => {{
+            let raw_index = reader.read_packed_int()?;
+            let object = if runtime.load_options.skip_script_bodies {
+                None
+            } else {
+                runtime.load_object_by_raw_index::<E, _>(
+                    raw_index,
+                    linker,
+                    LoadKind::Create,
+                    reader,
+                )?
+            };
+
+            *bytes_read += sizes.object;
+
+            ScriptObject { raw_index, object }
+        }};
+    }
+
+    macro_rules! read_name {
+        () => {{
+            let name = FName::from_raw(reader.read_packed_int()?);
+            *bytes_read += sizes.name;
+
+            name
+        }};
+    }
+
+    macro_rules! read_u8 {
+        () => {{
+            *bytes_read += 1;
+            reader.read_u8()?
+        }};
+    }
+
+    macro_rules! read_u16 {
+        () => {{
+            *bytes_read += sizes.word;
+            reader.read_u16::<E>()?
+        }};
+    }
+
+    macro_rules! read_i32 {
+        () => {{
+            *bytes_read += 4;
+            reader.read_i32::<E>()?
+        }};
+    }
+
+    macro_rules! read_f32 {
+        () => {{
+            *bytes_read += 4;
+            reader.read_f32::<E>()?
+        }};
+    }
+
+    let expr = match token {
+        ExprToken::LocalVariable => Expr::LocalVariable(read_object!()),
+        ExprToken::InstanceVariable => Expr::InstanceVariable(read_object!()),
+        ExprToken::DefaultVariable => Expr::DefaultVariable(read_object!()),
+        ExprToken::NativeParm => Expr::NativeParm(read_object!()),
+        ExprToken::DelegateProperty => Expr::DelegateProperty(read_name!()),
+        ExprToken::BoolVariable => Expr::BoolVariable(expr!()),
+        ExprToken::Return => Expr::Return(expr!()),
+        ExprToken::Switch => {
+            let size = read_u8!();
+            Expr::Switch {
+                size,
+                value: expr!(),
+            }
+        }
+        ExprToken::Jump => Expr::Jump {
+            offset: read_u16!(),
+        },
+        ExprToken::JumpIfNot => {
+            let offset = read_u16!();
+            Expr::JumpIfNot {
+                offset,
+                condition: expr!(),
+            }
+        }
+        ExprToken::Assert => {
+            let line = read_u16!();
+            Expr::Assert {
+                line,
+                condition: expr!(),
+            }
+        }
+        ExprToken::Case => {
+            let offset = read_u16!();
+            let value = if offset == CASE_DEFAULT {
+                None
+            } else {
+                Some(expr!())
+            };
+
+            Expr::Case { offset, value }
+        }
+        ExprToken::LabelTable => {
+            let mut labels = Vec::new();
+            loop {
+                let name = read_name!();
+                let offset = reader.read_u32::<E>()?;
+                *bytes_read += 4;
+
+                labels.push(Label { name, offset });
+                if name.is_none() {
+                    break;
+                }
+            }
+
+            Expr::LabelTable(labels)
+        }
+        ExprToken::GotoLabel => Expr::GotoLabel(expr!()),
+        ExprToken::EatString => Expr::EatString(expr!()),
+        ExprToken::Let | ExprToken::LetBool | ExprToken::LetDelegate => {
+            let kind = match token {
+                ExprToken::LetBool => LetKind::Bool,
+                ExprToken::LetDelegate => LetKind::Delegate,
+                _ => LetKind::Value,
+            };
+
+            let lhs = expr!();
+            let rhs = expr!();
+            Expr::Let { kind, lhs, rhs }
+        }
+        ExprToken::ArrayElement | ExprToken::DynArrayElement => {
+            let index = expr!();
+            let array = expr!();
+            Expr::ArrayElement {
+                dynamic: matches!(token, ExprToken::DynArrayElement),
+                index,
+                array,
+            }
+        }
+        ExprToken::DynArrayLength => Expr::DynArrayLength(expr!()),
+        ExprToken::DynArrayInsert | ExprToken::DynArrayRemove => {
+            let array = expr!();
+            let index = expr!();
+            let count = expr!();
+
+            if matches!(token, ExprToken::DynArrayInsert) {
+                Expr::DynArrayInsert {
+                    array,
+                    index,
+                    count,
+                }
+            } else {
+                Expr::DynArrayRemove {
+                    array,
+                    index,
+                    count,
+                }
+            }
+        }
+        ExprToken::New => {
+            let outer = expr!();
+            let name = expr!();
+            let flags = expr!();
+            let class = expr!();
+            Expr::New {
+                outer,
+                name,
+                flags,
+                class,
+            }
+        }
+        ExprToken::ClassContext | ExprToken::Context => {
+            let object = expr!();
+            let skip_offset = read_u16!();
+            let zero_size = read_u8!();
+            let context = expr!();
+            Expr::Context {
+                class: matches!(token, ExprToken::ClassContext),
+                object,
+                skip_offset,
+                zero_size,
+                context,
+            }
+        }
+        ExprToken::MetaCast | ExprToken::DynamicCast => {
+            let class = read_object!();
+            let expr = expr!();
+            Expr::Cast {
+                meta: matches!(token, ExprToken::MetaCast),
+                class,
+                expr,
+            }
+        }
+        ExprToken::PrimitiveCast => {
+            let cast = read_u8!();
+            Expr::PrimitiveCast {
+                cast,
+                expr: expr!(),
+            }
+        }
+        ExprToken::LineNumber => {
+            let line = read_u16!();
+            Expr::LineNumber {
+                line,
+                expr: expr!(),
+            }
+        }
+        ExprToken::Skip => {
+            let size = read_u16!();
+            Expr::Skip {
+                size,
+                expr: expr!(),
+            }
+        }
+        ExprToken::Iterator => {
+            let expr = expr!();
+            let end_offset = read_u16!();
+            Expr::Iterator { expr, end_offset }
+        }
+        ExprToken::StructCmpEq | ExprToken::StructCmpNe => {
+            let struct_obj = read_object!();
+            let lhs = expr!();
+            let rhs = expr!();
+            Expr::StructCmp {
+                equal: matches!(token, ExprToken::StructCmpEq),
+                struct_obj,
+                lhs,
+                rhs,
+            }
+        }
+        ExprToken::StructMember => {
+            let property = read_object!();
+            Expr::StructMember {
+                property,
+                expr: expr!(),
+            }
+        }
+        ExprToken::VirtualFunction => call!(Callee::Virtual(read_name!())),
+        ExprToken::GlobalFunction => call!(Callee::Global(read_name!())),
+        ExprToken::FinalFunction => call!(Callee::Final(read_object!())),
+        ExprToken::DelegateFunction => {
+            let property = read_object!();
+            let name = read_name!();
+            call!(Callee::Delegate { property, name })
+        }
+        ExprToken::IntConst => Expr::Const(Const::Int(read_i32!())),
+        ExprToken::FloatConst => Expr::Const(Const::Float(read_f32!())),
+        ExprToken::ByteConst => Expr::Const(Const::Byte(read_u8!())),
+        ExprToken::IntConstByte => Expr::Const(Const::IntByte(read_u8!())),
+        ExprToken::StringConst => {
+            let mut string = String::new();
+            loop {
+                match read_u8!() {
+                    0 => break,
+                    c => string.push(c as char),
+                }
+            }
+
+            Expr::Const(Const::String(string))
+        }
+        ExprToken::UnicodeStringConst => {
+            let mut chars = Vec::new();
+            loop {
+                // Characters are always UTF-16 regardless of the word size
+                *bytes_read += 2;
+                match reader.read_u16::<E>()? {
+                    0 => break,
+                    c => chars.push(c),
+                }
+            }
+
+            Expr::Const(Const::UnicodeString(String::from_utf16_lossy(&chars)))
+        }
+        ExprToken::ObjectConst => Expr::Const(Const::Object(read_object!())),
+        ExprToken::NameConst => Expr::Const(Const::Name(read_name!())),
+        ExprToken::RotationConst => {
+            let pitch = read_i32!();
+            let yaw = read_i32!();
+            let roll = read_i32!();
+            Expr::Const(Const::Rotation { pitch, yaw, roll })
+        }
+        ExprToken::VectorConst => {
+            let x = read_f32!();
+            let y = read_f32!();
+            let z = read_f32!();
+            Expr::Const(Const::Vector { x, y, z })
+        }
+        ExprToken::RangeConst => {
+            let min = read_f32!();
+            let max = read_f32!();
+            Expr::Const(Const::Range { min, max })
+        }
+        ExprToken::PointerConst => Expr::Const(Const::Pointer(read_i32!())),
+        ExprToken::IntZero => Expr::Const(Const::Int(0)),
+        ExprToken::IntOne => Expr::Const(Const::Int(1)),
+        ExprToken::True => Expr::Const(Const::Bool(true)),
+        ExprToken::False => Expr::Const(Const::Bool(false)),
+        ExprToken::NoObject => Expr::Const(Const::Object(ScriptObject::default())),
+        ExprToken::DebugInfo => {
+            let version = read_i32!();
+            let line = read_i32!();
+            let pos = read_i32!();
+            let opcode = read_u8!();
+            Expr::DebugInfo(DebugInfo {
+                version,
+                line,
+                pos,
+                opcode,
+            })
+        }
+        ExprToken::Nothing => Expr::Nothing,
+        ExprToken::EndFunctionParms => Expr::EndFunctionParms,
+        ExprToken::SelfObj => Expr::SelfObj,
+        ExprToken::IteratorPop => Expr::IteratorPop,
+        ExprToken::IteratorNext => Expr::IteratorNext,
+        ExprToken::Stop => Expr::Stop,
+        ExprToken::EndOfScript => Expr::EndOfScript,
+        ExprToken::ExtendedNative | ExprToken::FirstNative => {
+            unreachable!("native tokens are handled above")
+        }
+    };
+
+    Ok(expr)
+}
+
+/// Function calls may be followed by debug info. Only debug info of the expected version
+/// is consumed, and only if it fits in what's left of the script.
+///
+/// This has to look ahead, which streamed readers can't do without consuming the bytes, so
+/// nothing is read from them. Debug info following a call is then decoded as the next
+/// argument or statement, where [`attach_debug_info`] moves it onto the call.
+fn read_trailing_debug_info<E, R>(
+    runtime: &UnrealRuntime,
+    reader: &mut R,
+    bytes_read: &mut usize,
+    script_size: usize,
+) -> std::io::Result<Option<DebugInfo>>
+where
+    E: byteorder::ByteOrder,
+    R: LinRead,
+{
+    if runtime.load_options.skip_script_debug_info
+        || !reader.supports_rereads()
+        || script_size.saturating_sub(*bytes_read) < DEBUG_INFO_SIZE
+    {
+        return Ok(None);
+    }
+
+    trace!("Reading possible debug info");
+
+    // The peeked bytes weren't part of the recorded reads if they turn out not to be debug
+    // info, so they aren't verified
+    let before_pos = reader.stream_position()?;
+    let mut peeked = [0u8; DEBUG_INFO_SIZE];
+    let peek = reader.unverified_scope(|reader| match reader.read_exact(&mut peeked) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    });
+    reader.seek(SeekFrom::Start(before_pos))?;
+    if !peek? || !matches!(ExprToken::try_from(peeked[0]), Ok(ExprToken::DebugInfo)) {
+        return Ok(None);
+    }
+
+    let info = DebugInfo {
+        version: E::read_i32(&peeked[1..5]),
+        line: E::read_i32(&peeked[5..9]),
+        pos: E::read_i32(&peeked[9..13]),
+        opcode: peeked[13],
+    };
+    if info.version != DEBUG_INFO_VERSION {
+        return Ok(None);
+    }
+
+    trace!("Reading actual debug info");
+    reader.read_exact(&mut peeked)?;
+    *bytes_read += DEBUG_INFO_SIZE;
+
+    Ok(Some(info))
+}
+
+/// Attaches `info`, which was decoded as a standalone expression, to `previous` if it's a
+/// call that the debug info trails. Returns whether it was attached.
+fn attach_debug_info(runtime: &UnrealRuntime, previous: &mut Expr, info: DebugInfo) -> bool {
+    if runtime.load_options.skip_script_debug_info || info.version != DEBUG_INFO_VERSION {
+        return false;
+    }
+
+    match previous {
+        Expr::Call { debug_info, .. } if debug_info.is_none() => {
+            *debug_info = Some(info);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Maps the names and objects a script refers to to the indices they're serialized as.
+#[derive(Clone, Copy)]
+pub(crate) struct ScriptIndices<'i> {
+    pub(crate) name: &'i dyn Fn(FName) -> io::Result<i32>,
+    /// Maps raw object indices
+    pub(crate) object: &'i dyn Fn(i32) -> io::Result<i32>,
+}
+
+impl ScriptIndices<'_> {
+    const UNCHANGED: ScriptIndices<'static> = ScriptIndices {
+        name: &|name| Ok(name.index()),
+        object: &|index| Ok(index),
+    };
+}
+
+/// Encodes script expressions as they're serialized in a package. Scripts which were decoded
+/// and left unmodified are re-encoded byte-identically.
+pub fn serialize_exprs<'a, E, W>(
+    writer: &mut W,
+    exprs: impl IntoIterator<Item = &'a Expr>,
+) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    write_exprs::<E, _>(writer, exprs, ScriptIndices::UNCHANGED)
+}
+
+pub fn serialize_expr<E, W>(writer: &mut W, expr: &Expr) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    write_expr::<E, _>(writer, expr, ScriptIndices::UNCHANGED)
+}
+
+/// Every constant in `exprs` and the expressions nested inside them, in the order they appear
+/// in the script.
+pub fn constants<'a>(exprs: impl IntoIterator<Item = &'a Expr>) -> Vec<&'a Const> {
+    let mut constants = Vec::new();
+    let mut pending = exprs.into_iter().collect::<Vec<_>>();
+    pending.reverse();
+
+    while let Some(expr) = pending.pop() {
+        if let Expr::Const(constant) = expr {
+            constants.push(constant);
+        }
+
+        pending.extend(expr.sub_exprs().into_iter().rev());
+    }
+
+    constants
+}
+
+/// Like [`serialize_exprs`], but writes each name and object as the index `indices` maps it
+/// to. Used to re-encode a script after its package's tables have changed.
+pub(crate) fn serialize_exprs_remapped<'a, E, W>(
+    writer: &mut W,
+    exprs: impl IntoIterator<Item = &'a Expr>,
+    indices: ScriptIndices,
+) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    write_exprs::<E, _>(writer, exprs, indices)
+}
+
+fn write_exprs<'a, E, W>(
+    writer: &mut W,
+    exprs: impl IntoIterator<Item = &'a Expr>,
+    indices: ScriptIndices,
+) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    for expr in exprs {
+        write_expr::<E, _>(writer, expr, indices)?;
+    }
+
+    Ok(())
+}
+
+fn write_expr<E, W>(writer: &mut W, expr: &Expr, indices: ScriptIndices) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    let token = |writer: &mut W, token: ExprToken| writer.write_u8(token as u8);
+
+    match expr {
+        Expr::LocalVariable(object) => {
+            token(writer, ExprToken::LocalVariable)?;
+            write_packed_int(writer, (indices.object)(object.raw_index)?)?;
+        }
+        Expr::InstanceVariable(object) => {
+            token(writer, ExprToken::InstanceVariable)?;
+            write_packed_int(writer, (indices.object)(object.raw_index)?)?;
+        }
+        Expr::DefaultVariable(object) => {
+            token(writer, ExprToken::DefaultVariable)?;
+            write_packed_int(writer, (indices.object)(object.raw_index)?)?;
+        }
+        Expr::NativeParm(object) => {
+            token(writer, ExprToken::NativeParm)?;
+            write_packed_int(writer, (indices.object)(object.raw_index)?)?;
+        }
+        Expr::DelegateProperty(name) => {
+            token(writer, ExprToken::DelegateProperty)?;
+            write_packed_int(writer, (indices.name)(*name)?)?;
+        }
+        Expr::BoolVariable(expr) => {
+            token(writer, ExprToken::BoolVariable)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Return(expr) => {
+            token(writer, ExprToken::Return)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Switch { size, value } => {
+            token(writer, ExprToken::Switch)?;
+            writer.write_u8(*size)?;
+            write_expr::<E, _>(writer, value, indices)?;
+        }
+        Expr::Jump { offset } => {
+            token(writer, ExprToken::Jump)?;
+            writer.write_u16::<E>(*offset)?;
+        }
+        Expr::JumpIfNot { offset, condition } => {
+            token(writer, ExprToken::JumpIfNot)?;
+            writer.write_u16::<E>(*offset)?;
+            write_expr::<E, _>(writer, condition, indices)?;
+        }
+        Expr::Assert { line, condition } => {
+            token(writer, ExprToken::Assert)?;
+            writer.write_u16::<E>(*line)?;
+            write_expr::<E, _>(writer, condition, indices)?;
+        }
+        Expr::Case { offset, value } => {
+            token(writer, ExprToken::Case)?;
+            writer.write_u16::<E>(*offset)?;
+            if let Some(value) = value {
+                write_expr::<E, _>(writer, value, indices)?;
+            }
+        }
+        Expr::LabelTable(labels) => {
+            token(writer, ExprToken::LabelTable)?;
+            for label in labels {
+                write_packed_int(writer, (indices.name)(label.name)?)?;
+                writer.write_u32::<E>(label.offset)?;
+            }
+        }
+        Expr::GotoLabel(expr) => {
+            token(writer, ExprToken::GotoLabel)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::EatString(expr) => {
+            token(writer, ExprToken::EatString)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Let { kind, lhs, rhs } => {
+            let let_token = match kind {
+                LetKind::Value => ExprToken::Let,
+                LetKind::Bool => ExprToken::LetBool,
+                LetKind::Delegate => ExprToken::LetDelegate,
+            };
+            token(writer, let_token)?;
+            write_expr::<E, _>(writer, lhs, indices)?;
+            write_expr::<E, _>(writer, rhs, indices)?;
+        }
+        Expr::ArrayElement {
+            dynamic,
+            index,
+            array,
+        } => {
+            token(
+                writer,
+                if *dynamic {
+                    ExprToken::DynArrayElement
+                } else {
+                    ExprToken::ArrayElement
+                },
+            )?;
+            write_expr::<E, _>(writer, index, indices)?;
+            write_expr::<E, _>(writer, array, indices)?;
+        }
+        Expr::DynArrayLength(expr) => {
+            token(writer, ExprToken::DynArrayLength)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::DynArrayInsert {
+            array,
+            index,
+            count,
+        } => {
+            token(writer, ExprToken::DynArrayInsert)?;
+            write_exprs::<E, _>(writer, [&**array, index, count], indices)?;
+        }
+        Expr::DynArrayRemove {
+            array,
+            index,
+            count,
+        } => {
+            token(writer, ExprToken::DynArrayRemove)?;
+            write_exprs::<E, _>(writer, [&**array, index, count], indices)?;
+        }
+        Expr::New {
+            outer,
+            name,
+            flags,
+            class,
+        } => {
+            token(writer, ExprToken::New)?;
+            write_exprs::<E, _>(writer, [&**outer, name, flags, class], indices)?;
+        }
+        Expr::Context {
+            class,
+            object,
+            skip_offset,
+            zero_size,
+            context,
+        } => {
+            token(
+                writer,
+                if *class {
+                    ExprToken::ClassContext
+                } else {
+                    ExprToken::Context
+                },
+            )?;
+            write_expr::<E, _>(writer, object, indices)?;
+            writer.write_u16::<E>(*skip_offset)?;
+            writer.write_u8(*zero_size)?;
+            write_expr::<E, _>(writer, context, indices)?;
+        }
+        Expr::Cast { meta, class, expr } => {
+            token(
+                writer,
+                if *meta {
+                    ExprToken::MetaCast
+                } else {
+                    ExprToken::DynamicCast
+                },
+            )?;
+            write_packed_int(writer, (indices.object)(class.raw_index)?)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::PrimitiveCast { cast, expr } => {
+            token(writer, ExprToken::PrimitiveCast)?;
+            writer.write_u8(*cast)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::LineNumber { line, expr } => {
+            token(writer, ExprToken::LineNumber)?;
+            writer.write_u16::<E>(*line)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Skip { size, expr } => {
+            token(writer, ExprToken::Skip)?;
+            writer.write_u16::<E>(*size)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Iterator { expr, end_offset } => {
+            token(writer, ExprToken::Iterator)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+            writer.write_u16::<E>(*end_offset)?;
+        }
+        Expr::StructCmp {
+            equal,
+            struct_obj,
+            lhs,
+            rhs,
+        } => {
+            token(
+                writer,
+                if *equal {
+                    ExprToken::StructCmpEq
+                } else {
+                    ExprToken::StructCmpNe
+                },
+            )?;
+            write_packed_int(writer, (indices.object)(struct_obj.raw_index)?)?;
+            write_expr::<E, _>(writer, lhs, indices)?;
+            write_expr::<E, _>(writer, rhs, indices)?;
+        }
+        Expr::StructMember { property, expr } => {
+            token(writer, ExprToken::StructMember)?;
+            write_packed_int(writer, (indices.object)(property.raw_index)?)?;
+            write_expr::<E, _>(writer, expr, indices)?;
+        }
+        Expr::Call {
+            func,
+            args,
+            debug_info,
+        } => {
+            match func {
+                // Natives which fit in a single byte are stored as the token itself
+                Callee::Native(index)
+                    if *index >= ExprToken::FirstNative as u16 && *index <= 0xFF =>
+                {
+                    writer.write_u8(*index as u8)?;
+                }
+                Callee::Native(index) => {
+                    writer.write_u8(ExprToken::ExtendedNative as u8 + (*index >> 8) as u8)?;
+                    writer.write_u8(*index as u8)?;
+                }
+                Callee::Final(function) => {
+                    token(writer, ExprToken::FinalFunction)?;
+                    write_packed_int(writer, (indices.object)(function.raw_index)?)?;
+                }
+                Callee::Virtual(name) => {
+                    token(writer, ExprToken::VirtualFunction)?;
+                    write_packed_int(writer, (indices.name)(*name)?)?;
+                }
+                Callee::Global(name) => {
+                    token(writer, ExprToken::GlobalFunction)?;
+                    write_packed_int(writer, (indices.name)(*name)?)?;
+                }
+                Callee::Delegate { property, name } => {
+                    token(writer, ExprToken::DelegateFunction)?;
+                    write_packed_int(writer, (indices.object)(property.raw_index)?)?;
+                    write_packed_int(writer, (indices.name)(*name)?)?;
+                }
+            }
+
+            write_exprs::<E, _>(writer, args, indices)?;
+            token(writer, ExprToken::EndFunctionParms)?;
+            if let Some(debug_info) = debug_info {
+                write_debug_info::<E, _>(writer, debug_info)?;
+            }
+        }
+        Expr::Const(constant) => write_const::<E, _>(writer, constant, indices)?,
+        Expr::DebugInfo(info) => write_debug_info::<E, _>(writer, info)?,
+        Expr::Nothing => token(writer, ExprToken::Nothing)?,
+        Expr::EndFunctionParms => token(writer, ExprToken::EndFunctionParms)?,
+        Expr::SelfObj => token(writer, ExprToken::SelfObj)?,
+        Expr::IteratorPop => token(writer, ExprToken::IteratorPop)?,
+        Expr::IteratorNext => token(writer, ExprToken::IteratorNext)?,
+        Expr::Stop => token(writer, ExprToken::Stop)?,
+        Expr::EndOfScript => token(writer, ExprToken::EndOfScript)?,
+        Expr::Unknown { opcode, raw_bytes } => {
+            writer.write_u8(*opcode)?;
+            writer.write_all(raw_bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_debug_info<E, W>(writer: &mut W, info: &DebugInfo) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    writer.write_u8(ExprToken::DebugInfo as u8)?;
+    writer.write_i32::<E>(info.version)?;
+    writer.write_i32::<E>(info.line)?;
+    writer.write_i32::<E>(info.pos)?;
+    writer.write_u8(info.opcode)?;
+
+    Ok(())
+}
+
+fn write_const<E, W>(writer: &mut W, constant: &Const, indices: ScriptIndices) -> io::Result<()>
+where
+    E: ByteOrder,
+    W: Write,
+{
+    let token = |writer: &mut W, token: ExprToken| writer.write_u8(token as u8);
+
+    match constant {
+        Const::Int(0) => token(writer, ExprToken::IntZero)?,
+        Const::Int(1) => token(writer, ExprToken::IntOne)?,
+        Const::Int(value) => {
+            token(writer, ExprToken::IntConst)?;
+            writer.write_i32::<E>(*value)?;
+        }
+        Const::IntByte(value) => {
+            token(writer, ExprToken::IntConstByte)?;
+            writer.write_u8(*value)?;
+        }
+        Const::Byte(value) => {
+            token(writer, ExprToken::ByteConst)?;
+            writer.write_u8(*value)?;
+        }
+        Const::Float(value) => {
+            token(writer, ExprToken::FloatConst)?;
+            writer.write_f32::<E>(*value)?;
+        }
+        Const::Bool(true) => token(writer, ExprToken::True)?,
+        Const::Bool(false) => token(writer, ExprToken::False)?,
+        Const::String(string) => {
+            token(writer, ExprToken::StringConst)?;
+            // Strings were decoded one byte per character
+            for c in string.chars() {
+                writer.write_u8(c as u8)?;
+            }
+            writer.write_u8(0)?;
+        }
+        Const::UnicodeString(string) => {
+            token(writer, ExprToken::UnicodeStringConst)?;
+            for c in string.encode_utf16() {
+                writer.write_u16::<E>(c)?;
+            }
+            writer.write_u16::<E>(0)?;
+        }
+        Const::Object(object) if object.raw_index == 0 => token(writer, ExprToken::NoObject)?,
+        Const::Object(object) => {
+            token(writer, ExprToken::ObjectConst)?;
+            write_packed_int(writer, (indices.object)(object.raw_index)?)?;
+        }
+        Const::Name(name) => {
+            token(writer, ExprToken::NameConst)?;
+            write_packed_int(writer, (indices.name)(*name)?)?;
+        }
+        Const::Rotation { pitch, yaw, roll } => {
+            token(writer, ExprToken::RotationConst)?;
+            for value in [pitch, yaw, roll] {
+                writer.write_i32::<E>(*value)?;
+            }
+        }
+        Const::Vector { x, y, z } => {
+            token(writer, ExprToken::VectorConst)?;
+            for value in [x, y, z] {
+                writer.write_f32::<E>(*value)?;
+            }
+        }
+        Const::Range { min, max } => {
+            token(writer, ExprToken::RangeConst)?;
+            writer.write_f32::<E>(*min)?;
+            writer.write_f32::<E>(*max)?;
+        }
+        Const::Pointer(value) => {
+            token(writer, ExprToken::PointerConst)?;
+            writer.write_i32::<E>(*value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `Case` offset which marks the `default` case.
+const CASE_DEFAULT: u16 = u16::MAX;
+
+/// The function invoked by a call expression.
+#[derive(Clone, Debug)]
+pub enum Callee {
+    /// Call to the native function with this index. See [`NativeFunctionTable`].
+    ///
+    /// [`NativeFunctionTable`]: crate::object::internal::natives::NativeFunctionTable
+    Native(u16),
+    /// A prebound function
+    Final(ScriptObject),
+    /// A function looked up by name on the context object
+    Virtual(FName),
+    /// The non-state version of a function
+    Global(FName),
+    /// A delegate. `name` is the function called if the delegate is unset.
+    Delegate { property: ScriptObject, name: FName },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LetKind {
+    Value,
+    Bool,
+    Delegate,
+}
+
+#[derive(Clone, Debug)]
+pub enum Const {
+    /// `0` and `1` are serialized as [`ExprToken::IntZero`] and [`ExprToken::IntOne`]
+    Int(i32),
+    /// An int constant which fit in a single byte
+    IntByte(u8),
+    Byte(u8),
+    Float(f32),
+    Bool(bool),
+    String(String),
+    UnicodeString(String),
+    /// Null objects are serialized as [`ExprToken::NoObject`]
+    Object(ScriptObject),
+    Name(FName),
+    Rotation {
+        pitch: i32,
+        yaw: i32,
+        roll: i32,
+    },
+    Vector {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    Range {
+        min: f32,
+        max: f32,
+    },
+    Pointer(i32),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Label {
+    pub name: FName,
+    /// Code offset of the label
+    pub offset: u32,
+}
+
+/// A decoded script expression. Offsets are relative to the start of the in-memory script.
+#[derive(Clone, Debug)]
+pub enum Expr {
+    LocalVariable(ScriptObject),
+    InstanceVariable(ScriptObject),
+    DefaultVariable(ScriptObject),
+    /// Native function parameter
+    NativeParm(ScriptObject),
+    DelegateProperty(FName),
+    /// Wraps a bool variable expression, which requires a bitmask
+    BoolVariable(Box<Expr>),
+    Return(Box<Expr>),
+    Switch {
+        /// Size of the value being switched on
+        size: u8,
+        value: Box<Expr>,
+    },
+    Jump {
+        offset: u16,
+    },
+    JumpIfNot {
+        offset: u16,
+        condition: Box<Expr>,
+    },
+    Assert {
+        line: u16,
+        condition: Box<Expr>,
+    },
+    Case {
+        /// Offset of the next case
+        offset: u16,
+        /// `None` for the default case
+        value: Option<Box<Expr>>,
+    },
+    /// Ends with the `None` label which terminates the table
+    LabelTable(Vec<Label>),
+    GotoLabel(Box<Expr>),
+    EatString(Box<Expr>),
+    Let {
+        kind: LetKind,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    ArrayElement {
+        dynamic: bool,
+        index: Box<Expr>,
+        array: Box<Expr>,
+    },
+    DynArrayLength(Box<Expr>),
+    DynArrayInsert {
+        array: Box<Expr>,
+        index: Box<Expr>,
+        count: Box<Expr>,
+    },
+    DynArrayRemove {
+        array: Box<Expr>,
+        index: Box<Expr>,
+        count: Box<Expr>,
+    },
+    New {
+        outer: Box<Expr>,
+        name: Box<Expr>,
+        flags: Box<Expr>,
+        class: Box<Expr>,
+    },
+    /// Evaluates `context` on `object`. If `class` is set, `object` is a class and `context`
+    /// is evaluated on its default object.
+    Context {
+        class: bool,
+        object: Box<Expr>,
+        /// Size of `context`, skipped if `object` is None
+        skip_offset: u16,
+        /// Size of the result to zero-fill when skipped
+        zero_size: u8,
+        context: Box<Expr>,
+    },
+    /// A class cast. `meta` casts are for class references (`class<T>(...)`).
+    Cast {
+        meta: bool,
+        class: ScriptObject,
+        expr: Box<Expr>,
+    },
+    PrimitiveCast {
+        cast: u8,
+        expr: Box<Expr>,
+    },
+    LineNumber {
+        line: u16,
+        expr: Box<Expr>,
+    },
+    /// A short-circuitable expression
+    Skip {
+        size: u16,
+        expr: Box<Expr>,
+    },
+    Iterator {
+        expr: Box<Expr>,
+        /// Offset of the end of the loop
+        end_offset: u16,
+    },
+    StructCmp {
+        equal: bool,
+        struct_obj: ScriptObject,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+    StructMember {
+        property: ScriptObject,
+        expr: Box<Expr>,
+    },
+    Call {
+        func: Callee,
+        args: Vec<Expr>,
+        debug_info: Option<DebugInfo>,
+    },
+    Const(Const),
+    DebugInfo(DebugInfo),
+    Nothing,
+    EndFunctionParms,
+    SelfObj,
+    IteratorPop,
+    IteratorNext,
+    Stop,
+    EndOfScript,
+    /// A token which couldn't be decoded. Decoding stops here, so this is always the last
+    /// statement of a partially decoded script.
+    Unknown {
+        opcode: u8,
+        /// The rest of the script's data following the token
+        raw_bytes: Vec<u8>,
+    },
+}
+
+impl Expr {
+    /// The expressions directly nested in this one, in the order they're serialized.
+    pub fn sub_exprs(&self) -> Vec<&Expr> {
+        match self {
+            Expr::BoolVariable(expr)
+            | Expr::Return(expr)
+            | Expr::GotoLabel(expr)
+            | Expr::EatString(expr)
+            | Expr::DynArrayLength(expr)
+            | Expr::Switch { value: expr, .. }
+            | Expr::JumpIfNot {
+                condition: expr, ..
+            }
+            | Expr::Assert {
+                condition: expr, ..
+            }
+            | Expr::Case {
+                value: Some(expr), ..
+            }
+            | Expr::Cast { expr, .. }
+            | Expr::PrimitiveCast { expr, .. }
+            | Expr::LineNumber { expr, .. }
+            | Expr::Skip { expr, .. }
+            | Expr::Iterator { expr, .. }
+            | Expr::StructMember { expr, .. } => vec![expr],
+            Expr::Let { lhs, rhs, .. } | Expr::StructCmp { lhs, rhs, .. } => vec![lhs, rhs],
+            Expr::ArrayElement { index, array, .. } => vec![index, array],
+            Expr::DynArrayInsert {
+                array,
+                index,
+                count,
+            }
+            | Expr::DynArrayRemove {
+                array,
+                index,
+                count,
+            } => vec![array, index, count],
+            Expr::New {
+                outer,
+                name,
+                flags,
+                class,
+            } => vec![outer, name, flags, class],
+            Expr::Context {
+                object, context, ..
+            } => vec![object, context],
+            Expr::Call { args, .. } => args.iter().collect(),
+            Expr::LocalVariable(_)
+            | Expr::InstanceVariable(_)
+            | Expr::DefaultVariable(_)
+            | Expr::NativeParm(_)
+            | Expr::DelegateProperty(_)
+            | Expr::Jump { .. }
+            | Expr::Case { value: None, .. }
+            | Expr::LabelTable(_)
+            | Expr::Const(_)
+            | Expr::DebugInfo(_)
+            | Expr::Nothing
+            | Expr::EndFunctionParms
+            | Expr::SelfObj
+            | Expr::IteratorPop
+            | Expr::IteratorNext
+            | Expr::Stop
+            | Expr::EndOfScript
+            | Expr::Unknown { .. } => Vec::new(),
+        }
+    }
+}
+
+/// Evaluatable expression item types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ExprToken {
+    // Variable references.
+    /// A local variable.
+    LocalVariable = 0x00,
+    /// An object variable.
+    InstanceVariable = 0x01,
+    /// Default variable for a concrete object.
+    DefaultVariable = 0x02,
+
+    // Tokens.
+    /// Return from function.
+    Return = 0x04,
+    /// Switch.
+    Switch = 0x05,
+    /// Goto a local address in code.
+    Jump = 0x06,
+    /// Goto if not expression.
+    JumpIfNot = 0x07,
+    /// Stop executing state code.
+    Stop = 0x08,
+    /// Assertion.
+    Assert = 0x09,
+    /// Case.
+    Case = 0x0A,
+    /// No operation.
+    Nothing = 0x0B,
+    /// Table of labels.
+    LabelTable = 0x0C,
+    /// Goto a label.
+    GotoLabel = 0x0D,
+    /// Ignore a dynamic string.
+    EatString = 0x0E,
+    /// Assign an arbitrary size value to a variable.
+    Let = 0x0F,
+    /// Dynamic array element.!!
+    DynArrayElement = 0x10,
+    /// New object allocation.
+    New = 0x11,
+    /// Class default metaobject context.
+    ClassContext = 0x12,
+    /// Metaclass cast.
+    MetaCast = 0x13,
+    /// Let boolean variable.
+    LetBool = 0x14,
+    /// Set current source code line number in stack frame.
+    LineNumber = 0x15,
+    /// End of function call parameters.
+    EndFunctionParms = 0x16,
+    /// Self object.
+    SelfObj = 0x17,
+    /// Skippable expression.
+    Skip = 0x18,
+    /// Call a function through an object context.
+    Context = 0x19,
+    /// Array element.
+    ArrayElement = 0x1A,
+    /// A function call with parameters.
+    VirtualFunction = 0x1B,
+    /// A prebound function call with parameters.
+    FinalFunction = 0x1C,
+    /// Int constant.
+    IntConst = 0x1D,
+    /// Floating point constant.
+    FloatConst = 0x1E,
+    /// String constant.
+    StringConst = 0x1F,
+    /// An object constant.
+    ObjectConst = 0x20,
+    /// A name constant.
+    NameConst = 0x21,
+    /// A rotation constant.
+    RotationConst = 0x22,
+    /// A vector constant.
+    VectorConst = 0x23,
+    /// A byte constant.
+    ByteConst = 0x24,
+    /// Zero.
+    IntZero = 0x25,
+    /// One.
+    IntOne = 0x26,
+    /// Bool True.
+    True = 0x27,
+    /// Bool False.
+    False = 0x28,
+    /// Native function parameter offset.
+    NativeParm = 0x29,
+    /// NoObject.
+    NoObject = 0x2A,
+    /// Int constant that requires 1 byte.
+    IntConstByte = 0x2C,
+    /// A bool variable which requires a bitmask.
+    BoolVariable = 0x2D,
+    /// Safe dynamic class casting.
+    DynamicCast = 0x2E,
+    /// Begin an iterator operation.
+    Iterator = 0x2F,
+    /// Pop an iterator level.
+    IteratorPop = 0x30,
+    /// Go to next iteration.
+    IteratorNext = 0x31,
+    /// Struct binary compare-for-equal.
+    StructCmpEq = 0x32,
+    /// Struct binary compare-for-unequal.
+    StructCmpNe = 0x33,
+    /// Unicode string constant.
+    UnicodeStringConst = 0x34,
+    /// A range constant.
+    RangeConst = 0x35,
+    /// Struct member.
+    StructMember = 0x36,
+    /// A dynamic array length for setting/getting
+    DynArrayLength = 0x37,
+    /// Call non-state version of a function.
+    GlobalFunction = 0x38,
+    /// A casting operator for primitives which reads the type as the subsequent byte
+    PrimitiveCast = 0x39,
+    /// Inserts into a dynamic array
+    DynArrayInsert = 0x40,
+    /// Removes from a dynamic array
+    DynArrayRemove = 0x41,
+    /// DEBUGGER Debug information
+    DebugInfo = 0x42,
+    /// Call to a delegate function
+    DelegateFunction = 0x43,
+    /// Delegate expression
+    DelegateProperty = 0x44,
+    /// Assignment to a delegate
+    LetDelegate = 0x45,
+    /// Int constant.
+    PointerConst = 0x46,
+    /// Last byte in script code
+    EndOfScript = 0x47,
+
+    // Natives.
+    ExtendedNative = 0x60,
+    FirstNative = 0x70,
+}
+
+impl TryFrom<u8> for ExprToken {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(ExprToken::LocalVariable),
+            0x01 => Ok(ExprToken::InstanceVariable),
+            0x02 => Ok(ExprToken::DefaultVariable),
+            0x04 => Ok(ExprToken::Return),
+            0x05 => Ok(ExprToken::Switch),
+            0x06 => Ok(ExprToken::Jump),
+            0x07 => Ok(ExprToken::JumpIfNot),
+            0x08 => Ok(ExprToken::Stop),
+            0x09 => Ok(ExprToken::Assert),
+            0x0A => Ok(ExprToken::Case),
+            0x0B => Ok(ExprToken::Nothing),
+            0x0C => Ok(ExprToken::LabelTable),
+            0x0D => Ok(ExprToken::GotoLabel),
+            0x0E => Ok(ExprToken::EatString),
+            0x0F => Ok(ExprToken::Let),
+            0x10 => Ok(ExprToken::DynArrayElement),
+            0x11 => Ok(ExprToken::New),
+            0x12 => Ok(ExprToken::ClassContext),
+            0x13 => Ok(ExprToken::MetaCast),
+            0x14 => Ok(ExprToken::LetBool),
+            0x15 => Ok(ExprToken::LineNumber),
+            0x16 => Ok(ExprToken::EndFunctionParms),
+            0x17 => Ok(ExprToken::SelfObj),
+            0x18 => Ok(ExprToken::Skip),
+            0x19 => Ok(ExprToken::Context),
+            0x1A => Ok(ExprToken::ArrayElement),
+            0x1B => Ok(ExprToken::VirtualFunction),
+            0x1C => Ok(ExprToken::FinalFunction),
+            0x1D => Ok(ExprToken::IntConst),
+            0x1E => Ok(ExprToken::FloatConst),
+            0x1F => Ok(ExprToken::StringConst),
+            0x20 => Ok(ExprToken::ObjectConst),
+            0x21 => Ok(ExprToken::NameConst),
+            0x22 => Ok(ExprToken::RotationConst),
+            0x23 => Ok(ExprToken::VectorConst),
+            0x24 => Ok(ExprToken::ByteConst),
+            0x25 => Ok(ExprToken::IntZero),
+            0x26 => Ok(ExprToken::IntOne),
+            0x27 => Ok(ExprToken::True),
+            0x28 => Ok(ExprToken::False),
+            0x29 => Ok(ExprToken::NativeParm),
+            0x2A => Ok(ExprToken::NoObject),
+            0x2C => Ok(ExprToken::IntConstByte),
+            0x2D => Ok(ExprToken::BoolVariable),
+            0x2E => Ok(ExprToken::DynamicCast),
+            0x2F => Ok(ExprToken::Iterator),
+            0x30 => Ok(ExprToken::IteratorPop),
+            0x31 => Ok(ExprToken::IteratorNext),
+            0x32 => Ok(ExprToken::StructCmpEq),
+            0x33 => Ok(ExprToken::StructCmpNe),
+            0x34 => Ok(ExprToken::UnicodeStringConst),
+            0x35 => Ok(ExprToken::RangeConst),
+            0x36 => Ok(ExprToken::StructMember),
+            0x37 => Ok(ExprToken::DynArrayLength),
+            0x38 => Ok(ExprToken::GlobalFunction),
+            0x39 => Ok(ExprToken::PrimitiveCast),
+            0x40 => Ok(ExprToken::DynArrayInsert),
+            0x41 => Ok(ExprToken::DynArrayRemove),
+            0x42 => Ok(ExprToken::DebugInfo),
+            0x43 => Ok(ExprToken::DelegateFunction),
+            0x44 => Ok(ExprToken::DelegateProperty),
+            0x45 => Ok(ExprToken::LetDelegate),
+            0x46 => Ok(ExprToken::PointerConst),
+            0x47 => Ok(ExprToken::EndOfScript),
+            0x60 => Ok(ExprToken::ExtendedNative),
+            0x70 => Ok(ExprToken::FirstNative),
+            _ => Err(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        de::{Linker, NamePool, PackageHeader, RawPackage},
+        profile::ScriptSizeModel,
+        reader::{LinReader, PackageReader},
+    };
+
+    fn test_linker() -> RcLinker {
+        let package = RawPackage {
+            header: PackageHeader {
+                version: 0,
+                flags: 0,
+                name_count: 0,
+                name_offset: 0,
+                export_count: 0,
+                export_offset: 0,
+                import_count: 0,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: Vec::new(),
+            },
+            names: Vec::new(),
+            imports: Vec::new(),
+            exports: Vec::new(),
+        };
+
+        Rc::new(RefCell::new(Linker::new(
+            "Test".to_string(),
+            package,
+            &mut NamePool::default(),
+            None,
+        )))
+    }
+
+    #[test]
+    fn decodes_nested_expressions() {
+        #[rustfmt::skip]
+        let code = [
+            // Let(LocalVariable, Sin(1.0))
+            0x0F, 0x00, 0x00, 0xBB, 0x1E, 0x00, 0x00, 0x80, 0x3F, 0x16,
+            // Return(Nothing)
+            0x04, 0x0B,
+            0x47,
+        ];
+
+        let mut runtime = UnrealRuntime::default();
+        runtime.load_options.skip_script_bodies = true;
+        let linker = test_linker();
+        let mut reader = PackageReader::new(Cursor::new(code));
+
+        let statements =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 16, None)
+                .unwrap();
+
+        assert_eq!(
+            statements.iter().map(|s| s.offset).collect::<Vec<_>>(),
+            [0, 13, 15]
+        );
+
+        let Expr::Let { kind, lhs, rhs } = &statements[0].expr else {
+            panic!("expected Let, got {:?}", statements[0].expr);
+        };
+        assert_eq!(*kind, LetKind::Value);
+        assert!(matches!(
+            **lhs,
+            Expr::LocalVariable(ScriptObject {
+                raw_index: 0,
+                object: None
+            })
+        ));
+
+        let Expr::Call { func, args, .. } = &**rhs else {
+            panic!("expected Call, got {rhs:?}");
+        };
+        assert!(matches!(func, Callee::Native(187)));
+        assert!(matches!(args.as_slice(), [Expr::Const(Const::Float(1.0))]));
+
+        assert!(
+            matches!(&statements[1].expr, Expr::Return(inner) if matches!(**inner, Expr::Nothing))
+        );
+        assert!(matches!(statements[2].expr, Expr::EndOfScript));
+    }
+
+    #[test]
+    fn counts_operands_with_profile_sizes() {
+        #[rustfmt::skip]
+        let code = [
+            // Jump(0x10)
+            0x06, 0x10, 0x00,
+            // NameConst(None)
+            0x21, 0x00,
+            0x47,
+        ];
+
+        let mut runtime = UnrealRuntime::default();
+        let linker = test_linker();
+        linker.borrow_mut().profile.quirks.script_sizes = ScriptSizeModel {
+            name: 8,
+            ..ScriptSizeModel::UE2
+        };
+        let mut reader = PackageReader::new(Cursor::new(code));
+
+        let statements =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 13, None)
+                .unwrap();
+
+        assert_eq!(
+            statements.iter().map(|s| s.offset).collect::<Vec<_>>(),
+            [0, 3, 12]
+        );
+
+        // With the wider names, the name overruns a script sized for stock names
+        let mut reader = PackageReader::new(Cursor::new(code));
+        let err =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 8, None)
+                .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn keeps_unknown_tokens_undecoded() {
+        #[rustfmt::skip]
+        let code = [
+            // Return(Nothing)
+            0x04, 0x0B,
+            // Unknown token and its data
+            0x03, 0xAA, 0xBB,
+            // Data following the script
+            0xCC,
+        ];
+
+        let mut runtime = UnrealRuntime::default();
+        let linker = test_linker();
+        let mut reader = PackageReader::new(Cursor::new(code));
+
+        let statements =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 5, None)
+                .unwrap();
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[1].offset, 2);
+        let Expr::Unknown { opcode, raw_bytes } = &statements[1].expr else {
+            panic!("expected Unknown, got {:?}", statements[1].expr);
+        };
+        assert_eq!(
+            (*opcode, raw_bytes.as_slice()),
+            (0x03, [0xAA, 0xBB].as_slice())
+        );
+        assert_eq!(reader.read_u8().unwrap(), 0xCC);
+    }
+
+    #[test]
+    fn stops_unknown_tokens_at_the_end_of_the_export() {
+        #[rustfmt::skip]
+        let code = [
+            // Unknown token and its data
+            0x03, 0xAA, 0xBB,
+            // Data following the export
+            0xCC, 0xDD, 0xEE, 0xFF,
+        ];
+
+        let mut runtime = UnrealRuntime::default();
+        let linker = test_linker();
+        let mut reader = PackageReader::new(Cursor::new(code));
+
+        // Object references in the undecoded data make it longer in memory than on disk
+        let statements =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 7, Some(3))
+                .unwrap();
+
+        let Expr::Unknown { raw_bytes, .. } = &statements[0].expr else {
+            panic!("expected Unknown, got {:?}", statements[0].expr);
+        };
+        assert_eq!(raw_bytes.as_slice(), [0xAA, 0xBB]);
+        assert_eq!(reader.read_u8().unwrap(), 0xCC);
+    }
+
+    #[test]
+    fn reencodes_scripts_byte_identically() {
+        #[rustfmt::skip]
+        let code = [
+            // LabelTable([Begin @ 0x10], None @ 0xFFFF)
+            0x0C, 0x02, 0x10, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0x00, 0x00,
+            // Let(InstanceVariable(export 3), FinalFunction(import 1, IntConstByte(5), "Hi"))
+            0x0F, 0x01, 0x03, 0x1C, 0x81, 0x2C, 0x05, 0x1F, b'H', b'i', 0x00, 0x16,
+            // JumpIfNot(0x20, NoObject)
+            0x07, 0x20, 0x00, 0x2A,
+            // ExtendedNative 0x1A3(IntOne, VectorConst(1.0, 0.0, 0.0))
+            0x61, 0xA3, 0x26, 0x23, 0x00, 0x00, 0x80, 0x3F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x16,
+            // Case(default)
+            0x0A, 0xFF, 0xFF,
+            // Unknown token and its data
+            0x03, 0xAA, 0xBB,
+        ];
+
+        let mut runtime = UnrealRuntime::default();
+        runtime.load_options.skip_script_bodies = true;
+        let linker = test_linker();
+        let mut reader = PackageReader::new(Cursor::new(code));
+
+        let statements =
+            deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, 62, None)
+                .unwrap();
+
+        let Expr::Let { lhs, .. } = &statements[1].expr else {
+            panic!("expected Let, got {:?}", statements[1].expr);
+        };
+        assert!(matches!(
+            **lhs,
+            Expr::InstanceVariable(ScriptObject { raw_index: 3, .. })
+        ));
+
+        let mut encoded = Vec::new();
+        serialize_exprs::<LittleEndian, _>(
+            &mut encoded,
+            statements.iter().map(|statement| &statement.expr),
+        )
+        .unwrap();
+        assert_eq!(encoded, code);
+    }
+
+    #[test]
+    fn attaches_trailing_debug_info() {
+        let debug_info = |line: i32, opcode: u8| {
+            let mut bytes = vec![ExprToken::DebugInfo as u8];
+            for value in [DEBUG_INFO_VERSION, line, 0] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.push(opcode);
+            bytes
+        };
+        #[rustfmt::skip]
+        let code = [
+            // Native 0x90(Native 0x91(IntOne))
+            vec![0x90, 0x91, 0x26, 0x16],
+            debug_info(1, 0x10),
+            vec![0x16],
+            debug_info(2, 0x10),
+            // A new statement
+            debug_info(3, 0x05),
+            vec![0x47],
+        ]
+        .concat();
+
+        let decode = |streamed: bool, skip_debug_info: bool| {
+            let mut runtime = UnrealRuntime::default();
+            runtime.load_options.skip_script_bodies = true;
+            runtime.load_options.skip_script_debug_info = skip_debug_info;
+            let linker = test_linker();
+            let len = code.len();
+
+            if streamed {
+                let mut reader = LinReader::new(Cursor::new(&code));
+                deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, len, None)
+            } else {
+                let mut reader = PackageReader::new(Cursor::new(&code));
+                deserialize_script::<LittleEndian, _>(&mut runtime, &linker, &mut reader, len, None)
+            }
+            .unwrap()
+        };
+        let line = |info: &Option<DebugInfo>| info.map(|info| info.line);
+
+        // Streamed readers can't look ahead, but end up with the same statements
+        for streamed in [false, true] {
+            let statements = decode(streamed, false);
+            assert_eq!(statements.len(), 3);
+            let Expr::Call {
+                args, debug_info, ..
+            } = &statements[0].expr
+            else {
+                panic!("expected Call, got {:?}", statements[0].expr);
+            };
+            assert_eq!(line(debug_info), Some(2));
+            let Expr::Call { debug_info, .. } = &args[0] else {
+                panic!("expected Call, got {:?}", args[0]);
+            };
+            assert_eq!(line(debug_info), Some(1));
+            assert!(matches!(statements[1].expr, Expr::DebugInfo(info) if info.line == 3));
+            assert_eq!(statements[1].offset, 33);
+
+            let mut encoded = Vec::new();
+            serialize_exprs::<LittleEndian, _>(
+                &mut encoded,
+                statements.iter().map(|statement| &statement.expr),
+            )
+            .unwrap();
+            assert_eq!(encoded, code);
+        }
+
+        // Standalone debug info is left where it was decoded
+        let statements = decode(false, true);
+        assert_eq!(statements.len(), 4);
+        assert!(matches!(
+            statements[0].expr,
+            Expr::Call {
+                debug_info: None,
+                ..
+            }
+        ));
+        assert!(matches!(statements[1].expr, Expr::DebugInfo(info) if info.line == 2));
+    }
+
+    #[test]
+    fn collects_nested_constants_in_order() {
+        let script = [
+            Expr::Let {
+                kind: LetKind::Value,
+                lhs: Box::new(Expr::LocalVariable(ScriptObject::default())),
+                rhs: Box::new(Expr::Call {
+                    func: Callee::Native(0x90),
+                    args: vec![Expr::Const(Const::Int(1)), Expr::Const(Const::Float(2.5))],
+                    debug_info: None,
+                }),
+            },
+            Expr::Return(Box::new(Expr::Const(Const::String("done".to_owned())))),
+            Expr::EndOfScript,
+        ];
+
+        let constants = constants(&script);
+
+        assert_eq!(constants.len(), 3);
+        assert!(matches!(constants[0], Const::Int(1)));
+        assert!(matches!(constants[1], Const::Float(2.5)));
+        assert!(matches!(constants[2], Const::String(text) if text == "done"));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::object::UObjectKind;
+
+/// Classes which are implemented natively by the engine. Imports of these are never
+/// resolved against a package's exports; an intrinsic object is created instead.
+///
+/// Classes which are also builtins (see [`UObjectKind`]) are not listed here.
+const NATIVE_CLASSES: &[(&str, &str)] = &[
+    ("Core", "ArrayProperty"),
+    ("Core", "DelegateProperty"),
+    ("Core", "FixedArrayProperty"),
+    ("Core", "Linker"),
+    ("Core", "LinkerLoad"),
+    ("Core", "LinkerSave"),
+    ("Core", "MapProperty"),
+    ("Core", "Package"),
+    ("Core", "PointerProperty"),
+    ("Core", "Subsystem"),
+];
+
+/// Returns the object kind to use for an intrinsic object if `package.name` refers
+/// to a class implemented natively by the engine.
+pub(crate) fn find_native_class(package: &str, name: &str) -> Option<UObjectKind> {
+    if !package.eq_ignore_ascii_case("Core") {
+        return None;
+    }
+
+    if UObjectKind::try_from(name).is_ok()
+        || NATIVE_CLASSES
+            .iter()
+            .any(|(_, native_name)| native_name.eq_ignore_ascii_case(name))
+    {
+        return Some(UObjectKind::Class);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_classes() {
+        assert_eq!(
+            find_native_class("Core", "Object"),
+            Some(UObjectKind::Class)
+        );
+        assert_eq!(
+            find_native_class("Core", "Package"),
+            Some(UObjectKind::Class)
+        );
+        assert_eq!(
+            find_native_class("core", "PACKAGE"),
+            Some(UObjectKind::Class)
+        );
+        assert_eq!(find_native_class("Engine", "Object"), None);
+        assert_eq!(find_native_class("Core", "Commandlet"), None);
+    }
+}
@@ -1,7 +1,9 @@
 /// Internal types that are not directly exposed to the scripting engine
-mod internal;
+pub(crate) mod internal;
+pub(crate) mod intrinsic;
 #[cfg(test)]
 mod test_common;
+mod typed;
 mod uclass;
 mod uconst;
 mod uenum;
@@ -26,25 +28,31 @@ use bitflags::bitflags;
 use byteorder::ByteOrder;
 use paste::paste;
 pub mod builtins {
-    pub use super::uclass::Class;
+    pub use super::uclass::{Class, ClassDependency, ClassFlags};
     pub use super::uconst::Const;
     pub use super::uenum::Enum;
     pub use super::ufield::Field;
-    pub use super::ufunction::Function;
+    pub use super::ufunction::{Function, FunctionFlags};
     pub use super::uobject::Object;
     pub use super::uproperty::*;
     pub use super::ustate::State;
     pub use super::ustruct::Struct;
-    pub use super::utext_buffer::TextBuffer;
+    pub use super::utext_buffer::{TextBuffer, TextData};
 }
 
 use builtins::*;
+pub use internal::class_ref::ClassRef;
+pub use internal::property::PropertyValue;
+pub use typed::{BuiltinObject, TypedObject};
 
 use crate::de::{ExportIndex, Linker, ObjectExport, RcLinker, WeakLinker};
 use crate::reader::LinRead;
 use crate::runtime::UnrealRuntime;
 
+/// A shared handle to a loaded object. Objects are owned by the linker they were loaded from.
 pub type RcUnrealObject = Rc<RefCell<dyn UnrealObject>>;
+/// A handle which doesn't keep its object alive. Used for references back up the object
+/// graph, such as an object's outer, so that they don't form reference cycles.
 pub type WeakUnrealObject = Weak<RefCell<dyn UnrealObject>>;
 
 pub trait UnrealObject: std::fmt::Debug {
@@ -58,6 +66,45 @@ pub trait UnrealObject: std::fmt::Debug {
     fn is_a(&self, kind: UObjectKind) -> bool;
     fn parent_of_kind(&self, kind: UObjectKind) -> Option<&dyn UnrealObject>;
     fn parent_of_kind_mut(&mut self, kind: UObjectKind) -> Option<&mut dyn UnrealObject>;
+
+    /// The value of the tagged property `name`, whatever the object's concrete type.
+    fn get_property(&self, name: &str) -> Option<&PropertyValue> {
+        self.base_object().property(name)
+    }
+
+    /// Sets the tagged property `name`, replacing its value if it has one.
+    fn set_property(&mut self, name: &str, value: PropertyValue) {
+        self.base_object_mut().set_property(name, value);
+    }
+
+    /// Whether the object is an instance of the class `name` or a subclass of it, ignoring
+    /// ASCII case. `name` may be qualified with its package, e.g. `Engine.Actor`.
+    ///
+    /// Unlike [`UnrealObject::is_a`], which only knows about the builtin classes, this
+    /// follows the object's real class so script classes and the classes they extend are
+    /// matched too.
+    fn is_a_named(&self, name: &str) -> bool {
+        if let Some(class) = self.base_object().class_ref()
+            && class.is_child_of(name)
+        {
+            return true;
+        }
+
+        // The builtin classes all live in Core
+        let builtin_name = match name.split_once('.') {
+            Some((package, name)) if package.eq_ignore_ascii_case("Core") => name,
+            Some(_) => return false,
+            None => name,
+        };
+        UObjectKind::try_from(builtin_name).is_ok_and(|kind| self.is_a(kind))
+    }
+
+    /// Whether the object is an instance of `class` or a subclass of it.
+    fn is_a_class(&self, class: &ClassRef) -> bool {
+        self.base_object()
+            .class_ref()
+            .is_some_and(|own_class| own_class.is_child_of_class(class))
+    }
 }
 
 pub trait DeserializeUnrealObject {
@@ -90,7 +137,7 @@ macro_rules! register_builtins {
                 ].as_slice()
             }
 
-            pub fn construct(&self, linker: WeakLinker, export_index: ExportIndex) -> RcUnrealObject  {
+            pub(crate) fn construct(&self, linker: WeakLinker, export_index: ExportIndex) -> RcUnrealObject  {
                 match self {
                     $(
                         Self::$name => {
@@ -109,6 +156,35 @@ macro_rules! register_builtins {
                 }
             }
 
+            /// Constructs an object which has no backing export, such as a class
+            /// implemented natively by the engine.
+            pub fn construct_intrinsic(&self) -> RcUnrealObject  {
+                match self {
+                    $(
+                        Self::$name => {
+                            let mut obj = $name::default();
+                            obj.base_object_mut().set_concrete_object_kind(UObjectKind::$name);
+
+                            Rc::new(RefCell::new(obj))
+                        }
+                    )*
+                }
+            }
+
+            /// Resets `obj`, which must be of this kind, to its default state. This drops every
+            /// reference it holds so that cycles between unloaded objects can be freed.
+            pub(crate) fn release(&self, obj: &mut dyn UnrealObject) {
+                match self {
+                    $(
+                        Self::$name => {
+                            if let Some(obj) = obj.as_any_mut().downcast_mut::<$name>() {
+                                *obj = $name::default();
+                            }
+                        }
+                    )*
+                }
+            }
+
             pub fn as_str(&self) -> &'static str {
                 match self {
                     $(
@@ -119,6 +195,11 @@ macro_rules! register_builtins {
                 }
             }
 
+            /// The builtin kind named `class_name`, if there is one.
+            pub(crate) fn from_class_name(class_name: &str) -> Option<UObjectKind> {
+                Self::all().iter().copied().find(|kind| kind.as_str() == class_name)
+            }
+
             $(
               paste! {
                   pub fn [<is_ $name:lower>](&self) -> bool {
@@ -128,20 +209,24 @@ macro_rules! register_builtins {
             )*
         }
 
+        $(
+            impl BuiltinObject for $name {
+                const KIND: UObjectKind = UObjectKind::$name;
+            }
+        )*
+
         impl TryFrom<&str> for UObjectKind {
             type Error = ();
 
+            /// Class names are matched case-insensitively, as the engine does.
             fn try_from(name: &str) -> Result<Self, Self::Error> {
-                match name {
-                    $(
-                        stringify!($name) => {
-                            Ok(UObjectKind::$name)
-                        }
-                    )*
-                    _ => {
-                        Err(())
+                $(
+                    if name.eq_ignore_ascii_case(stringify!($name)) {
+                        return Ok(UObjectKind::$name);
                     }
-                }
+                )*
+
+                Err(())
             }
         }
 
@@ -200,6 +285,10 @@ register_builtins!(
     NameProperty,
     StructProperty,
     ByteProperty,
+    DelegateProperty,
+    PointerProperty,
+    MapProperty,
+    FixedArrayProperty,
     Enum
 );
 
@@ -305,6 +394,10 @@ make_inherited_objects!(
     NameProperty,
     StructProperty,
     ByteProperty,
+    DelegateProperty,
+    PointerProperty,
+    MapProperty,
+    FixedArrayProperty,
     Enum
 );
 
@@ -360,7 +453,11 @@ register_linkable!(
     ObjectProperty,
     ClassProperty,
     StructProperty,
-    ByteProperty
+    ByteProperty,
+    DelegateProperty,
+    PointerProperty,
+    MapProperty,
+    FixedArrayProperty
 );
 
 bitflags! {
@@ -440,3 +537,11 @@ bitflags! {
         const DEBUG_DESTROY     = 0x80000000;
     }
 }
+
+impl ObjectFlags {
+    /// Set bits which don't correspond to any known flag. Every bit currently has a name, but
+    /// this is kept alongside the other flag types for licensee formats which repurpose them.
+    pub fn unknown_bits(&self) -> u32 {
+        self.bits() & !Self::all().bits()
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    cell::{Ref, RefMut},
+    fmt,
+    marker::PhantomData,
+    rc::Rc,
+};
+
+use crate::object::{RcUnrealObject, UObjectKind, UnrealObject};
+
+/// A builtin object type, such as [`Class`](super::builtins::Class), which can be borrowed
+/// out of a loaded object through a [`TypedObject`].
+pub trait BuiltinObject: UnrealObject + 'static {
+    const KIND: UObjectKind;
+}
+
+/// A loaded object which is known to be a `T`. Borrowing it returns its `T` part directly,
+/// so that callers don't need to walk its parents and downcast them.
+pub struct TypedObject<T> {
+    object: RcUnrealObject,
+    _kind: PhantomData<T>,
+}
+
+impl<T: BuiltinObject> TypedObject<T> {
+    /// Wraps `object` if it's a `T`, including if it's a type derived from `T`.
+    pub fn new(object: RcUnrealObject) -> Option<Self> {
+        let is_a = object.borrow().is_a(T::KIND);
+
+        is_a.then_some(TypedObject {
+            object,
+            _kind: PhantomData,
+        })
+    }
+
+    /// Immutably borrows the object as a `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is already mutably borrowed.
+    pub fn borrow(&self) -> Ref<'_, T> {
+        Ref::map(self.object.borrow(), |object| {
+            object
+                .parent_of_kind(T::KIND)
+                .and_then(|object| object.as_any().downcast_ref::<T>())
+                .expect("typed object changed kind")
+        })
+    }
+
+    /// Mutably borrows the object as a `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the object is already borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        RefMut::map(self.object.borrow_mut(), |object| {
+            object
+                .parent_of_kind_mut(T::KIND)
+                .and_then(|object| object.as_any_mut().downcast_mut::<T>())
+                .expect("typed object changed kind")
+        })
+    }
+
+    /// The untyped object.
+    pub fn object(&self) -> &RcUnrealObject {
+        &self.object
+    }
+
+    pub fn into_object(self) -> RcUnrealObject {
+        self.object
+    }
+}
+
+impl<T> Clone for TypedObject<T> {
+    fn clone(&self) -> Self {
+        TypedObject {
+            object: Rc::clone(&self.object),
+            _kind: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for TypedObject<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedObject").field(&self.object).finish()
+    }
+}
@@ -5,19 +5,224 @@ use std::{
 };
 
 use crate::{
+    annotate::annotated,
     de::{Linker, ObjectExport, RcLinker},
+    guid::{Guid, read_guid},
     object::{
-        DeserializeUnrealObject, UnrealObject, builtins::Link, ustate::State, ustruct::Struct,
+        DeserializeUnrealObject, TypedObject, UnrealObject,
+        builtins::{Function, Link, Property},
+        internal::{fname::FName, object_ref::ObjectRef},
+        ustate::State,
+        ustruct::Struct,
     },
-    reader::LinRead,
+    reader::{LinRead, UnrealReadExt},
     runtime::UnrealRuntime,
 };
+use bitflags::bitflags;
 use byteorder::ReadBytesExt;
-use tracing::{Level, span};
+use serde::Serialize;
+use tracing::{Level, debug, span};
 
 #[derive(Default, Debug)]
 pub struct Class {
     pub parent_object: State,
+
+    class_flags: ClassFlags,
+    class_guid: Guid,
+    dependencies: Vec<ClassDependency>,
+    /// Packages the class's script imports from
+    package_imports: Vec<FName>,
+    /// Class objects of this class must be created within
+    within: ObjectRef,
+    /// Base name of the .ini file the class's config variables are read from
+    config_name: FName,
+    /// Editor categories hidden for this class
+    hide_categories: Vec<FName>,
+}
+
+/// A class the script of a [`Class`] was compiled against, used by the script compiler to
+/// decide whether it must be recompiled.
+#[derive(Debug, Default)]
+pub struct ClassDependency {
+    class: ObjectRef,
+    /// Whether the dependency is on the class's whole hierarchy
+    pub deep: bool,
+    /// CRC of the class's script text when the dependency was recorded
+    pub script_text_crc: u32,
+}
+
+impl ClassDependency {
+    /// Name of the class depended on.
+    pub fn class_name(&self) -> Option<String> {
+        self.class.object_name()
+    }
+}
+
+impl Class {
+    /// The properties declared directly in this class.
+    pub fn properties(&self) -> impl Iterator<Item = TypedObject<Property>> {
+        self.parent_object.parent_object.properties()
+    }
+
+    /// The functions declared directly in this class.
+    pub fn functions(&self) -> impl Iterator<Item = TypedObject<Function>> {
+        self.parent_object.parent_object.functions()
+    }
+
+    pub fn class_flags(&self) -> ClassFlags {
+        self.class_flags
+    }
+
+    pub fn guid(&self) -> Guid {
+        self.class_guid
+    }
+
+    pub fn dependencies(&self) -> &[ClassDependency] {
+        &self.dependencies
+    }
+
+    /// Name of the class that objects of this class must be created within, as declared
+    /// with `within`. Every class is within `Object` unless it says otherwise. `None` if
+    /// the package doesn't record it.
+    pub fn within_class(&self) -> Option<String> {
+        self.within.object_name()
+    }
+
+    /// Base name of the .ini file the class's config variables are read from, e.g. `User`
+    /// for `User.ini`, resolved through the linker the class was loaded from. Classes which
+    /// don't declare one inherit their super class's, which is `System` for `Object`.
+    pub fn config_name(&self) -> Option<String> {
+        self.resolve_name(self.config_name)
+    }
+
+    /// The editor categories hidden for this class, resolved through its linker.
+    pub fn hide_categories(&self) -> Vec<String> {
+        self.hide_categories
+            .iter()
+            .filter_map(|&name| self.resolve_name(name))
+            .collect()
+    }
+
+    /// Names the packages the class's script imports from, resolved through its linker.
+    pub fn package_imports(&self) -> Vec<String> {
+        self.package_imports
+            .iter()
+            .filter_map(|&name| self.resolve_name(name))
+            .collect()
+    }
+
+    fn resolve_name(&self, name: FName) -> Option<String> {
+        if name.is_none() {
+            return None;
+        }
+
+        let linker = self.base_object().linker.as_ref()?.upgrade()?;
+        let name = name.resolve(&linker.borrow()).to_string();
+
+        Some(name)
+    }
+
+    /// Reads everything before the class's default properties: its state, followed by the
+    /// class's own declaration.
+    pub(crate) fn deserialize_declaration<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+        reader: &mut R,
+    ) -> io::Result<()>
+    where
+        E: byteorder::ByteOrder,
+        R: LinRead,
+    {
+        self.parent_object
+            .deserialize::<E, _>(runtime, linker, reader)?;
+
+        self.class_flags = annotated!(
+            reader,
+            "class_flags",
+            ClassFlags::from_bits_retain(reader.read_u32::<E>()?)
+        );
+        self.class_guid = annotated!(reader, "class_guid", read_guid::<E, _>(reader)?);
+
+        let count = reader.read_packed_int()?;
+        self.dependencies = annotated!(
+            reader,
+            "dependencies",
+            {
+                let mut dependencies = Vec::new();
+                for _ in 0..count {
+                    dependencies.push(ClassDependency {
+                        class: reader.read_object_ref::<E>(runtime, linker)?,
+                        deep: reader.read_u32::<E>()? != 0,
+                        script_text_crc: reader.read_u32::<E>()?,
+                    });
+                }
+
+                dependencies
+            },
+            |dependencies: &Vec<ClassDependency>| {
+                format!("{} dependencies", dependencies.len())
+            }
+        );
+
+        self.package_imports = annotated!(
+            reader,
+            "package_imports",
+            read_names::<E, _>(runtime, linker, reader)?
+        );
+
+        let profile = linker.borrow().profile.clone();
+        if profile.has_class_within() {
+            self.within = annotated!(
+                reader,
+                "within",
+                reader.read_object_ref::<E>(runtime, linker)?
+            );
+            annotated!(
+                reader,
+                "config_name",
+                self.config_name
+                    .deserialize::<E, _>(runtime, linker, reader)?,
+                |_| self.config_name.resolve(&linker.borrow()).to_string()
+            );
+            debug!(
+                "within= {:?}, config= {:?}",
+                self.within_class(),
+                self.config_name()
+            );
+        }
+
+        if profile.has_class_hide_categories() {
+            self.hide_categories = annotated!(
+                reader,
+                "hide_categories",
+                read_names::<E, _>(runtime, linker, reader)?
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads an array of names.
+fn read_names<E, R>(
+    runtime: &mut UnrealRuntime,
+    linker: &RcLinker,
+    reader: &mut R,
+) -> io::Result<Vec<FName>>
+where
+    E: byteorder::ByteOrder,
+    R: LinRead,
+{
+    let count = reader.read_packed_int()?;
+    let mut names = Vec::new();
+    for _ in 0..count {
+        let mut name = FName::default();
+        name.deserialize::<E, _>(runtime, linker, reader)?;
+        names.push(name);
+    }
+
+    Ok(names)
 }
 
 impl DeserializeUnrealObject for Class {
@@ -34,11 +239,52 @@ impl DeserializeUnrealObject for Class {
         let span = span!(Level::DEBUG, "deserialize_class");
         let _enter = span.enter();
 
-        self.parent_object
-            .deserialize::<E, _>(runtime, linker, reader)?;
+        self.deserialize_declaration::<E, _>(runtime, linker, reader)?;
+
+        // The declaration is followed by the class's default properties, which are tagged
+        // properties
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "default properties of class {} can't be read yet",
+                self.parent_object
+                    .parent_object
+                    .parent_object
+                    .parent_object
+                    .name()
+            ),
+        ))
+    }
+}
 
-        reader.read_u32::<E>()?;
-        todo!("class deserialization");
+bitflags! {
+    /// Class flags.
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+    pub struct ClassFlags: u32 {
+        /// Class is abstract and can't be instantiated directly.
+        const ABSTRACT = 0x00000001;
+        /// Script has been compiled successfully.
+        const COMPILED = 0x00000002;
+        /// Load object configuration at construction time.
+        const CONFIG = 0x00000004;
+        /// This object type can't be saved; null it out at save time.
+        const TRANSIENT = 0x00000008;
+        /// Successfully parsed.
+        const PARSED = 0x00000010;
+        /// Class contains localized text.
+        const LOCALIZED = 0x00000020;
+        /// Objects of this class can be safely replaced with default or None.
+        const SAFE_REPLACE = 0x00000040;
+        /// Objects of this class are static during gameplay.
+        const RUNTIME_STATIC = 0x00000080;
+        /// Don't export to C++ header.
+        const NO_EXPORT = 0x00000100;
+        /// Allow users to create in the editor.
+        const PLACEABLE = 0x00000200;
+        /// Handle object configuration on a per-object basis, rather than per-class.
+        const PER_OBJECT_CONFIG = 0x00000400;
+        /// Replication handled in C++.
+        const NATIVE_REPLICATION = 0x00000800;
     }
 }
 
@@ -4,6 +4,7 @@ use byteorder::ByteOrder;
 use tracing::{Level, debug, span};
 
 use crate::{
+    annotate::annotated,
     de::RcLinker,
     object::{DeserializeUnrealObject, ufield::Field},
     reader::{LinRead, UnrealReadExt},
@@ -34,10 +35,30 @@ impl DeserializeUnrealObject for Const {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        debug!("deserializing value");
-        self.value = reader.read_string()?;
+        self.value = annotated!(reader, "value", reader.read_string()?);
         debug!("Const value: {}", self.value);
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::object::{UObjectKind, UnrealObject, test_common::test_object_is_a};
+
+    use super::*;
+
+    pub fn expected_uobjectkind() -> impl IntoIterator<Item = UObjectKind> {
+        [UObjectKind::Const]
+            .iter()
+            .cloned()
+            .chain(crate::object::ufield::tests::expected_uobjectkind())
+    }
+
+    #[test]
+    fn test_is_a() {
+        let test_obj = Const::default();
+
+        test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
+    }
+}
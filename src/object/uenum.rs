@@ -1,8 +1,9 @@
-use std::io;
+use std::{io, rc::Rc};
 
-use tracing::{Level, span};
+use tracing::{Level, debug, span};
 
 use crate::{
+    annotate::annotated,
     de::RcLinker,
     object::{DeserializeUnrealObject, ufield::Field},
     reader::{LinRead, UnrealReadExt},
@@ -13,7 +14,14 @@ use crate::{
 pub struct Enum {
     pub parent_object: Field,
 
-    names: Vec<i32>,
+    /// Name of each value, in value order
+    names: Vec<Rc<str>>,
+}
+
+impl Enum {
+    pub fn names(&self) -> &[Rc<str>] {
+        &self.names
+    }
 }
 
 impl DeserializeUnrealObject for Enum {
@@ -33,7 +41,25 @@ impl DeserializeUnrealObject for Enum {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        self.names = reader.read_packed_int_array()?;
+        let indices = annotated!(reader, "names", reader.read_packed_int_array()?);
+
+        let linker = linker.borrow();
+        let name_count = linker.package.names.len();
+        self.names = indices
+            .into_iter()
+            .map(|index| {
+                if index < 0 || index as usize >= name_count {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("enum value name index {index} out of bounds"),
+                    ));
+                }
+
+                Ok(linker.name(index))
+            })
+            .collect::<io::Result<_>>()?;
+
+        debug!("Enum values: {:?}", self.names);
 
         Ok(())
     }
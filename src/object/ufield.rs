@@ -5,9 +5,10 @@ use std::{
 };
 
 use byteorder::ReadBytesExt;
-use tracing::{Level, debug, span, trace};
+use tracing::{Level, debug, span};
 
 use crate::{
+    annotate::annotated,
     de::{Linker, RcLinker},
     object::{
         DeserializeUnrealObject, RcUnrealObject, UObjectKind, UnrealObject, builtins::Link,
@@ -34,6 +35,12 @@ impl Field {
         }
     }
 
+    /// Links the struct this one extends before the field's data has been read, so that
+    /// class hierarchies can be walked without deserializing the classes in them.
+    pub(crate) fn set_super_field(&mut self, super_field: RcUnrealObject) {
+        self.super_field = Some(super_field);
+    }
+
     pub fn next(&self) -> Option<RcUnrealObject> {
         self.next.clone()
     }
@@ -59,15 +66,17 @@ impl DeserializeUnrealObject for Field {
         {
             let span = span!(Level::DEBUG, "super_field");
             let _enter = span.enter();
-            trace!("deserializing super_field");
-            self.super_field = reader.read_object::<E>(runtime, linker)?;
+            self.super_field = annotated!(
+                reader,
+                "super_field",
+                reader.read_object::<E>(runtime, linker)?
+            );
         }
 
         {
             let span = span!(Level::DEBUG, "next");
             let _enter = span.enter();
-            trace!("deserializing next");
-            self.next = reader.read_object::<E>(runtime, linker)?;
+            self.next = annotated!(reader, "next", reader.read_object::<E>(runtime, linker)?);
         }
 
         Ok(())
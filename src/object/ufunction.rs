@@ -1,8 +1,19 @@
+use std::fmt;
+
 use bitflags::bitflags;
 use byteorder::ReadBytesExt;
+use serde::Serialize;
 use tracing::{Level, debug, span};
 
-use crate::object::{DeserializeUnrealObject, builtins::Link, ustruct::Struct};
+use crate::annotate::annotated;
+use crate::object::{
+    DeserializeUnrealObject, UnrealObject,
+    builtins::{Link, Property, PropertyFlags},
+    internal::natives::{NativeFunction, NativeFunctionTable},
+    typed::TypedObject,
+    ustruct::{PropertySlot, Struct},
+};
+use crate::warnings::WarningKind;
 
 #[derive(Default, Debug)]
 pub struct Function {
@@ -13,7 +24,92 @@ pub struct Function {
     num_params: u8,
     operator_precedence: u8,
     return_value_offset: u16,
-    function_flags: FunctionFlags,
+    pub(crate) function_flags: FunctionFlags,
+    rep_offset: u16,
+}
+
+impl Function {
+    /// Index of this function in the native function table. Zero for functions which aren't
+    /// native or are only bound by name.
+    pub fn inative(&self) -> u16 {
+        self.inative
+    }
+
+    /// Looks up this function's entry in `table`.
+    pub fn native_function<'t>(
+        &self,
+        table: &'t NativeFunctionTable,
+    ) -> Option<&'t NativeFunction> {
+        if self.inative == 0 {
+            return None;
+        }
+
+        table.get(self.inative)
+    }
+
+    /// Size of the parameters, including the return value, when they're laid out in memory.
+    pub fn params_size(&self) -> u16 {
+        self.params_size
+    }
+
+    /// Number of parameters, including the return value.
+    pub fn num_params(&self) -> u8 {
+        self.num_params
+    }
+
+    /// Offset of the return value within the parameters, if the function returns one.
+    pub fn return_value_offset(&self) -> Option<u16> {
+        self.parameters()
+            .any(|param| param.borrow().flags().contains(PropertyFlags::RETURN_PARM))
+            .then_some(self.return_value_offset)
+    }
+
+    /// The function's parameters in declaration order, including the return value.
+    pub fn parameters(&self) -> impl Iterator<Item = TypedObject<Property>> {
+        self.parent_object
+            .properties()
+            .filter(|property| property.borrow().flags().contains(PropertyFlags::PARM))
+    }
+
+    /// The function's local variables in declaration order.
+    pub fn locals(&self) -> impl Iterator<Item = TypedObject<Property>> {
+        self.parent_object
+            .properties()
+            .filter(|property| !property.borrow().flags().contains(PropertyFlags::PARM))
+    }
+
+    fn link_params(&mut self, slots: &[PropertySlot]) {
+        self.num_params = 0;
+        self.params_size = 0;
+        self.return_value_offset = 0;
+
+        for slot in slots {
+            let flags = slot.property.borrow().flags();
+            if !flags.contains(PropertyFlags::PARM) {
+                continue;
+            }
+
+            self.num_params = self.num_params.saturating_add(1);
+            self.params_size = (slot.offset + slot.size) as u16;
+            if flags.contains(PropertyFlags::RETURN_PARM) {
+                self.return_value_offset = slot.offset as u16;
+            }
+        }
+    }
+
+    /// Offset of the replication condition in the owning class's script, if the function is
+    /// replicated.
+    pub fn rep_offset(&self) -> Option<u16> {
+        self.function_flags
+            .contains(FunctionFlags::NET)
+            .then_some(self.rep_offset)
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.parent_object.fmt(f)
+    }
 }
 
 impl DeserializeUnrealObject for Function {
@@ -33,48 +129,64 @@ impl DeserializeUnrealObject for Function {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        let version = linker.borrow().version();
+        let has_param_info = linker.borrow().profile.has_function_param_info();
 
-        if version <= 63 {
-            debug!("params_size");
-            self.params_size = reader.read_u16::<E>()?;
+        if has_param_info {
+            self.params_size = annotated!(reader, "params_size", reader.read_u16::<E>()?);
         }
 
-        debug!("inative");
-        self.inative = reader.read_u16::<E>()?;
+        self.inative = annotated!(reader, "inative", reader.read_u16::<E>()?);
+        if let Some(native) = self.native_function(&runtime.native_functions) {
+            debug!("inative= {} ({})", self.inative(), native.name);
+        }
 
-        if version <= 63 {
-            debug!("num_params");
-            self.num_params = reader.read_u8()?;
+        if has_param_info {
+            self.num_params = annotated!(reader, "num_params", reader.read_u8()?);
         }
 
-        debug!("operator_precedence");
-        self.operator_precedence = reader.read_u8()?;
+        self.operator_precedence = annotated!(reader, "operator_precedence", reader.read_u8()?);
 
-        if version <= 63 {
-            debug!("return_value_offset");
-            self.return_value_offset = reader.read_u16::<E>()?;
+        if has_param_info {
+            self.return_value_offset =
+                annotated!(reader, "return_value_offset", reader.read_u16::<E>()?);
         }
 
-        debug!("function_flags");
-        self.function_flags = FunctionFlags::from_bits(reader.read_u32::<E>()?)
-            .expect("failed to parse function flags");
+        self.function_flags = annotated!(
+            reader,
+            "function_flags",
+            FunctionFlags::from_bits_retain(reader.read_u32::<E>()?)
+        );
+        if self.function_flags.unknown_bits() != 0 {
+            runtime.warn(
+                WarningKind::UnknownFlags,
+                Some(self.base_object().full_name()),
+                format!(
+                    "function has unknown flags {:#010X}",
+                    self.function_flags.unknown_bits()
+                ),
+            );
+        }
 
         if self.function_flags.contains(FunctionFlags::NET) {
-            todo!("deserialize function_flags");
+            self.rep_offset = annotated!(reader, "rep_offset", reader.read_u16::<E>()?);
+        }
+        if let Some(rep_offset) = self.rep_offset() {
+            debug!("rep_offset= {rep_offset:#X}");
         }
 
-        self.num_params = 0;
-        self.params_size = 0;
-
-        // if let Some(child) = &self.parent_object.children {
-        //     for property in &self.parent_object.properties {
-        //         self.params_size = property.offset() + property.len();
-        //         if property.flags().contains(PropertyFlags::ReturnParam) {
-        //             self.return_value_offset = property.offset();
-        //         }
-        //     }
-        // }
+        // Older packages don't store the parameter layout, so compute it the way the engine
+        // does when linking the function
+        if !has_param_info {
+            let model = linker.borrow().profile.quirks.script_sizes;
+            match self.parent_object.property_layout(&model) {
+                Some((slots, _)) => self.link_params(&slots),
+                None => runtime.warn(
+                    WarningKind::ParameterLayout,
+                    Some(self.base_object().full_name()),
+                    "couldn't lay out the function's parameters",
+                ),
+            }
+        }
 
         Ok(())
     }
@@ -82,7 +194,7 @@ impl DeserializeUnrealObject for Function {
 
 bitflags! {
     /// Function flags.
-    #[derive(Default, Debug, Copy, Clone)]
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize)]
     pub struct FunctionFlags: u32 {
         /// Function is final (prebindable, non-overridable function).
         const FINAL = 0x00000001;
@@ -131,9 +243,24 @@ bitflags! {
     }
 }
 
+impl FunctionFlags {
+    /// Set bits which don't correspond to any known flag.
+    pub fn unknown_bits(&self) -> u32 {
+        self.bits() & !Self::all().bits()
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::object::{UObjectKind, UnrealObject, test_common::test_object_is_a};
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use crate::{
+        object::{UObjectKind, UnrealObject, test_common::test_object_is_a},
+        package::Package,
+        test_support::{DataBuilder, PackageBuilder},
+    };
 
     use super::*;
 
@@ -150,4 +277,118 @@ pub(crate) mod tests {
 
         test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
     }
+
+    #[test]
+    fn rep_offset_requires_net() {
+        let mut function = Function {
+            rep_offset: 0x2A,
+            ..Default::default()
+        };
+        assert_eq!(function.rep_offset(), None);
+
+        function.function_flags = FunctionFlags::NET | FunctionFlags::NET_RELIABLE;
+        assert_eq!(function.rep_offset(), Some(0x2A));
+    }
+
+    #[test]
+    fn computes_params_from_children() {
+        let mut builder = PackageBuilder::new();
+        let int_property = builder.core_class("IntProperty");
+        let bool_property = builder.core_class("BoolProperty");
+        let byte_property = builder.core_class("ByteProperty");
+        let function = builder.core_class("Function");
+
+        // The function is exported after its five properties
+        let outer = 6;
+        let parm = PropertyFlags::PARM.bits();
+        let return_parm = parm | PropertyFlags::RETURN_PARM.bits();
+        let children = [
+            (int_property, "Amount", parm),
+            (bool_property, "bForce", parm),
+            (bool_property, "bOther", parm),
+            (byte_property, "ReturnValue", return_parm),
+            (int_property, "Count", 0),
+        ];
+        for (i, (class, name, flags)) in children.into_iter().enumerate() {
+            let next = if i + 1 < children.len() {
+                i as i32 + 2
+            } else {
+                0
+            };
+            let mut data = DataBuilder::ue2_property(next, flags, 0);
+            if class == byte_property {
+                // eenum
+                data = data.packed(0);
+            }
+            builder.export(class, outer, name, data.build());
+        }
+
+        let name = builder.name("Sum");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name
+            .packed(0)
+            .packed(1)
+            .packed(name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        assert_eq!(builder.export(function, 0, "Sum", data), outer);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        let sum = package.get_function("Test.Sum").unwrap();
+        let sum = sum.borrow();
+        // Amount at 0, both bools share the dword at 4 and ReturnValue is at 8
+        assert_eq!(sum.num_params(), 4);
+        assert_eq!(sum.params_size(), 9);
+        assert_eq!(sum.return_value_offset(), Some(8));
+
+        let names = |properties: Vec<TypedObject<Property>>| {
+            properties
+                .iter()
+                .map(|property| property.object().borrow().base_object().name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(sum.parameters().collect()),
+            ["Amount", "bForce", "bOther", "ReturnValue"]
+        );
+        assert_eq!(names(sum.locals().collect()), ["Count"]);
+    }
+
+    #[test]
+    fn displays_friendly_names() {
+        let mut builder = PackageBuilder::new();
+        let function = builder.core_class("Function");
+        for (name, friendly_name) in [("Jump", "Jump"), ("Subtract_PreInt", "-")] {
+            let friendly_name = builder.name(friendly_name);
+            let data = DataBuilder::ue2_struct(friendly_name)
+                // inative, operator_precedence, function_flags
+                .u16(0)
+                .u8(0)
+                .u32(0x2)
+                .build();
+            builder.export(function, 0, name, data);
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        let jump = package.get_function("Test.Jump").unwrap();
+        assert_eq!(jump.borrow().to_string(), "Function Test.Jump");
+
+        let subtract = package.get_function("Test.Subtract_PreInt").unwrap();
+        let subtract = subtract.borrow();
+        assert_eq!(subtract.parent_object.friendly_name(), Some("-"));
+        assert_eq!(subtract.to_string(), "Function Test.Subtract_PreInt (-)");
+    }
 }
@@ -11,7 +11,11 @@ use crate::{
     de::{ExportIndex, Linker, ObjectExport, RcLinker, WeakLinker},
     object::{
         DeserializeUnrealObject, NAME_NONE, ObjectFlags, RcUnrealObject, UObjectKind, UnrealObject,
-        WeakUnrealObject, internal::property::PropertyTag,
+        WeakUnrealObject,
+        internal::{
+            class_ref::ClassRef,
+            property::{PropertyTag, PropertyValue},
+        },
     },
     reader::LinRead,
     runtime::UnrealRuntime,
@@ -19,16 +23,24 @@ use crate::{
 
 #[derive(Debug)]
 pub struct Object {
-    pub name: String,
+    pub name: Rc<str>,
     pub flags: ObjectFlags,
     /// The concrete type of this object
     pub concrete_object_kind: Option<UObjectKind>,
     pub needs_load: bool,
     pub needs_post_load: bool,
-    pub linker: Option<WeakLinker>,
-    pub export_index: Option<ExportIndex>,
-    pub outer_object: Option<RcUnrealObject>,
+    /// Whether the object's export had no data to load
+    stripped: bool,
+    pub(crate) linker: Option<WeakLinker>,
+    pub(crate) export_index: Option<ExportIndex>,
+    /// Weak since outers commonly refer back to the objects inside them, e.g. a struct's
+    /// children. The outer is kept alive by its linker.
+    pub outer_object: Option<WeakUnrealObject>,
+    /// The object's real class, which may be a script class rather than its builtin kind
+    class: Option<ClassRef>,
     pub concrete_obj: Option<WeakUnrealObject>,
+    /// Values of the object's tagged properties, in the order they were serialized
+    properties: Vec<(Rc<str>, PropertyValue)>,
     // package_index: usize,
     // class: i32,
     // outer: i32, //RcUnrealObject,
@@ -37,25 +49,28 @@ pub struct Object {
 impl Default for Object {
     fn default() -> Self {
         Self {
-            name: "None".to_owned(),
+            name: Rc::from("None"),
             flags: ObjectFlags::empty(),
             concrete_object_kind: None,
             needs_load: true,
             needs_post_load: true,
+            stripped: false,
             linker: Default::default(),
             export_index: Default::default(),
             outer_object: None,
+            class: None,
             concrete_obj: None,
+            properties: Vec::new(),
         }
     }
 }
 
 impl Object {
     pub fn name(&self) -> &str {
-        self.name.as_str()
+        &self.name
     }
 
-    pub fn set_name(&mut self, name: String) {
+    pub fn set_name(&mut self, name: Rc<str>) {
         self.name = name;
     }
 
@@ -95,13 +110,23 @@ impl Object {
         !self.needs_load() && !self.needs_post_load()
     }
 
-    pub fn set_linker(&mut self, linker: WeakLinker) {
+    /// Whether the object's export was stripped of its data, as cooked packages do for
+    /// editor-only objects. Stripped objects are left as they were constructed.
+    pub fn was_stripped(&self) -> bool {
+        self.stripped
+    }
+
+    pub(crate) fn set_stripped(&mut self) {
+        self.stripped = true;
+    }
+
+    pub(crate) fn set_linker(&mut self, linker: WeakLinker) {
         assert!(self.linker.is_none());
 
         self.linker = Some(linker);
     }
 
-    pub fn linker(&self) -> RcLinker {
+    pub(crate) fn linker(&self) -> RcLinker {
         self.linker
             .as_ref()
             .expect("linker is not set")
@@ -109,22 +134,93 @@ impl Object {
             .expect("could not upgrade WeakLinker")
     }
 
-    pub fn set_export_index(&mut self, export_index: ExportIndex) {
+    pub(crate) fn set_export_index(&mut self, export_index: ExportIndex) {
         assert!(self.export_index.is_none());
 
         self.export_index = Some(export_index);
     }
 
-    pub fn export_index(&self) -> ExportIndex {
+    pub(crate) fn export_index(&self) -> ExportIndex {
         self.export_index.expect("export_index is not set")
     }
 
-    pub fn set_outer_object(&mut self, outer: RcUnrealObject) {
+    pub fn set_outer_object(&mut self, outer: WeakUnrealObject) {
         self.outer_object = Some(outer);
     }
 
-    pub fn outer_object(&self) -> Option<&RcUnrealObject> {
-        self.outer_object.as_ref()
+    /// The object this one is inside of, or `None` if it has no outer or the outer has since
+    /// been released.
+    pub fn outer_object(&self) -> Option<RcUnrealObject> {
+        self.outer_object.as_ref().and_then(Weak::upgrade)
+    }
+
+    pub fn set_class(&mut self, class: RcUnrealObject) {
+        self.class = Some(ClassRef::new(class));
+    }
+
+    pub fn class(&self) -> Option<&RcUnrealObject> {
+        self.class.as_ref().map(ClassRef::object)
+    }
+
+    pub fn class_ref(&self) -> Option<&ClassRef> {
+        self.class.as_ref()
+    }
+
+    /// The value of the tagged property `name`, ignoring ASCII case.
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties
+            .iter()
+            .find(|(property, _)| property.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Sets the tagged property `name`, replacing its value if it has one.
+    pub fn set_property(&mut self, name: &str, value: PropertyValue) {
+        match self
+            .properties
+            .iter_mut()
+            .find(|(property, _)| property.eq_ignore_ascii_case(name))
+        {
+            Some((_, existing)) => *existing = value,
+            None => self.properties.push((Rc::from(name), value)),
+        }
+    }
+
+    /// Every tagged property and its value, in the order they were serialized.
+    pub fn properties(&self) -> impl Iterator<Item = (&str, &PropertyValue)> {
+        self.properties
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value))
+    }
+
+    /// The object's full name, e.g. `Engine.Actor.Tick`. Objects which aren't exported, or
+    /// whose linker is in use, are named after their outer objects instead.
+    pub fn full_name(&self) -> String {
+        if let Some(export_index) = self.export_index
+            && let Some(linker) = self.linker.as_ref().and_then(Weak::upgrade)
+            && let Ok(linker) = linker.try_borrow()
+            && let Some(export) = linker.package.exports.get(export_index.index())
+        {
+            return export.full_name(&linker);
+        }
+
+        let mut parts = vec![self.name().to_owned()];
+        let mut outer = self.outer_object();
+        while let Some(obj) = outer {
+            let Ok(obj) = obj.try_borrow() else {
+                break;
+            };
+            parts.push(obj.base_object().name().to_owned());
+            outer = obj.base_object().outer_object();
+        }
+
+        parts.reverse();
+        parts.join(".")
+    }
+
+    /// Intrinsic objects are not backed by an export in any package.
+    pub fn is_intrinsic(&self) -> bool {
+        self.linker.is_none()
     }
 
     pub fn set_concrete_obj(&mut self, outer: WeakUnrealObject) {
@@ -249,4 +345,27 @@ pub(crate) mod tests {
 
         test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
     }
+
+    #[test]
+    fn sets_properties() {
+        let mut obj = Object::default();
+        let obj = &mut obj as &mut dyn UnrealObject;
+        assert!(obj.get_property("DrawScale").is_none());
+
+        obj.set_property("DrawScale", PropertyValue::Float(2.0));
+        obj.set_property("bHidden", PropertyValue::Bool(true));
+        obj.set_property("drawscale", PropertyValue::Float(0.5));
+
+        assert!(matches!(
+            obj.get_property("DRAWSCALE"),
+            Some(PropertyValue::Float(0.5))
+        ));
+        assert_eq!(
+            obj.base_object()
+                .properties()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["DrawScale", "bHidden"]
+        );
+    }
 }
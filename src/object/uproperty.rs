@@ -1,11 +1,16 @@
 use std::{cell::RefCell, io, rc::Rc};
 
 use crate::{
+    annotate::annotated,
     de::RcLinker,
     object::{
-        DeserializeUnrealObject, RcUnrealObject, UnrealObject, internal::fname::FName,
-        ufield::Field, ustruct::Struct,
+        DeserializeUnrealObject, RcUnrealObject, UObjectKind, UnrealObject,
+        internal::{fname::FName, object_ref::ObjectRef, script::Expr},
+        uenum::Enum,
+        ufield::Field,
+        ustruct::Struct,
     },
+    profile::{ArrayDimWidth, ScriptSizeModel},
     reader::{LinRead, UnrealReadExt},
     runtime::{self, UnrealRuntime},
 };
@@ -30,19 +35,156 @@ pub trait Link: UnrealObject {
 pub struct Property {
     pub parent_object: Field,
 
-    array_dim: u16,
+    array_dim: u32,
     element_size: u32,
-    property_flags: PropertyFlags,
+    pub(crate) property_flags: PropertyFlags,
     category: FName,
     rep_offset: u16,
     rep_index: u16,
     comment_string: Option<String>,
+    /// Condition from the owning class's `replication` block, decoded from its script
+    rep_condition: Option<Expr>,
+}
+
+/// Summary of a property's declaration, for tools which document parsed classes.
+#[derive(Debug, Clone)]
+pub struct PropertyInfo {
+    pub name: String,
+    /// Name of the struct, state or function the property is declared in
+    pub owner: Option<String>,
+    /// Editor category, if the property has one
+    pub category: Option<String>,
+    pub flags: PropertyFlags,
+    /// Number of elements in a static array; 1 for other properties
+    pub array_dim: u32,
+    /// Offset of the property's replication condition in its class's script. Only set for
+    /// replicated properties.
+    pub rep_offset: Option<u16>,
+    /// Condition under which the property is replicated
+    pub rep_condition: Option<Expr>,
+    /// Comment shown in the editor
+    pub comment: Option<String>,
 }
 
 impl Property {
     pub fn flags(&self) -> PropertyFlags {
         self.property_flags
     }
+
+    pub fn array_dim(&self) -> u32 {
+        self.array_dim
+    }
+
+    /// Size of one element as serialized with the property, for profiles which store it. The
+    /// size is otherwise worked out from the property's type.
+    pub fn serialized_element_size(&self) -> Option<u32> {
+        (self.element_size != 0).then_some(self.element_size)
+    }
+
+    /// The editor category name. Resolve it against the property's linker.
+    pub fn category(&self) -> FName {
+        self.category
+    }
+
+    /// Offset of the replication condition in the owning class's script, if the property is
+    /// replicated.
+    pub fn rep_offset(&self) -> Option<u16> {
+        self.property_flags
+            .contains(PropertyFlags::NET)
+            .then_some(self.rep_offset)
+    }
+
+    /// The expression from the owning class's `replication` block which decides when this
+    /// property is replicated. Only available once the owning class has been loaded.
+    pub fn rep_condition(&self) -> Option<&Expr> {
+        self.rep_condition.as_ref()
+    }
+
+    /// Index of the property in its class's list of replicated properties, if the property is
+    /// replicated. See [`Struct::replicated_properties`].
+    pub fn rep_index(&self) -> Option<u16> {
+        self.property_flags
+            .contains(PropertyFlags::NET)
+            .then_some(self.rep_index)
+    }
+
+    pub(crate) fn set_rep_index(&mut self, rep_index: u16) {
+        self.rep_index = rep_index;
+    }
+
+    pub(crate) fn set_rep_condition(&mut self, condition: Expr) {
+        self.rep_condition = Some(condition);
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment_string.as_deref()
+    }
+
+    /// Summarizes this property, resolving its category through the linker it was loaded from.
+    pub fn info(&self) -> PropertyInfo {
+        let base = self.base_object();
+        let linker = base.linker.as_ref().and_then(|linker| linker.upgrade());
+
+        let category = match linker {
+            Some(linker) if !self.category().is_none() => {
+                Some(self.category().resolve(&linker.borrow()).to_string())
+            }
+            _ => None,
+        };
+
+        PropertyInfo {
+            name: base.name().to_owned(),
+            owner: base
+                .outer_object()
+                .map(|outer| outer.borrow().base_object().name().to_owned()),
+            category,
+            flags: self.flags(),
+            array_dim: self.array_dim(),
+            rep_offset: self.rep_offset(),
+            rep_condition: self.rep_condition().cloned(),
+            comment: self.comment().map(str::to_owned),
+        }
+    }
+}
+
+/// Size in memory of one element of `property`, which must be a concrete property, as the
+/// engine lays it out when linking the struct declaring it. `None` if it isn't known, such as
+/// for maps or structs which haven't been loaded.
+pub(crate) fn element_size(property: &dyn UnrealObject, model: &ScriptSizeModel) -> Option<u32> {
+    let object = model.object as u32;
+    let name = model.name as u32;
+
+    let size = match property.kind() {
+        UObjectKind::ByteProperty => 1,
+        UObjectKind::IntProperty | UObjectKind::FloatProperty | UObjectKind::BoolProperty => 4,
+        UObjectKind::ObjectProperty | UObjectKind::ClassProperty | UObjectKind::PointerProperty => {
+            object
+        }
+        UObjectKind::NameProperty => name,
+        // Data pointer, length and capacity
+        UObjectKind::StrProperty => object + 8,
+        // Object and function name
+        UObjectKind::DelegateProperty => object + name,
+        UObjectKind::StructProperty => {
+            let property = property.as_any().downcast_ref::<StructProperty>()?;
+            let ustruct = property.struct_obj.as_ref()?.borrow();
+
+            ustruct
+                .parent_of_kind(UObjectKind::Struct)?
+                .as_any()
+                .downcast_ref::<Struct>()?
+                .properties_size(model)?
+        }
+        UObjectKind::FixedArrayProperty => {
+            let property = property.as_any().downcast_ref::<FixedArrayProperty>()?;
+            let inner = property.inner.as_ref()?.borrow();
+
+            element_size(&*inner, model)? * u32::try_from(property.count).ok()?
+        }
+        _ => return None,
+    };
+
+    Some(size)
 }
 
 impl DeserializeUnrealObject for Property {
@@ -62,21 +204,43 @@ impl DeserializeUnrealObject for Property {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        trace!("array_dim");
-        // TODO: This is only for splinter cell?
-        self.array_dim = reader.read_u16::<E>()?;
-        trace!("property_flags");
-        self.property_flags = PropertyFlags::from_bits(reader.read_u32::<E>()?)
-            .expect("failed to parse property flags");
-        trace!("category");
-        self.category.deserialize::<E, _>(runtime, linker, reader)?;
+        let quirks = linker.borrow().profile.quirks;
+        self.array_dim = annotated!(
+            reader,
+            "array_dim",
+            match quirks.property_array_dim {
+                ArrayDimWidth::U16 => reader.read_u16::<E>()? as u32,
+                ArrayDimWidth::U32 => reader.read_u32::<E>()?,
+            }
+        );
+        if quirks.property_element_size {
+            self.element_size = annotated!(reader, "element_size", reader.read_u32::<E>()?);
+        }
+        self.property_flags = annotated!(
+            reader,
+            "property_flags",
+            PropertyFlags::from_bits_retain(reader.read_u32::<E>()?)
+        );
+        if self.property_flags.unknown_bits() != 0 {
+            debug!(
+                "Property has unknown flags {:#010X}",
+                self.property_flags.unknown_bits()
+            );
+        }
+        annotated!(
+            reader,
+            "category",
+            self.category.deserialize::<E, _>(runtime, linker, reader)?,
+            |_| self.category.resolve(&linker.borrow()).to_string()
+        );
+        trace!("category= {}", self.category.resolve(&linker.borrow()));
 
         if self.property_flags.contains(PropertyFlags::NET) {
-            self.rep_offset = reader.read_u16::<E>()?;
+            self.rep_offset = annotated!(reader, "rep_offset", reader.read_u16::<E>()?);
         }
 
         if self.property_flags.contains(PropertyFlags::COMMENT_STRING) {
-            self.comment_string = Some(reader.read_string()?);
+            self.comment_string = annotated!(reader, "comment_string", Some(reader.read_string()?));
         }
 
         Ok(())
@@ -292,7 +456,7 @@ impl Link for NameProperty {
 pub struct ObjectProperty {
     pub parent_object: Property,
 
-    pub property_class: Option<RcUnrealObject>,
+    pub property_class: ObjectRef,
 }
 
 impl DeserializeUnrealObject for ObjectProperty {
@@ -312,7 +476,11 @@ impl DeserializeUnrealObject for ObjectProperty {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        self.property_class = reader.read_object::<E>(runtime, linker)?;
+        self.property_class = annotated!(
+            reader,
+            "property_class",
+            reader.read_object_ref::<E>(runtime, linker)?
+        );
 
         Ok(())
     }
@@ -337,7 +505,7 @@ impl Link for ObjectProperty {
 pub struct ClassProperty {
     pub parent_object: ObjectProperty,
 
-    pub meta_class: Option<RcUnrealObject>,
+    pub meta_class: ObjectRef,
 }
 
 impl DeserializeUnrealObject for ClassProperty {
@@ -357,7 +525,11 @@ impl DeserializeUnrealObject for ClassProperty {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        self.meta_class = reader.read_object::<E>(runtime, linker)?;
+        self.meta_class = annotated!(
+            reader,
+            "meta_class",
+            reader.read_object_ref::<E>(runtime, linker)?
+        );
 
         Ok(())
     }
@@ -379,7 +551,7 @@ impl Link for ClassProperty {
             "link_class_property",
             meta_class = self
                 .meta_class
-                .as_ref()
+                .get()
                 .map(|c| format!("{:#x}", c.as_ptr().expose_provenance()))
         );
         let _enter = span.enter();
@@ -412,7 +584,7 @@ impl DeserializeUnrealObject for ByteProperty {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        self.eenum = reader.read_object::<E>(runtime, linker)?;
+        self.eenum = annotated!(reader, "eenum", reader.read_object::<E>(runtime, linker)?);
 
         Ok(())
     }
@@ -432,6 +604,12 @@ impl Link for ByteProperty {
         let span = span!(Level::DEBUG, "link_byte_property",);
         let _enter = span.enter();
 
+        if let Some(eenum) = &self.eenum
+            && let Some(eenum) = eenum.borrow().as_any().downcast_ref::<Enum>()
+        {
+            trace!("Byte property values: {:?}", eenum.names());
+        }
+
         Ok(())
     }
 }
@@ -460,7 +638,11 @@ impl DeserializeUnrealObject for StructProperty {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        self.struct_obj = reader.read_object::<E>(runtime, linker)?;
+        self.struct_obj = annotated!(
+            reader,
+            "struct_obj",
+            reader.read_object::<E>(runtime, linker)?
+        );
 
         Ok(())
     }
@@ -490,10 +672,217 @@ impl Link for StructProperty {
     }
 }
 
+#[derive(Default, Debug)]
+pub struct DelegateProperty {
+    pub parent_object: Property,
+
+    /// Function whose signature delegates assigned to this property must match
+    pub function: ObjectRef,
+}
+
+impl DeserializeUnrealObject for DelegateProperty {
+    fn deserialize<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+        reader: &mut R,
+    ) -> std::io::Result<()>
+    where
+        E: byteorder::ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(Level::DEBUG, "deserialize_delegate_property");
+        let _enter = span.enter();
+
+        self.parent_object
+            .deserialize::<E, _>(runtime, linker, reader)?;
+
+        self.function = annotated!(
+            reader,
+            "function",
+            reader.read_object_ref::<E>(runtime, linker)?
+        );
+
+        Ok(())
+    }
+}
+
+impl Link for DelegateProperty {
+    fn link<E, R>(
+        &self,
+        _runtime: &mut UnrealRuntime,
+        _linker: &RcLinker,
+        _reader: &mut R,
+    ) -> io::Result<()>
+    where
+        E: ByteOrder,
+        R: LinRead,
+    {
+        Ok(())
+    }
+}
+
+/// A native pointer. The value itself is never serialized.
+#[derive(Default, Debug)]
+pub struct PointerProperty {
+    pub parent_object: Property,
+}
+
+impl DeserializeUnrealObject for PointerProperty {
+    fn deserialize<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+        reader: &mut R,
+    ) -> std::io::Result<()>
+    where
+        E: byteorder::ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(Level::DEBUG, "deserialize_pointer_property");
+        let _enter = span.enter();
+
+        self.parent_object
+            .deserialize::<E, _>(runtime, linker, reader)?;
+
+        Ok(())
+    }
+}
+
+impl Link for PointerProperty {
+    fn link<E, R>(
+        &self,
+        _runtime: &mut UnrealRuntime,
+        _linker: &RcLinker,
+        _reader: &mut R,
+    ) -> io::Result<()>
+    where
+        E: ByteOrder,
+        R: LinRead,
+    {
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct MapProperty {
+    pub parent_object: Property,
+
+    pub key: Option<RcUnrealObject>,
+    pub value: Option<RcUnrealObject>,
+}
+
+impl DeserializeUnrealObject for MapProperty {
+    fn deserialize<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+        reader: &mut R,
+    ) -> std::io::Result<()>
+    where
+        E: byteorder::ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(Level::DEBUG, "deserialize_map_property");
+        let _enter = span.enter();
+
+        self.parent_object
+            .deserialize::<E, _>(runtime, linker, reader)?;
+
+        self.key = annotated!(reader, "key", reader.read_object::<E>(runtime, linker)?);
+        self.value = annotated!(reader, "value", reader.read_object::<E>(runtime, linker)?);
+
+        Ok(())
+    }
+}
+
+impl Link for MapProperty {
+    fn link<E, R>(
+        &self,
+        runtime: &mut UnrealRuntime,
+        _linker: &RcLinker,
+        reader: &mut R,
+    ) -> io::Result<()>
+    where
+        E: ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(Level::DEBUG, "link_map_property");
+        let _enter = span.enter();
+
+        for property in [&self.key, &self.value].into_iter().flatten() {
+            runtime.full_load_object::<E, _>(property, reader)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct FixedArrayProperty {
+    pub parent_object: Property,
+
+    pub inner: Option<RcUnrealObject>,
+    pub count: i32,
+}
+
+impl DeserializeUnrealObject for FixedArrayProperty {
+    fn deserialize<E, R>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+        reader: &mut R,
+    ) -> std::io::Result<()>
+    where
+        E: byteorder::ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(Level::DEBUG, "deserialize_fixed_array_property");
+        let _enter = span.enter();
+
+        self.parent_object
+            .deserialize::<E, _>(runtime, linker, reader)?;
+
+        self.inner = annotated!(reader, "inner", reader.read_object::<E>(runtime, linker)?);
+        self.count = annotated!(reader, "count", reader.read_i32::<E>()?);
+        trace!("count= {}", self.count);
+
+        Ok(())
+    }
+}
+
+impl Link for FixedArrayProperty {
+    fn link<E, R>(
+        &self,
+        runtime: &mut UnrealRuntime,
+        _linker: &RcLinker,
+        reader: &mut R,
+    ) -> io::Result<()>
+    where
+        E: ByteOrder,
+        R: LinRead,
+    {
+        let span = span!(
+            Level::DEBUG,
+            "link_fixed_array_property",
+            count = self.count
+        );
+        let _enter = span.enter();
+
+        let Some(inner) = self.inner.as_ref() else {
+            return Ok(());
+        };
+
+        runtime.full_load_object::<E, _>(inner, reader)?;
+
+        Ok(())
+    }
+}
+
 bitflags! {
     /// Flags associated with each property in a class, overriding the
     /// property's default behavior.
-    #[derive(Default, Clone, Copy, Debug)]
+    #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize)]
     pub struct PropertyFlags: u32 {
         /// Property is user-settable in the editor.
         const EDIT = 0x00000001;
@@ -562,9 +951,25 @@ bitflags! {
     }
 }
 
+impl PropertyFlags {
+    /// Set bits which don't correspond to any known flag.
+    pub fn unknown_bits(&self) -> u32 {
+        self.bits() & !Self::all().bits()
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
     use crate::object::{UObjectKind, UnrealObject, test_common::test_object_is_a};
+    use crate::{
+        package::Package,
+        profile::FormatProfile,
+        test_support::{DataBuilder, PackageBuilder},
+    };
 
     use super::*;
 
@@ -581,4 +986,104 @@ pub(crate) mod tests {
 
         test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
     }
+
+    /// A property replicated according to the condition at `rep_offset`.
+    pub fn replicated_property(rep_offset: u16) -> Property {
+        Property {
+            property_flags: PropertyFlags::NET,
+            rep_offset,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn property_info() {
+        let mut property = Property {
+            array_dim: 4,
+            property_flags: PropertyFlags::EDIT | PropertyFlags::NET,
+            rep_offset: 0x12,
+            comment_string: Some("Shown in the editor".to_owned()),
+            ..Default::default()
+        };
+        property.base_object_mut().set_name(Rc::from("Health"));
+
+        let info = property.info();
+        assert_eq!(info.name, "Health");
+        assert_eq!(info.owner, None);
+        assert_eq!(info.category, None);
+        assert_eq!(info.array_dim, 4);
+        assert_eq!(info.rep_offset, Some(0x12));
+        assert_eq!(info.comment.as_deref(), Some("Shown in the editor"));
+
+        property.property_flags = PropertyFlags::EDIT;
+        assert_eq!(property.rep_offset(), None);
+    }
+
+    /// Loads a package holding a single replicated `IntProperty` whose data starts with
+    /// `array_dim`, and returns the property's summary.
+    fn load_property(profile: FormatProfile, array_dim: &[u8]) -> PropertyInfo {
+        let mut builder = PackageBuilder::new();
+        let int_property = builder.core_class("IntProperty");
+        let category = builder.name("Display");
+        let mut data = DataBuilder::field(0, 0).build();
+        data.extend_from_slice(array_dim);
+        data.extend(
+            DataBuilder::default()
+                .u32(PropertyFlags::NET.bits())
+                .packed(category)
+                .u16(0x24)
+                .build(),
+        );
+        builder.export(int_property, 0, "Ammo", data);
+
+        let mut package = Package::<LittleEndian, _>::open_with_profile(
+            "Test",
+            Cursor::new(builder.build()),
+            profile,
+        )
+        .unwrap();
+        let contents = package.load_objects().unwrap();
+        let mut properties = contents.properties();
+
+        assert_eq!(properties.len(), 1);
+        properties.pop().unwrap()
+    }
+
+    #[test]
+    fn array_dim_layout_follows_profile() {
+        let check = |info: PropertyInfo| {
+            assert_eq!(info.array_dim, 3);
+            assert_eq!(info.flags, PropertyFlags::NET);
+            assert_eq!(info.category.as_deref(), Some("Display"));
+            assert_eq!(info.rep_offset, Some(0x24));
+        };
+
+        check(load_property(FormatProfile::stock_ue2(), &[3, 0, 0, 0]));
+        check(load_property(FormatProfile::splinter_cell(), &[3, 0]));
+
+        let mut profile = FormatProfile::stock_ue2();
+        profile.quirks.property_element_size = true;
+        check(load_property(profile, &[3, 0, 0, 0, 4, 0, 0, 0]));
+
+        let mut profile = FormatProfile::splinter_cell();
+        profile.quirks.property_element_size = true;
+        check(load_property(profile, &[3, 0, 4, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_licensee_properties_are_properties() {
+        let properties: [(&dyn UnrealObject, UObjectKind); 4] = [
+            (&DelegateProperty::default(), UObjectKind::DelegateProperty),
+            (&PointerProperty::default(), UObjectKind::PointerProperty),
+            (&MapProperty::default(), UObjectKind::MapProperty),
+            (
+                &FixedArrayProperty::default(),
+                UObjectKind::FixedArrayProperty,
+            ),
+        ];
+
+        for (test_obj, kind) in properties {
+            test_object_is_a(test_obj, expected_uobjectkind().into_iter().chain([kind]));
+        }
+    }
 }
@@ -1,11 +1,17 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{borrow::Cow, cell::RefCell, fmt, rc::Rc};
 
 use byteorder::ReadBytesExt;
-use tracing::{Level, span, trace};
+use tracing::{Level, span, warn};
 
 use crate::{
+    annotate::annotated,
     de::RcLinker,
-    object::{DeserializeUnrealObject, builtins::Link, ustruct::Struct},
+    object::{
+        DeserializeUnrealObject,
+        builtins::Link,
+        internal::{probes::ProbeNameTable, script::Expr},
+        ustruct::Struct,
+    },
     reader::LinRead,
     runtime::UnrealRuntime,
 };
@@ -18,6 +24,79 @@ pub struct State {
     ignore_mask: u64,
     label_table_offset: u16,
     state_flags: u32,
+    /// Labels in the state's code, resolved from the label table
+    labels: Vec<(Rc<str>, u32)>,
+}
+
+impl State {
+    /// Bit `n` is set if the state implements the `n`th probe function.
+    pub fn probe_mask(&self) -> u64 {
+        self.probe_mask
+    }
+
+    /// Bit `n` is cleared if the state ignores the `n`th probe function.
+    pub fn ignore_mask(&self) -> u64 {
+        self.ignore_mask
+    }
+
+    /// Names of the probe functions the state implements and doesn't ignore.
+    pub fn probe_functions<'t>(&self, table: &'t ProbeNameTable) -> Vec<Cow<'t, str>> {
+        table.decode(self.probe_mask & self.ignore_mask)
+    }
+
+    /// The state's labels in declaration order, along with the script offsets they mark.
+    pub fn labels(&self) -> &[(Rc<str>, u32)] {
+        &self.labels
+    }
+
+    /// Script offset of the label named `name`, ignoring ASCII case, such as the target of a
+    /// `GotoLabel`.
+    pub fn label_offset(&self, name: &str) -> Option<u32> {
+        self.labels
+            .iter()
+            .find(|(label, _)| label.eq_ignore_ascii_case(name))
+            .map(|(_, offset)| *offset)
+    }
+
+    /// Resolves the labels in the label table at `label_table_offset`, if the state has one.
+    fn resolve_labels(&mut self, linker: &RcLinker) {
+        if self.label_table_offset == u16::MAX {
+            return;
+        }
+
+        let script = self.parent_object.script();
+        let table = script
+            .binary_search_by_key(&(self.label_table_offset as usize), |statement| {
+                statement.offset
+            })
+            .ok()
+            .map(|index| &script[index].expr);
+        let Some(Expr::LabelTable(labels)) = table else {
+            warn!(
+                "No label table at script offset {:#X}",
+                self.label_table_offset
+            );
+            return;
+        };
+
+        let linker = linker.borrow();
+        self.labels = labels
+            .iter()
+            .take_while(|label| !label.name.is_none())
+            .map(|label| (label.name.resolve(&linker), label.offset))
+            .collect();
+    }
+
+    /// Names of the probe functions the state ignores.
+    pub fn ignored_functions<'t>(&self, table: &'t ProbeNameTable) -> Vec<Cow<'t, str>> {
+        table.decode(!self.ignore_mask)
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.parent_object.fmt(f)
+    }
 }
 
 impl DeserializeUnrealObject for State {
@@ -37,22 +116,43 @@ impl DeserializeUnrealObject for State {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        trace!("probe_mask");
-        self.probe_mask = reader.read_u64::<E>()?;
-        trace!("ignore_mask");
-        self.ignore_mask = reader.read_u64::<E>()?;
-        trace!("label_table_offset");
-        self.label_table_offset = reader.read_u16::<E>()?;
-        trace!("state_flags");
-        self.state_flags = reader.read_u32::<E>()?;
+        let probes = &runtime.probe_names;
+        self.probe_mask = annotated!(reader, "probe_mask", reader.read_u64::<E>()?, |mask| {
+            describe_mask(*mask, probes.decode(*mask), "")
+        });
+        self.ignore_mask = annotated!(reader, "ignore_mask", reader.read_u64::<E>()?, |mask| {
+            describe_mask(*mask, probes.decode(!*mask), "ignores ")
+        });
+        self.label_table_offset = annotated!(reader, "label_table_offset", reader.read_u16::<E>()?);
+        self.resolve_labels(linker);
+        self.state_flags = annotated!(reader, "state_flags", reader.read_u32::<E>()?, |flags| {
+            format!("{flags:#X}")
+        });
 
         Ok(())
     }
 }
 
+/// Formats a probe or ignore mask followed by the names of the probes it refers to.
+fn describe_mask(mask: u64, probes: Vec<Cow<'_, str>>, prefix: &str) -> String {
+    if probes.is_empty() {
+        return format!("{mask:#X}");
+    }
+
+    format!("{mask:#X} ({prefix}{})", probes.join(", "))
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
-    use crate::object::{UObjectKind, UnrealObject, test_common::test_object_is_a};
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use crate::{
+        object::{TypedObject, UObjectKind, UnrealObject, test_common::test_object_is_a},
+        package::Package,
+        test_support::{DataBuilder, PackageBuilder},
+    };
 
     use super::*;
 
@@ -69,4 +169,75 @@ pub(crate) mod tests {
 
         test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
     }
+
+    #[test]
+    fn decodes_probe_masks() {
+        let table = ProbeNameTable::stock();
+        let touch = table.bit("touch").unwrap();
+        let tick = table.bit("Tick").unwrap();
+
+        let state = State {
+            probe_mask: (1 << touch) | (1 << tick),
+            ignore_mask: !(1 << tick),
+            ..Default::default()
+        };
+        assert_eq!(state.probe_functions(&table), ["Touch"]);
+        assert_eq!(state.ignored_functions(&table), ["Tick"]);
+
+        let table = ProbeNameTable::new(["Spawned"]);
+        assert_eq!(table.decode(0b11), ["Spawned", "Probe1"]);
+        assert_eq!(
+            describe_mask(
+                state.ignore_mask,
+                table.decode(!state.ignore_mask),
+                "ignores "
+            ),
+            format!("{:#X} (ignores Probe{tick})", state.ignore_mask)
+        );
+    }
+
+    #[test]
+    fn resolves_labels() {
+        let mut builder = PackageBuilder::new();
+        let state = builder.core_class("State");
+        let name = builder.name("Idle");
+        let begin = builder.name("Begin");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name
+            .packed(0)
+            .packed(0)
+            .packed(name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(23)
+            // GotoLabel(NameConst(Begin))
+            .u8(0x0D)
+            .u8(0x21)
+            .packed(begin)
+            // LabelTable([Begin @ 0], None @ 0xFFFF)
+            .u8(0x0C)
+            .packed(begin)
+            .u32(0)
+            .packed(0)
+            .u32(0xFFFF)
+            // probe_mask, ignore_mask, label_table_offset, state_flags
+            .u64(0)
+            .u64(u64::MAX)
+            .u16(6)
+            .u32(0)
+            .build();
+        builder.export(state, 0, "Idle", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        let idle = package.get_struct("Test.Idle").unwrap().into_object();
+        let idle = TypedObject::<State>::new(idle).unwrap();
+        let idle = idle.borrow();
+        assert_eq!(idle.labels(), [("Begin".into(), 0)]);
+        assert_eq!(idle.label_offset("begin"), Some(0));
+        assert_eq!(idle.label_offset("End"), None);
+    }
 }
@@ -1,21 +1,24 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, fmt, io, io::SeekFrom, rc::Rc};
 
 use byteorder::ReadBytesExt;
-use tracing::{Level, debug, span};
+use tracing::{Level, debug, span, trace, warn};
 
 use crate::{
+    annotate::annotated,
     de::{Linker, RcLinker},
     object::{
-        DeserializeUnrealObject, RcUnrealObject, UObjectKind, UnrealObject,
-        builtins::{Link, Property},
-        internal::script,
+        DeserializeUnrealObject, NAME_NONE, RcUnrealObject, TypedObject, UObjectKind, UnrealObject,
+        builtins::{Function, Link, Property, PropertyFlags},
+        internal::script::{self, Statement},
         link_object,
         ufield::Field,
         uobject::Object,
-        uproperty::PropertyFlags,
+        uproperty::element_size,
     },
+    profile::ScriptSizeModel,
     reader::{LinRead, UnrealReadExt},
     runtime::UnrealRuntime,
+    warnings::WarningKind,
 };
 
 #[derive(Default, Debug)]
@@ -25,16 +28,175 @@ pub struct Struct {
     script_text: Option<RcUnrealObject>,
     pub children: Option<RcUnrealObject>,
 
-    friendly_name: i32,
+    /// Name the struct was declared with, e.g. the operator symbol of an operator function.
+    /// `None` if the name is `None`.
+    friendly_name: Option<Rc<str>>,
 
     flags: u32,
     line: u32,
     text_pos: u32,
     script_size: u32,
-    script: Vec<u8>,
+    script: Vec<Statement>,
+    /// The script as it was serialized
+    script_bytes: Vec<u8>,
+    /// Replicated properties, including those inherited from the super struct
+    replicated: Vec<TypedObject<Property>>,
+}
+
+/// Where a property is stored within its struct once laid out.
+pub(crate) struct PropertySlot {
+    pub(crate) property: TypedObject<Property>,
+    pub(crate) offset: u32,
+    pub(crate) size: u32,
 }
 
 impl Struct {
+    /// The decoded bytecode of this struct's script.
+    pub fn script(&self) -> &[Statement] {
+        &self.script
+    }
+
+    /// The name the struct was declared with, which differs from its object name for
+    /// operators.
+    pub fn friendly_name(&self) -> Option<&str> {
+        self.friendly_name.as_deref()
+    }
+
+    /// The serialized bytecode of this struct's script. See [`script::serialize_exprs`] for
+    /// re-encoding a modified script.
+    pub fn script_bytes(&self) -> &[u8] {
+        &self.script_bytes
+    }
+
+    /// Whether decoding the script stopped at a token this crate doesn't know.
+    pub fn script_partially_decoded(&self) -> bool {
+        self.script
+            .last()
+            .is_some_and(|statement| matches!(statement.expr, script::Expr::Unknown { .. }))
+    }
+
+    /// The fields declared directly in this struct, such as its properties and functions, in
+    /// declaration order. Fields inherited from its super struct aren't included.
+    pub fn fields(&self) -> impl Iterator<Item = RcUnrealObject> {
+        std::iter::successors(self.children.clone(), |field| {
+            field
+                .borrow()
+                .parent_of_kind(UObjectKind::Field)?
+                .as_any()
+                .downcast_ref::<Field>()?
+                .next()
+        })
+    }
+
+    /// The properties declared directly in this struct.
+    pub fn properties(&self) -> impl Iterator<Item = TypedObject<Property>> {
+        self.fields().filter_map(TypedObject::new)
+    }
+
+    /// The functions declared directly in this struct.
+    pub fn functions(&self) -> impl Iterator<Item = TypedObject<Function>> {
+        self.fields().filter_map(TypedObject::new)
+    }
+
+    /// The properties replicated over the network, indexed by their
+    /// [`Property::rep_index`]. Those inherited from the super struct come first, followed by
+    /// the `NET` properties declared in this struct. Empty until the struct is linked.
+    pub fn replicated_properties(&self) -> &[TypedObject<Property>] {
+        &self.replicated
+    }
+
+    /// Builds the list of replicated properties from the super struct's list and this
+    /// struct's own properties, assigning each of the latter its index in the list. The
+    /// super struct must already be linked.
+    fn collect_replicated_properties(&mut self) {
+        let mut replicated = self
+            .parent_object
+            .super_field()
+            .and_then(|super_field| {
+                super_field
+                    .borrow()
+                    .parent_of_kind(UObjectKind::Struct)?
+                    .as_any()
+                    .downcast_ref::<Struct>()
+                    .map(|super_struct| super_struct.replicated.clone())
+            })
+            .unwrap_or_default();
+
+        for property in self.properties() {
+            if property.borrow().flags().contains(PropertyFlags::NET) {
+                property.borrow_mut().set_rep_index(replicated.len() as u16);
+                replicated.push(property);
+            }
+        }
+        debug!("{} replicated properties", replicated.len());
+
+        self.replicated = replicated;
+    }
+
+    /// Lays out the properties declared in this struct after those inherited from its super
+    /// struct, as the engine does when linking it, and returns them along with the size of
+    /// every property. Properties are aligned to their element size, up to 4 bytes, and
+    /// consecutive bools share a 32-bit bitfield. Element sizes serialized with a property
+    /// are used as they are. `None` if any property's size isn't known.
+    pub(crate) fn property_layout(
+        &self,
+        model: &ScriptSizeModel,
+    ) -> Option<(Vec<PropertySlot>, u32)> {
+        let mut offset = match self.parent_object.super_field() {
+            Some(super_struct) => super_struct
+                .borrow()
+                .parent_of_kind(UObjectKind::Struct)?
+                .as_any()
+                .downcast_ref::<Struct>()?
+                .properties_size(model)?,
+            None => 0,
+        };
+
+        let mut slots = Vec::new();
+        // Offset of the bitfield the last property was stored in, if it was a bool, and how
+        // many of its bits are used
+        let mut bitfield = None;
+        for property in self.properties() {
+            let is_bool = property.object().borrow().kind() == UObjectKind::BoolProperty;
+            if is_bool
+                && let Some((bitfield_offset, bits)) = bitfield
+                && bits < 32
+            {
+                bitfield = Some((bitfield_offset, bits + 1));
+                slots.push(PropertySlot {
+                    property,
+                    offset: bitfield_offset,
+                    size: 4,
+                });
+                continue;
+            }
+
+            let serialized_size = property.borrow().serialized_element_size();
+            let element_size = match serialized_size {
+                Some(size) => size,
+                None => element_size(&*property.object().borrow(), model)?,
+            };
+            let size = element_size * property.borrow().array_dim();
+            offset = offset.next_multiple_of(element_size.clamp(1, 4));
+
+            bitfield = is_bool.then_some((offset, 1));
+            slots.push(PropertySlot {
+                property,
+                offset,
+                size,
+            });
+            offset += size;
+        }
+
+        Some((slots, offset))
+    }
+
+    /// Size of this struct's properties, including those it inherits. See
+    /// [`Struct::property_layout`].
+    pub(crate) fn properties_size(&self, model: &ScriptSizeModel) -> Option<u32> {
+        self.property_layout(model).map(|(_, size)| size)
+    }
+
     pub fn visit_children(&self, kind: UObjectKind) {
         let mut current_field = self.children.as_ref().map(Rc::clone);
         loop {
@@ -72,8 +234,22 @@ impl Struct {
                 .downcast_mut::<Property>()
                 .expect("failed to cast child as Property");
 
-            if child_as_property.flags().contains(PropertyFlags::NET) {
-                todo!("handle property");
+            // The replication condition is an expression in the script of the struct which
+            // declares the property
+            if let Some(rep_offset) = child_as_property.rep_offset() {
+                match self
+                    .script
+                    .binary_search_by_key(&(rep_offset as usize), |statement| statement.offset)
+                {
+                    Ok(index) => {
+                        let condition = self.script[index].expr.clone();
+                        trace!("Replication condition at {rep_offset:#X}: {condition:?}");
+                        child_as_property.set_rep_condition(condition);
+                    }
+                    Err(_) => {
+                        warn!("No replication condition at script offset {rep_offset:#X}");
+                    }
+                }
             }
 
             let as_field = child_inner
@@ -101,6 +277,24 @@ impl Struct {
     }
 }
 
+/// Formats the struct as its kind and full name, e.g. `Function Engine.Actor.Tick`, followed by
+/// its friendly name if it differs from its object name.
+impl fmt::Display for Struct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let object = &self.parent_object.parent_object;
+        let kind = object.concrete_object_kind.unwrap_or(UObjectKind::Struct);
+        write!(f, "{} {}", kind.as_str(), object.full_name())?;
+
+        if let Some(friendly_name) = self.friendly_name()
+            && !friendly_name.eq_ignore_ascii_case(object.name())
+        {
+            write!(f, " ({friendly_name})")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl DeserializeUnrealObject for Struct {
     fn deserialize<E, R>(
         &mut self,
@@ -115,59 +309,95 @@ impl DeserializeUnrealObject for Struct {
         let span = span!(Level::DEBUG, "deserialize_struct");
         let _enter = span.enter();
 
-        let licensee_version = linker.borrow().licensee_version();
+        let has_struct_flags = linker.borrow().profile.has_struct_flags();
 
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        debug!("deserializing script_text");
-        self.script_text = reader.read_object::<E>(runtime, linker)?;
-
-        debug!("deserializing children");
-        self.children = reader.read_object::<E>(runtime, linker)?;
+        self.script_text = annotated!(
+            reader,
+            "script_text",
+            reader.read_object::<E>(runtime, linker)?
+        );
+        self.children = annotated!(
+            reader,
+            "children",
+            reader.read_object::<E>(runtime, linker)?
+        );
+        let friendly_name = annotated!(reader, "friendly_name", reader.read_packed_int()?);
+        self.friendly_name = {
+            let linker = linker.borrow();
+            if friendly_name < 0 || friendly_name as usize >= linker.package.names.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("friendly name index {friendly_name} out of bounds"),
+                ));
+            }
 
-        debug!("deserializing friendly_name");
-        self.friendly_name = reader.read_packed_int()?;
+            (friendly_name as usize != NAME_NONE).then(|| linker.name(friendly_name))
+        };
+        debug!("friendly_name= {:?}", self.friendly_name);
 
-        if licensee_version > 0x1A {
-            self.flags = reader.read_u32::<E>()?;
+        if has_struct_flags {
+            self.flags = annotated!(reader, "flags", reader.read_u32::<E>()?);
         }
 
-        debug!("deserializing line");
-        self.line = reader.read_u32::<E>()?;
+        self.line = annotated!(reader, "line", reader.read_u32::<E>()?);
+        self.text_pos = annotated!(reader, "text_pos", reader.read_u32::<E>()?);
+        self.script_size = annotated!(reader, "script_size", reader.read_u32::<E>()?);
 
-        debug!("deserializing text_pos");
-        self.text_pos = reader.read_u32::<E>()?;
-
-        debug!("deserializing script_size");
-        self.script_size = reader.read_u32::<E>()?;
-
-        let mut script = Vec::new();
         let start_pos = reader.stream_position()?;
-        let expected_end_pos = start_pos + self.script_size as u64;
         debug!(
-            "deserializing script. start_pos= {start_pos:#X}, expected_end= {expected_end_pos:#X}, len= {:#X}",
+            "deserializing script. start_pos= {start_pos:#X}, len= {:#X}",
             self.script_size
         );
 
-        let mut bytes_read = 0;
-
-        while bytes_read < self.script_size as usize {
-            debug!("Bytes read: {bytes_read:#X} / {:#X}", self.script_size);
-            script.append(&mut script::deserialize_expr::<E, _>(
+        let serial_end = self.base_object().export_index.and_then(|index| {
+            let linker = linker.borrow();
+            let export = linker.find_export_by_index(index)?;
+            let range = export.serial_range(&export.full_name(&linker)).ok()?;
+
+            Some(range.end)
+        });
+        self.script = annotated!(
+            reader,
+            "script",
+            script::deserialize_script::<E, _>(
                 runtime,
                 linker,
                 reader,
-                &mut bytes_read,
                 self.script_size as usize,
-            )?);
-        }
-
-        assert_eq!(
-            bytes_read, self.script_size as usize,
-            "Did not read the expected amount of script data"
+                serial_end
+            )?,
+            |script: &Vec<Statement>| format!("{} statements", script.len())
         );
 
+        // Streamed readers can't go back over the script, but re-encoding it gives the same
+        // bytes
+        let end_pos = reader.stream_position()?;
+        self.script_bytes = if reader.supports_rereads() {
+            let mut script_bytes = vec![0u8; (end_pos - start_pos) as usize];
+            reader.seek(SeekFrom::Start(start_pos))?;
+            reader.read_exact(&mut script_bytes)?;
+
+            script_bytes
+        } else {
+            let mut script_bytes = Vec::new();
+            script::serialize_exprs::<E, _>(
+                &mut script_bytes,
+                self.script.iter().map(|statement| &statement.expr),
+            )?;
+
+            script_bytes
+        };
+        if self.script_partially_decoded() {
+            runtime.warn(
+                WarningKind::PartialScript,
+                Some(self.base_object().full_name()),
+                "script was only partially decoded",
+            );
+        }
+
         // Deserialize properties. UStruct::Link
         //
         // First, ensure that the super field is fully loaded
@@ -199,7 +429,7 @@ impl DeserializeUnrealObject for Struct {
 
             {
                 let this_concrete = self.base_object().concrete_obj();
-                if !Rc::ptr_eq(field_outer, &this_concrete) {
+                if !Rc::ptr_eq(&field_outer, &this_concrete) {
                     break;
                 }
             }
@@ -227,6 +457,7 @@ impl DeserializeUnrealObject for Struct {
         // Handle properties with flags. This needs to walk up from the current struct,
         // through its fields, then to the next inheritence struct
         self.visit_children(UObjectKind::Property);
+        self.collect_replicated_properties();
 
         Ok(())
     }
@@ -251,4 +482,119 @@ pub(crate) mod tests {
 
         test_object_is_a(&test_obj as &dyn UnrealObject, expected_uobjectkind());
     }
+
+    #[test]
+    fn resolves_replication_conditions() {
+        use crate::object::{
+            internal::script::{Const, Expr},
+            uproperty::tests::replicated_property,
+        };
+
+        let script = vec![
+            Statement {
+                offset: 0,
+                expr: Expr::Nothing,
+            },
+            Statement {
+                offset: 3,
+                expr: Expr::Const(Const::Bool(true)),
+            },
+        ];
+
+        let condition = |rep_offset| {
+            let property: RcUnrealObject = Rc::new(RefCell::new(replicated_property(rep_offset)));
+            let mut test_struct = Struct {
+                children: Some(Rc::clone(&property)),
+                script: script.clone(),
+                ..Default::default()
+            };
+            test_struct
+                .base_object_mut()
+                .set_concrete_object_kind(UObjectKind::Struct);
+
+            test_struct.visit_children(UObjectKind::Property);
+
+            property
+                .borrow()
+                .as_any()
+                .downcast_ref::<Property>()
+                .unwrap()
+                .rep_condition()
+                .cloned()
+        };
+
+        assert!(matches!(condition(3), Some(Expr::Const(Const::Bool(true)))));
+        // Not the start of an expression
+        assert!(condition(1).is_none());
+    }
+
+    #[test]
+    fn collects_replicated_properties() {
+        use crate::object::uproperty::tests::replicated_property;
+
+        let new_struct = |property: Property, super_struct: Option<&Rc<RefCell<Struct>>>| {
+            let mut test_struct = Struct {
+                children: Some(Rc::new(RefCell::new(property))),
+                ..Default::default()
+            };
+            test_struct
+                .base_object_mut()
+                .set_concrete_object_kind(UObjectKind::Struct);
+            if let Some(super_struct) = super_struct {
+                let super_struct: RcUnrealObject = super_struct.clone();
+                test_struct.parent_object.set_super_field(super_struct);
+            }
+            test_struct.collect_replicated_properties();
+
+            Rc::new(RefCell::new(test_struct))
+        };
+        let rep_indices = |test_struct: &Rc<RefCell<Struct>>| {
+            test_struct
+                .borrow()
+                .replicated_properties()
+                .iter()
+                .map(|property| property.borrow().rep_index())
+                .collect::<Vec<_>>()
+        };
+
+        let actor = new_struct(replicated_property(0), None);
+        let pawn = new_struct(replicated_property(4), Some(&actor));
+        let player_pawn = new_struct(Property::default(), Some(&pawn));
+
+        assert_eq!(rep_indices(&actor), [Some(0)]);
+        // Inherited properties keep their index
+        assert_eq!(rep_indices(&pawn), [Some(0), Some(1)]);
+        assert!(Rc::ptr_eq(
+            pawn.borrow().replicated_properties()[0].object(),
+            actor.borrow().replicated_properties()[0].object()
+        ));
+        assert_eq!(rep_indices(&player_pawn), [Some(0), Some(1)]);
+        assert_eq!(
+            player_pawn
+                .borrow()
+                .properties()
+                .next()
+                .unwrap()
+                .borrow()
+                .rep_index(),
+            None
+        );
+    }
+
+    #[test]
+    fn reports_partially_decoded_scripts() {
+        use crate::object::internal::script::Expr;
+
+        let mut test_struct = Struct::default();
+        assert!(!test_struct.script_partially_decoded());
+
+        test_struct.script = vec![Statement {
+            offset: 0,
+            expr: Expr::Unknown {
+                opcode: 0x03,
+                raw_bytes: vec![0xAA],
+            },
+        }];
+        assert!(test_struct.script_partially_decoded());
+    }
 }
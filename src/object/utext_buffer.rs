@@ -1,22 +1,69 @@
-use std::{cell::RefCell, io, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    io::{self, SeekFrom},
+    rc::Rc,
+};
 
 use byteorder::ReadBytesExt;
-use tracing::{Level, debug, span, trace};
+use tracing::{Level, span, trace};
 
 use crate::{
+    annotate::annotated,
     de::{Linker, RcLinker},
     object::{DeserializeUnrealObject, builtins::Link, uobject::Object},
     reader::{LinRead, UnrealReadExt},
     runtime::UnrealRuntime,
 };
 
+/// Where a text buffer's text is held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextData {
+    /// Read while deserializing, as is done for streams which can only be read once
+    Loaded(String),
+    /// Left in the package to be read on demand. `offset` is the stream position of the
+    /// serialized string, and `len` its size in bytes including the length prefix.
+    Deferred { offset: u64, len: u64 },
+}
+
+impl Default for TextData {
+    fn default() -> Self {
+        TextData::Loaded(String::new())
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct TextBuffer {
     pub parent_object: Object,
 
     pub position: u32,
     pub top: u32,
-    pub text: String,
+    pub text: TextData,
+}
+
+impl TextBuffer {
+    /// The text, if it was read while deserializing.
+    pub fn loaded_text(&self) -> Option<&str> {
+        match &self.text {
+            TextData::Loaded(text) => Some(text),
+            TextData::Deferred { .. } => None,
+        }
+    }
+
+    /// The text, read from `reader` if it was deferred. `reader` must read the package this
+    /// text buffer was loaded from, and is left at the position it started at.
+    pub fn text<R: LinRead>(&self, reader: &mut R) -> io::Result<Cow<'_, str>> {
+        let TextData::Deferred { offset, .. } = self.text else {
+            return Ok(Cow::Borrowed(self.loaded_text().expect("text is loaded")));
+        };
+
+        let saved_pos = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(offset))?;
+        let text = reader.read_string();
+        reader.seek(SeekFrom::Start(saved_pos))?;
+
+        text.map(Cow::Owned)
+    }
 }
 
 impl DeserializeUnrealObject for TextBuffer {
@@ -36,14 +83,24 @@ impl DeserializeUnrealObject for TextBuffer {
         self.parent_object
             .deserialize::<E, _>(runtime, linker, reader)?;
 
-        debug!("Reading position");
-        self.position = reader.read_u32::<E>()?;
-
-        debug!("Reading top");
-        self.top = reader.read_u32::<E>()?;
-
-        debug!("Reading text");
-        self.text = reader.read_string()?;
+        self.position = annotated!(reader, "position", reader.read_u32::<E>()?);
+        self.top = annotated!(reader, "top", reader.read_u32::<E>()?);
+        // Script text can be hundreds of KB and is rarely needed, so only remember where it
+        // is if it can be read again later. Annotated reads keep it so it shows in dumps.
+        let defer = reader.supports_rereads() && reader.annotation_position().is_none();
+        self.text = annotated!(
+            reader,
+            "text",
+            if defer {
+                skip_string(reader)?
+            } else {
+                TextData::Loaded(reader.read_string()?)
+            },
+            |text: &TextData| match text {
+                TextData::Loaded(text) => format!("{} bytes", text.len()),
+                TextData::Deferred { len, .. } => format!("{len} bytes, deferred"),
+            }
+        );
 
         trace!("{:?}", self);
 
@@ -51,6 +108,25 @@ impl DeserializeUnrealObject for TextBuffer {
     }
 }
 
+/// Seeks past the string at the reader's position.
+fn skip_string<R: LinRead>(reader: &mut R) -> io::Result<TextData> {
+    let offset = reader.stream_position()?;
+
+    let string_len = reader.read_packed_int()?;
+    // Negative lengths count UTF-16 characters
+    let byte_len = if string_len < 0 {
+        string_len.unsigned_abs() as i64 * 2
+    } else {
+        string_len as i64
+    };
+    let end = reader.seek(SeekFrom::Current(byte_len))?;
+
+    Ok(TextData::Deferred {
+        offset,
+        len: end - offset,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::object::{UObjectKind, UnrealObject, test_common::test_object_is_a};
@@ -0,0 +1,49 @@
+use std::io;
+
+use crate::object::RcUnrealObject;
+
+/// Describes an object at the point a load hook fires.
+#[derive(Debug)]
+pub struct ObjEvent<'a> {
+    /// Name of the linker (package) the object was exported from
+    pub linker_name: &'a str,
+    /// Full name of the object
+    pub full_name: &'a str,
+    pub class_name: &'a str,
+    /// Zero-based index of the object in its package's export table
+    pub export_index: usize,
+    pub object: &'a RcUnrealObject,
+    /// Number of bytes deserialized for the object. Only set once the object has been
+    /// deserialized.
+    pub serial_size: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct LinkerEvent<'a> {
+    pub name: &'a str,
+    pub name_count: usize,
+    pub import_count: usize,
+    pub export_count: usize,
+}
+
+/// Hooks called by the runtime as packages and objects are loaded.
+///
+/// All methods default to doing nothing. Returning an error from any hook aborts the
+/// load that triggered it and the error is returned to the caller.
+pub trait LoadObserver {
+    /// Called after an object has been constructed and linked to its outer, but before
+    /// it has been deserialized.
+    fn on_object_constructed(&mut self, _event: &ObjEvent) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called after an object's serialized data has been read.
+    fn on_object_deserialized(&mut self, _event: &ObjEvent) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called after a package's header and tables have been read.
+    fn on_linker_loaded(&mut self, _event: &LinkerEvent) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,2829 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use byteorder::ByteOrder;
+use tracing::{Level, debug, span};
+
+use crate::{
+    annotate::{AnnotatingReader, FieldAnnotation, ObjectAnnotations},
+    cache::ExportCache,
+    common::normalize_index,
+    constants::{ConstantValue, ScriptConstant, ScriptConstants},
+    de::{ExportIndex, Linker, ObjectExport, RcLinker, VirtualPackage},
+    detect::{FileKind, detect, endianness_of},
+    edit::{DataReferences, PackageEditor, ScriptRanges},
+    graph::{DependencyGraph, Referencer},
+    headers::{ClassHeader, FunctionDecl, VariableDecl, declared_within_and_config},
+    hierarchy::ClassHierarchy,
+    localize::{LocalizationTable, LocalizedString, StringKind},
+    object::{
+        ObjectFlags, RcUnrealObject, TypedObject, UObjectKind,
+        builtins::{
+            Class, Const, FixedArrayProperty, Function, Property, PropertyFlags, Struct, TextBuffer,
+        },
+        deserialize_object,
+        internal::{
+            natives::NativeFunctionTable, object_ref::ReferencePolicy, probes::ProbeNameTable,
+            script,
+        },
+    },
+    observer::LoadObserver,
+    packed::read_packed_int,
+    postload::PostLoadHook,
+    profile::FormatProfile,
+    reader::{LinRead, PackageReader},
+    report::LoadReport,
+    runtime::{LoadKind, LoadOptions, SerialSizeMismatch, UnrealRuntime},
+    ser::{LayoutMode, serialize_unreal_package, write_var_string},
+    snapshot::Snapshot,
+    stats::{LinkerUsage, PackageStats},
+    validate::ValidationFinding,
+    warnings::LoadWarning,
+};
+
+/// UnrealScript source recovered from a `TextBuffer` export.
+#[derive(Debug, Clone)]
+pub struct ScriptSource {
+    /// Name of the object which owns the text buffer. For class scripts this is the class name.
+    pub class_name: String,
+    /// Name of the text buffer, such as `ScriptText` or `CppText`
+    pub name: String,
+    pub text: String,
+}
+
+impl ScriptSource {
+    /// File name to write the source to: `{class}.uc` for a class's script text and
+    /// `{class}.{name}.txt` for its other text buffers. Both names are sanitized, so the
+    /// file name can be joined to a directory without escaping it.
+    pub fn file_name(&self) -> String {
+        let class_name = sanitize_file_name_part(&self.class_name);
+        if self.name.eq_ignore_ascii_case("ScriptText") {
+            format!("{class_name}.uc")
+        } else {
+            format!("{class_name}.{}.txt", sanitize_file_name_part(&self.name))
+        }
+    }
+}
+
+/// A single standalone package file such as a .u, .utx or .unr.
+pub struct Package<E, R> {
+    runtime: UnrealRuntime,
+    linker: RcLinker,
+    reader: AnnotatingReader<PackageReader<R>>,
+    _endian: PhantomData<E>,
+}
+
+impl<E, R> Package<E, R>
+where
+    E: ByteOrder,
+    R: Read + Seek,
+{
+    /// Reads the package header, name, import, and export tables from `reader`.
+    pub fn open(name: impl Into<String>, reader: R) -> io::Result<Self> {
+        Self::open_inner(name.into(), reader, None)
+    }
+
+    /// Like [`Package::open`], but reads the package using `profile` instead of detecting
+    /// its format.
+    pub fn open_with_profile(
+        name: impl Into<String>,
+        reader: R,
+        profile: FormatProfile,
+    ) -> io::Result<Self> {
+        Self::open_inner(name.into(), reader, Some(profile))
+    }
+
+    fn open_inner(name: String, mut reader: R, profile: Option<FormatProfile>) -> io::Result<Self> {
+        // Catch the wrong kind of file or byte order before the header is misread
+        let kind = detect(&mut reader)?;
+        if kind != FileKind::Package(endianness_of::<E>()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{name} is not a {:?} endian package: detected {kind:?}",
+                    endianness_of::<E>()
+                ),
+            ));
+        }
+
+        let mut runtime = UnrealRuntime {
+            standalone: true,
+            ..Default::default()
+        };
+        if let Some(profile) = profile {
+            if let Some(natives) = &profile.native_functions {
+                runtime.native_functions = natives.clone();
+            }
+            runtime.format_profile = Some(profile);
+        }
+        let mut reader = AnnotatingReader::new(PackageReader::new(reader));
+
+        runtime.load_linker::<E, _>(name.clone(), &mut reader)?;
+        let linker = runtime.linker(&name).expect("linker was just loaded");
+
+        Ok(Package {
+            runtime,
+            linker,
+            reader,
+            _endian: PhantomData,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.linker.borrow().name.clone()
+    }
+
+    /// Loads the export at `path` (e.g. `MyGroup.MyTexture`) along with its dependencies.
+    ///
+    /// Only this package is available, so objects imported from other packages are loaded
+    /// as intrinsic placeholders.
+    pub fn load_object(&mut self, path: &str) -> io::Result<Option<RcUnrealObject>> {
+        let Some((export_index, _)) = self.linker.borrow().find_export_by_path(path) else {
+            return Ok(None);
+        };
+
+        self.runtime
+            .load_object_by_export_index::<E, _>(
+                export_index,
+                &self.linker,
+                LoadKind::Load,
+                &mut self.reader,
+            )
+            .map(Some)
+    }
+
+    /// Loads every export in the package.
+    pub fn load_objects(&mut self) -> io::Result<VirtualPackage> {
+        let span = span!(Level::DEBUG, "load_objects");
+        let _enter = span.enter();
+
+        let export_count = self.linker.borrow().package.exports.len();
+        let linker = &self.linker;
+        let reader = &mut self.reader;
+        self.runtime.load_batch(|runtime| {
+            for i in 0..export_count {
+                runtime.load_object_by_export_index::<E, _>(
+                    ExportIndex::from_raw(i as i32 + 1),
+                    linker,
+                    LoadKind::Load,
+                    reader,
+                )?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(self.contents())
+    }
+
+    /// The package's tables and whichever of its objects have been loaded so far.
+    pub fn contents(&self) -> VirtualPackage {
+        VirtualPackage {
+            linker: Rc::clone(&self.linker),
+        }
+    }
+
+    /// Replaces the table used to name native functions called from script code.
+    pub fn set_native_functions(&mut self, table: NativeFunctionTable) {
+        self.runtime.native_functions = table;
+    }
+
+    /// Replaces the table used to name the probe functions in states' probe and ignore masks.
+    pub fn set_probe_names(&mut self, table: ProbeNameTable) {
+        self.runtime.probe_names = table;
+    }
+
+    /// Limits how much is loaded when objects in this package are requested.
+    pub fn set_load_options(&mut self, options: LoadOptions) {
+        self.runtime.load_options = options;
+    }
+
+    /// Reads the data of every export into memory up front so that loading objects doesn't
+    /// seek around the underlying reader. `max_bytes` bounds how much data is cached; exports
+    /// past the limit are read from the underlying reader as usual.
+    pub fn cache_exports(&mut self, max_bytes: Option<usize>) -> io::Result<&ExportCache> {
+        // Drop any existing cache so the new one is built from the underlying reader
+        self.reader.get_mut().set_export_cache(None);
+
+        let cache = ExportCache::build(
+            &mut self.reader,
+            &self.linker.borrow().package.exports,
+            max_bytes,
+        )?;
+        self.reader.get_mut().set_export_cache(Some(cache));
+
+        Ok(self
+            .reader
+            .get_ref()
+            .export_cache()
+            .expect("export cache was just set"))
+    }
+
+    /// Sets whether the fields read while deserializing objects are recorded. Annotations
+    /// are collected with [`Package::take_annotations`].
+    pub fn set_annotate_reads(&mut self, annotate: bool) {
+        self.reader.set_enabled(annotate);
+    }
+
+    /// Takes the annotations recorded for each object deserialized since the last call, in
+    /// the order they finished loading.
+    pub fn take_annotations(&mut self) -> Vec<ObjectAnnotations> {
+        self.reader.take_annotations()
+    }
+
+    /// The text held by `obj` if it's a `TextBuffer`. Text buffers loaded from a package file
+    /// don't hold their text in memory, so it's read from the package here.
+    pub fn script_text(&mut self, obj: &RcUnrealObject) -> io::Result<Option<String>> {
+        let obj = obj.borrow();
+        let Some(text_buffer) = obj.as_any().downcast_ref::<TextBuffer>() else {
+            return Ok(None);
+        };
+
+        text_buffer
+            .text(&mut self.reader)
+            .map(|text| Some(text.into_owned()))
+    }
+
+    /// Checks the package's name, import, and export tables for corruption.
+    pub fn validate(&mut self) -> io::Result<Vec<ValidationFinding>> {
+        let saved_pos = self.reader.stream_position()?;
+        let file_size = self.reader.seek(SeekFrom::End(0))?;
+        self.reader.seek(SeekFrom::Start(saved_pos))?;
+
+        Ok(self.linker.borrow().package.validate(file_size))
+    }
+
+    /// Counts and sizes summarizing the package's tables.
+    pub fn stats(&self) -> PackageStats {
+        self.linker.borrow().package.stats()
+    }
+
+    /// Exports whose data didn't match their serial size. Only populated when loading with
+    /// [`LoadOptions::lenient`] set.
+    pub fn serial_size_mismatches(&self) -> &[SerialSizeMismatch] {
+        &self.runtime.serial_size_mismatches
+    }
+
+    /// Conditions noticed while loading which didn't stop the load, oldest first.
+    pub fn warnings(&self) -> &[LoadWarning] {
+        self.runtime.warnings.records()
+    }
+
+    /// Removes and returns the warnings recorded so far.
+    pub fn take_warnings(&mut self) -> Vec<LoadWarning> {
+        self.runtime.warnings.take()
+    }
+
+    /// Calls `callback` with each warning as it's recorded. Warnings are still collected.
+    pub fn set_warning_callback(&mut self, callback: impl FnMut(&LoadWarning) + 'static) {
+        self.runtime.warnings.set_callback(Some(Box::new(callback)));
+    }
+
+    /// Registers a hook which is notified as objects in this package are loaded.
+    pub fn add_observer(&mut self, observer: Box<dyn LoadObserver>) {
+        self.runtime.add_observer(observer);
+    }
+
+    /// Sets whether the time spent deserializing each object is recorded for
+    /// [`LoadReport`]s. Enabling recording restarts it.
+    pub fn set_record_load_report(&mut self, record: bool) {
+        self.runtime.set_record_load_report(record);
+    }
+
+    /// Builds a report of every object deserialized since recording was enabled, or `None`
+    /// if it isn't.
+    pub fn load_report(&self) -> Option<LoadReport> {
+        self.runtime.load_report()
+    }
+
+    /// Registers a hook which is run on objects in this package of `kind`, or a subclass of it, once
+    /// they've been loaded.
+    pub fn add_post_load_hook(&mut self, kind: UObjectKind, hook: Box<dyn PostLoadHook>) {
+        self.runtime.add_post_load_hook(kind, hook);
+    }
+
+    /// Builds a graph of this package's dependencies from its import and export tables.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        self.runtime.dependency_graph()
+    }
+
+    /// What refers to the object named `full_name` (e.g. `MyPackage.MyGroup.MyTexture`) in
+    /// this package's tables and the objects loaded from it.
+    pub fn referencers_of(&self, full_name: &str) -> Vec<Referencer> {
+        self.runtime.referencers_of(full_name)
+    }
+
+    /// Builds the hierarchy of the classes this package exports.
+    pub fn class_hierarchy(&self) -> ClassHierarchy {
+        self.runtime.class_hierarchy()
+    }
+
+    /// Object counts and estimated memory usage of this package and the packages it
+    /// depends on.
+    pub fn linker_usage(&self) -> Vec<LinkerUsage> {
+        self.runtime.linker_usage()
+    }
+
+    /// The loaded class named `full_name`, e.g. `Engine.Actor`.
+    pub fn get_class(&self, full_name: &str) -> Option<TypedObject<Class>> {
+        self.runtime.get_class(full_name)
+    }
+
+    /// The loaded function named `full_name`, e.g. `Engine.Actor.Spawn`.
+    pub fn get_function(&self, full_name: &str) -> Option<TypedObject<Function>> {
+        self.runtime.get_function(full_name)
+    }
+
+    /// The loaded struct named `full_name`. Functions, states and classes are structs too.
+    pub fn get_struct(&self, full_name: &str) -> Option<TypedObject<Struct>> {
+        self.runtime.get_struct(full_name)
+    }
+
+    /// Every loaded object along with the name of the package it was loaded from and its
+    /// index in that package's export table.
+    pub fn iter_objects(&self) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects()
+    }
+
+    /// Every loaded object which is a `kind`, including kinds derived from it.
+    pub fn iter_objects_of_kind(
+        &self,
+        kind: UObjectKind,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects_of_kind(kind)
+    }
+
+    /// Every loaded object which has all of `flags` set.
+    pub fn iter_objects_with_flags(
+        &self,
+        flags: ObjectFlags,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.runtime.iter_objects_with_flags(flags)
+    }
+
+    /// Copies the loaded object graph into a [`Snapshot`] which can be shared between
+    /// threads.
+    pub fn freeze(&self) -> Snapshot {
+        self.runtime.freeze()
+    }
+
+    /// Recovers the declarations of every class this package exports from the compiled
+    /// variables and functions inside it.
+    ///
+    /// Each field is read on its own, so only the class's children and the classes and
+    /// structs their types name need to be resolvable.
+    pub fn class_headers(&mut self) -> io::Result<Vec<ClassHeader>> {
+        let span = span!(Level::DEBUG, "class_headers");
+        let _enter = span.enter();
+
+        let classes = {
+            let linker = self.linker.borrow();
+            linker
+                .package
+                .exports
+                .iter()
+                .enumerate()
+                .filter(|(_, export)| {
+                    export.class_name(&linker) == UObjectKind::Class.as_str()
+                        && export.serial_size() > 0
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        };
+
+        classes
+            .into_iter()
+            .map(|index| self.class_header_at(index))
+            .collect()
+    }
+
+    /// Recovers the declaration of the class at `path` (e.g. `MyActor`), like
+    /// [`Package::class_headers`]. Returns `None` if the package doesn't export it.
+    pub fn class_header(&mut self, path: &str) -> io::Result<Option<ClassHeader>> {
+        let Some((export_index, _)) = self.linker.borrow().find_export_by_path(path) else {
+            return Ok(None);
+        };
+
+        self.class_header_at(export_index.index()).map(Some)
+    }
+
+    fn class_header_at(&mut self, index: usize) -> io::Result<ClassHeader> {
+        let (name, super_class) = {
+            let linker = self.linker.borrow();
+            let export = &linker.package.exports[index];
+
+            (
+                export.object_name(&linker).to_owned(),
+                raw_object_name(&linker, export.super_index),
+            )
+        };
+        debug!("Generating header for {name}");
+
+        let (class, fields) = self.read_class_declaration(index)?;
+        let data = self.export_data(index)?;
+
+        let (within, config_name) = {
+            let class = class.borrow();
+            let class = class
+                .as_any()
+                .downcast_ref::<Class>()
+                .expect("object was read as a class");
+
+            declared_within_and_config(class)
+        };
+        let mut header = ClassHeader {
+            name,
+            super_class,
+            within,
+            config_name,
+            variables: Vec::new(),
+            functions: Vec::new(),
+        };
+        for (child, obj, fields) in
+            self.children(field_object_index(&data, &fields, "children")?)?
+        {
+            let kind = obj.borrow().kind();
+            if obj.borrow().is_a(UObjectKind::Property) {
+                header
+                    .variables
+                    .push(self.variable_decl(child, &obj, &fields)?);
+            } else if kind == UObjectKind::Function {
+                header
+                    .functions
+                    .push(self.function_decl(child, &obj, &fields)?);
+            }
+        }
+
+        Ok(header)
+    }
+
+    /// Follows the chain of fields starting at the raw index `first`, reading each on its own.
+    /// Returns the export index of each along with what was read.
+    fn children(
+        &mut self,
+        first: i32,
+    ) -> io::Result<Vec<(usize, RcUnrealObject, Vec<FieldAnnotation>)>> {
+        let mut children = Vec::new();
+        let mut next = first;
+        while next > 0 {
+            let index = normalize_index(next);
+            if children.iter().any(|(child, _, _)| *child == index) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("the fields after export {index} loop back to it"),
+                ));
+            }
+
+            let (obj, fields) = self.read_detached(index, None)?;
+            let data = self.export_data(index)?;
+            next = field_object_index(&data, &fields, "next")?;
+
+            children.push((index, obj, fields));
+        }
+
+        Ok(children)
+    }
+
+    fn variable_decl(
+        &mut self,
+        index: usize,
+        obj: &RcUnrealObject,
+        fields: &[FieldAnnotation],
+    ) -> io::Result<VariableDecl> {
+        let type_name = self.property_type(index, obj, fields)?;
+
+        let obj = obj.borrow();
+        let property = obj
+            .parent_of_kind(UObjectKind::Property)
+            .and_then(|property| property.as_any().downcast_ref::<Property>())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("export {index} isn't a property"),
+                )
+            })?;
+        let category = (!property.category().is_none()).then(|| {
+            property
+                .category()
+                .resolve(&self.linker.borrow())
+                .to_string()
+        });
+
+        Ok(VariableDecl {
+            name: obj.base_object().name().to_owned(),
+            type_name,
+            category,
+            flags: property.flags(),
+            array_dim: property.array_dim(),
+        })
+    }
+
+    /// The UnrealScript type of the property at `index`, which was read as `obj`.
+    fn property_type(
+        &mut self,
+        index: usize,
+        obj: &RcUnrealObject,
+        fields: &[FieldAnnotation],
+    ) -> io::Result<String> {
+        let kind = obj.borrow().kind();
+        let data = self.export_data(index)?;
+        let object_name = |field: &str, default: &str| {
+            let raw_index = field_object_index(&data, fields, field)?;
+            Ok::<_, io::Error>(
+                raw_object_name(&self.linker.borrow(), raw_index)
+                    .unwrap_or_else(|| default.to_owned()),
+            )
+        };
+
+        let type_name = match kind {
+            UObjectKind::ByteProperty => object_name("eenum", "byte")?,
+            UObjectKind::IntProperty => "int".to_owned(),
+            UObjectKind::BoolProperty => "bool".to_owned(),
+            UObjectKind::FloatProperty => "float".to_owned(),
+            UObjectKind::NameProperty => "name".to_owned(),
+            UObjectKind::StrProperty => "string".to_owned(),
+            UObjectKind::PointerProperty => "pointer".to_owned(),
+            UObjectKind::ObjectProperty => object_name("property_class", "Object")?,
+            UObjectKind::ClassProperty => {
+                format!("class<{}>", object_name("meta_class", "Object")?)
+            }
+            UObjectKind::StructProperty => object_name("struct_obj", "struct")?,
+            UObjectKind::DelegateProperty => {
+                format!("delegate<{}>", object_name("function", "None")?)
+            }
+            UObjectKind::FixedArrayProperty | UObjectKind::MapProperty => {
+                let inner = |this: &mut Self, field: &str| -> io::Result<String> {
+                    match field_object_index(&data, fields, field)? {
+                        raw_index @ 1.. => {
+                            let inner = normalize_index(raw_index);
+                            let (obj, fields) = this.read_detached(inner, None)?;
+                            this.property_type(inner, &obj, &fields)
+                        }
+                        _ => Ok("Object".to_owned()),
+                    }
+                };
+
+                if kind == UObjectKind::MapProperty {
+                    format!("map<{}, {}>", inner(self, "key")?, inner(self, "value")?)
+                } else {
+                    let count = obj
+                        .borrow()
+                        .as_any()
+                        .downcast_ref::<FixedArrayProperty>()
+                        .map(|array| array.count)
+                        .unwrap_or_default();
+                    format!("{}[{count}]", inner(self, "inner")?)
+                }
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("export {index} is a {}, not a property", kind.as_str()),
+                ));
+            }
+        };
+
+        Ok(type_name)
+    }
+
+    fn function_decl(
+        &mut self,
+        index: usize,
+        obj: &RcUnrealObject,
+        fields: &[FieldAnnotation],
+    ) -> io::Result<FunctionDecl> {
+        let data = self.export_data(index)?;
+        let (name, flags, native_index) = {
+            let obj = obj.borrow();
+            let function = obj
+                .as_any()
+                .downcast_ref::<Function>()
+                .expect("export was read as a function");
+
+            (
+                obj.base_object().name().to_owned(),
+                function.function_flags,
+                function.inative(),
+            )
+        };
+
+        let mut decl = FunctionDecl {
+            name,
+            flags,
+            native_index,
+            return_type: None,
+            params: Vec::new(),
+        };
+        for (child, obj, fields) in self.children(field_object_index(&data, fields, "children")?)? {
+            if !obj.borrow().is_a(UObjectKind::Property) {
+                continue;
+            }
+
+            let variable = self.variable_decl(child, &obj, &fields)?;
+            if variable.flags.contains(PropertyFlags::RETURN_PARM) {
+                decl.return_type = Some(variable.type_name);
+            } else if variable.flags.contains(PropertyFlags::PARM) {
+                decl.params.push(variable);
+            }
+        }
+
+        Ok(decl)
+    }
+
+    /// Deserializes every `TextBuffer` export and returns the script text it holds.
+    ///
+    /// Text buffers don't reference other objects, so they are deserialized without loading
+    /// their class or outer. This keeps extraction working for packages whose imports
+    /// cannot be resolved.
+    pub fn extract_script_sources(&mut self) -> io::Result<Vec<ScriptSource>> {
+        let span = span!(Level::DEBUG, "extract_script_sources");
+        let _enter = span.enter();
+
+        let text_buffers = {
+            let linker = self.linker.borrow();
+            linker
+                .package
+                .exports
+                .iter()
+                .enumerate()
+                .filter(|(_, export)| {
+                    export.class_name(&linker) == UObjectKind::TextBuffer.as_str()
+                        && export.serial_size() > 0
+                })
+                .map(|(i, export)| {
+                    let owner = match export.package_index {
+                        0 => Some(linker.name.clone()),
+                        index if index > 0 => linker
+                            .package
+                            .exports
+                            .get(normalize_index(index))
+                            .map(|outer| outer.object_name(&linker).to_owned()),
+                        index => linker
+                            .package
+                            .imports
+                            .get(normalize_index(index))
+                            .map(|outer| outer.object_name(&linker).to_owned()),
+                    };
+                    let Some(owner) = owner else {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "text buffer {} has an invalid outer index {}",
+                                export.full_name(&linker),
+                                export.package_index
+                            ),
+                        ));
+                    };
+
+                    Ok((
+                        ExportIndex::from_raw(i as i32 + 1),
+                        export.clone(),
+                        export.full_name(&linker),
+                        owner,
+                    ))
+                })
+                .collect::<io::Result<Vec<_>>>()?
+        };
+
+        let mut sources = Vec::with_capacity(text_buffers.len());
+        for (export_index, export, full_name, owner) in text_buffers {
+            debug!("Extracting script text for {owner}");
+
+            let range = export.checked_serial_range(&full_name, &mut self.reader)?;
+            let obj =
+                construct_detached(&self.linker, UObjectKind::TextBuffer, export_index, &export);
+            self.reader.seek(SeekFrom::Start(range.start))?;
+            deserialize_object::<E, _>(
+                &mut self.runtime,
+                Rc::clone(&obj),
+                &self.linker,
+                &mut self.reader,
+            )?;
+
+            let obj_inner = obj.borrow();
+            let text_buffer = obj_inner
+                .as_any()
+                .downcast_ref::<TextBuffer>()
+                .expect("failed to cast object to TextBuffer");
+
+            sources.push(ScriptSource {
+                class_name: owner,
+                name: obj_inner.base_object().name().to_owned(),
+                text: text_buffer.text(&mut self.reader)?.into_owned(),
+            });
+        }
+
+        Ok(sources)
+    }
+
+    /// Reads the serialized data of the export at `index` in the export table.
+    pub fn export_data(&mut self, index: usize) -> io::Result<Vec<u8>> {
+        let Some(export) = self.linker.borrow().package.exports.get(index).cloned() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("export {index} out of bounds"),
+            ));
+        };
+
+        let full_name = {
+            let linker = self.linker.borrow();
+            export.full_name(&linker)
+        };
+        let range = export.checked_serial_range(&full_name, &mut self.reader)?;
+
+        let saved_pos = self.reader.stream_position()?;
+        let mut data = vec![0u8; (range.end - range.start) as usize];
+        self.reader.seek(SeekFrom::Start(range.start))?;
+        self.reader.read_exact(&mut data)?;
+        self.reader.seek(SeekFrom::Start(saved_pos))?;
+
+        Ok(data)
+    }
+
+    /// Writes the serialized data of each export to its own file in `dir`, named
+    /// `<index>_<name>_<class>.bin`. Exports without any data are skipped. Returns the paths
+    /// written, in export table order.
+    ///
+    /// Characters which aren't safe in file names are replaced. The export index keeps names
+    /// unique, even on case-insensitive file systems.
+    pub fn carve_exports(&mut self, dir: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let span = span!(Level::DEBUG, "carve_exports");
+        let _enter = span.enter();
+
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        let exports = {
+            let linker = self.linker.borrow();
+            linker
+                .package
+                .exports
+                .iter()
+                .enumerate()
+                .filter(|(_, export)| export.serial_size() > 0)
+                .map(|(i, export)| {
+                    let file_name = carved_file_name(
+                        i,
+                        export.object_name(&linker),
+                        export.class_name(&linker),
+                    );
+                    (i, file_name)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut written = Vec::with_capacity(exports.len());
+        for (i, file_name) in exports {
+            let path = dir.join(file_name);
+            debug!("Writing export {i} to {path:?}");
+
+            std::fs::write(&path, self.export_data(i)?)?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+
+    /// Collects the constants used by the scripts of every function, state and class this
+    /// package exports. Scripts without any constants are left out.
+    ///
+    /// Each script is read on its own, so the objects it references don't need to be
+    /// resolvable.
+    pub fn script_constants(&mut self) -> io::Result<Vec<ScriptConstants>> {
+        let span = span!(Level::DEBUG, "script_constants");
+        let _enter = span.enter();
+
+        let scripted = {
+            let linker = self.linker.borrow();
+            linker
+                .package
+                .exports
+                .iter()
+                .enumerate()
+                .filter_map(|(i, export)| {
+                    let kind = UObjectKind::from_class_name(export.class_name(&linker))?;
+                    let has_script = matches!(
+                        kind,
+                        UObjectKind::Function | UObjectKind::State | UObjectKind::Class
+                    );
+
+                    (has_script && export.serial_size() > 0).then_some((i, kind))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut all_constants = Vec::new();
+        for (index, kind) in scripted {
+            let (obj, _) = if kind == UObjectKind::Class {
+                self.read_class_declaration(index)?
+            } else {
+                self.read_detached(index, Some(kind))?
+            };
+
+            let linker = self.linker.borrow();
+            let obj = obj.borrow();
+            let Some(ustruct) = obj
+                .parent_of_kind(UObjectKind::Struct)
+                .and_then(|obj| obj.as_any().downcast_ref::<Struct>())
+            else {
+                continue;
+            };
+
+            let constants = ustruct
+                .script()
+                .iter()
+                .flat_map(|statement| {
+                    script::constants([&statement.expr])
+                        .into_iter()
+                        .map(|constant| ScriptConstant {
+                            offset: statement.offset,
+                            value: ConstantValue::resolve(constant, &linker),
+                        })
+                })
+                .collect::<Vec<_>>();
+            if constants.is_empty() {
+                continue;
+            }
+
+            all_constants.push(ScriptConstants {
+                owner: linker.package.exports[index].full_name(&linker),
+                constants,
+            });
+        }
+
+        Ok(all_constants)
+    }
+
+    /// Collects the strings in this package which can be translated: every name table entry
+    /// other than `None`, and the values of `Const` and `TextBuffer` exports.
+    ///
+    /// Localized property values aren't included since tagged properties can't be read yet.
+    pub fn localization_table(&mut self) -> io::Result<LocalizationTable> {
+        let span = span!(Level::DEBUG, "localization_table");
+        let _enter = span.enter();
+
+        let mut table = LocalizationTable::default();
+
+        let linker = self.linker.borrow();
+        for (i, name) in linker.package.names.iter().enumerate() {
+            if !name.name.eq_ignore_ascii_case("None") {
+                table.entries.push(LocalizedString {
+                    kind: StringKind::Name,
+                    key: i.to_string(),
+                    source: name.name.to_string(),
+                    translation: String::new(),
+                });
+            }
+        }
+        drop(linker);
+
+        for field in self.string_fields()? {
+            table.entries.push(LocalizedString {
+                kind: field.kind,
+                key: field.full_name,
+                source: field.value,
+                translation: String::new(),
+            });
+        }
+
+        Ok(table)
+    }
+
+    /// Writes the package back out unmodified, keeping every table and export at the offset
+    /// it was read from. The output matches the file the package was read from byte for byte,
+    /// apart from the normalizations listed on [`LayoutMode::PreserveLayout`].
+    pub fn resave<W: Write>(&mut self, writer: W) -> io::Result<()> {
+        let mut package = self.linker.borrow().package.clone();
+
+        let mut export_data = Vec::with_capacity(package.exports.len());
+        for (i, export) in package.exports.iter().enumerate() {
+            if export.serial_size() == 0 {
+                export_data.push(Vec::new());
+                continue;
+            }
+
+            export_data.push(vec![(export.serial_offset(), self.export_data(i)?)]);
+        }
+
+        serialize_unreal_package::<E, _>(
+            writer,
+            &mut package,
+            &export_data,
+            LayoutMode::PreserveLayout,
+        )
+    }
+
+    /// Writes a copy of this package with the translations in `table` applied.
+    ///
+    /// Name table entries are renamed in place so that every index into the name table stays
+    /// valid. `Const` and `TextBuffer` strings are rewritten within their export's data, and
+    /// the package is laid out again to make room for them. Translations whose source string
+    /// doesn't match the package, and renames which would collide with another name, are
+    /// rejected without writing anything.
+    pub fn write_localized<W: Write>(
+        &mut self,
+        table: &LocalizationTable,
+        writer: W,
+    ) -> io::Result<()> {
+        let span = span!(Level::DEBUG, "write_localized");
+        let _enter = span.enter();
+
+        let stale = |entry: &LocalizedString, current: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{} {} is {current:?} in the package, not {:?}",
+                    entry.kind, entry.key, entry.source
+                ),
+            )
+        };
+
+        let mut package = self.linker.borrow().package.clone();
+        for entry in table
+            .translated()
+            .filter(|entry| entry.kind == StringKind::Name)
+        {
+            let Some(index) = entry
+                .key
+                .parse::<usize>()
+                .ok()
+                .filter(|&index| index < package.names.len())
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("name index {:?} out of bounds", entry.key),
+                ));
+            };
+
+            if *package.names[index].name != *entry.source {
+                return Err(stale(entry, &package.names[index].name));
+            }
+            // Names are looked up ignoring case, so a rename must stay distinct from the rest
+            if package
+                .names
+                .iter()
+                .enumerate()
+                .any(|(i, name)| i != index && name.name.eq_ignore_ascii_case(&entry.translation))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "renaming name {index} to {:?} collides with an existing name",
+                        entry.translation
+                    ),
+                ));
+            }
+
+            debug!("Renaming name {index} to {:?}", entry.translation);
+            package.names[index].name = Rc::from(entry.translation.as_str());
+        }
+
+        let fields = self.string_fields()?;
+        for entry in table
+            .translated()
+            .filter(|entry| entry.kind != StringKind::Name)
+        {
+            if !fields
+                .iter()
+                .any(|field| field.kind == entry.kind && field.full_name == entry.key)
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no {} export named {}", entry.kind, entry.key),
+                ));
+            }
+        }
+
+        let mut export_data = Vec::with_capacity(package.exports.len());
+        for (i, export) in package.exports.iter().enumerate() {
+            if export.serial_size() == 0 {
+                export_data.push(Vec::new());
+                continue;
+            }
+
+            export_data.push(vec![(export.serial_offset(), self.export_data(i)?)]);
+        }
+
+        // Later fields are rewritten first so that earlier ranges in the same export stay put
+        for field in fields.iter().rev() {
+            let Some(entry) = table
+                .get(field.kind, &field.full_name)
+                .filter(|entry| !entry.translation.is_empty())
+            else {
+                continue;
+            };
+            if entry.source != field.value {
+                return Err(stale(entry, &field.value));
+            }
+
+            let mut encoded = Vec::new();
+            write_var_string(&mut encoded, &entry.translation)?;
+
+            debug!("Rewriting {} {}", field.kind, field.full_name);
+            let (_, data) = &mut export_data[field.export][0];
+            data.splice(field.range.clone(), encoded);
+        }
+
+        serialize_unreal_package::<E, _>(writer, &mut package, &export_data, LayoutMode::Compact)
+    }
+
+    /// Starts a set of edits to this package, which are applied when the package is written
+    /// out with [`PackageEditor::write`].
+    pub fn edit(&mut self) -> PackageEditor<'_, E, R> {
+        PackageEditor::new(self)
+    }
+
+    /// Finds where the `Function` export at `index` stores its script size and script within
+    /// the export's data.
+    pub(crate) fn function_script_ranges(&mut self, index: usize) -> io::Result<ScriptRanges> {
+        let (_, fields) = self.read_detached(index, Some(UObjectKind::Function))?;
+
+        let field = |name: &str| {
+            fields
+                .iter()
+                .rfind(|field| field.name == name)
+                .map(|field| field.offset as usize..(field.offset + field.len) as usize)
+                .ok_or_else(|| {
+                    io::Error::other(format!("export {index} was read without its {name}"))
+                })
+        };
+
+        Ok(ScriptRanges {
+            script_size: field("script_size")?,
+            script: field("script")?,
+        })
+    }
+
+    /// Finds every name and object index stored in the data of the export at `index`.
+    pub(crate) fn data_references(&mut self, index: usize) -> io::Result<DataReferences> {
+        let (obj, fields) = self.read_detached(index, None)?;
+        let kind = obj.borrow().kind();
+
+        let mut references = DataReferences::default();
+        for field in fields {
+            let range = field.offset as usize..(field.offset + field.len) as usize;
+            match field.name {
+                "name" | "friendly_name" | "category" => references.names.push(range),
+                "names" => references.name_arrays.push(range),
+                "script_text" | "children" | "super_field" | "next" | "property_class"
+                | "meta_class" | "eenum" | "struct_obj" | "function" | "inner" => {
+                    references.objects.push(range)
+                }
+                // A Const's value is a string
+                "key" | "value" if kind == UObjectKind::MapProperty => {
+                    references.objects.push(range)
+                }
+                "script" => {
+                    let obj = obj.borrow();
+                    let statements = obj
+                        .parent_of_kind(UObjectKind::Struct)
+                        .and_then(|obj| obj.as_any().downcast_ref::<Struct>())
+                        .map(|obj| obj.script().to_vec())
+                        .unwrap_or_default();
+
+                    references.script = Some((range, statements));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(references)
+    }
+
+    /// Deserializes the export at `index` on its own, without loading anything its script
+    /// refers to, and returns it along with the fields it read. Field offsets are relative to
+    /// the start of the export's data.
+    ///
+    /// `expected` is the kind the export must be. If `None`, the export may be any builtin
+    /// kind which can be deserialized.
+    pub(crate) fn read_detached(
+        &mut self,
+        index: usize,
+        expected: Option<UObjectKind>,
+    ) -> io::Result<(RcUnrealObject, Vec<FieldAnnotation>)> {
+        let (export, full_name, kind) = {
+            let linker = self.linker.borrow();
+            let Some(export) = linker.package.exports.get(index) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("export {index} out of bounds"),
+                ));
+            };
+
+            let full_name = export.full_name(&linker);
+            let class_name = export.class_name(&linker);
+            let kind = match (UObjectKind::from_class_name(class_name), expected) {
+                (Some(kind), Some(expected)) if kind == expected => kind,
+                (_, Some(expected)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{full_name} is a {class_name}, not a {}", expected.as_str()),
+                    ));
+                }
+                // Classes and the tagged properties of other objects can't be read yet
+                (Some(kind), None) if kind != UObjectKind::Class => kind,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!("{full_name} is a {class_name}, which can't be read on its own"),
+                    ));
+                }
+            };
+
+            (export.clone(), full_name, kind)
+        };
+
+        self.read_detached_as(index, &export, &full_name, kind, false)
+    }
+
+    /// Reads the declaration of the `Class` export at `index`, which holds the class's
+    /// children, within class and config name, along with the fields it read. The default
+    /// properties which follow can't be read yet.
+    pub(crate) fn read_class_declaration(
+        &mut self,
+        index: usize,
+    ) -> io::Result<(RcUnrealObject, Vec<FieldAnnotation>)> {
+        let (export, full_name) = {
+            let linker = self.linker.borrow();
+            let Some(export) = linker.package.exports.get(index) else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("export {index} out of bounds"),
+                ));
+            };
+
+            let full_name = export.full_name(&linker);
+            let class_name = export.class_name(&linker);
+            if class_name != UObjectKind::Class.as_str() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{full_name} is a {class_name}, not a Class"),
+                ));
+            }
+
+            (export.clone(), full_name)
+        };
+
+        self.read_detached_as(index, &export, &full_name, UObjectKind::Class, true)
+    }
+
+    /// Deserializes `export` as a detached `kind`. If `declaration_only` is set, only the
+    /// declaration part of a class is read.
+    fn read_detached_as(
+        &mut self,
+        index: usize,
+        export: &ObjectExport,
+        full_name: &str,
+        kind: UObjectKind,
+        declaration_only: bool,
+    ) -> io::Result<(RcUnrealObject, Vec<FieldAnnotation>)> {
+        export.checked_serial_range(full_name, &mut self.reader)?;
+
+        // Only the layout of the export's own data is needed
+        let reference_policy =
+            std::mem::replace(&mut self.runtime.reference_policy, ReferencePolicy::Lazy);
+        let skip_script_bodies =
+            std::mem::replace(&mut self.runtime.load_options.skip_script_bodies, true);
+
+        let obj = construct_detached(
+            &self.linker,
+            kind,
+            ExportIndex::from_raw(index as i32 + 1),
+            export,
+        );
+        let (result, annotations) = self.reader.annotate_scoped(|reader| {
+            reader.seek(SeekFrom::Start(export.serial_offset()))?;
+            reader.begin_object_annotation(full_name, kind.as_str(), export.serial_offset());
+            let result = if declaration_only {
+                let mut obj = obj.borrow_mut();
+                let class = obj
+                    .as_any_mut()
+                    .downcast_mut::<Class>()
+                    .expect("object was constructed as a class");
+                class.deserialize_declaration::<E, _>(&mut self.runtime, &self.linker, reader)
+            } else {
+                deserialize_object::<E, _>(&mut self.runtime, Rc::clone(&obj), &self.linker, reader)
+            };
+            reader.end_object_annotation();
+
+            result
+        });
+
+        self.runtime.reference_policy = reference_policy;
+        self.runtime.load_options.skip_script_bodies = skip_script_bodies;
+        result?;
+
+        // Loading the export's children may have loaded it again, so use the detached
+        // object's annotations which finished last
+        let mut fields = annotations
+            .into_iter()
+            .rfind(|annotations| annotations.full_name == full_name)
+            .map(|annotations| annotations.fields)
+            .unwrap_or_default();
+        for field in &mut fields {
+            field.offset -= export.serial_offset();
+        }
+
+        Ok((obj, fields))
+    }
+
+    /// The size `script` takes up once loaded, as stored in a struct's script size.
+    pub(crate) fn script_memory_size(&mut self, script: &[u8]) -> io::Result<u32> {
+        let size = script::script_memory_size::<E>(&mut self.runtime, &self.linker, script)?;
+
+        Ok(size as u32)
+    }
+
+    /// Reads the string held by every `Const` and `TextBuffer` export, and where it's stored
+    /// in the export's data.
+    fn string_fields(&mut self) -> io::Result<Vec<StringField>> {
+        let targets = {
+            let linker = self.linker.borrow();
+            linker
+                .package
+                .exports
+                .iter()
+                .enumerate()
+                .filter(|(_, export)| export.serial_size() > 0)
+                .filter_map(|(i, export)| {
+                    let kind = match export.class_name(&linker) {
+                        class if class == UObjectKind::Const.as_str() => StringKind::Const,
+                        class if class == UObjectKind::TextBuffer.as_str() => {
+                            StringKind::TextBuffer
+                        }
+                        _ => return None,
+                    };
+
+                    Some((i, kind, export.clone(), export.full_name(&linker)))
+                })
+                .collect::<Vec<_>>()
+        };
+        for (_, _, export, full_name) in &targets {
+            export.checked_serial_range(full_name, &mut self.reader)?;
+        }
+
+        // Strings don't depend on the objects these exports refer to, so don't load them
+        let reference_policy =
+            std::mem::replace(&mut self.runtime.reference_policy, ReferencePolicy::Lazy);
+
+        let (values, annotations) = self.reader.annotate_scoped(|reader| {
+            let mut values = Vec::with_capacity(targets.len());
+            for (i, kind, export, full_name) in &targets {
+                let (object_kind, field_name) = match kind {
+                    StringKind::Const => (UObjectKind::Const, "value"),
+                    _ => (UObjectKind::TextBuffer, "text"),
+                };
+
+                let obj = construct_detached(
+                    &self.linker,
+                    object_kind,
+                    ExportIndex::from_raw(*i as i32 + 1),
+                    export,
+                );
+
+                reader.seek(SeekFrom::Start(export.serial_offset()))?;
+                reader.begin_object_annotation(
+                    full_name,
+                    object_kind.as_str(),
+                    export.serial_offset(),
+                );
+                let result = deserialize_object::<E, _>(
+                    &mut self.runtime,
+                    Rc::clone(&obj),
+                    &self.linker,
+                    reader,
+                );
+                reader.end_object_annotation();
+                result?;
+
+                let obj = obj.borrow();
+                let value = match kind {
+                    StringKind::Const => obj
+                        .as_any()
+                        .downcast_ref::<Const>()
+                        .expect("failed to cast object to Const")
+                        .value
+                        .clone(),
+                    _ => obj
+                        .as_any()
+                        .downcast_ref::<TextBuffer>()
+                        .expect("failed to cast object to TextBuffer")
+                        .text(reader)?
+                        .into_owned(),
+                };
+
+                values.push((field_name, value));
+            }
+
+            io::Result::Ok(values)
+        });
+
+        self.runtime.reference_policy = reference_policy;
+        let values = values?;
+
+        let mut fields = Vec::with_capacity(targets.len());
+        for ((i, kind, export, full_name), (field_name, value)) in targets.into_iter().zip(values) {
+            let Some(field) = annotations
+                .iter()
+                .find(|annotations| annotations.full_name == full_name)
+                .and_then(|annotations| {
+                    annotations
+                        .fields
+                        .iter()
+                        .rfind(|field| field.name == field_name)
+                })
+            else {
+                return Err(io::Error::other(format!(
+                    "{full_name} was read without recording its {field_name}"
+                )));
+            };
+
+            let start = (field.offset - export.serial_offset()) as usize;
+            fields.push(StringField {
+                kind,
+                export: i,
+                full_name,
+                value,
+                range: start..start + field.len as usize,
+            });
+        }
+
+        Ok(fields)
+    }
+}
+
+/// File name [`Package::carve_exports`] writes the export at `index` to.
+fn carved_file_name(index: usize, name: &str, class: &str) -> String {
+    format!(
+        "{index}_{}_{}.bin",
+        sanitize_file_name_part(name),
+        sanitize_file_name_part(class)
+    )
+}
+
+/// Replaces everything but ASCII alphanumerics, `_` and `-` in an object name so that it can
+/// be used in a file name.
+fn sanitize_file_name_part(part: &str) -> String {
+    part.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Constructs the object for `export` without loading its class or outer, so that it can be
+/// deserialized on its own.
+fn construct_detached(
+    linker: &RcLinker,
+    kind: UObjectKind,
+    export_index: ExportIndex,
+    export: &ObjectExport,
+) -> RcUnrealObject {
+    let obj = kind.construct(Rc::downgrade(linker), export_index);
+    {
+        let mut obj_inner = obj.borrow_mut();
+        let base = obj_inner.base_object_mut();
+        base.set_flags(ObjectFlags::from_bits_retain(export.object_flags));
+        base.set_name(linker.borrow().name(export.object_name));
+        base.set_concrete_obj(Rc::downgrade(&obj));
+    }
+
+    obj
+}
+
+/// Name of the object at the raw index `raw_index`, or `None` if it's null or out of bounds.
+fn raw_object_name(linker: &Linker, raw_index: i32) -> Option<String> {
+    let name = match raw_index {
+        0 => return None,
+        1.. => linker
+            .package
+            .exports
+            .get(normalize_index(raw_index))?
+            .object_name(linker),
+        _ => linker
+            .package
+            .imports
+            .get(normalize_index(raw_index))?
+            .object_name(linker),
+    };
+
+    Some(name.to_owned())
+}
+
+/// The raw object index a detached read found in the field `name`, or 0 if it didn't read
+/// the field. `data` is the export's data, which field offsets are relative to.
+fn field_object_index(data: &[u8], fields: &[FieldAnnotation], name: &str) -> io::Result<i32> {
+    let Some(field) = fields.iter().rfind(|field| field.name == name) else {
+        return Ok(0);
+    };
+
+    let start = field.offset as usize;
+    let end = start + field.len as usize;
+    let Some(mut bytes) = data.get(start..end) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("field {name} lies outside its export's data"),
+        ));
+    };
+
+    read_packed_int(&mut bytes)
+}
+
+/// A translatable string within an export's data.
+struct StringField {
+    kind: StringKind,
+    /// Index of the export in the export table
+    export: usize,
+    full_name: String,
+    value: String,
+    /// Where the serialized string sits within the export's data
+    range: Range<usize>,
+}
+
+impl<E> Package<E, BufReader<File>>
+where
+    E: ByteOrder,
+{
+    /// Opens the package file at `path`. The package is named after the file, so
+    /// `Engine.u` is loaded as `Engine`.
+    pub fn open_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        Self::open(
+            Self::file_package_name(path)?,
+            BufReader::new(File::open(path)?),
+        )
+    }
+
+    /// Like [`Package::open_file`], but reads the package using `profile`.
+    pub fn open_file_with_profile(
+        path: impl AsRef<Path>,
+        profile: FormatProfile,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        Self::open_with_profile(
+            Self::file_package_name(path)?,
+            BufReader::new(File::open(path)?),
+            profile,
+        )
+    }
+
+    fn file_package_name(path: &Path) -> io::Result<String> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} has no file name", path.display()),
+                )
+            })?;
+
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, io::Cursor};
+
+    use byteorder::{BigEndian, LittleEndian};
+
+    use super::*;
+    use crate::object::{
+        WeakUnrealObject,
+        builtins::{ClassFlags, TextData},
+    };
+    use crate::test_support::{DataBuilder, PackageBuilder};
+    use crate::warnings::WarningKind;
+
+    /// A package holding a single text buffer, which also imports a texture from a package
+    /// that isn't available.
+    fn package_file() -> Vec<u8> {
+        let mut builder = PackageBuilder::new();
+
+        let engine = builder.import("Core", "Package", 0, "Engine");
+        builder.import("Core", "Class", engine, "Texture");
+
+        let text_buffer = builder.core_class("TextBuffer");
+        let data = DataBuilder::object().u32(0).u32(0).string("hi").build();
+        builder.export(text_buffer, 0, "ScriptText", data);
+
+        builder.build()
+    }
+
+    #[test]
+    fn loads_standalone_package() {
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(package_file())).unwrap();
+
+        let contents = package.load_objects().unwrap();
+        assert_eq!(contents.name(), "MyPackage");
+
+        let objects = contents.objects();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].0, "MyPackage.ScriptText");
+
+        let obj = package.load_object("scripttext").unwrap().unwrap();
+        assert!(Rc::ptr_eq(&obj, &objects[0].1));
+        // The text is left in the package until it's asked for
+        assert!(matches!(
+            obj.borrow()
+                .as_any()
+                .downcast_ref::<TextBuffer>()
+                .unwrap()
+                .text,
+            TextData::Deferred { len: 4, .. }
+        ));
+        assert_eq!(package.script_text(&obj).unwrap().as_deref(), Some("hi"));
+        assert!(package.load_object("Missing").unwrap().is_none());
+
+        // Engine isn't available, so its objects are placeholders rather than being read from
+        // this package's stream
+        let texture = package
+            .runtime
+            .load_object_by_full_name::<LittleEndian, _>(
+                "Engine.Texture",
+                LoadKind::Load,
+                &mut package.reader,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(texture.borrow().base_object().is_intrinsic());
+        assert!(package.runtime.linker("Engine").is_none());
+    }
+
+    #[test]
+    fn annotates_object_reads() {
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(package_file())).unwrap();
+
+        package.set_annotate_reads(true);
+        package.load_objects().unwrap();
+
+        let annotations = package.take_annotations();
+        assert_eq!(annotations.len(), 1);
+
+        let text_buffer = &annotations[0];
+        assert_eq!(text_buffer.full_name, "MyPackage.ScriptText");
+        assert_eq!(text_buffer.class_name, "TextBuffer");
+        assert_eq!(text_buffer.data.len(), 13);
+
+        let fields = text_buffer
+            .fields
+            .iter()
+            .map(|field| {
+                (
+                    field.offset - text_buffer.serial_offset,
+                    field.len,
+                    field.name,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                (0, 1, "name"),
+                (1, 4, "position"),
+                (5, 4, "top"),
+                (9, 4, "text")
+            ]
+        );
+        assert_eq!(text_buffer.fields[3].value, "2 bytes");
+
+        assert!(text_buffer.to_string().contains("text = 2 bytes"));
+        assert!(package.take_annotations().is_empty());
+    }
+
+    #[test]
+    fn extracts_script_sources() {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let text_buffer = builder.core_class("TextBuffer");
+        let owner = builder.export(
+            const_class,
+            0,
+            "../Owner",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+        for (name, text) in [("ScriptText", "script"), ("CppText", "cpp")] {
+            let data = DataBuilder::object().u32(0).u32(0).string(text).build();
+            builder.export(text_buffer, owner, name, data);
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let sources = package.extract_script_sources().unwrap();
+        let files = sources
+            .iter()
+            .map(|source| (source.file_name(), source.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            [
+                ("___Owner.uc".to_owned(), "script"),
+                ("___Owner.CppText.txt".to_owned(), "cpp")
+            ]
+        );
+
+        // A text buffer whose outer isn't in the export table
+        let data = DataBuilder::object().u32(0).u32(0).string("lost").build();
+        builder.export(text_buffer, 99, "ScriptText", data);
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let err = package.extract_script_sources().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_negative_serial_sizes() {
+        let mut builder = PackageBuilder::new();
+        let text_buffer = builder.core_class("TextBuffer");
+        let data = DataBuilder::object().u32(0).u32(0).string("text").build();
+        builder.export(text_buffer, 0, "ScriptText", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.linker.borrow_mut().package.exports[0].serial_size = -1;
+
+        let err = package.extract_script_sources().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = package.localization_table().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = package.resave(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = package.cache_exports(None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn applies_translations() {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        let text_buffer = builder.core_class("TextBuffer");
+        let greeting = builder.export(
+            const_class,
+            0,
+            "Greeting",
+            DataBuilder::field(0, 0).string("Hello").build(),
+        );
+        builder.export(
+            text_buffer,
+            greeting,
+            "ScriptText",
+            DataBuilder::object().u32(0).u32(0).string("hi").build(),
+        );
+
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(builder.build())).unwrap();
+        let mut table = package.localization_table().unwrap();
+
+        let greeting = table.get(StringKind::Const, "MyPackage.Greeting").unwrap();
+        assert_eq!(greeting.source, "Hello");
+        let script = table
+            .get(StringKind::TextBuffer, "MyPackage.Greeting.ScriptText")
+            .unwrap();
+        assert_eq!(script.source, "hi");
+
+        let translate = |table: &mut LocalizationTable, kind, source: &str, translation: &str| {
+            let entry = table
+                .entries
+                .iter_mut()
+                .find(|entry| entry.kind == kind && entry.source == source)
+                .unwrap();
+            entry.translation = translation.to_owned();
+        };
+        translate(
+            &mut table,
+            StringKind::Const,
+            "Hello",
+            "Bonjour tout le monde",
+        );
+        translate(&mut table, StringKind::TextBuffer, "hi", "salut");
+        translate(&mut table, StringKind::Name, "Greeting", "Salutation");
+
+        let mut out = Vec::new();
+        package.write_localized(&table, &mut out).unwrap();
+
+        let mut localized =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(out)).unwrap();
+        let localized_table = localized.localization_table().unwrap();
+        assert_eq!(
+            localized_table
+                .get(StringKind::Const, "MyPackage.Salutation")
+                .unwrap()
+                .source,
+            "Bonjour tout le monde"
+        );
+        assert_eq!(
+            localized_table
+                .get(StringKind::TextBuffer, "MyPackage.Salutation.ScriptText")
+                .unwrap()
+                .source,
+            "salut"
+        );
+
+        // Renaming a name onto another is refused
+        let mut table = package.localization_table().unwrap();
+        translate(&mut table, StringKind::Name, "Greeting", "scripttext");
+        let err = package.write_localized(&table, io::sink()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn runs_post_load_hooks_after_dependencies() {
+        let mut builder = PackageBuilder::new();
+        let struct_class = builder.core_class("Struct");
+        let const_class = builder.core_class("Const");
+        let friendly_name = builder.name("Holder");
+        // The struct is exported after its child so that it finishes loading last
+        let value = builder.export(
+            const_class,
+            2,
+            "Value",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+        let data = DataBuilder::field(0, 0)
+            // script_text, children
+            .packed(0)
+            .packed(value)
+            .packed(friendly_name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .build();
+        builder.export(struct_class, 0, "Holder", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(builder.build())).unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        for (kind, label) in [(UObjectKind::Field, "field"), (UObjectKind::Const, "const")] {
+            let seen = Rc::clone(&seen);
+            package.add_post_load_hook(
+                kind,
+                Box::new(move |obj: &RcUnrealObject| {
+                    assert!(!obj.borrow().base_object().needs_load());
+                    let name = obj.borrow().base_object().name().to_owned();
+                    seen.borrow_mut().push(format!("{label} {name}"));
+                    Ok(())
+                }),
+            );
+        }
+
+        let objects = package.load_objects().unwrap().objects();
+        assert!(
+            objects
+                .iter()
+                .all(|(_, obj)| obj.borrow().base_object().is_fully_loaded())
+        );
+        // The outer is post-loaded first even though its child was deserialized first
+        assert_eq!(
+            *seen.borrow(),
+            ["field Holder", "field Value", "const Value"]
+        );
+
+        // Objects are only post-loaded once
+        package.load_objects().unwrap();
+        assert_eq!(seen.borrow().len(), 3);
+    }
+
+    #[test]
+    fn serial_size_mismatches_fail_unless_lenient() {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        // Two bytes short of what a const reads, so reading it runs into the next export
+        let mut short = DataBuilder::field(0, 0).string("12345").build();
+        short.truncate(short.len() - 2);
+        builder.export(const_class, 0, "Short", short);
+        builder.export(
+            const_class,
+            0,
+            "Next",
+            DataBuilder::field(0, 0).string("2").build(),
+        );
+        // Two bytes more than a const reads
+        let mut long = DataBuilder::field(0, 0).string("3").build();
+        long.extend([0, 0]);
+        builder.export(const_class, 0, "Long", long);
+        let data = builder.build();
+
+        for name in ["Short", "Long"] {
+            let mut package =
+                Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+            let err = package.load_object(name).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            assert!(package.serial_size_mismatches().is_empty());
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+        package.set_load_options(LoadOptions {
+            lenient: true,
+            ..Default::default()
+        });
+        package.load_objects().unwrap();
+
+        let mismatches = package
+            .serial_size_mismatches()
+            .iter()
+            .map(|mismatch| {
+                (
+                    mismatch.object_name.as_str(),
+                    mismatch.actual as isize - mismatch.expected as isize,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(mismatches, [("Test.Short", 2), ("Test.Long", -2)]);
+        // The stream was realigned after Short, so Next loaded from the right place
+        let warnings = package.warnings();
+        assert!(
+            warnings
+                .iter()
+                .all(|warning| warning.kind == WarningKind::SerialSizeMismatch)
+        );
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn records_load_report() {
+        let mut builder = PackageBuilder::new();
+        let const_class = builder.core_class("Const");
+        builder.export(
+            const_class,
+            0,
+            "Good",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+        // Two bytes more than a const reads
+        let mut padded = DataBuilder::field(0, 0).string("2").build();
+        padded.extend([0, 0]);
+        builder.export(const_class, 0, "Padded", padded);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(builder.build())).unwrap();
+        assert!(package.load_report().is_none());
+
+        package.set_load_options(LoadOptions {
+            lenient: true,
+            ..Default::default()
+        });
+        package.set_record_load_report(true);
+        package.load_objects().unwrap();
+
+        let report = package.load_report().unwrap();
+        let names = report
+            .objects
+            .iter()
+            .map(|object| object.full_name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["MyPackage.Good", "MyPackage.Padded"]);
+
+        let padded = &report.objects[1];
+        assert_eq!(padded.class_name, "Const");
+        assert_eq!(padded.export_index, 1);
+        assert_eq!(padded.bytes_read, package.export_data(1).unwrap().len() - 2);
+        // Its class and outer package
+        assert_eq!(padded.dependencies, 2);
+        assert_eq!(report.with_warnings().count(), 1);
+        assert!(padded.warnings[0].contains("does not match expected"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["objects"][1]["full_name"], "MyPackage.Padded");
+    }
+
+    #[test]
+    fn rejects_other_file_kinds() {
+        let data = PackageBuilder::new().build();
+
+        let err = Package::<BigEndian, _>::open("MyPackage", Cursor::new(&data))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Package(Little)"));
+
+        let err = Package::<LittleEndian, _>::open("MyPackage", Cursor::new(&data[4..]))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Unknown"));
+    }
+
+    #[test]
+    fn drops_object_graphs() {
+        // A struct whose child constant points back at it as its outer
+        let mut builder = PackageBuilder::new();
+        let struct_class = builder.core_class("Struct");
+        let const_class = builder.core_class("Const");
+        let friendly_name = builder.name("Holder");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children
+            .packed(0)
+            .packed(2)
+            .packed(friendly_name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .build();
+        let holder = builder.export(struct_class, 0, "Holder", data);
+        builder.export(
+            const_class,
+            holder,
+            "Value",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(builder.build())).unwrap();
+        let objects = package.load_objects().unwrap().objects();
+        assert_eq!(objects.len(), 2);
+
+        let value = package.load_object("Holder.Value").unwrap().unwrap();
+        let outer = value.borrow().base_object().outer_object().unwrap();
+        assert!(Rc::ptr_eq(&outer, &objects[0].1) || Rc::ptr_eq(&outer, &objects[1].1));
+        drop(outer);
+
+        let weak = objects
+            .iter()
+            .map(|(_, obj)| Rc::downgrade(obj))
+            .chain([Rc::downgrade(&value)])
+            .collect::<Vec<WeakUnrealObject>>();
+        drop((objects, value, package));
+
+        assert!(weak.iter().all(|obj| obj.strong_count() == 0));
+    }
+
+    #[test]
+    fn reads_export_data() {
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(package_file())).unwrap();
+
+        let data = package.export_data(0).unwrap();
+        assert_eq!(
+            data,
+            DataBuilder::object().u32(0).u32(0).string("hi").build()
+        );
+        assert_eq!(
+            package.export_data(1).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        assert_eq!(
+            carved_file_name(3, "ScriptText", "TextBuffer"),
+            "3_ScriptText_TextBuffer.bin"
+        );
+        assert_eq!(
+            carved_file_name(12, "../Foo Bar", "Core:Class"),
+            "12____Foo_Bar_Core_Class.bin"
+        );
+    }
+
+    #[test]
+    fn gets_typed_objects() {
+        let mut builder = PackageBuilder::new();
+
+        // The property is declared in the function, which is the next export
+        let int_property = builder.core_class("IntProperty");
+        let data = DataBuilder::ue2_property(0, 0, 0).build();
+        let height = builder.export(int_property, 2, "Height", data);
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Jump");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name
+            .packed(0)
+            .packed(height)
+            .packed(name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Jump", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        assert!(package.get_class("Test.Jump").is_none());
+        assert!(package.get_function("Test.Missing").is_none());
+
+        let jump = package.get_function("test.jump").unwrap();
+        assert_eq!(jump.borrow().inative(), 0);
+
+        let jump = package.get_struct("Test.Jump").unwrap();
+        let properties = jump.borrow().properties().collect::<Vec<_>>();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].borrow().info().name, "Height");
+        assert_eq!(jump.borrow().functions().count(), 0);
+    }
+
+    #[test]
+    fn iterates_loaded_objects() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        let int_property = builder.core_class("IntProperty");
+        let data = DataBuilder::ue2_property(0, 0, 0).build();
+        builder.export(int_property, 0, "Health", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        let objects = package
+            .iter_objects()
+            .map(|(package, index, obj)| {
+                (package, index, obj.borrow().base_object().name().to_owned())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            objects,
+            [
+                ("Test".to_owned(), 0, "MaxCount".to_owned()),
+                ("Test".to_owned(), 1, "Health".to_owned())
+            ]
+        );
+
+        let properties = package
+            .iter_objects_of_kind(UObjectKind::Property)
+            .collect::<Vec<_>>();
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].1, 1);
+
+        assert_eq!(
+            package.iter_objects_with_flags(ObjectFlags::NATIVE).count(),
+            0
+        );
+        properties[0]
+            .2
+            .borrow_mut()
+            .base_object_mut()
+            .set_flags(ObjectFlags::NATIVE | ObjectFlags::PUBLIC);
+        let native = package
+            .iter_objects_with_flags(ObjectFlags::NATIVE)
+            .collect::<Vec<_>>();
+        assert_eq!(native.len(), 1);
+        assert!(Rc::ptr_eq(&native[0].2, &properties[0].2));
+    }
+
+    #[test]
+    fn rejects_exports_past_the_end() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        // The constant's data is last, so it's cut short
+        let mut file = builder.build();
+        file.truncate(file.len() - 2);
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(file)).unwrap();
+        let err = package.export_data(0).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Test.MaxCount"), "{err}");
+
+        let Err(err) = package.load_objects() else {
+            panic!("loaded a truncated export");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("past the end"), "{err}");
+    }
+
+    #[test]
+    fn collects_script_constants() {
+        use crate::object::internal::{
+            fname::FName,
+            script::{Callee, Const, Expr, ScriptObject},
+        };
+
+        let mut builder = PackageBuilder::new();
+        let function = builder.core_class("Function");
+        let engine = builder.import("Core", "Package", 0, "Engine");
+        let sound = builder.import("Engine", "Sound", engine, "Beep");
+        let tick_name = builder.name("Tick");
+        let fire = builder.name("Fire");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(0)
+            .packed(tick_name)
+            .u32(0)
+            .u32(0)
+            .u32(1)
+            // EndOfScript
+            .u8(0x47)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Tick", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        assert!(package.script_constants().unwrap().is_empty());
+
+        let script = vec![
+            Expr::Call {
+                func: Callee::Virtual(FName::from_raw(fire)),
+                args: vec![
+                    Expr::Const(Const::Name(FName::from_raw(fire))),
+                    Expr::Const(Const::Object(ScriptObject {
+                        raw_index: sound,
+                        object: None,
+                    })),
+                ],
+                debug_info: None,
+            },
+            Expr::Return(Box::new(Expr::Const(Const::String("Hi".to_owned())))),
+            Expr::EndOfScript,
+        ];
+        let mut editor = package.edit();
+        editor.replace_function_script("Tick", script).unwrap();
+        let mut patched = Vec::new();
+        editor.write(&mut patched).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(patched)).unwrap();
+        let scripts = package.script_constants().unwrap();
+
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].owner, "Test.Tick");
+        let constants = scripts[0]
+            .constants
+            .iter()
+            .map(|constant| (constant.offset, constant.value.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constants,
+            [
+                (0, "'Fire'".to_owned()),
+                (0, "Engine.Beep".to_owned()),
+                (16, "\"Hi\"".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_script_referencers() {
+        use crate::{
+            graph::DependencyKind,
+            object::internal::script::{Const, Expr, ScriptObject},
+        };
+
+        let mut builder = PackageBuilder::new();
+        let function = builder.core_class("Function");
+        let tick_name = builder.name("Tick");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(0)
+            .packed(tick_name)
+            .u32(0)
+            .u32(0)
+            .u32(1)
+            // EndOfScript
+            .u8(0x47)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        let tick = builder.export(function, 0, "Tick", data);
+        let text_buffer = builder.core_class("TextBuffer");
+        let data = DataBuilder::object().u32(0).u32(0).string("hi").build();
+        builder.export(text_buffer, 0, "Notes", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let script = vec![
+            Expr::Return(Box::new(Expr::Const(Const::Object(ScriptObject {
+                raw_index: tick + 1,
+                object: None,
+            })))),
+            Expr::EndOfScript,
+        ];
+        let mut editor = package.edit();
+        editor.replace_function_script("Tick", script).unwrap();
+        let mut patched = Vec::new();
+        editor.write(&mut patched).unwrap();
+
+        let mut package = Package::<LittleEndian, _>::open("Test", Cursor::new(patched)).unwrap();
+        assert!(package.referencers_of("Test.Notes").is_empty());
+
+        package.load_objects().unwrap();
+        assert_eq!(
+            package.referencers_of("Test.Notes"),
+            [Referencer {
+                from: "Test.Tick".to_owned(),
+                kind: DependencyKind::ScriptReference,
+            }]
+        );
+    }
+
+    #[test]
+    fn decodes_strings_with_the_profile_encoding() {
+        use crate::profile::StringEncoding;
+
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        // "café" in Latin-1, with its null terminator
+        let data = DataBuilder::field(0, 0)
+            .packed(5)
+            .u8(b'c')
+            .u8(b'a')
+            .u8(b'f')
+            .u8(0xE9)
+            .u8(0)
+            .build();
+        builder.export(constant, 0, "Drink", data);
+        let data = builder.build();
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+        let err = package.load_object("Drink").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut profile = FormatProfile::stock_ue2();
+        profile.quirks.string_encoding = StringEncoding::Latin1;
+        let mut package =
+            Package::<LittleEndian, _>::open_with_profile("Test", Cursor::new(data), profile)
+                .unwrap();
+        let drink = package.load_object("Drink").unwrap().unwrap();
+        let drink = drink.borrow();
+        let drink = drink.as_any().downcast_ref::<Const>().unwrap();
+        assert_eq!(drink.value, "café");
+    }
+
+    #[test]
+    fn generates_class_headers() {
+        let mut builder = PackageBuilder::new();
+        let class = builder.core_class("Class");
+        let int_property = builder.core_class("IntProperty");
+        let float_property = builder.core_class("FloatProperty");
+        let object_property = builder.core_class("ObjectProperty");
+        let function = builder.core_class("Function");
+        let engine = builder.import("Core", "Package", 0, "Engine");
+        let actor = builder.import("Core", "Class", engine, "Actor");
+        let class_name = builder.name("MyActor");
+        let display = builder.name("Display");
+        let tick_name = builder.name("Tick");
+        let controller = builder.import("Core", "Class", engine, "Controller");
+        let user = builder.name("User");
+
+        // Classes have no tagged properties before their fields
+        let data = DataBuilder::default()
+            // super_field, next
+            .packed(0)
+            .packed(0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(2)
+            .packed(class_name)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // probe_mask, ignore_mask, label_table_offset, state_flags
+            .u64(0)
+            .u64(u64::MAX)
+            .u16(0xFFFF)
+            .u32(0)
+            .ue2_class_declaration(ClassFlags::CONFIG.bits(), controller, user)
+            .build();
+        let my_actor = builder.export(class, 0, "MyActor", data);
+        builder.set_super(my_actor, actor);
+
+        let data = DataBuilder::ue2_property(3, PropertyFlags::EDIT.bits(), display).build();
+        builder.export(int_property, my_actor, "Count", data);
+        let data = DataBuilder::ue2_property(4, PropertyFlags::CONFIG.bits(), 0)
+            .packed(actor)
+            .build();
+        builder.export(object_property, my_actor, "Target", data);
+
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name, line, text_pos, script_size
+            .packed(0)
+            .packed(5)
+            .packed(tick_name)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // inative, operator_precedence, function_flags (final, defined)
+            .u16(0)
+            .u8(0)
+            .u32(0x3)
+            .build();
+        let tick = builder.export(function, my_actor, "Tick", data);
+        let data = DataBuilder::ue2_property(6, PropertyFlags::PARM.bits(), 0).build();
+        builder.export(float_property, tick, "DeltaTime", data);
+        let flags = PropertyFlags::PARM | PropertyFlags::OUT_PARM | PropertyFlags::RETURN_PARM;
+        let data = DataBuilder::ue2_property(0, flags.bits(), 0).build();
+        builder.export(int_property, tick, "ReturnValue", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let headers = package.class_headers().unwrap();
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(
+            headers[0].to_string(),
+            "class MyActor extends Actor within Controller config(User);\n\
+             \n\
+             var(Display) int Count;\n\
+             var config Actor Target;\n\
+             \n\
+             final function int Tick(float DeltaTime);\n"
+        );
+
+        // Default properties can't be read, so loading the class itself fails rather than
+        // panicking
+        let err = package.load_object("MyActor").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn leaves_stripped_exports_empty() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        builder.export(constant, 0, "Stripped", Vec::new());
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let contents = package.load_objects().unwrap();
+
+        let stripped = contents.object("Stripped").unwrap();
+        let stripped = stripped.borrow();
+        assert!(stripped.base_object().was_stripped());
+        assert!(stripped.base_object().is_fully_loaded());
+        assert_eq!(stripped.as_any().downcast_ref::<Const>().unwrap().value, "");
+
+        let max_count = contents.object("MaxCount").unwrap();
+        assert!(!max_count.borrow().base_object().was_stripped());
+    }
+
+    #[test]
+    fn checks_script_class_hierarchy() {
+        let mut builder = PackageBuilder::new();
+        let class = builder.core_class("Class");
+        let base_pawn = builder.export(class, 0, "BasePawn", Vec::new());
+        let my_pawn = builder.export(class, 0, "MyPawn", Vec::new());
+        builder.set_super(my_pawn, base_pawn);
+        let data = DataBuilder::object().build();
+        builder.export(my_pawn, 0, "MyPawn0", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let pawn = package.load_object("MyPawn0").unwrap().unwrap();
+        let pawn = pawn.borrow();
+
+        assert_eq!(pawn.kind(), UObjectKind::Object);
+        let class = pawn.base_object().class_ref().unwrap();
+        assert_eq!(&*class.name(), "MyPawn");
+        assert_eq!(
+            class
+                .hierarchy()
+                .map(|class| class.name())
+                .collect::<Vec<_>>(),
+            [Rc::from("MyPawn"), Rc::from("BasePawn")]
+        );
+        assert!(pawn.is_a_named("mypawn"));
+        assert!(pawn.is_a_named("BasePawn"));
+        assert!(pawn.is_a_named("Object"));
+        assert!(!pawn.is_a_named("Actor"));
+        assert!(!pawn.is_a_named("Class"));
+    }
+
+    #[test]
+    fn checks_qualified_and_loaded_classes() {
+        let mut builder = PackageBuilder::new();
+        let class = builder.core_class("Class");
+        let actor = builder.export(class, 0, "Actor", Vec::new());
+        let pawn = builder.export(class, 0, "Pawn", Vec::new());
+        builder.set_super(pawn, actor);
+        builder.export(pawn, 0, "Pawn0", DataBuilder::object().build());
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let pawn = package.load_object("Pawn0").unwrap().unwrap();
+        let pawn = pawn.borrow();
+
+        assert!(pawn.is_a_named("Test.Actor"));
+        assert!(pawn.is_a_named("core.object"));
+        assert!(!pawn.is_a_named("Engine.Actor"));
+        assert!(!pawn.is_a_named("Engine.Object"));
+
+        let classes = pawn
+            .base_object()
+            .class_ref()
+            .unwrap()
+            .hierarchy()
+            .collect::<Vec<_>>();
+        assert_eq!(classes[1].full_name(), "Test.Actor");
+        assert!(classes.iter().all(|class| pawn.is_a_class(class)));
+
+        // The class of a class is Core.Class, which the pawn isn't
+        let meta_class = classes[1]
+            .object()
+            .borrow()
+            .base_object()
+            .class_ref()
+            .cloned();
+        assert!(!pawn.is_a_class(&meta_class.unwrap()));
+    }
+
+    #[test]
+    fn collects_load_warnings() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.set_load_options(LoadOptions {
+            max_bytes: Some(0),
+            ..Default::default()
+        });
+        let streamed = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&streamed);
+        package.set_warning_callback(move |warning| sink.borrow_mut().push(warning.clone()));
+        package.load_objects().unwrap();
+
+        let warnings = package.warnings().to_vec();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::SkippedExport);
+        assert_eq!(warnings[0].object.as_deref(), Some("Test.MaxCount"));
+        assert_eq!(*streamed.borrow(), warnings);
+
+        assert_eq!(package.take_warnings(), warnings);
+        assert!(package.warnings().is_empty());
+    }
+
+    /// Full names of the objects deserialized since the load report was turned on.
+    fn deserialized_names(package: &Package<LittleEndian, Cursor<Vec<u8>>>) -> Vec<String> {
+        let report = package.load_report().unwrap();
+        report
+            .objects
+            .into_iter()
+            .map(|object| object.full_name)
+            .collect()
+    }
+
+    #[test]
+    fn max_depth_stops_nested_loads() {
+        let mut builder = PackageBuilder::new();
+        let struct_class = builder.core_class("Struct");
+        let base = builder.export(struct_class, 0, "Base", DataBuilder::ue2_struct(0).build());
+        let derived = builder.export(
+            struct_class,
+            0,
+            "Derived",
+            DataBuilder::ue2_struct(0).build(),
+        );
+        builder.set_super(derived, base);
+        let data = builder.build();
+
+        for (max_depth, expected) in [
+            (None, &["Test.Base", "Test.Derived"][..]),
+            (Some(1), &["Test.Derived"][..]),
+        ] {
+            let mut package =
+                Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+            package.set_load_options(LoadOptions {
+                max_depth,
+                ..Default::default()
+            });
+            package.set_record_load_report(true);
+            package.load_object("Derived").unwrap();
+
+            let mut names = deserialized_names(&package);
+            names.sort();
+            assert_eq!(names, expected);
+        }
+    }
+
+    #[test]
+    fn max_objects_stops_constructing_objects() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        for (name, value) in [("One", "1"), ("Two", "2")] {
+            let data = DataBuilder::field(0, 0).string(value).build();
+            builder.export(constant, 0, name, data);
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.set_load_options(LoadOptions {
+            max_objects: Some(1),
+            ..Default::default()
+        });
+        package.set_record_load_report(true);
+        package.load_objects().unwrap();
+
+        assert_eq!(deserialized_names(&package), ["Test.One"]);
+        // Two is still constructed so references to it can be held
+        let two = package.load_object("Two").unwrap().unwrap();
+        assert!(two.borrow().base_object().needs_load());
+    }
+
+    #[test]
+    fn max_bytes_stops_deserializing_exports() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let small = DataBuilder::field(0, 0).string("1").build();
+        let small_len = small.len() as u64;
+        builder.export(constant, 0, "Small", small);
+        let large = DataBuilder::field(0, 0).string("12345").build();
+        builder.export(constant, 0, "Large", large);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.set_load_options(LoadOptions {
+            max_bytes: Some(small_len + 1),
+            ..Default::default()
+        });
+        package.set_record_load_report(true);
+        package.load_objects().unwrap();
+
+        assert_eq!(deserialized_names(&package), ["Test.Small"]);
+        let warnings = package.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, WarningKind::SkippedExport);
+        assert_eq!(warnings[0].object.as_deref(), Some("Test.Large"));
+    }
+
+    #[test]
+    fn max_bytes_rejects_negative_serial_sizes() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        builder.export(
+            constant,
+            0,
+            "Negative",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.linker.borrow_mut().package.exports[0].serial_size = -1;
+        package.set_load_options(LoadOptions {
+            max_bytes: Some(1024),
+            ..Default::default()
+        });
+
+        let Err(err) = package.load_objects() else {
+            panic!("loaded an export with a negative size");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(package.warnings().is_empty());
+    }
+
+    #[test]
+    fn skip_script_bodies_leaves_script_references_unloaded() {
+        use crate::{Const, Expr, ScriptObject};
+
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        let target = builder.export(
+            constant,
+            0,
+            "Target",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+        let struct_class = builder.core_class("Struct");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name, line, text_pos
+            .packed(0)
+            .packed(0)
+            .packed(0)
+            .u32(0)
+            .u32(0)
+            // script_size, ObjectConst(Target), EndOfScript
+            .u32(6)
+            .u8(0x20)
+            .packed(target)
+            .u8(0x47)
+            .build();
+        builder.export(struct_class, 0, "Script", data);
+        let data = builder.build();
+
+        for skip_script_bodies in [false, true] {
+            let mut package =
+                Package::<LittleEndian, _>::open("Test", Cursor::new(data.clone())).unwrap();
+            package.set_load_options(LoadOptions {
+                skip_script_bodies,
+                ..Default::default()
+            });
+            package.load_object("Script").unwrap();
+
+            let script = package.get_struct("Test.Script").unwrap();
+            let script = script.borrow();
+            let Expr::Const(Const::Object(ScriptObject { raw_index, object })) =
+                &script.script()[0].expr
+            else {
+                panic!("expected an object constant, got {:?}", script.script()[0]);
+            };
+            assert_eq!(*raw_index, target);
+            assert_eq!(object.is_none(), skip_script_bodies);
+        }
+    }
+
+    #[test]
+    fn load_kinds_take_objects_different_distances() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        for name in ["Created", "Loaded", "FullyLoaded"] {
+            builder.export(
+                constant,
+                0,
+                name,
+                DataBuilder::field(0, 0).string("1").build(),
+            );
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let linker = &package.linker;
+        let reader = &mut package.reader;
+        let state = |obj: &RcUnrealObject| {
+            let obj = obj.borrow();
+            let base = obj.base_object();
+            (base.needs_load(), base.needs_post_load())
+        };
+        let (created, loaded) = package
+            .runtime
+            .load_batch(|runtime| {
+                let mut load = |index, kind| {
+                    runtime.load_object_by_export_index::<LittleEndian, _>(
+                        ExportIndex::from_raw(index),
+                        linker,
+                        kind,
+                        reader,
+                    )
+                };
+
+                let created = load(1, LoadKind::Create)?;
+                assert_eq!(state(&created), (true, true));
+
+                // Post-loading waits for the batch to finish
+                let loaded = load(2, LoadKind::Load)?;
+                assert_eq!(state(&loaded), (false, true));
+
+                // Unless the object is fully loaded, which post-loads everything pending
+                let fully_loaded = load(3, LoadKind::Full)?;
+                assert_eq!(state(&fully_loaded), (false, false));
+                assert_eq!(state(&loaded), (false, false));
+
+                Ok((created, loaded))
+            })
+            .unwrap();
+
+        assert_eq!(state(&created), (true, true));
+        assert!(loaded.borrow().base_object().is_fully_loaded());
+    }
+
+    #[test]
+    fn failed_observers_leave_objects_reloadable() {
+        use crate::observer::{LoadObserver, ObjEvent};
+        use std::io::Seek;
+
+        struct FailOnce(bool);
+
+        impl LoadObserver for FailOnce {
+            fn on_object_deserialized(&mut self, event: &ObjEvent) -> io::Result<()> {
+                if std::mem::replace(&mut self.0, false) {
+                    return Err(io::Error::other(format!("rejected {}", event.full_name)));
+                }
+                Ok(())
+            }
+        }
+
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        builder.export(
+            constant,
+            0,
+            "One",
+            DataBuilder::field(0, 0).string("1").build(),
+        );
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.add_observer(Box::new(FailOnce(true)));
+        let saved_pos = package.reader.stream_position().unwrap();
+
+        let load = |package: &mut Package<LittleEndian, _>| {
+            package
+                .runtime
+                .load_object_by_export_index::<LittleEndian, _>(
+                    ExportIndex::from_raw(1),
+                    &package.linker,
+                    LoadKind::Full,
+                    &mut package.reader,
+                )
+        };
+        let err = load(&mut package).unwrap_err();
+        assert_eq!(err.to_string(), "rejected Test.One");
+        assert!(package.runtime.objects_full_loading.is_empty());
+        assert_eq!(package.reader.stream_position().unwrap(), saved_pos);
+
+        let one = load(&mut package).unwrap();
+        assert!(one.borrow().base_object().is_fully_loaded());
+    }
+
+    #[test]
+    fn reloading_a_loaded_object_still_post_loads_early() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        for name in ["First", "Loaded", "Second"] {
+            builder.export(
+                constant,
+                0,
+                name,
+                DataBuilder::field(0, 0).string("1").build(),
+            );
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let linker = &package.linker;
+        let reader = &mut package.reader;
+        package
+            .runtime
+            .load_batch(|runtime| {
+                let mut load = |index, kind| {
+                    runtime.load_object_by_export_index::<LittleEndian, _>(
+                        ExportIndex::from_raw(index),
+                        linker,
+                        kind,
+                        reader,
+                    )
+                };
+
+                // The second load finds the object already deserialized
+                load(1, LoadKind::Full)?;
+                load(1, LoadKind::Full)?;
+
+                let loaded = load(2, LoadKind::Load)?;
+                assert!(loaded.borrow().base_object().needs_post_load());
+                load(3, LoadKind::Full)?;
+                assert!(loaded.borrow().base_object().is_fully_loaded());
+
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn resaves_byte_for_byte() {
+        let compact = package_file();
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(compact.clone())).unwrap();
+        let mut out = Vec::new();
+        package.resave(&mut out).unwrap();
+        assert_eq!(out, compact);
+
+        // Move the export's data away from the tables, leaving padding in between
+        let mut reader = crate::reader::PackageReader::new(Cursor::new(compact.as_slice()));
+        let mut raw = crate::de::read_package::<LittleEndian, _>(&mut reader).unwrap();
+        let export = &mut raw.exports[0];
+        let start = export.serial_offset as usize;
+        let data = compact[start..start + export.serial_size as usize].to_vec();
+        export.serial_offset += 0x20;
+        let mut padded = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut padded,
+            &mut raw,
+            &[vec![(0, data)]],
+            LayoutMode::PreserveLayout,
+        )
+        .unwrap();
+        assert_eq!(padded.len(), compact.len() + 0x20);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("MyPackage", Cursor::new(padded.clone())).unwrap();
+        let mut out = Vec::new();
+        package.resave(&mut out).unwrap();
+        assert_eq!(out, padded);
+    }
+}
@@ -0,0 +1,143 @@
+use std::io::{self, Read, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+
+/// Set in the first byte when the value is negative
+const SIGN_BIT: u8 = 0x80;
+/// Set in the first byte when another byte follows
+const FIRST_CONTINUE_BIT: u8 = 0x40;
+/// Set in the second through fourth bytes when another byte follows
+const CONTINUE_BIT: u8 = 0x80;
+
+/// A packed int is never longer than this. The last byte has no continuation bit.
+const MAX_PACKED_LEN: usize = 5;
+
+/// Reads a packed int (the engine's "compact index").
+///
+/// The first byte holds the sign, a continuation bit, and the low 6 bits of the magnitude.
+/// Up to three following bytes each hold 7 more bits and a continuation bit, and a fifth
+/// byte holds whatever bits remain.
+pub fn read_packed_int<R: Read + ?Sized>(reader: &mut R) -> io::Result<i32> {
+    let b0 = reader.read_u8()?;
+
+    let mut value = (b0 & (FIRST_CONTINUE_BIT - 1)) as u32;
+    let mut more = b0 & FIRST_CONTINUE_BIT != 0;
+    let mut shift = 6;
+
+    for len in 2..=MAX_PACKED_LEN {
+        if !more {
+            break;
+        }
+
+        let b = reader.read_u8()?;
+        if len == MAX_PACKED_LEN {
+            value |= (b as u32) << shift;
+            break;
+        }
+
+        value |= ((b & (CONTINUE_BIT - 1)) as u32) << shift;
+        more = b & CONTINUE_BIT != 0;
+        shift += 7;
+    }
+
+    // i32::MIN's magnitude doesn't fit in an i32, so negate with wrapping
+    let value = value as i32;
+    if b0 & SIGN_BIT != 0 {
+        Ok(value.wrapping_neg())
+    } else {
+        Ok(value)
+    }
+}
+
+/// Writes `value` as a packed int. See [`read_packed_int`] for the format.
+pub fn write_packed_int<W: Write + ?Sized>(writer: &mut W, value: i32) -> io::Result<()> {
+    let mut magnitude = value.unsigned_abs();
+
+    let mut b0 = (magnitude & (FIRST_CONTINUE_BIT - 1) as u32) as u8;
+    if value < 0 {
+        b0 |= SIGN_BIT;
+    }
+    magnitude >>= 6;
+    if magnitude != 0 {
+        b0 |= FIRST_CONTINUE_BIT;
+    }
+    writer.write_u8(b0)?;
+
+    let mut len = 1;
+    while magnitude != 0 {
+        len += 1;
+
+        let b = if len == MAX_PACKED_LEN {
+            magnitude as u8
+        } else {
+            let mut b = (magnitude & (CONTINUE_BIT - 1) as u32) as u8;
+            if magnitude >> 7 != 0 {
+                b |= CONTINUE_BIT;
+            }
+            b
+        };
+        writer.write_u8(b)?;
+
+        magnitude >>= 7;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(value: i32) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_packed_int(&mut out, value).unwrap();
+        out
+    }
+
+    fn assert_round_trips(value: i32) {
+        let encoded = encode(value);
+        assert!(encoded.len() <= MAX_PACKED_LEN, "{value} encoded too long");
+
+        let mut reader = encoded.as_slice();
+        assert_eq!(read_packed_int(&mut reader).unwrap(), value);
+        assert!(reader.is_empty(), "{value} left unread bytes");
+    }
+
+    #[test]
+    fn known_encodings() {
+        assert_eq!(encode(0), [0x00]);
+        assert_eq!(encode(0x3F), [0x3F]);
+        assert_eq!(encode(-1), [0x81]);
+        assert_eq!(encode(0x40), [0x40, 0x01]);
+        assert_eq!(encode(0x2000), [0x40, 0x80, 0x01]);
+        assert_eq!(encode(i32::MAX), [0x7F, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert_eq!(encode(i32::MIN), [0xC0, 0x80, 0x80, 0x80, 0x10]);
+    }
+
+    #[test]
+    fn round_trips_boundaries() {
+        for bits in [6, 13, 20, 27, 31] {
+            let edge = (1i64 << bits) as i32;
+            for value in [edge.wrapping_sub(1), edge, edge.wrapping_add(1)] {
+                assert_round_trips(value);
+                assert_round_trips(value.wrapping_neg());
+            }
+        }
+
+        assert_round_trips(i32::MIN);
+        assert_round_trips(i32::MAX);
+    }
+
+    #[test]
+    fn round_trips_full_range() {
+        // xorshift32, so that every run checks the same spread of values
+        let mut state = 0x9E37_79B9u32;
+        for _ in 0..200_000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+
+            assert_round_trips(state as i32);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/// Selects exports by their full name and class.
+///
+/// A pattern is a list of whitespace-separated terms which must all match:
+///
+/// - `class=<glob>` matches the export's class name, e.g. `class=Function`
+/// - anything else matches the export's full name, e.g. `Engine.*` or `*Weapon*`
+///
+/// Globs support `*` (any run of characters) and `?` (any single character) and ignore
+/// ASCII case. `:` is treated like `.` so that `Core.Object:*` matches everything inside
+/// `Core.Object`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportPattern {
+    paths: Vec<String>,
+    classes: Vec<String>,
+}
+
+impl ExportPattern {
+    pub fn new(pattern: &str) -> Self {
+        let mut parsed = ExportPattern::default();
+
+        for term in pattern.split_whitespace() {
+            match term.split_once('=') {
+                Some((key, class)) if key.eq_ignore_ascii_case("class") => {
+                    parsed.classes.push(class.to_owned())
+                }
+                _ => parsed.paths.push(term.replace(':', ".")),
+            }
+        }
+
+        parsed
+    }
+
+    pub fn matches(&self, full_name: &str, class_name: &str) -> bool {
+        self.paths.iter().all(|path| glob_match(path, full_name))
+            && self
+                .classes
+                .iter()
+                .all(|class| glob_match(class, class_name))
+    }
+}
+
+/// Matches `text` against `pattern`, ignoring ASCII case.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let text = text.as_bytes();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text position it's currently matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star, star_t)) => {
+                    backtrack = Some((star, star_t + 1));
+                    p = star + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("Engine.*", "Engine.Actor"));
+        assert!(glob_match("*weapon*", "Game.BaseWeapon.Fire"));
+        assert!(glob_match("Core.Obj?ct", "core.object"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+
+        assert!(!glob_match("Engine.*", "Core.Object"));
+        assert!(!glob_match("Core.Obj?ct", "Core.Objct"));
+        assert!(!glob_match("a*b", "aXbY"));
+    }
+
+    #[test]
+    fn patterns() {
+        let pattern = ExportPattern::new("Core.Object:* class=Func*");
+        assert!(pattern.matches("Core.Object.Tick", "Function"));
+        assert!(!pattern.matches("Core.Object.Tick", "IntProperty"));
+        assert!(!pattern.matches("Core.Class", "Function"));
+
+        assert!(ExportPattern::new("").matches("Anything", "Class"));
+    }
+}
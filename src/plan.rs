@@ -0,0 +1,157 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::ExportedData,
+    de::RawPackage,
+    graph::{Dependency, DependencyGraph, DependencyKind},
+};
+
+/// The order in which a linear file's packages and objects are loaded.
+///
+/// Loading an object loads everything it depends on, so `objects` only needs to name the
+/// objects the game asked for. Objects which were already loaded as a dependency are
+/// skipped.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// Package names in the order they're loaded
+    pub packages: Vec<String>,
+    /// Full names of the objects to load, in order
+    pub objects: Vec<String>,
+}
+
+impl Plan {
+    /// The load order recorded while the game loaded the linear file.
+    pub fn from_exported_data(metadata: &ExportedData) -> Self {
+        Plan {
+            packages: metadata.file_load_order.clone(),
+            objects: metadata.object_load_order.clone(),
+        }
+    }
+
+    /// Derives a load order from the import and export tables of `packages`, which are given
+    /// in file table order.
+    ///
+    /// Packages are ordered after the packages they import from, and each export after its
+    /// class, super struct, and outer. Where nothing constrains the order, or dependencies
+    /// form a cycle, file table and export table order is kept.
+    pub fn derive<'a>(packages: impl IntoIterator<Item = (&'a str, &'a RawPackage)>) -> Self {
+        let packages = packages.into_iter().collect::<Vec<_>>();
+
+        let mut graph = DependencyGraph::new();
+        for (name, package) in &packages {
+            graph.add_package(name, package);
+        }
+
+        // Paths in import tables don't necessarily use the same case as the exports they
+        // refer to
+        let package_names = packages
+            .iter()
+            .map(|(name, _)| (name.to_ascii_lowercase(), name.to_string()))
+            .collect::<HashMap<_, _>>();
+        let export_paths = packages
+            .iter()
+            .flat_map(|(name, package)| {
+                package
+                    .exports
+                    .iter()
+                    .map(|export| export.path(name, package))
+            })
+            .map(|path| (path.to_ascii_lowercase(), path))
+            .collect::<HashMap<_, _>>();
+
+        let mut plan = Plan::default();
+
+        let mut visited = HashSet::new();
+        for (name, _) in &packages {
+            visit(&graph, name, &mut visited, &mut plan.packages, &|dep| {
+                (dep.kind == DependencyKind::Import)
+                    .then(|| package_names.get(&dep.to.to_ascii_lowercase()))
+                    .flatten()
+            });
+        }
+
+        let mut visited = HashSet::new();
+        for name in &plan.packages {
+            let (_, package) = packages
+                .iter()
+                .find(|(package_name, _)| package_name == name)
+                .expect("planned package was not given");
+
+            for export in &package.exports {
+                let path = export.path(name, package);
+                visit(&graph, &path, &mut visited, &mut plan.objects, &|dep| {
+                    matches!(
+                        dep.kind,
+                        DependencyKind::Class | DependencyKind::Super | DependencyKind::Outer
+                    )
+                    .then(|| export_paths.get(&dep.to.to_ascii_lowercase()))
+                    .flatten()
+                });
+            }
+        }
+
+        plan
+    }
+}
+
+/// Appends `node` to `order` after every dependency `follow` maps to a node.
+fn visit<'g>(
+    graph: &DependencyGraph,
+    node: &str,
+    visited: &mut HashSet<String>,
+    order: &mut Vec<String>,
+    follow: &dyn Fn(&Dependency) -> Option<&'g String>,
+) {
+    // Marking the node before its dependencies are visited breaks cycles, e.g. `Core.Class`
+    // being an instance of itself
+    if !visited.insert(node.to_owned()) {
+        return;
+    }
+
+    for dep in graph.dependencies(node) {
+        if let Some(dep) = follow(dep) {
+            visit(graph, dep, visited, order, follow);
+        }
+    }
+
+    order.push(node.to_owned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{DataBuilder, PackageBuilder};
+
+    #[test]
+    fn derives_dependency_order() {
+        // Actor is exported after Pawn, which inherits from it
+        let mut engine = PackageBuilder::new();
+        let class = engine.core_class("Class");
+        let pawn = engine.export(class, 0, "Pawn", DataBuilder::object().build());
+        let actor = engine.export(class, 0, "Actor", DataBuilder::object().build());
+        let mut engine = engine.raw_package();
+        engine.exports[(pawn - 1) as usize].super_index = actor;
+
+        // The map is listed first in the file table but imports from Engine
+        let mut map = PackageBuilder::new();
+        let class = map.core_class("Class");
+        let group = map.export(class, 0, "Group", DataBuilder::object().build());
+        map.export(class, group, "Thing", DataBuilder::object().build());
+        let engine_import = map.import("Core", "Package", 0, "engine");
+        map.import("Core", "Class", engine_import, "Pawn");
+        let map = map.raw_package();
+
+        let plan = Plan::derive([("Map", &map), ("Engine", &engine)]);
+
+        assert_eq!(plan.packages, ["Engine", "Map"]);
+        assert_eq!(
+            plan.objects,
+            [
+                "Engine.Actor",
+                "Engine.Pawn",
+                "Map.Group",
+                "Map.Group.Thing"
+            ]
+        );
+    }
+}
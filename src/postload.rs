@@ -0,0 +1,82 @@
+use std::{collections::HashSet, io, rc::Rc};
+
+use crate::{
+    object::{RcUnrealObject, UObjectKind, builtins::Field},
+    runtime::RcUnrealObjPointer,
+};
+
+/// A step run on objects once they and everything loaded alongside them have been
+/// deserialized, like the engine's `PostLoad`.
+///
+/// Returning an error aborts the load which queued the object and the error is returned to
+/// the caller.
+pub trait PostLoadHook {
+    fn post_load(&mut self, obj: &RcUnrealObject) -> io::Result<()>;
+}
+
+impl<F> PostLoadHook for F
+where
+    F: FnMut(&RcUnrealObject) -> io::Result<()>,
+{
+    fn post_load(&mut self, obj: &RcUnrealObject) -> io::Result<()> {
+        self(obj)
+    }
+}
+
+/// Hooks registered for a class, along with the objects waiting for them to run.
+#[derive(Default)]
+pub(crate) struct PostLoadQueue {
+    /// Deserialized objects which still need post-loading, in the order they finished loading
+    pub(crate) pending: Vec<RcUnrealObject>,
+    /// Each hook runs on objects which are the kind it was registered for, or a subclass of it
+    pub(crate) hooks: Vec<(UObjectKind, Box<dyn PostLoadHook>)>,
+}
+
+impl PostLoadQueue {
+    /// Takes the pending objects, ordered so that each object's class, super struct, and outer
+    /// come before it if they're pending too.
+    pub(crate) fn take_ordered(&mut self) -> Vec<RcUnrealObject> {
+        let pending = std::mem::take(&mut self.pending);
+        let queued = pending
+            .iter()
+            .map(RcUnrealObjPointer::from_unreal_object)
+            .collect::<HashSet<_>>();
+
+        let mut ordered = Vec::with_capacity(pending.len());
+        let mut visited = HashSet::with_capacity(pending.len());
+        for obj in &pending {
+            visit(obj, &queued, &mut visited, &mut ordered);
+        }
+
+        ordered
+    }
+}
+
+fn visit(
+    obj: &RcUnrealObject,
+    queued: &HashSet<RcUnrealObjPointer>,
+    visited: &mut HashSet<RcUnrealObjPointer>,
+    ordered: &mut Vec<RcUnrealObject>,
+) {
+    let pointer = RcUnrealObjPointer::from_unreal_object(obj);
+    // Marked before visiting dependencies so that cycles between them terminate
+    if !queued.contains(&pointer) || !visited.insert(pointer) {
+        return;
+    }
+
+    let dependencies = {
+        let obj_inner = obj.borrow();
+        let base = obj_inner.base_object();
+        let super_field = obj_inner
+            .parent_of_kind(UObjectKind::Field)
+            .and_then(|field| field.as_any().downcast_ref::<Field>())
+            .and_then(Field::super_field);
+
+        [base.class().cloned(), super_field, base.outer_object()]
+    };
+    for dependency in dependencies.iter().flatten() {
+        visit(dependency, queued, visited, ordered);
+    }
+
+    ordered.push(Rc::clone(obj));
+}
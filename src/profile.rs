@@ -0,0 +1,467 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::object::internal::natives::NativeFunctionTable;
+
+/// Serialization differences introduced by a licensee's engine modifications.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Quirks {
+    /// `UStruct` serializes an extra `u32` flags field once the licensee version
+    /// is above this value.
+    pub struct_flags_after_licensee_version: Option<u16>,
+    /// Width of `UProperty::ArrayDim`. Stock packages serialize a `u32`.
+    pub property_array_dim: ArrayDimWidth,
+    /// `UProperty` serializes its `ElementSize` as a `u32` right after `ArrayDim`. Stock
+    /// packages don't, and the size is computed when the property is linked.
+    pub property_element_size: bool,
+    /// Number of single-`u32` compressed blocks (decompressed size, compressed size, ...)
+    /// that precede the data blocks of a .lin file.
+    pub lin_header_blocks: u8,
+    /// How script bytecode is counted towards its in-memory size.
+    pub script_sizes: ScriptSizeModel,
+    /// How the data blocks of a .lin file are compressed.
+    pub lin_compression: LinCompression,
+    /// How the bytes of ANSI strings, such as names and script text, are decoded.
+    pub string_encoding: StringEncoding,
+    /// Layout of the licensee extension in package headers.
+    pub header_extension: HeaderExtensionLayout,
+}
+
+/// Layout of the licensee extension which package headers hold between the import table's
+/// offset and the package GUID: a `u32` followed by a packed length and that many bytes.
+/// See [`PackageHeader::extension`](crate::de::PackageHeader::extension).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderExtensionLayout {
+    /// The layout isn't known, so the extension is only available as read.
+    #[default]
+    Raw,
+    /// The `u32` is a tag and the bytes are a string naming the package.
+    SplinterCell,
+}
+
+/// How the bytes of an ANSI string are turned into text.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringEncoding {
+    /// Strings must be valid UTF-8. Strings which aren't fail to decode with an
+    /// `InvalidData` error.
+    #[default]
+    Utf8Strict,
+    /// Each byte is the code point with the same value, as in ISO-8859-1. Windows-1252 text
+    /// decodes the same apart from the characters it places in 0x80..=0x9F.
+    Latin1,
+    /// Invalid UTF-8 sequences are replaced with U+FFFD.
+    Lossy,
+}
+
+impl StringEncoding {
+    /// Decodes the bytes of a string, without its null terminator.
+    pub fn decode(&self, bytes: Vec<u8>) -> io::Result<String> {
+        match self {
+            StringEncoding::Utf8Strict => String::from_utf8(bytes).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("string is not valid UTF-8: {err}"),
+                )
+            }),
+            StringEncoding::Latin1 => Ok(bytes.into_iter().map(char::from).collect()),
+            StringEncoding::Lossy => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinCompression {
+    #[default]
+    Zlib,
+    /// The file holds the decompressed data directly, with no header or data blocks
+    None,
+}
+
+/// Byte order packages are stored in. The library is generic over [`byteorder::ByteOrder`],
+/// so callers pick the matching type parameter.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+/// Sizes of script operands in the engine's in-memory bytecode.
+///
+/// Script sizes and jump offsets are measured in memory, where object references and names
+/// are fixed-width, rather than in the serialized stream where they're compact indices.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub struct ScriptSizeModel {
+    /// Size of an object reference
+    pub object: usize,
+    /// Size of a name
+    pub name: usize,
+    /// Size of a code offset or other 16-bit word
+    pub word: usize,
+}
+
+impl ScriptSizeModel {
+    /// 32-bit Unreal Engine 2 builds: 4-byte pointers and name indices.
+    pub const UE2: ScriptSizeModel = ScriptSizeModel {
+        object: 4,
+        name: 4,
+        word: 2,
+    };
+}
+
+impl Default for ScriptSizeModel {
+    fn default() -> Self {
+        ScriptSizeModel::UE2
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArrayDimWidth {
+    U16,
+    #[default]
+    U32,
+}
+
+/// Describes the serialized layout of a package: the engine version it was saved with
+/// and any licensee-specific quirks that change how objects are read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatProfile {
+    pub name: String,
+    pub version: u16,
+    pub licensee_version: u16,
+    pub quirks: Quirks,
+    pub endianness: Endianness,
+    /// Engine version used instead of the one in package headers
+    pub version_override: Option<u16>,
+    /// Licensee version used instead of the one in package headers
+    pub licensee_version_override: Option<u16>,
+    /// Names of the game's native functions. When set, this replaces the loader's table.
+    pub native_functions: Option<NativeFunctionTable>,
+    /// Licensee class names mapped to the builtin class they're loaded as
+    pub class_aliases: BTreeMap<String, String>,
+}
+
+impl FormatProfile {
+    /// Last engine version which serialized parameter information with `UFunction`s.
+    const LAST_FUNCTION_PARAM_INFO_VERSION: u16 = 63;
+    /// First engine version which serialized the class a class is within and its config name.
+    const FIRST_CLASS_WITHIN_VERSION: u16 = 62;
+    /// First engine version which serialized a class's hidden editor categories.
+    const FIRST_HIDE_CATEGORIES_VERSION: u16 = 99;
+
+    pub fn new(name: impl Into<String>, quirks: Quirks) -> Self {
+        FormatProfile {
+            name: name.into(),
+            version: 0,
+            licensee_version: 0,
+            quirks,
+            endianness: Endianness::Little,
+            version_override: None,
+            licensee_version_override: None,
+            native_functions: None,
+            class_aliases: BTreeMap::new(),
+        }
+    }
+
+    /// Loads a profile from a JSON file such as:
+    ///
+    /// ```json
+    /// {
+    ///     "name": "Chaos Theory",
+    ///     "base": "splinter_cell",
+    ///     "endianness": "little",
+    ///     "compression": "zlib",
+    ///     "string_encoding": "latin1",
+    ///     "licensee_version": 40,
+    ///     "natives": "chaostheory_natives.txt",
+    ///     "class_aliases": { "ECHOProperty": "IntProperty" }
+    /// }
+    /// ```
+    ///
+    /// Only `name` is required. `base` is the builtin profile the rest of the fields
+    /// modify: `stock_ue2` (the default) or `splinter_cell`. The quirks fields of
+    /// [`Quirks`] may also be given by name. `natives` is a native function table in the
+    /// format accepted by [`NativeFunctionTable`]'s `FromStr` impl, relative to the profile
+    /// file.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)?;
+
+        Self::from_json(&json, path.parent().unwrap_or(Path::new("")))
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {err}", path.display())))
+    }
+
+    /// Parses a profile file's contents. Paths are resolved relative to `base_dir`.
+    fn from_json(json: &str, base_dir: &Path) -> io::Result<Self> {
+        let file: ProfileFile = serde_json::from_str(json)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        let mut profile = match file.base.unwrap_or_default() {
+            BaseProfile::StockUe2 => FormatProfile::stock_ue2(),
+            BaseProfile::SplinterCell => FormatProfile::splinter_cell(),
+        };
+        profile.name = file.name;
+
+        let quirks = &mut profile.quirks;
+        if let Some(min) = file.struct_flags_after_licensee_version {
+            quirks.struct_flags_after_licensee_version = Some(min);
+        }
+        if let Some(width) = file.property_array_dim {
+            quirks.property_array_dim = width;
+        }
+        if let Some(present) = file.property_element_size {
+            quirks.property_element_size = present;
+        }
+        if let Some(blocks) = file.lin_header_blocks {
+            quirks.lin_header_blocks = blocks;
+        }
+        if let Some(sizes) = file.script_sizes {
+            quirks.script_sizes = sizes;
+        }
+        if let Some(compression) = file.compression {
+            quirks.lin_compression = compression;
+        }
+        if let Some(encoding) = file.string_encoding {
+            quirks.string_encoding = encoding;
+        }
+        if let Some(layout) = file.header_extension {
+            quirks.header_extension = layout;
+        }
+
+        if let Some(endianness) = file.endianness {
+            profile.endianness = endianness;
+        }
+        profile.version_override = file.version;
+        profile.licensee_version_override = file.licensee_version;
+        profile.class_aliases = file.class_aliases;
+
+        if let Some(natives) = file.natives {
+            let natives = base_dir.join(natives);
+            let table = std::fs::read_to_string(&natives)?.parse().map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {err}", natives.display()),
+                )
+            })?;
+            profile.native_functions = Some(table);
+        }
+
+        Ok(profile)
+    }
+
+    /// Unmodified Unreal Engine 2 packages.
+    pub fn stock_ue2() -> Self {
+        FormatProfile::new("Unreal Engine 2", Quirks::default())
+    }
+
+    /// Splinter Cell (2002) packages.
+    pub fn splinter_cell() -> Self {
+        FormatProfile::new(
+            "Splinter Cell",
+            Quirks {
+                struct_flags_after_licensee_version: Some(0x1A),
+                property_array_dim: ArrayDimWidth::U16,
+                property_element_size: false,
+                lin_header_blocks: 4,
+                script_sizes: ScriptSizeModel::UE2,
+                lin_compression: LinCompression::Zlib,
+                string_encoding: StringEncoding::Utf8Strict,
+                header_extension: HeaderExtensionLayout::SplinterCell,
+            },
+        )
+    }
+
+    /// Guesses which profile applies to a package from the raw version field of its header.
+    pub fn detect(raw_version: u32) -> Self {
+        let (version, licensee_version) = split_version(raw_version);
+
+        let profile = if licensee_version == 0 {
+            FormatProfile::stock_ue2()
+        } else {
+            // Splinter Cell is currently the only licensee game we know the layout of.
+            FormatProfile::splinter_cell()
+        };
+
+        debug!(
+            "Detected format profile {:?} for version {version} (licensee version {licensee_version:#X})",
+            profile.name
+        );
+
+        profile.with_raw_version(raw_version)
+    }
+
+    /// Returns this profile with the engine and licensee versions taken from a package header.
+    pub fn with_raw_version(mut self, raw_version: u32) -> Self {
+        let (version, licensee_version) = split_version(raw_version);
+        self.version = self.version_override.unwrap_or(version);
+        self.licensee_version = self.licensee_version_override.unwrap_or(licensee_version);
+
+        self
+    }
+
+    /// The builtin class `class_name` is loaded as. Aliases are matched ignoring ASCII case.
+    pub fn resolve_class_alias<'a>(&'a self, class_name: &'a str) -> &'a str {
+        self.class_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(class_name))
+            .map_or(class_name, |(_, class)| class.as_str())
+    }
+
+    /// Whether `UFunction` serializes its params size, param count and return value offset.
+    pub fn has_function_param_info(&self) -> bool {
+        self.version <= Self::LAST_FUNCTION_PARAM_INFO_VERSION
+    }
+
+    /// Whether `UClass` serializes the class it's within and its config name.
+    pub fn has_class_within(&self) -> bool {
+        self.version >= Self::FIRST_CLASS_WITHIN_VERSION
+    }
+
+    /// Whether `UClass` serializes the editor categories it hides.
+    pub fn has_class_hide_categories(&self) -> bool {
+        self.version >= Self::FIRST_HIDE_CATEGORIES_VERSION
+    }
+
+    /// Whether `UStruct` serializes a licensee-specific flags field after its friendly name.
+    pub fn has_struct_flags(&self) -> bool {
+        self.quirks
+            .struct_flags_after_licensee_version
+            .is_some_and(|min| self.licensee_version > min)
+    }
+}
+
+impl Default for FormatProfile {
+    fn default() -> Self {
+        FormatProfile::stock_ue2()
+    }
+}
+
+/// Layout of a profile file. See [`FormatProfile::from_file`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileFile {
+    name: String,
+    base: Option<BaseProfile>,
+    endianness: Option<Endianness>,
+    compression: Option<LinCompression>,
+    version: Option<u16>,
+    licensee_version: Option<u16>,
+    struct_flags_after_licensee_version: Option<u16>,
+    property_array_dim: Option<ArrayDimWidth>,
+    property_element_size: Option<bool>,
+    lin_header_blocks: Option<u8>,
+    script_sizes: Option<ScriptSizeModel>,
+    string_encoding: Option<StringEncoding>,
+    header_extension: Option<HeaderExtensionLayout>,
+    natives: Option<PathBuf>,
+    #[serde(default)]
+    class_aliases: BTreeMap<String, String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BaseProfile {
+    #[default]
+    StockUe2,
+    SplinterCell,
+}
+
+fn split_version(raw_version: u32) -> (u16, u16) {
+    (
+        (raw_version & 0xFFFF) as u16,
+        ((raw_version & 0xFFFF_0000) >> 16) as u16,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_profiles() {
+        let stock = FormatProfile::detect(0x0000_0079);
+        assert_eq!(stock.name, FormatProfile::stock_ue2().name);
+        assert!(!stock.has_struct_flags());
+        assert!(!stock.has_function_param_info());
+        assert!(stock.has_class_within());
+        assert!(stock.has_class_hide_categories());
+
+        let sc = FormatProfile::detect(0x001D_0064);
+        assert_eq!(sc.name, FormatProfile::splinter_cell().name);
+        assert_eq!(sc.version, 100);
+        assert_eq!(sc.licensee_version, 0x1D);
+        assert!(sc.has_struct_flags());
+
+        let old_sc = FormatProfile::detect(0x0011_0064);
+        assert!(!old_sc.has_struct_flags());
+    }
+
+    #[test]
+    fn decodes_strings() {
+        let bytes = b"caf\xE9".to_vec();
+
+        let err = StringEncoding::Utf8Strict
+            .decode(bytes.clone())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            StringEncoding::Latin1.decode(bytes.clone()).unwrap(),
+            "café"
+        );
+        assert_eq!(StringEncoding::Lossy.decode(bytes).unwrap(), "caf\u{FFFD}");
+        assert_eq!(
+            StringEncoding::Utf8Strict
+                .decode("café".as_bytes().to_vec())
+                .unwrap(),
+            "café"
+        );
+    }
+
+    #[test]
+    fn profile_files() {
+        let json = r#"{
+            "name": "Chaos Theory",
+            "base": "splinter_cell",
+            "endianness": "big",
+            "licensee_version": 40,
+            "property_array_dim": "u32",
+            "property_element_size": true,
+            "script_sizes": { "object": 4, "name": 8, "word": 2 },
+            "string_encoding": "latin1",
+            "header_extension": "raw",
+            "class_aliases": { "ECHOProperty": "IntProperty" }
+        }"#;
+        let profile = FormatProfile::from_json(json, Path::new("")).unwrap();
+
+        assert_eq!(profile.name, "Chaos Theory");
+        assert_eq!(profile.endianness, Endianness::Big);
+        assert_eq!(profile.quirks.lin_header_blocks, 4);
+        assert_eq!(profile.quirks.property_array_dim, ArrayDimWidth::U32);
+        assert!(profile.quirks.property_element_size);
+        assert_eq!(profile.quirks.script_sizes.name, 8);
+        assert_eq!(profile.quirks.string_encoding, StringEncoding::Latin1);
+        assert_eq!(profile.quirks.header_extension, HeaderExtensionLayout::Raw);
+        assert_eq!(profile.resolve_class_alias("echoproperty"), "IntProperty");
+        assert_eq!(profile.resolve_class_alias("Class"), "Class");
+
+        // The licensee version from the header is replaced
+        let profile = profile.with_raw_version(0x001D_0064);
+        assert_eq!((profile.version, profile.licensee_version), (100, 40));
+        assert!(profile.has_struct_flags());
+
+        let err =
+            FormatProfile::from_json(r#"{"name": "x", "typo": 1}"#, Path::new("")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
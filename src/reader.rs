@@ -2,17 +2,25 @@ use std::{
     array,
     cell::RefCell,
     collections::{BTreeMap, VecDeque},
-    io::{self, Read, Seek},
+    fmt,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
     rc::Rc,
 };
 
 use byteorder::{ByteOrder, ReadBytesExt};
-use tracing::{Level, debug, span, trace};
+use tracing::{Level, debug, span, trace, warn};
 
 use crate::{
+    cache::ExportCache,
     common::IoOp,
     de::{ExportIndex, ImportIndex, Linker, RcLinker},
-    object::{RcUnrealObject, UnrealObject},
+    object::{
+        RcUnrealObject, UnrealObject,
+        internal::object_ref::{ObjectRef, ReferencePolicy},
+    },
+    packed,
+    profile::StringEncoding,
     runtime::{LoadKind, UnrealRuntime},
 };
 
@@ -37,52 +45,43 @@ pub trait UnrealReadExt: LinRead + Sized {
         runtime.load_object_by_raw_index::<E, _>(index, linker, LoadKind::Create, self)
     }
 
-    /// Decodes the packed integer from the byte stream.
-    /// Assumes `u8(input)` reads one byte from `input`.
-    fn read_packed_int(&mut self) -> io::Result<i32> {
-        const CONTINUE_BIT: u8 = 0x40;
-        const NEGATE_BIT: u8 = 0x80;
-
-        let span = span!(Level::TRACE, "read_packed_int");
+    /// Reads an object reference. Whether the referenced object is loaded immediately
+    /// depends on the runtime's [`ReferencePolicy`].
+    fn read_object_ref<E>(
+        &mut self,
+        runtime: &mut UnrealRuntime,
+        linker: &RcLinker,
+    ) -> io::Result<ObjectRef>
+    where
+        E: ByteOrder,
+    {
+        let span = span!(Level::DEBUG, "read_object_ref");
         let _enter = span.enter();
 
-        let b0 = self.read_u8()?;
-
-        trace!("b0: {:#X}", b0);
-
-        // Build up the unsigned magnitude.
-        let mut value: u32 = 0;
-
-        if (b0 & CONTINUE_BIT) != 0 {
-            let b1 = self.read_u8()?;
-            trace!("b1: {b1:#X}");
-            if (b1 & NEGATE_BIT) != 0 {
-                let b2 = self.read_u8()?;
-                trace!("b2: {b2:#X}");
-                if (b2 & NEGATE_BIT) != 0 {
-                    let b3 = self.read_u8()?;
-                    trace!("b3: {b3:#X}");
-                    if (b3 & NEGATE_BIT) != 0 {
-                        let b4 = self.read_u8()?;
-                        trace!("b4: {b4:#X}");
-                        value = b4 as u32;
-                    }
-                    value = (value << 7) + ((b3 & (NEGATE_BIT - 1)) as u32);
-                }
-                value = (value << 7) + ((b2 & (NEGATE_BIT - 1)) as u32);
-            }
-            value = (value << 7) + ((b1 & (NEGATE_BIT - 1)) as u32);
-        }
+        let mut obj_ref = ObjectRef::new(self.read_packed_int()?, linker);
 
-        value = (value << 6) + ((b0 & (CONTINUE_BIT - 1)) as u32);
+        trace!(
+            "Read object reference {:#X} (policy= {:?})",
+            obj_ref.raw_index(),
+            runtime.reference_policy
+        );
 
-        // Apply sign bit from B0.
-        let mut result = value as i32;
-        if (b0 & 0x80) != 0 {
-            result = -result;
+        if runtime.reference_policy == ReferencePolicy::Eager {
+            obj_ref.resolve::<E, _>(runtime, self)?;
         }
 
-        Ok(result)
+        Ok(obj_ref)
+    }
+
+    /// Reads a packed int. See [`crate::read_packed_int`].
+    fn read_packed_int(&mut self) -> io::Result<i32> {
+        let span = span!(Level::TRACE, "read_packed_int");
+        let _enter = span.enter();
+
+        let value = packed::read_packed_int(self)?;
+        trace!("value: {value:#X}");
+
+        Ok(value)
     }
 
     fn read_array(&mut self) -> io::Result<Vec<u8>> {
@@ -120,11 +119,14 @@ pub trait UnrealReadExt: LinRead + Sized {
         }
 
         let is_unicode = string_len < 0;
-        let actual_len = string_len.abs() as usize;
+        let actual_len = string_len.unsigned_abs() as usize;
 
         if is_unicode {
             // Unicode strings - read as wide chars (not implemented yet)
-            panic!("Unicode strings not yet implemented");
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unicode strings are not supported yet",
+            ))
         } else {
             // ANSI strings - read byte by byte
             let mut string_data = Vec::with_capacity(actual_len);
@@ -137,7 +139,7 @@ pub trait UnrealReadExt: LinRead + Sized {
                 string_data.pop();
             }
 
-            Ok(String::from_utf8(string_data).expect("string is not valid UTF-8"))
+            self.string_encoding().decode(string_data)
         }
     }
 }
@@ -148,6 +150,7 @@ pub struct LinReader<R> {
     source: R,
     pos: u64,
     version: u16,
+    string_encoding: StringEncoding,
 }
 
 impl<R> LinReader<R> {
@@ -156,6 +159,7 @@ impl<R> LinReader<R> {
             source: reader,
             pos: 0,
             version: 0,
+            string_encoding: StringEncoding::default(),
         }
     }
 }
@@ -173,26 +177,317 @@ where
 }
 
 impl<R> Seek for LinReader<R> {
-    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.pos = resolve_seek(pos, self.pos, None)?;
+        Ok(self.pos)
+    }
+}
+
+/// Converts `pos` to an absolute position. `len` is the length of the logical stream, which
+/// linear file readers generally don't know.
+fn resolve_seek(pos: SeekFrom, current: u64, len: Option<u64>) -> io::Result<u64> {
+    let (base, offset) = match pos {
+        SeekFrom::Start(pos) => return Ok(pos),
+        SeekFrom::Current(offset) => (current, offset),
+        SeekFrom::End(offset) => {
+            let len = len.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek relative to the end of a stream with an unknown length",
+                )
+            })?;
+            (len, offset)
+        }
+    };
+
+    base.checked_add_signed(offset).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid seek to {offset} bytes from {base:#X}"),
+        )
+    })
+}
+
+/// Reader for regular package files (.u, .utx, etc.) which, unlike linear files,
+/// must be read with random access.
+pub struct PackageReader<R> {
+    source: R,
+    /// Logical position of this reader. Only differs from the source's position when data has
+    /// been served from `cache`.
+    pos: u64,
+    /// Whether `source` needs to be seeked to `pos` before it's next read from
+    source_stale: bool,
+    cache: Option<ExportCache>,
+    /// Length of `source`, once it's been probed
+    len: Option<u64>,
+    string_encoding: StringEncoding,
+}
+
+impl<R> PackageReader<R> {
+    pub fn new(reader: R) -> Self {
+        PackageReader {
+            source: reader,
+            pos: 0,
+            source_stale: false,
+            cache: None,
+            len: None,
+            string_encoding: StringEncoding::default(),
+        }
+    }
+
+    /// Serves reads which fall inside cached exports from memory.
+    pub fn set_export_cache(&mut self, cache: Option<ExportCache>) {
+        self.cache = cache;
+    }
+
+    pub fn export_cache(&self) -> Option<&ExportCache> {
+        self.cache.as_ref()
+    }
+
+    fn sync_source(&mut self) -> io::Result<()>
+    where
+        R: Seek,
+    {
+        if self.source_stale {
+            self.source.seek(SeekFrom::Start(self.pos))?;
+            self.source_stale = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Read for PackageReader<R>
+where
+    R: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if let Some(cached) = self.cache.as_ref().and_then(|cache| cache.lookup(self.pos)) {
+            let len = buf.len().min(cached.len());
+            buf[..len].copy_from_slice(&cached[..len]);
+
+            self.pos += len as u64;
+            self.source_stale = true;
+
+            return Ok(len);
+        }
+
+        self.sync_source()?;
+
+        let bytes_read = self.source.read(buf)?;
+        self.pos += bytes_read as u64;
+
+        Ok(bytes_read)
+    }
+}
+
+impl<R> Seek for PackageReader<R>
+where
+    R: Seek,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
         match pos {
-            std::io::SeekFrom::Start(pos) => {
-                self.pos = pos;
-                Ok(pos)
+            // Defer seeking the source until it's actually read from
+            SeekFrom::Start(_) | SeekFrom::Current(_) => {
+                self.pos = resolve_seek(pos, self.pos, None)?;
+                self.source_stale = true;
+            }
+            SeekFrom::End(_) => {
+                self.pos = self.source.seek(pos)?;
+                self.source_stale = false;
+            }
+        }
+
+        Ok(self.pos)
+    }
+}
+
+/// Adapts a source which can't seek, such as a network stream or decompression pipe, so that
+/// a [`crate::Package`] can be loaded from it.
+///
+/// Data is spooled as it's pulled from the source so that earlier parts can be sought back
+/// to. Seeking past what's been spooled reads ahead, and seeking relative to the end reads
+/// the whole source. Linear files are streamed already and don't need this.
+pub struct BufferingReader<R> {
+    source: R,
+    spool: Spool,
+    pos: u64,
+    /// Number of bytes pulled from `source` so far
+    spooled: u64,
+    source_done: bool,
+}
+
+enum Spool {
+    Memory(Vec<u8>),
+    File(File),
+}
+
+impl<R> BufferingReader<R> {
+    /// Size of the chunks pulled from the source
+    const CHUNK_SIZE: usize = 0x10000;
+
+    /// Spools data in memory.
+    pub fn new(reader: R) -> Self {
+        Self::with_spool(reader, Spool::Memory(Vec::new()))
+    }
+
+    /// Spools data to `file`, which must be empty and opened for reading and writing, for
+    /// sources too large to hold in memory.
+    pub fn with_spool_file(reader: R, file: File) -> Self {
+        Self::with_spool(reader, Spool::File(file))
+    }
+
+    fn with_spool(source: R, spool: Spool) -> Self {
+        BufferingReader {
+            source,
+            spool,
+            pos: 0,
+            spooled: 0,
+            source_done: false,
+        }
+    }
+
+    /// Pulls data from the source until at least `target` bytes have been spooled or the
+    /// source runs out.
+    fn spool_to(&mut self, target: u64) -> io::Result<()>
+    where
+        R: Read,
+    {
+        if self.spooled >= target || self.source_done {
+            return Ok(());
+        }
+
+        let mut chunk = vec![0u8; Self::CHUNK_SIZE];
+        while self.spooled < target && !self.source_done {
+            let read = match self.source.read(&mut chunk) {
+                Ok(0) => {
+                    self.source_done = true;
+                    break;
+                }
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            match &mut self.spool {
+                Spool::Memory(data) => data.extend_from_slice(&chunk[..read]),
+                Spool::File(file) => {
+                    file.seek(SeekFrom::Start(self.spooled))?;
+                    file.write_all(&chunk[..read])?;
+                }
             }
-            std::io::SeekFrom::End(_) => todo!("end position seeking not implemented"),
-            std::io::SeekFrom::Current(0) => Ok(self.pos),
-            std::io::SeekFrom::Current(_) => todo!("current position seeking not implemented"),
+            self.spooled += read as u64;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R> Read for BufferingReader<R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.spool_to(self.pos + buf.len() as u64)?;
+
+        let available = self.spooled.saturating_sub(self.pos);
+        let len = buf
+            .len()
+            .min(usize::try_from(available).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+
+        match &mut self.spool {
+            Spool::Memory(data) => {
+                let start = self.pos as usize;
+                buf[..len].copy_from_slice(&data[start..start + len]);
+            }
+            Spool::File(file) => {
+                file.seek(SeekFrom::Start(self.pos))?;
+                file.read_exact(&mut buf[..len])?;
+            }
+        }
+        self.pos += len as u64;
+
+        Ok(len)
+    }
+}
+
+impl<R> Seek for BufferingReader<R>
+where
+    R: Read,
+{
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let len = if let SeekFrom::End(_) = pos {
+            self.spool_to(u64::MAX)?;
+            Some(self.spooled)
+        } else {
+            None
+        };
+        self.pos = resolve_seek(pos, self.pos, len)?;
+
+        Ok(self.pos)
+    }
+}
+
+/// An IO operation which didn't match the one recorded at the same point in the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Position in the file the operation was made at
+    pub position: u64,
+    /// The recorded operation, or `None` if the trace had already ended
+    pub expected: Option<IoOp>,
+    pub actual: IoOp,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "at {:#X}: did {}, ",
+            self.position,
+            DisplayOp(&self.actual)
+        )?;
+        match &self.expected {
+            Some(expected) => write!(f, "expected {}", DisplayOp(expected)),
+            None => write!(f, "expected no more IO"),
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+struct DisplayOp<'a>(&'a IoOp);
+
+impl fmt::Display for DisplayOp<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            IoOp::Seek { to, from } => write!(f, "a seek from {from:#X} to {to:#X}"),
+            IoOp::Read { len } => write!(f, "a read of {len:#X} bytes"),
         }
     }
 }
 
+/// Divergences recorded by [`CheckedLinReader`]s sharing a trace.
+pub type DivergenceLog = Rc<RefCell<Vec<Divergence>>>;
+
 pub struct CheckedLinReader<R> {
     source: R,
     pos: u64,
     version: u16,
-    /// Package headers are not included in the raw IO ops
-    reading_linker_header: bool,
+    /// Number of unverified regions being read, such as package headers, which are not
+    /// included in the raw IO ops
+    unverified_depth: usize,
     io_ops: Rc<RefCell<VecDeque<IoOp>>>,
+    /// Logical length of the current package, if known
+    package_len: Option<u64>,
+    /// Where divergences are recorded instead of being returned as errors
+    divergences: Option<DivergenceLog>,
+    /// Whether the reader diverged from the trace and is waiting for a seek it can resume
+    /// verifying from
+    diverged: bool,
+    string_encoding: StringEncoding,
 }
 
 impl<R> CheckedLinReader<R> {
@@ -200,11 +495,54 @@ impl<R> CheckedLinReader<R> {
         CheckedLinReader {
             source: reader,
             pos: 0,
-            reading_linker_header: false,
+            unverified_depth: 0,
             io_ops,
             version: 0,
+            package_len: None,
+            divergences: None,
+            diverged: false,
+            string_encoding: StringEncoding::default(),
         }
     }
+
+    /// Records divergences from the trace in `log` rather than failing on them. After a
+    /// divergence, verification resumes at the next seek which is also in the trace.
+    pub fn set_divergence_log(&mut self, log: Option<DivergenceLog>) {
+        self.divergences = log;
+    }
+
+    /// The recorded operations which haven't been checked yet.
+    pub(crate) fn io_ops(&self) -> Rc<RefCell<VecDeque<IoOp>>> {
+        Rc::clone(&self.io_ops)
+    }
+
+    fn verifying(&self) -> bool {
+        self.unverified_depth == 0 && !self.diverged
+    }
+
+    /// Handles `actual` not matching `expected`, the recorded op it was checked against. The
+    /// recorded op is put back if the divergence is an error.
+    fn diverge(&mut self, expected: Option<IoOp>, actual: IoOp) -> io::Result<()> {
+        let divergence = Divergence {
+            position: self.pos,
+            expected,
+            actual,
+        };
+
+        let Some(log) = &self.divergences else {
+            if let Some(op) = expected {
+                self.io_ops.borrow_mut().push_front(op);
+            }
+
+            return Err(io::Error::new(io::ErrorKind::InvalidData, divergence));
+        };
+
+        warn!("Diverged from the trace {divergence}");
+        log.borrow_mut().push(divergence);
+        self.diverged = true;
+
+        Ok(())
+    }
 }
 
 impl<R> Read for CheckedLinReader<R>
@@ -212,28 +550,13 @@ where
     R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if !self.reading_linker_header {
-            let mut ops = self.io_ops.borrow_mut();
-
-            match ops
-                .pop_front()
-                .expect("conducting an IO op but there are no more IO ops")
-            {
-                IoOp::Read { len } => {
-                    assert_eq!(
-                        buf.len() as u64,
-                        len,
-                        "Expected a read of {:#X} bytes, got read of {:#X} instead",
-                        len,
-                        buf.len()
-                    );
-                }
-                other => panic!(
-                    "doing a read of {:#X} bytes at {:#X}, expected: {:#X?}",
-                    buf.len(),
-                    self.pos,
-                    other
-                ),
+        if self.verifying() {
+            let actual = IoOp::Read {
+                len: buf.len() as u64,
+            };
+            let expected = self.io_ops.borrow_mut().pop_front();
+            if expected != Some(actual) {
+                self.diverge(expected, actual)?;
             }
         }
 
@@ -244,113 +567,426 @@ where
     }
 }
 
+impl<R> CheckedLinReader<R> {
+    /// Consumes the recorded reads covering the next `len` bytes. A recorded read which
+    /// extends past them is left with the bytes it has remaining.
+    fn consume_recorded_reads(&mut self, mut len: u64) -> io::Result<()> {
+        while len > 0 {
+            let op = self.io_ops.borrow_mut().pop_front();
+            match op {
+                Some(IoOp::Read { len: read_len }) if read_len <= len => len -= read_len,
+                Some(IoOp::Read { len: read_len }) => {
+                    self.io_ops.borrow_mut().push_front(IoOp::Read {
+                        len: read_len - len,
+                    });
+                    len = 0;
+                }
+                other => return self.diverge(other, IoOp::Read { len }),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn seek_to(&mut self, pos: u64) -> io::Result<u64> {
+        trace!("to= {:#X}, from= {:#X}", pos, self.pos);
+
+        if self.unverified_depth == 0 && self.diverged {
+            // Resume verifying from the next time the trace seeks to the same place
+            let mut ops = self.io_ops.borrow_mut();
+            if let Some(index) = ops
+                .iter()
+                .position(|op| matches!(op, IoOp::Seek { to, .. } if *to == pos))
+            {
+                debug!("Resynchronized with the trace at a seek to {pos:#X}");
+
+                ops.drain(..=index);
+                self.diverged = false;
+            }
+        } else if self.verifying() {
+            let actual = IoOp::Seek {
+                to: pos,
+                from: self.pos,
+            };
+            let expected = self.io_ops.borrow_mut().pop_front();
+            if expected != Some(actual) {
+                self.diverge(expected, actual)?;
+            }
+        }
+
+        self.pos = pos;
+        Ok(pos)
+    }
+}
+
 impl<R> Seek for CheckedLinReader<R> {
-    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
         let span = span!(Level::TRACE, "seek");
         let _enter = span.enter();
 
         match pos {
-            std::io::SeekFrom::Start(pos) => {
-                trace!("to= {:#X}, from= {:#X}", pos, self.pos);
-
-                if !self.reading_linker_header {
-                    let mut ops = self.io_ops.borrow_mut();
-
-                    match ops
-                        .pop_front()
-                        .expect("conducting an IO op but there are no more IO ops")
-                    {
-                        IoOp::Seek { to, from } => {
-                            if self.pos != from || to != pos {
-                                panic!(
-                                    "Attempted to seek from {:#X} to {:#X}; should be seeking from {:#X} to {:#X}",
-                                    self.pos, pos, from, to
-                                );
-                            }
-                        }
-                        other => {
-                            let bytes_until_next_seek = ops
-                                .iter()
-                                .take_while(|op| !matches!(op, IoOp::Seek { .. }))
-                                .fold(0, |accum, op| {
-                                    if let IoOp::Read { len } = op {
-                                        accum + *len
-                                    } else {
-                                        unreachable!("unexpected op");
-                                    }
-                                });
-                            panic!(
-                                "doing a seek from {:#X} to {:#X}. Bytes until next seek: {bytes_until_next_seek:#X}. Expected op: {other:#X?}",
-                                self.pos, pos
-                            )
-                        }
-                    }
-                }
-
-                self.pos = pos;
-                Ok(pos)
+            // Querying the position doesn't correspond to a recorded IO op
+            SeekFrom::Current(0) => Ok(self.pos),
+            pos => {
+                let target = resolve_seek(pos, self.pos, self.package_len)?;
+                self.seek_to(target)
             }
-            std::io::SeekFrom::End(_) => todo!("end position seeking not implemented"),
-            std::io::SeekFrom::Current(0) => Ok(self.pos),
-            std::io::SeekFrom::Current(_) => todo!("current position seeking not implemented"),
         }
     }
 }
 
 pub trait LinRead: io::Read + io::Seek {
-    fn set_reading_linker_header(&mut self, reading_linker_header: bool);
-    fn cheat(&mut self, buf: &mut [u8]) -> io::Result<()>;
+    /// Starts a region whose reads and seeks aren't verified against recorded IO ops, such
+    /// as a package header. Regions nest, and each must be closed with
+    /// [`LinRead::end_unverified`]; prefer [`LinRead::unverified_scope`], which does so.
+    fn begin_unverified(&mut self) {}
+
+    /// Ends the innermost region started by [`LinRead::begin_unverified`].
+    fn end_unverified(&mut self) {}
+
+    /// Runs `f` in an unverified region, which is closed however `f` returns.
+    fn unverified_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> io::Result<T>) -> io::Result<T>
+    where
+        Self: Sized,
+    {
+        self.begin_unverified();
+        let result = f(self);
+        self.end_unverified();
+
+        result
+    }
+
+    /// Length of the stream, or `None` if it can't be known up front, as for streamed linear
+    /// files.
+    fn known_len(&mut self) -> io::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Sets the length of the package currently being read, for readers which can't know
+    /// it from the stream. Readers which know their length ignore this.
+    fn set_package_len(&mut self, _len: Option<u64>) {}
+
+    /// How ANSI strings read by [`UnrealReadExt::read_string`] are decoded.
+    fn string_encoding(&self) -> StringEncoding {
+        StringEncoding::default()
+    }
+
+    /// Changes how ANSI strings are decoded. Readers which can't hold an encoding ignore this.
+    fn set_string_encoding(&mut self, _encoding: StringEncoding) {}
+
+    /// Reads exactly enough bytes to fill `buf` as a single bulk payload, such as a blob of
+    /// script bytecode. Unlike ordinary reads, a checked reader doesn't verify how the bytes
+    /// were split into reads when they were recorded, only that they were read.
+    fn read_raw_unverified(&mut self, buf: &mut [u8]) -> io::Result<()>;
+
+    /// Skips the next `len` bytes as a bulk payload. See [`LinRead::read_raw_unverified`].
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        let mut buf = vec![0u8; len];
+        self.read_raw_unverified(&mut buf)
+    }
+
+    /// Whether data which has been read can be read again by seeking back to it. Linear
+    /// files are streamed, so each byte can only be read once.
+    fn supports_rereads(&self) -> bool {
+        false
+    }
+
+    /// Position the next annotated field starts at, or `None` if reads aren't being
+    /// annotated. See [`crate::annotate::AnnotatingReader`].
+    fn annotation_position(&self) -> Option<u64> {
+        None
+    }
+
+    /// Records that the field `name` was read from `offset` up to the current position.
+    fn annotate_field(&mut self, _offset: u64, _name: &'static str, _value: String) {}
+
+    /// Called before an export's serial data is deserialized.
+    fn begin_object_annotation(
+        &mut self,
+        _full_name: &str,
+        _class_name: &str,
+        _serial_offset: u64,
+    ) {
+    }
+
+    /// Called once an export's serial data has been deserialized, whether or not it succeeded.
+    fn end_object_annotation(&mut self) {}
 }
 
 impl<R> LinRead for LinReader<R>
 where
     R: Read,
 {
-    fn set_reading_linker_header(&mut self, _reading_linker_header: bool) {
-        // Do nothing
+    fn read_raw_unverified(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        self.read_exact(buf)
+    }
+
+    fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
     }
 
-    fn cheat(&mut self, buf: &mut [u8]) -> io::Result<()> {
-        // We have no IO ops to cheat
+    fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.string_encoding = encoding;
+    }
+}
+
+impl<R> LinRead for PackageReader<R>
+where
+    R: Read + Seek,
+{
+    fn read_raw_unverified(&mut self, buf: &mut [u8]) -> io::Result<()> {
         self.read_exact(buf)
     }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.seek(SeekFrom::Current(len as i64))?;
+
+        Ok(())
+    }
+
+    fn known_len(&mut self) -> io::Result<Option<u64>> {
+        if self.len.is_none() {
+            self.len = Some(self.source.seek(SeekFrom::End(0))?);
+            self.source_stale = true;
+        }
+
+        Ok(self.len)
+    }
+
+    fn supports_rereads(&self) -> bool {
+        true
+    }
+
+    fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
+    }
+
+    fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.string_encoding = encoding;
+    }
 }
 
 impl<R> LinRead for CheckedLinReader<R>
 where
     R: Read,
 {
-    fn set_reading_linker_header(&mut self, reading_linker_header: bool) {
-        self.reading_linker_header = reading_linker_header;
+    fn string_encoding(&self) -> StringEncoding {
+        self.string_encoding
     }
 
-    fn cheat(&mut self, buf: &mut [u8]) -> io::Result<()> {
-        // Remove however many io ops are part of this read
-        let mut remove_len = 0;
+    fn set_string_encoding(&mut self, encoding: StringEncoding) {
+        self.string_encoding = encoding;
+    }
 
-        let mut io_ops = self.io_ops.borrow_mut();
-        while remove_len < buf.len() {
-            match io_ops.pop_front().expect("no io op?") {
-                IoOp::Seek { to, from } => panic!("unexpected seek op while cheating reads"),
-                IoOp::Read { len } => {
-                    remove_len += len as usize;
-                }
-            }
+    fn begin_unverified(&mut self) {
+        self.unverified_depth += 1;
+    }
+
+    fn end_unverified(&mut self) {
+        self.unverified_depth = self
+            .unverified_depth
+            .checked_sub(1)
+            .expect("ended an unverified region which was never started");
+    }
+
+    fn known_len(&mut self) -> io::Result<Option<u64>> {
+        Ok(self.package_len)
+    }
+
+    /// Also lets [`SeekFrom::End`] seeks be resolved.
+    fn set_package_len(&mut self, len: Option<u64>) {
+        self.package_len = len;
+    }
+
+    fn read_raw_unverified(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if self.verifying() {
+            self.consume_recorded_reads(buf.len() as u64)?;
         }
 
-        assert_eq!(remove_len, buf.len());
+        // Read straight from the source so that short reads aren't verified individually
+        self.source.read_exact(buf)?;
+        self.pos += buf.len() as u64;
 
-        // Insert a fake read of this exact size. 0-sized reads are short-circuited
-        // by read_exact, so don't add this read if the data size is zero since the IO op
-        // will never be popped.
-        if remove_len > 0 {
-            io_ops.push_front(IoOp::Read {
-                len: buf.len() as u64,
-            });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checked_reader(ops: Vec<IoOp>) -> CheckedLinReader<&'static [u8]> {
+        CheckedLinReader::new(&[0u8; 0x20][..], Rc::new(RefCell::new(VecDeque::from(ops))))
+    }
+
+    /// A source which can only be read forwards
+    struct Stream<'a>(&'a [u8]);
+
+    impl Read for Stream<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            // Hand out a few bytes at a time, as a pipe would
+            let len = buf.len().min(3);
+            self.0.read(&mut buf[..len])
         }
+    }
 
-        drop(io_ops);
+    #[test]
+    fn buffering_reader_seeks_over_a_stream() {
+        let data = (0..0x20u8).collect::<Vec<_>>();
+        let mut reader = BufferingReader::new(Stream(&data));
 
-        self.read_exact(buf)
+        let mut buf = [0u8; 4];
+        reader.seek(SeekFrom::Start(0x10)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0x10, 0x11, 0x12, 0x13]);
+
+        // Data before the furthest read is replayed from the spool
+        reader.seek(SeekFrom::Start(2)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [2, 3, 4, 5]);
+
+        assert_eq!(reader.seek(SeekFrom::End(-2)).unwrap(), 0x1E);
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn package_reader_reads_through_cache() {
+        let data = (0..0x20u8).collect::<Vec<_>>();
+        let exports = [crate::de::ObjectExport {
+            class_index: 0,
+            super_index: 0,
+            package_index: 0,
+            object_name: 0,
+            object_flags: 0,
+            serial_size: 4,
+            serial_offset: 8,
+        }];
+
+        let mut reader = PackageReader::new(std::io::Cursor::new(data));
+        let cache = ExportCache::build(&mut reader, &exports, None).unwrap();
+        reader.set_export_cache(Some(cache));
+
+        // Reads spanning the end of a cached export continue from the source
+        let mut buf = [0u8; 6];
+        reader.seek(SeekFrom::Start(0xA)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [0xA, 0xB, 0xC, 0xD, 0xE, 0xF]);
+        assert_eq!(reader.stream_position().unwrap(), 0x10);
+    }
+
+    #[test]
+    fn relative_seeks_match_recorded_ops() {
+        let mut reader = checked_reader(vec![
+            IoOp::Seek { to: 0x10, from: 0 },
+            IoOp::Seek {
+                to: 0x18,
+                from: 0x10,
+            },
+            IoOp::Seek {
+                to: 0x40,
+                from: 0x18,
+            },
+        ]);
+        reader.set_package_len(Some(0x50));
+
+        assert_eq!(reader.seek(SeekFrom::Current(0x10)).unwrap(), 0x10);
+        assert_eq!(reader.seek(SeekFrom::Current(8)).unwrap(), 0x18);
+        assert_eq!(reader.stream_position().unwrap(), 0x18);
+        assert_eq!(reader.seek(SeekFrom::End(-0x10)).unwrap(), 0x40);
+    }
+
+    #[test]
+    fn invalid_seeks_are_errors() {
+        let mut reader = checked_reader(Vec::new());
+
+        assert_eq!(
+            reader.seek(SeekFrom::End(0)).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            reader.seek(SeekFrom::Current(-1)).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn unverified_scopes_nest_and_close_on_error() {
+        let mut reader = checked_reader(vec![IoOp::Read { len: 4 }]);
+        let mut buf = [0u8; 2];
+
+        reader
+            .unverified_scope(|reader| {
+                reader.read_exact(&mut buf)?;
+                reader.unverified_scope(|reader| reader.read_exact(&mut buf))?;
+                // Still unverified once the inner scope ends
+                reader.read_exact(&mut buf)
+            })
+            .unwrap();
+
+        let err = reader
+            .unverified_scope(|reader| {
+                reader.read_exact(&mut buf)?;
+                Err::<(), _>(io::Error::other("bad header"))
+            })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "bad header");
+
+        // Verification resumes, consuming the recorded read
+        reader.read_exact(&mut [0u8; 4]).unwrap();
+        assert!(reader.io_ops.borrow().is_empty());
+    }
+
+    #[test]
+    fn raw_reads_consume_recorded_reads() {
+        let mut reader = checked_reader(vec![
+            IoOp::Read { len: 2 },
+            IoOp::Read { len: 4 },
+            IoOp::Read { len: 3 },
+            IoOp::Seek { to: 0, from: 9 },
+        ]);
+
+        // Ends partway through the second recorded read
+        reader.read_raw_unverified(&mut [0u8; 5]).unwrap();
+        reader.read_exact(&mut [0u8; 1]).unwrap();
+        reader.skip(3).unwrap();
+        assert_eq!(reader.stream_position().unwrap(), 9);
+
+        let err = reader.skip(1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        reader.seek(SeekFrom::Start(0)).unwrap();
+    }
+
+    #[test]
+    fn logged_divergences_resync_at_next_seek() {
+        let mut reader = checked_reader(vec![
+            IoOp::Read { len: 4 },
+            IoOp::Read { len: 4 },
+            IoOp::Seek { to: 0x10, from: 8 },
+            IoOp::Read { len: 2 },
+        ]);
+
+        // Without a log, a mismatch is an error and leaves the trace untouched
+        let err = reader.read_exact(&mut [0u8; 2]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(reader.io_ops.borrow().len(), 4);
+
+        let log = DivergenceLog::default();
+        reader.set_divergence_log(Some(Rc::clone(&log)));
+        reader.read_exact(&mut [0u8; 2]).unwrap();
+        // Unchecked until the trace seeks somewhere the reader does
+        reader.read_exact(&mut [0u8; 6]).unwrap();
+        reader.seek(SeekFrom::Start(0x10)).unwrap();
+        reader.read_exact(&mut [0u8; 2]).unwrap();
+
+        assert_eq!(
+            *log.borrow(),
+            [Divergence {
+                position: 0,
+                expected: Some(IoOp::Read { len: 4 }),
+                actual: IoOp::Read { len: 2 },
+            }]
+        );
+        assert!(reader.io_ops.borrow().is_empty());
     }
 }
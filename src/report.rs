@@ -0,0 +1,173 @@
+use std::{
+    rc::Rc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::{
+    de::ExportIndex,
+    object::builtins::{Function, Property},
+    object::{RcUnrealObject, UObjectKind},
+    runtime::UnrealRuntime,
+};
+
+/// How long an object took to deserialize, recorded as it's loaded.
+#[derive(Debug, Clone)]
+pub(crate) struct ObjectTiming {
+    pub(crate) package: String,
+    pub(crate) full_name: String,
+    pub(crate) class_name: String,
+    pub(crate) export_index: usize,
+    pub(crate) started: Duration,
+    pub(crate) elapsed: Duration,
+    pub(crate) bytes_read: usize,
+}
+
+/// Timings collected while recording a load report.
+#[derive(Debug, Clone)]
+pub(crate) struct LoadTimings {
+    pub(crate) started: Instant,
+    pub(crate) started_at: SystemTime,
+    pub(crate) objects: Vec<ObjectTiming>,
+}
+
+impl LoadTimings {
+    pub(crate) fn new() -> Self {
+        LoadTimings {
+            started: Instant::now(),
+            started_at: SystemTime::now(),
+            objects: Vec::new(),
+        }
+    }
+}
+
+/// A single deserialized object in a [`LoadReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ObjectLoadReport {
+    pub package: String,
+    pub full_name: String,
+    pub class_name: String,
+    /// Zero-based index of the object in its package's export table
+    pub export_index: usize,
+    /// When deserialization started, in microseconds since recording started
+    pub started_us: u64,
+    /// Time spent deserializing, in microseconds. Includes loading any objects it referenced
+    /// which hadn't been loaded yet.
+    pub elapsed_us: u64,
+    pub bytes_read: usize,
+    /// Number of objects it depends on: its class, super, outer, and the class of an object
+    /// property
+    pub dependencies: usize,
+    /// Problems found while loading the object, such as unknown flag bits or a size mismatch
+    pub warnings: Vec<String>,
+}
+
+/// Per-object timings and problems from loading packages, to help find slow or suspicious
+/// exports. Recording is enabled with `set_record_load_report`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LoadReport {
+    /// When recording started, in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Time from when recording started to when the report was built, in microseconds
+    pub elapsed_us: u64,
+    /// Objects in the order they finished deserializing
+    pub objects: Vec<ObjectLoadReport>,
+}
+
+impl LoadReport {
+    pub(crate) fn build(runtime: &UnrealRuntime, timings: &LoadTimings) -> Self {
+        let graph = runtime.dependency_graph();
+
+        let objects = timings
+            .objects
+            .iter()
+            .map(|timing| {
+                let mut warnings = runtime
+                    .serial_size_mismatches
+                    .iter()
+                    .filter(|mismatch| mismatch.object_name == timing.full_name)
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+
+                let export_index = ExportIndex::from_raw(timing.export_index as i32 + 1);
+                let object = runtime
+                    .linker(&timing.package)
+                    .and_then(|linker| linker.borrow().objects.get(&export_index).map(Rc::clone));
+                if let Some(object) = object {
+                    warnings.extend(unknown_flag_warnings(&object));
+                }
+
+                ObjectLoadReport {
+                    package: timing.package.clone(),
+                    full_name: timing.full_name.clone(),
+                    class_name: timing.class_name.clone(),
+                    export_index: timing.export_index,
+                    started_us: timing.started.as_micros() as u64,
+                    elapsed_us: timing.elapsed.as_micros() as u64,
+                    bytes_read: timing.bytes_read,
+                    dependencies: graph.dependencies(&timing.full_name).count(),
+                    warnings,
+                }
+            })
+            .collect();
+
+        LoadReport {
+            started_at: timings
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            elapsed_us: timings.started.elapsed().as_micros() as u64,
+            objects,
+        }
+    }
+
+    /// The `count` objects which took longest to deserialize, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<&ObjectLoadReport> {
+        let mut objects = self.objects.iter().collect::<Vec<_>>();
+        objects.sort_by_key(|object| std::cmp::Reverse(object.elapsed_us));
+        objects.truncate(count);
+
+        objects
+    }
+
+    /// Objects with at least one warning.
+    pub fn with_warnings(&self) -> impl Iterator<Item = &ObjectLoadReport> {
+        self.objects
+            .iter()
+            .filter(|object| !object.warnings.is_empty())
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+fn unknown_flag_warnings(object: &RcUnrealObject) -> Vec<String> {
+    let object = object.borrow();
+    let mut warnings = Vec::new();
+
+    let object_flags = object.base_object().flags().unknown_bits();
+    if object_flags != 0 {
+        warnings.push(format!("unknown object flags {object_flags:#010X}"));
+    }
+
+    let property_flags = object
+        .parent_of_kind(UObjectKind::Property)
+        .and_then(|property| property.as_any().downcast_ref::<Property>())
+        .map_or(0, |property| property.property_flags.unknown_bits());
+    if property_flags != 0 {
+        warnings.push(format!("unknown property flags {property_flags:#010X}"));
+    }
+
+    let function_flags = object
+        .parent_of_kind(UObjectKind::Function)
+        .and_then(|function| function.as_any().downcast_ref::<Function>())
+        .map_or(0, |function| function.function_flags.unknown_bits());
+    if function_flags != 0 {
+        warnings.push(format!("unknown function flags {function_flags:#010X}"));
+    }
+
+    warnings
+}
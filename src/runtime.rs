@@ -1,19 +1,36 @@
 use std::{
     cell::RefCell,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     io::{self, SeekFrom},
     rc::{Rc, Weak},
+    time::Instant,
 };
 
 use byteorder::ByteOrder;
 use tracing::{Level, debug, info, span, trace};
 
-use crate::object::{DeserializeUnrealObject, RcUnrealObject, deserialize_object};
+use crate::object::internal::natives::NativeFunctionTable;
+use crate::object::internal::object_ref::ReferencePolicy;
+use crate::object::internal::probes::ProbeNameTable;
+use crate::object::intrinsic::find_native_class;
+use crate::object::{
+    BuiltinObject, DeserializeUnrealObject, RcUnrealObject, TypedObject,
+    builtins::{Class, Field, Function, Struct},
+    deserialize_object,
+};
 use crate::{
-    de::{ExportIndex, ImportIndex, Linker, read_package},
-    object::builtins::*,
+    de::{ExportIndex, ImportIndex, Linker, NamePool, read_package},
+    graph::{DependencyGraph, Referencer},
+    hierarchy::ClassHierarchy,
     object::{ObjectFlags, UObjectKind, UnrealObject},
+    observer::{LinkerEvent, LoadObserver, ObjEvent},
+    postload::{PostLoadHook, PostLoadQueue},
+    profile::FormatProfile,
     reader::LinRead,
+    report::{LoadReport, LoadTimings, ObjectTiming},
+    snapshot::Snapshot,
+    stats::LinkerUsage,
+    warnings::{LoadWarning, WarningKind, Warnings},
 };
 
 type RcLinker = Rc<RefCell<Linker>>;
@@ -27,29 +44,183 @@ impl RcUnrealObjPointer {
     }
 }
 
+#[derive(Default)]
 pub struct UnrealRuntime {
-    pub linkers: HashMap<String, RcLinker>,
+    /// Loaded linkers, ordered by name
+    pub linkers: BTreeMap<String, RcLinker>,
+    /// Names of `linkers` in the order they were loaded
+    pub(crate) linker_load_order: Vec<String>,
+    /// Names shared between all loaded linkers
+    pub(crate) name_pool: NamePool,
     pub objects_full_loading: HashSet<RcUnrealObjPointer>,
+    /// Whether object references encountered during deserialization are loaded immediately
+    pub reference_policy: ReferencePolicy,
+    /// User-supplied format profile. When `None`, each linker detects its own.
+    pub format_profile: Option<FormatProfile>,
+    /// Objects which have no backing export, keyed by lowercased full name
+    pub intrinsic_objects: BTreeMap<String, RcUnrealObject>,
+    /// Hooks notified as linkers and objects are loaded
+    pub observers: Vec<Box<dyn LoadObserver>>,
+    /// Objects waiting to be post-loaded and the hooks run on them
+    pub(crate) post_load: PostLoadQueue,
+    /// Number of nested [`UnrealRuntime::load_batch`] calls. Post-loading waits until the
+    /// outermost batch finishes.
+    pub(crate) batch_depth: usize,
+    /// Deserialization timings, collected while a load report is being recorded
+    pub(crate) load_timings: Option<LoadTimings>,
+    pub load_options: LoadOptions,
+    /// Current depth of nested `load_object_by_export_index` calls
+    pub(crate) load_depth: usize,
+    pub(crate) objects_constructed: usize,
+    pub(crate) bytes_deserialized: u64,
+    /// Names of native functions referenced by script bytecode
+    pub native_functions: NativeFunctionTable,
+    /// Names of the probe functions in states' probe and ignore masks
+    pub probe_names: ProbeNameTable,
+    /// Size mismatches recorded while loading in lenient mode
+    pub serial_size_mismatches: Vec<SerialSizeMismatch>,
+    /// Conditions noticed while loading which didn't stop the load
+    pub warnings: Warnings,
+    /// Whether packages are read from standalone files. Linear files embed every package they
+    /// depend on, but a standalone file only holds its own package, so imports from packages
+    /// which aren't loaded resolve to intrinsic placeholders instead.
+    pub(crate) standalone: bool,
+    /// Lengths of the packages in a linear file, keyed by lowercased package name, from its file
+    /// table
+    pub(crate) package_lens: BTreeMap<String, u64>,
+    /// Lowercased full names of the exports whose data was skipped over rather than
+    /// deserialized. A linear file is streamed, so their data can't be read later.
+    pub(crate) skipped_exports: BTreeSet<String>,
+}
+
+/// Limits on how much the runtime will load when an object is requested.
+///
+/// Once a limit is reached, newly referenced objects are still constructed so that
+/// references to them can be held, but their dependencies are not loaded and their
+/// serialized data is not read.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// Maximum depth of nested object loads. The requested object is at depth 1.
+    pub max_depth: Option<usize>,
+    /// Maximum number of objects to construct.
+    pub max_objects: Option<usize>,
+    /// Maximum number of bytes of export data to deserialize.
+    pub max_bytes: Option<u64>,
+    /// Parse script bytecode without loading the objects it references.
+    pub skip_script_bodies: bool,
+    /// Don't attach debug info to the function calls it follows, and keep it as standalone
+    /// [`Expr::DebugInfo`](crate::Expr::DebugInfo) statements and arguments instead. For
+    /// scripts compiled without debug info whose bytes after a call happen to look like it.
+    pub skip_script_debug_info: bool,
+    /// Record exports whose deserialized size doesn't match their serial size and continue
+    /// loading instead of returning an error.
+    pub lenient: bool,
+}
+
+impl LoadOptions {
+    /// Whether these options can leave part of an export's data unread, which a reader that
+    /// can't seek back and forth has no way of skipping.
+    pub(crate) fn skips_data(&self) -> bool {
+        self.max_depth.is_some()
+            || self.max_objects.is_some()
+            || self.max_bytes.is_some()
+            || self.skip_script_bodies
+    }
+}
+
+/// An export whose deserialized size did not match the serial size in the export table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialSizeMismatch {
+    pub object_name: String,
+    pub class_name: String,
+    /// Serial size from the export table
+    pub expected: usize,
+    /// Number of bytes actually read while deserializing
+    pub actual: usize,
+    pub serial_offset: u64,
+    /// Stream position deserialization finished at
+    pub end_offset: u64,
+}
+
+impl std::fmt::Display for SerialSizeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "data read for {} ({}) does not match expected. Read {:#X} bytes, expected {:#X} (offset {:#X}..{:#X})",
+            self.object_name,
+            self.class_name,
+            self.actual,
+            self.expected,
+            self.serial_offset,
+            self.end_offset
+        )
+    }
 }
 
+impl std::error::Error for SerialSizeMismatch {}
+
+/// What was released by unloading a package or clearing the runtime.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct UnloadStats {
+    pub linkers: usize,
+    pub objects: usize,
+}
+
+/// How far an object is taken when it's loaded. Each kind does everything the one before it
+/// does.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) enum LoadKind {
-    Load,
+    /// Construct the object and link it to its class, outer and super struct, without
+    /// reading its data. Used for references which only need the object to exist.
     Create,
+    /// Also deserialize the object. Post-loading is deferred until the outermost load or
+    /// batch finishes, as the engine does, so that objects referring to each other are all
+    /// deserialized before any of them are post-loaded.
+    Load,
+    /// Also post-load the object before returning, even from inside a batch, unless an
+    /// object which may depend on it is still being deserialized; then it's post-loaded
+    /// along with that object. Classes are loaded this way so that their fields are linked
+    /// before their instances are deserialized. Their default properties aren't loaded, so
+    /// fully loading a class fails with [`io::ErrorKind::Unsupported`] once its declaration
+    /// has been read.
     Full,
 }
 
 impl UnrealRuntime {
-    fn load_linker<E, R>(&mut self, expected_name: String, reader: &mut R) -> io::Result<()>
+    /// Records a warning about `object`, or about the load as a whole.
+    pub(crate) fn warn(
+        &mut self,
+        kind: WarningKind,
+        object: Option<String>,
+        message: impl Into<String>,
+    ) {
+        self.warnings.push(LoadWarning {
+            kind,
+            object,
+            message: message.into(),
+        });
+    }
+
+    pub(crate) fn load_linker<E, R>(
+        &mut self,
+        expected_name: String,
+        reader: &mut R,
+    ) -> io::Result<()>
     where
         R: LinRead,
         E: ByteOrder,
     {
-        reader.set_reading_linker_header(true);
-        let package = read_package::<E, _>(reader)?;
-        reader.set_reading_linker_header(false);
-
-        let linker = Rc::new(RefCell::new(Linker::new(expected_name.clone(), package)));
+        if let Some(profile) = &self.format_profile {
+            reader.set_string_encoding(profile.quirks.string_encoding);
+        }
+        let package = reader.unverified_scope(|reader| read_package::<E, _>(reader))?;
+
+        let linker = Rc::new(RefCell::new(Linker::new(
+            expected_name.clone(),
+            package,
+            &mut self.name_pool,
+            self.format_profile.clone(),
+        )));
         let linker_inner = linker.borrow();
 
         // for export in &linker_inner.package.exports {
@@ -64,35 +235,438 @@ impl UnrealRuntime {
 
         drop(linker_inner);
 
+        debug!("Name pool size: {}", self.name_pool.len());
+
+        {
+            let linker_inner = linker.borrow();
+            let event = LinkerEvent {
+                name: &expected_name,
+                name_count: linker_inner.package.names.len(),
+                import_count: linker_inner.package.imports.len(),
+                export_count: linker_inner.package.exports.len(),
+            };
+            for observer in &mut self.observers {
+                observer.on_linker_loaded(&event)?;
+            }
+        }
+
+        self.linker_load_order.push(expected_name.clone());
         self.linkers.insert(expected_name, linker);
 
         Ok(())
     }
 
-    fn linker(&self, name: &str) -> Option<RcLinker> {
-        self.linkers.get(name).map(Rc::clone)
+    /// Unloads the package `name` and every object loaded from it.
+    ///
+    /// Objects can refer to each other in cycles, so each unloaded object is reset to its
+    /// default state to release its references. Handles to them held elsewhere stay valid but
+    /// no longer hold any data. Packages which another loaded package imports from can't be
+    /// unloaded.
+    pub fn unload_package(&mut self, name: &str) -> io::Result<UnloadStats> {
+        let Some(key) = self
+            .linkers
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case(name))
+            .cloned()
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("package {name} is not loaded"),
+            ));
+        };
+
+        let dependent = self.linkers.iter().find(|(other, linker)| {
+            let linker = linker.borrow();
+            **other != key
+                && linker.package.imports.iter().any(|import| {
+                    import.package_index == 0
+                        && import.object_name(&linker).eq_ignore_ascii_case(&key)
+                })
+        });
+        if let Some((dependent, _)) = dependent {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("package {key} is still imported by {dependent}"),
+            ));
+        }
+
+        debug!("Unloading package {key}");
+
+        let linker = self.linkers.remove(&key).expect("linker was just found");
+        self.linker_load_order.retain(|loaded| *loaded != key);
+
+        let objects = std::mem::take(&mut linker.borrow_mut().objects);
+        let stats = UnloadStats {
+            linkers: 1,
+            objects: self.release_objects(objects.into_values()),
+        };
+
+        Ok(stats)
+    }
+
+    /// Unloads every package and intrinsic object. Options, observers and the native function
+    /// table are kept.
+    pub fn clear(&mut self) -> UnloadStats {
+        let linkers = std::mem::take(&mut self.linkers);
+        let mut objects = linkers
+            .values()
+            .flat_map(|linker| std::mem::take(&mut linker.borrow_mut().objects).into_values())
+            .collect::<Vec<_>>();
+        objects.extend(std::mem::take(&mut self.intrinsic_objects).into_values());
+
+        let stats = UnloadStats {
+            linkers: linkers.len(),
+            objects: self.release_objects(objects),
+        };
+
+        self.linker_load_order.clear();
+        self.objects_full_loading.clear();
+        self.post_load.pending.clear();
+        self.name_pool = NamePool::default();
+        self.load_depth = 0;
+        self.batch_depth = 0;
+        self.objects_constructed = 0;
+        self.bytes_deserialized = 0;
+        self.serial_size_mismatches.clear();
+        self.warnings.clear();
+        if self.load_timings.is_some() {
+            self.load_timings = Some(LoadTimings::new());
+        }
+
+        debug!(
+            "Cleared {} linkers and {} objects",
+            stats.linkers, stats.objects
+        );
+
+        stats
+    }
+
+    /// Resets each object so that the references between them are dropped. Returns the number
+    /// of objects released.
+    fn release_objects(&mut self, objects: impl IntoIterator<Item = RcUnrealObject>) -> usize {
+        let mut count = 0;
+        for obj in objects {
+            self.objects_full_loading
+                .remove(&RcUnrealObjPointer::from_unreal_object(&obj));
+
+            let mut obj = obj.borrow_mut();
+            let kind = obj.kind();
+            kind.release(&mut *obj);
+            count += 1;
+        }
+
+        count
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn LoadObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Sets whether deserialization timings are recorded for a [`LoadReport`]. Enabling
+    /// recording restarts it, discarding timings recorded so far.
+    pub fn set_record_load_report(&mut self, record: bool) {
+        self.load_timings = record.then(LoadTimings::new);
+    }
+
+    /// Builds a report of every object deserialized since recording was enabled, or `None`
+    /// if it isn't.
+    pub fn load_report(&self) -> Option<LoadReport> {
+        self.load_timings
+            .as_ref()
+            .map(|timings| LoadReport::build(self, timings))
+    }
+
+    /// Registers a hook which is run on every object of `kind`, or a subclass of it, once it's
+    /// been loaded. See [`UnrealRuntime::run_post_load_passes`].
+    pub fn add_post_load_hook(&mut self, kind: UObjectKind, hook: Box<dyn PostLoadHook>) {
+        self.post_load.hooks.push((kind, hook));
+    }
+
+    /// Post-loads every object deserialized since the last pass. Objects are post-loaded
+    /// after their class, super struct, and outer, and each registered hook whose kind the
+    /// object is runs on it in registration order. Returns the number of objects post-loaded.
+    ///
+    /// Like the engine, this runs automatically once the outermost load finishes, so that
+    /// hooks see objects with all of their references deserialized.
+    pub fn run_post_load_passes(&mut self) -> io::Result<usize> {
+        let ordered = self.post_load.take_ordered();
+        let mut count = 0;
+        for (i, obj) in ordered.iter().enumerate() {
+            if !obj.borrow().base_object().needs_post_load() {
+                continue;
+            }
+
+            for (kind, hook) in &mut self.post_load.hooks {
+                // The object isn't borrowed while hooks run so that they can modify it
+                let is_kind = obj.borrow().is_a(*kind);
+                if !is_kind {
+                    continue;
+                }
+
+                if let Err(e) = hook.post_load(obj) {
+                    // Leave the rest for the next pass
+                    self.post_load.pending.extend(ordered[i..].iter().cloned());
+                    return Err(e);
+                }
+            }
+
+            obj.borrow_mut().base_object_mut().post_loaded();
+            count += 1;
+        }
+
+        if count > 0 {
+            debug!("Post-loaded {count} objects");
+        }
+
+        Ok(count)
+    }
+
+    /// Runs `load`, holding off post-loading until it returns so that objects loaded by
+    /// separate calls are post-loaded together.
+    pub(crate) fn load_batch<T>(
+        &mut self,
+        load: impl FnOnce(&mut Self) -> io::Result<T>,
+    ) -> io::Result<T> {
+        self.batch_depth += 1;
+        let result = load(self);
+        self.batch_depth -= 1;
+
+        let value = result?;
+        if self.batch_depth == 0 {
+            self.run_post_load_passes()?;
+        }
+
+        Ok(value)
+    }
+
+    /// Builds a dependency graph covering every loaded linker.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for linker in self.linkers_in_load_order() {
+            graph.add_linker(&linker.borrow());
+        }
+
+        graph
+    }
+
+    /// The objects and packages which refer to the object or package named `full_name`, e.g.
+    /// `Engine.Actor`. References come from the loaded linkers' tables and from the
+    /// properties and scripts of objects which have been loaded.
+    pub fn referencers_of(&self, full_name: &str) -> Vec<Referencer> {
+        self.dependency_graph().dependents(full_name)
+    }
+
+    /// Builds the class hierarchy of every loaded linker.
+    pub fn class_hierarchy(&self) -> ClassHierarchy {
+        let mut hierarchy = ClassHierarchy::new();
+        for linker in self.linkers_in_load_order() {
+            let linker = linker.borrow();
+            hierarchy.add_package(&linker.name, &linker.package);
+        }
+
+        hierarchy
+    }
+
+    /// Object counts and estimated memory usage of every loaded linker, in the order they
+    /// were loaded.
+    pub fn linker_usage(&self) -> Vec<LinkerUsage> {
+        self.linkers_in_load_order()
+            .map(|linker| linker.borrow().usage())
+            .collect()
+    }
+
+    /// Every loaded object along with the name of the package it was loaded from and its
+    /// index in that package's export table. Packages are visited in the order they were
+    /// loaded, and each package's objects in export table order.
+    pub fn iter_objects(&self) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.linkers_in_load_order().flat_map(|linker| {
+            let linker = linker.borrow();
+            linker
+                .objects
+                .iter()
+                .map(|(index, obj)| (linker.name.clone(), index.index(), Rc::clone(obj)))
+                .collect::<Vec<_>>()
+        })
     }
 
+    /// Every loaded object which is a `kind`, including kinds derived from it.
+    pub fn iter_objects_of_kind(
+        &self,
+        kind: UObjectKind,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.iter_objects()
+            .filter(move |(_, _, obj)| obj.borrow().is_a(kind))
+    }
+
+    /// Every loaded object which has all of `flags` set.
+    pub fn iter_objects_with_flags(
+        &self,
+        flags: ObjectFlags,
+    ) -> impl Iterator<Item = (String, usize, RcUnrealObject)> + '_ {
+        self.iter_objects()
+            .filter(move |(_, _, obj)| obj.borrow().base_object().flags().contains(flags))
+    }
+
+    /// Copies the object graph into a [`Snapshot`] which can be shared between threads.
+    pub fn freeze(&self) -> Snapshot {
+        Snapshot::freeze(self)
+    }
+
+    /// Every loaded linker, in the order they were loaded.
+    pub(crate) fn linkers_in_load_order(&self) -> impl Iterator<Item = RcLinker> + '_ {
+        self.linker_load_order
+            .iter()
+            .filter_map(|name| self.linker(name))
+    }
+
+    /// Returns the linker for the package `name`, ignoring ASCII case.
+    pub(crate) fn linker(&self, name: &str) -> Option<RcLinker> {
+        self.linkers
+            .get(name)
+            .or_else(|| {
+                self.linkers
+                    .iter()
+                    .find(|(linker_name, _)| linker_name.eq_ignore_ascii_case(name))
+                    .map(|(_, linker)| linker)
+            })
+            .map(Rc::clone)
+    }
+
+    /// Finds a loaded object named `name`. Linkers are searched in load order so the first
+    /// package loaded wins if several contain a match.
     fn find_object(&self, name: &str) -> Option<RcUnrealObject> {
-        self.linkers.values().find_map(|linker| {
+        self.linkers_in_load_order().find_map(|linker| {
             linker
                 .borrow()
                 .objects
                 .values()
-                .find(|obj| obj.borrow().base_object().name() == name)
+                .find(|obj| obj.borrow().base_object().name().eq_ignore_ascii_case(name))
                 .map(Rc::clone)
         })
     }
 
-    fn linker_by_export_name_mut(&mut self, name: &str) -> Option<RcLinker> {
-        let key = self.linkers.iter().find_map(|(name, linker)| {
-            linker
-                .borrow()
-                .find_export_by_name(name)
-                .map(|_| name.clone())
-        });
+    /// The object named `full_name`, e.g. `Engine.Actor.Spawn`, if it's been loaded.
+    pub(crate) fn loaded_object(&self, full_name: &str) -> Option<RcUnrealObject> {
+        let (package, path) = full_name.split_once('.')?;
+        let linker = self.linker(package)?;
+        let linker = linker.borrow();
+        let (export_index, _) = linker.find_export_by_path(path)?;
+
+        linker
+            .objects
+            .get(&export_index)
+            .filter(|obj| !obj.borrow().base_object().needs_load())
+            .map(Rc::clone)
+    }
 
-        key.and_then(|k| self.linkers.get(&k).map(Rc::clone))
+    /// The loaded object named `full_name` if it's a `T`.
+    pub fn get_object<T: BuiltinObject>(&self, full_name: &str) -> Option<TypedObject<T>> {
+        self.loaded_object(full_name).and_then(TypedObject::new)
+    }
+
+    /// The loaded class named `full_name`, e.g. `Engine.Actor`.
+    pub fn get_class(&self, full_name: &str) -> Option<TypedObject<Class>> {
+        self.get_object(full_name)
+    }
+
+    /// The loaded function named `full_name`, e.g. `Engine.Actor.Spawn`.
+    pub fn get_function(&self, full_name: &str) -> Option<TypedObject<Function>> {
+        self.get_object(full_name)
+    }
+
+    /// The loaded struct named `full_name`, e.g. `Core.Object.Vector`. Functions, states and
+    /// classes are structs too.
+    pub fn get_struct(&self, full_name: &str) -> Option<TypedObject<Struct>> {
+        self.get_object(full_name)
+    }
+
+    /// Returns the object standing in for the package `package`, constructing it if it does
+    /// not exist yet.
+    fn intrinsic_package(&mut self, package: &str) -> RcUnrealObject {
+        let key = package.to_ascii_lowercase();
+        if let Some(obj) = self.intrinsic_objects.get(&key) {
+            return Rc::clone(obj);
+        }
+
+        let obj = UObjectKind::Object.construct_intrinsic();
+        {
+            let mut obj_inner = obj.borrow_mut();
+            let base = obj_inner.base_object_mut();
+            base.set_name(self.name_pool.intern(package));
+            base.set_concrete_obj(Rc::downgrade(&obj));
+        }
+
+        self.intrinsic_objects.insert(key, Rc::clone(&obj));
+
+        obj
+    }
+
+    /// Returns the intrinsic object for `package.name`, constructing it and its outer
+    /// package if it does not exist yet.
+    fn intrinsic_object(&mut self, package: &str, name: &str, kind: UObjectKind) -> RcUnrealObject {
+        let full_name = format!("{package}.{name}");
+        // Keyed in lowercase so that differently-cased references share an object
+        let key = full_name.to_ascii_lowercase();
+        if let Some(obj) = self.intrinsic_objects.get(&key) {
+            return Rc::clone(obj);
+        }
+
+        debug!("Constructing intrinsic object {full_name} of kind {kind:?}");
+
+        let package_obj = self.intrinsic_package(package);
+
+        // Core.Class is its own class. Don't link it to itself to avoid a reference cycle.
+        let is_core_class =
+            package.eq_ignore_ascii_case("Core") && name.eq_ignore_ascii_case("Class");
+        let class = if kind == UObjectKind::Class && !is_core_class {
+            Some(self.intrinsic_object("Core", "Class", UObjectKind::Class))
+        } else {
+            None
+        };
+
+        let obj = kind.construct_intrinsic();
+        {
+            let mut obj_inner = obj.borrow_mut();
+            let base = obj_inner.base_object_mut();
+            base.set_name(self.name_pool.intern(name));
+            base.set_flags(ObjectFlags::NATIVE);
+            base.set_outer_object(Rc::downgrade(&package_obj));
+            if let Some(class) = class {
+                base.set_class(class);
+            }
+            base.set_concrete_obj(Rc::downgrade(&obj));
+            base.loaded();
+            base.post_loaded();
+        }
+
+        self.intrinsic_objects.insert(key, Rc::clone(&obj));
+
+        obj
+    }
+
+    /// The linker exporting `path`. Paths exported by several linkers are an error, since the
+    /// one meant can't be told apart without the package name.
+    fn linker_by_export_path(&self, path: &str) -> io::Result<Option<RcLinker>> {
+        let mut matches = self
+            .linkers
+            .iter()
+            .filter(|(_, linker)| linker.borrow().find_export_by_path(path).is_some());
+        let Some((name, linker)) = matches.next() else {
+            return Ok(None);
+        };
+
+        let others = matches.map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        if !others.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{path} is exported by {name} and {}; qualify it with its package",
+                    others.join(", ")
+                ),
+            ));
+        }
+
+        Ok(Some(Rc::clone(linker)))
     }
 
     pub fn full_load_object<E, R>(&mut self, obj: &RcUnrealObject, reader: &mut R) -> io::Result<()>
@@ -107,6 +681,11 @@ impl UnrealRuntime {
             return Ok(());
         }
 
+        if obj.borrow().base_object().is_intrinsic() {
+            trace!("Object is intrinsic; nothing to load");
+            return Ok(());
+        }
+
         let (linker, export_index) = {
             // This is unfortunately re-entrant.
             let obj_inner = obj.borrow();
@@ -164,6 +743,23 @@ impl UnrealRuntime {
         }
     }
 
+    /// Whether the depth or object count limits in [`LoadOptions`] have been reached.
+    fn load_limit_reached(&self) -> bool {
+        let options = &self.load_options;
+
+        options.max_depth.is_some_and(|max| self.load_depth > max)
+            || options
+                .max_objects
+                .is_some_and(|max| self.objects_constructed >= max)
+    }
+
+    /// Length of `package` from the linear file's table, if it's listed there.
+    pub(crate) fn package_len(&self, package: &str) -> Option<u64> {
+        self.package_lens
+            .get(&package.to_ascii_lowercase())
+            .copied()
+    }
+
     /// Loads and deserializes an object and its depencies by the export index.
     pub fn load_object_by_export_index<E, R>(
         &mut self,
@@ -176,14 +772,36 @@ impl UnrealRuntime {
         R: LinRead,
         E: ByteOrder,
     {
-        debug!("Linker count: {}", self.linkers.len());
-        for (name, linker) in self.linkers.iter() {
-            debug!(
-                "Linker {name} object count: {}",
-                linker.borrow().objects.len()
-            );
+        self.load_depth += 1;
+        let result =
+            self.load_object_by_export_index_inner::<E, _>(export_index, linker, load_kind, reader);
+        self.load_depth -= 1;
+
+        // Objects are post-loaded once the outermost load finishes, or as soon as a full load
+        // finishes if nothing is still being deserialized which they could depend on
+        let obj = result?;
+        let outermost = self.load_depth == 0 && self.batch_depth == 0;
+        let settled = load_kind == LoadKind::Full && self.objects_full_loading.is_empty();
+        if outermost || settled {
+            self.run_post_load_passes()?;
         }
 
+        Ok(obj)
+    }
+
+    fn load_object_by_export_index_inner<E, R>(
+        &mut self,
+        export_index: ExportIndex,
+        linker: &Rc<RefCell<Linker>>,
+        load_kind: LoadKind,
+        reader: &mut R,
+    ) -> io::Result<RcUnrealObject>
+    where
+        R: LinRead,
+        E: ByteOrder,
+    {
+        let limited = self.load_limit_reached();
+
         let linker_inner = linker.borrow();
 
         let export = linker_inner
@@ -230,20 +848,31 @@ impl UnrealRuntime {
                 "Constructing new object: {}, class = {}",
                 export_full_name, class_name
             );
-            let object_kind = UObjectKind::try_from(export.class_name(&linker_inner))
-                .unwrap_or_else(|_| panic!("could not find object kind {}", class_name));
+            // Instances of script classes are loaded as plain objects; their real class is
+            // kept on the object instead
+            let builtin_kind = UObjectKind::try_from(
+                linker_inner
+                    .profile
+                    .resolve_class_alias(export.class_name(&linker_inner)),
+            )
+            .ok();
+            if builtin_kind.is_none() {
+                debug!(
+                    "{class_name} is not a builtin class; loading {export_full_name} as an Object"
+                );
+            }
+            let object_kind = builtin_kind.unwrap_or(UObjectKind::Object);
 
             trace!("Resolved object kind: {object_kind:?}");
 
             let constructed_object = object_kind.construct(Rc::downgrade(linker), export_index);
             let mut object = constructed_object.borrow_mut();
-            object.base_object_mut().set_flags(
-                ObjectFlags::from_bits(export.object_flags)
-                    .expect("failed to construct ObjectFlags"),
-            );
             object
                 .base_object_mut()
-                .set_name(export.object_name(&linker_inner).to_owned());
+                .set_flags(ObjectFlags::from_bits_retain(export.object_flags));
+            object
+                .base_object_mut()
+                .set_name(linker_inner.name(export.object_name));
             object
                 .base_object_mut()
                 .set_concrete_obj(Rc::downgrade(&constructed_object));
@@ -257,12 +886,42 @@ impl UnrealRuntime {
 
             let contains_key = linker.borrow().objects.contains_key(&export_index);
 
+            if limited {
+                debug!("Load limits reached; not loading dependencies of {export_full_name}");
+
+                linker
+                    .borrow_mut()
+                    .objects
+                    .insert(export_index, Rc::clone(&constructed_object));
+                self.objects_constructed += 1;
+
+                return Ok(constructed_object);
+            }
+
             // If this is a struct, load the dependencies
             if class_index != 0 {
                 trace!("Loading class...");
                 // Load dependent types
 
-                self.load_object_by_raw_index::<E, _>(class_index, linker, LoadKind::Full, reader)?;
+                // Script classes only need to exist to be linked as the object's class
+                let class_load_kind = if builtin_kind.is_some() {
+                    LoadKind::Full
+                } else {
+                    LoadKind::Create
+                };
+                let class = self.load_object_by_raw_index::<E, _>(
+                    class_index,
+                    linker,
+                    class_load_kind,
+                    reader,
+                )?;
+
+                if let Some(class) = class {
+                    constructed_object
+                        .borrow_mut()
+                        .base_object_mut()
+                        .set_class(class);
+                }
             }
 
             let parent = self.load_object_by_raw_index::<E, _>(
@@ -282,7 +941,7 @@ impl UnrealRuntime {
                 constructed_object
                     .borrow_mut()
                     .base_object_mut()
-                    .set_outer_object(parent);
+                    .set_outer_object(Rc::downgrade(&parent));
             }
 
             let returned_obj = if let Some(obj) = object_parsed_by_parent {
@@ -292,6 +951,20 @@ impl UnrealRuntime {
                     .borrow_mut()
                     .objects
                     .insert(export_index, Rc::clone(&constructed_object));
+                self.objects_constructed += 1;
+
+                let linker_name = linker.borrow().name.clone();
+                let event = ObjEvent {
+                    linker_name: &linker_name,
+                    full_name: &export_full_name,
+                    class_name: &class_name,
+                    export_index: export_index.index(),
+                    object: &constructed_object,
+                    serial_size: None,
+                };
+                for observer in &mut self.observers {
+                    observer.on_object_constructed(&event)?;
+                }
 
                 constructed_object
             };
@@ -299,12 +972,20 @@ impl UnrealRuntime {
             // Ensure super class is loaded.
             if is_struct && export.super_index != 0 {
                 trace!("Loading super item");
-                self.load_object_by_raw_index::<E, _>(
+                let super_struct = self.load_object_by_raw_index::<E, _>(
                     export.super_index,
                     linker,
                     LoadKind::Create,
                     reader,
                 )?;
+                if let Some(super_struct) = super_struct
+                    && let Some(field) = returned_obj
+                        .borrow_mut()
+                        .parent_of_kind_mut(UObjectKind::Field)
+                        .and_then(|field| field.as_any_mut().downcast_mut::<Field>())
+                {
+                    field.set_super_field(super_struct);
+                }
                 trace!("Super item loaded");
             }
 
@@ -320,6 +1001,36 @@ impl UnrealRuntime {
                 debug!("Returning -- object was loaded with LoadKind::Create");
             }
             LoadKind::Full | LoadKind::Load => {
+                if self
+                    .skipped_exports
+                    .contains(&export_full_name.to_ascii_lowercase())
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!("the data of {export_full_name} was skipped and can't be reread"),
+                    ));
+                }
+
+                // Caught here rather than as an unexpected EOF partway through deserializing
+                let package_len = self.package_len(&linker.borrow().name);
+                reader.set_package_len(package_len);
+                let serial_range = export.checked_serial_range(&export_full_name, reader)?;
+                let serial_len = serial_range.end - serial_range.start;
+
+                let exceeds_bytes = self
+                    .load_options
+                    .max_bytes
+                    .is_some_and(|max| self.bytes_deserialized.saturating_add(serial_len) > max);
+                if limited || exceeds_bytes {
+                    self.warn(
+                        WarningKind::SkippedExport,
+                        Some(export_full_name),
+                        "load limits reached; not deserializing",
+                    );
+
+                    return Ok(obj);
+                }
+
                 // Ensure super class is loaded.
                 let is_struct = obj.borrow().is_a(UObjectKind::Struct);
                 if is_struct && export.super_index != 0 {
@@ -333,8 +1044,21 @@ impl UnrealRuntime {
                     trace!("Super item loaded");
                 }
 
-                let pointer_value = RcUnrealObjPointer::from_unreal_object(&obj);
-                self.objects_full_loading.insert(pointer_value);
+                // Cooked packages strip editor-only objects down to their export entry
+                if serial_len == 0 {
+                    let mut obj_inner = obj.borrow_mut();
+                    let obj_base = obj_inner.base_object_mut();
+                    if obj_base.needs_load() {
+                        debug!("{export_full_name} has no data; leaving it empty");
+
+                        obj_base.set_stripped();
+                        obj_base.loaded();
+                        obj_base.post_loaded();
+                    }
+                    drop(obj_inner);
+
+                    return Ok(obj);
+                }
 
                 let obj_inner = obj.borrow();
                 let obj_base = obj_inner.base_object();
@@ -354,34 +1078,137 @@ impl UnrealRuntime {
 
                 debug!("Export is {export:X?}");
 
-                trace!("Seeking to export position");
                 let saved_pos = reader.stream_position()?;
-                reader.seek(SeekFrom::Start(export.serial_offset()))?;
-
-                deserialize_object::<E, _>(self, Rc::clone(&obj), linker, reader)?;
+                let pointer_value = RcUnrealObjPointer::from_unreal_object(&obj);
+                self.objects_full_loading.insert(pointer_value);
 
-                let current_pos = reader.stream_position()?;
-                let read_size = (current_pos - export.serial_offset()) as usize;
-                assert_eq!(
-                    read_size,
-                    export.serial_size(),
-                    "Data read for export does not match expected. Read {read_size:#X} bytes, expected {:#X}",
-                    export.serial_size()
+                let result = self.deserialize_export::<E, _>(
+                    &obj,
+                    linker,
+                    export_index,
+                    &export_full_name,
+                    &class_name,
+                    reader,
                 );
 
+                // Whether or not it succeeded the object can be loaded again, from where the
+                // caller left the stream
+                self.objects_full_loading.remove(&pointer_value);
                 trace!("Seeking back to saved position");
-                reader.seek(SeekFrom::Start(saved_pos))?;
+                let restored = reader.seek(SeekFrom::Start(saved_pos));
+                result?;
+                restored?;
+            }
+        }
+
+        Ok(obj)
+    }
 
-                obj.borrow_mut().base_object_mut().loaded();
+    /// Reads `obj`'s data from its export and notifies observers. The caller is responsible for
+    /// tracking the object as being full loaded and restoring the stream position afterwards.
+    fn deserialize_export<E, R>(
+        &mut self,
+        obj: &RcUnrealObject,
+        linker: &Rc<RefCell<Linker>>,
+        export_index: ExportIndex,
+        export_full_name: &str,
+        class_name: &str,
+        reader: &mut R,
+    ) -> io::Result<()>
+    where
+        R: LinRead,
+        E: ByteOrder,
+    {
+        let export = linker
+            .borrow()
+            .find_export_by_index(export_index)
+            .expect("could not find export")
+            .clone();
 
-                self.objects_full_loading.remove(&pointer_value);
+        trace!("Seeking to export position");
+        reader.seek(SeekFrom::Start(export.serial_offset()))?;
+
+        reader.begin_object_annotation(export_full_name, class_name, export.serial_offset());
+        let deserialize_started = Instant::now();
+        let result = deserialize_object::<E, _>(self, Rc::clone(obj), linker, reader);
+        let elapsed = deserialize_started.elapsed();
+        reader.end_object_annotation();
+        result?;
+
+        let current_pos = reader.stream_position()?;
+        let Some(read_size) = current_pos.checked_sub(export.serial_offset()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{export_full_name} finished deserializing at {current_pos:#X}, before its data starts at {:#X}",
+                    export.serial_offset()
+                ),
+            ));
+        };
+        let read_size = read_size as usize;
+        if read_size != export.serial_size() {
+            let mismatch = SerialSizeMismatch {
+                object_name: export_full_name.to_owned(),
+                class_name: class_name.to_owned(),
+                expected: export.serial_size(),
+                actual: read_size,
+                serial_offset: export.serial_offset(),
+                end_offset: current_pos,
+            };
+
+            if !self.load_options.lenient {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, mismatch));
+            }
+
+            self.warn(
+                WarningKind::SerialSizeMismatch,
+                Some(mismatch.object_name.clone()),
+                format!("{mismatch}; realigning stream"),
+            );
+
+            // Realign the stream to the end of this export's data
+            if read_size < export.serial_size() {
+                reader.skip(export.serial_size() - read_size)?;
+            } else {
+                reader.seek(SeekFrom::Start(
+                    export.serial_offset() + export.serial_size() as u64,
+                ))?;
             }
+
+            self.serial_size_mismatches.push(mismatch);
+        }
+
+        obj.borrow_mut().base_object_mut().loaded();
+        self.bytes_deserialized += read_size as u64;
+        if let Some(timings) = &mut self.load_timings {
+            timings.objects.push(ObjectTiming {
+                package: linker.borrow().name.clone(),
+                full_name: export_full_name.to_owned(),
+                class_name: class_name.to_owned(),
+                export_index: export_index.index(),
+                started: deserialize_started.duration_since(timings.started),
+                elapsed,
+                bytes_read: read_size,
+            });
+        }
+        if obj.borrow().base_object().needs_post_load() {
+            self.post_load.pending.push(Rc::clone(obj));
         }
 
-        // TODO: for experimentation
-        // obj.borrow_mut().base_object_mut().post_loaded();
+        let linker_name = linker.borrow().name.clone();
+        let event = ObjEvent {
+            linker_name: &linker_name,
+            full_name: export_full_name,
+            class_name,
+            export_index: export_index.index(),
+            object: obj,
+            serial_size: Some(read_size),
+        };
+        for observer in &mut self.observers {
+            observer.on_object_deserialized(&event)?;
+        }
 
-        Ok(obj)
+        Ok(())
     }
 
     pub fn load_object_by_full_name<E, R>(
@@ -394,9 +1221,9 @@ impl UnrealRuntime {
         R: LinRead,
         E: ByteOrder,
     {
-        let mut parts = full_name.split('.');
-        let module = parts.next().expect("object name does not have a module");
-        let object_name = parts.next().expect("object is not a full name");
+        // The first segment is the package, the rest is the object's path within it
+        let (module, object_path) = full_name.split_once('.').unwrap_or((full_name, ""));
+        let object_name = object_path.rsplit('.').next().unwrap_or(object_path);
 
         let span = span!(
             Level::DEBUG,
@@ -408,33 +1235,273 @@ impl UnrealRuntime {
 
         debug!("Looking up {full_name}");
 
-        if module == "Core"
-            && let Ok(kind) = UObjectKind::try_from(object_name)
+        // Native classes are never nested in groups
+        if object_path == object_name
+            && let Some(kind) = find_native_class(module, object_name)
         {
-            debug!("Object is a builtin of kind {kind:?}");
+            debug!("Object is a native class");
 
-            return Ok(None);
+            return Ok(Some(self.intrinsic_object(module, object_name, kind)));
         }
 
         let linker = if module == "None" {
-            self.linker_by_export_name_mut(object_name)
+            self.linker_by_export_path(object_path)?
                 .expect("failed to find linker by export name -- these should be loaded by now")
         } else if let Some(linker) = self.linker(module) {
             linker
+        } else if self.standalone {
+            if object_path.is_empty() {
+                return Ok(Some(self.intrinsic_package(module)));
+            }
+
+            self.warn(
+                WarningKind::MissingObject,
+                Some(full_name.to_owned()),
+                format!("package {module} is not loaded; using an intrinsic placeholder"),
+            );
+
+            return Ok(Some(self.intrinsic_object(
+                module,
+                object_name,
+                UObjectKind::Object,
+            )));
         } else {
             self.load_linker::<E, _>(module.to_owned(), reader)?;
 
             self.linker(module).expect("failed to force load linker")
         };
 
+        if object_path.is_empty() {
+            debug!("Object is a package");
+
+            return Ok(Some(self.intrinsic_package(module)));
+        }
+
         let linker_inner = linker.borrow();
-        let (export_index, _) = linker_inner
-            .find_export_by_name(object_name)
-            .expect("failed to find export");
+        let Some((export_index, _)) = linker_inner.find_export_by_path(object_path) else {
+            drop(linker_inner);
+
+            self.warn(
+                WarningKind::MissingObject,
+                Some(full_name.to_owned()),
+                "could not find the export; using an intrinsic placeholder",
+            );
+
+            return Ok(Some(self.intrinsic_object(
+                module,
+                object_name,
+                UObjectKind::Object,
+            )));
+        };
 
         drop(linker_inner);
 
         self.load_object_by_export_index::<E, _>(export_index, &linker, load_kind, reader)
             .map(Some)
     }
+
+    /// Consumes the data of the export named `full_name` without deserializing it, reading
+    /// its package's tables first if they haven't been. Objects which aren't exports have no
+    /// data to consume.
+    pub(crate) fn skip_object_by_full_name<E, R>(
+        &mut self,
+        full_name: &str,
+        reader: &mut R,
+    ) -> io::Result<()>
+    where
+        R: LinRead,
+        E: ByteOrder,
+    {
+        let (module, object_path) = full_name.split_once('.').unwrap_or((full_name, ""));
+        let linker = match self.linker(module) {
+            Some(linker) => linker,
+            None => {
+                self.load_linker::<E, _>(module.to_owned(), reader)?;
+                self.linker(module).expect("linker was just loaded")
+            }
+        };
+
+        let serial_size = linker
+            .borrow()
+            .find_export_by_path(object_path)
+            .map(|(_, export)| export.serial_size());
+        let Some(serial_size) = serial_size else {
+            debug!("{full_name} is not an export; nothing to skip");
+
+            return Ok(());
+        };
+
+        debug!("Skipping {serial_size:#X} bytes of data for {full_name}");
+        reader.skip(serial_size)?;
+        self.skipped_exports.insert(full_name.to_ascii_lowercase());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::{Import, Name, ObjectExport, PackageHeader, RawPackage};
+
+    fn linker(runtime: &mut UnrealRuntime, name: &str, imports: Vec<Import>) -> RcLinker {
+        let names = ["None", "Core", "Package", name]
+            .into_iter()
+            .map(|name| Name {
+                name: Rc::from(name),
+                flags: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let package = RawPackage {
+            header: PackageHeader {
+                version: 0,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: 0,
+                export_offset: 0,
+                import_count: imports.len() as u32,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: Vec::new(),
+            },
+            names,
+            imports,
+            exports: Vec::new(),
+        };
+
+        let linker = Rc::new(RefCell::new(Linker::new(
+            name.to_owned(),
+            package,
+            &mut runtime.name_pool,
+            None,
+        )));
+        runtime.linkers.insert(name.to_owned(), Rc::clone(&linker));
+        runtime.linker_load_order.push(name.to_owned());
+
+        linker
+    }
+
+    #[test]
+    fn unload_frees_object_cycles() {
+        let mut runtime = UnrealRuntime::default();
+        let core = linker(&mut runtime, "Core", Vec::new());
+        // Engine imports Core
+        linker(
+            &mut runtime,
+            "Engine",
+            vec![Import {
+                class_package: 1,
+                class_name: 2,
+                package_index: 0,
+                object_name: 1,
+            }],
+        );
+
+        // Two objects which refer to each other
+        let a = UObjectKind::Object.construct(Rc::downgrade(&core), ExportIndex::from_raw(1));
+        let b = UObjectKind::Object.construct(Rc::downgrade(&core), ExportIndex::from_raw(2));
+        a.borrow_mut()
+            .base_object_mut()
+            .set_outer_object(Rc::downgrade(&b));
+        b.borrow_mut().base_object_mut().set_class(Rc::clone(&a));
+        {
+            let mut core = core.borrow_mut();
+            core.objects.insert(ExportIndex::from_raw(1), Rc::clone(&a));
+            core.objects.insert(ExportIndex::from_raw(2), Rc::clone(&b));
+        }
+
+        let weak = (Rc::downgrade(&a), Rc::downgrade(&b), Rc::downgrade(&core));
+        // Only the runtime holds on to them from here
+        drop((a, b, core));
+        let (a, b, core) = weak;
+
+        let err = runtime.unload_package("core").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let stats = runtime.unload_package("Engine").unwrap();
+        assert_eq!(
+            stats,
+            UnloadStats {
+                linkers: 1,
+                objects: 0
+            }
+        );
+
+        let stats = runtime.unload_package("core").unwrap();
+        assert_eq!(
+            stats,
+            UnloadStats {
+                linkers: 1,
+                objects: 2
+            }
+        );
+
+        assert_eq!(a.strong_count(), 0);
+        assert_eq!(b.strong_count(), 0);
+        assert_eq!(core.strong_count(), 0);
+        assert!(runtime.linkers.is_empty());
+        assert!(runtime.linker_load_order.is_empty());
+    }
+
+    #[test]
+    fn rejects_paths_exported_by_several_linkers() {
+        let mut runtime = UnrealRuntime::default();
+        for name in ["Engine", "Game", "Core"] {
+            let linker = linker(&mut runtime, name, Vec::new());
+            if name == "Core" {
+                continue;
+            }
+
+            let mut linker = linker.borrow_mut();
+            linker.package.names.push(Name {
+                name: Rc::from("Shared"),
+                flags: 0,
+            });
+            let object_name = linker.package.names.len() as i32 - 1;
+            linker.package.exports.push(ObjectExport {
+                class_index: 0,
+                super_index: 0,
+                package_index: 0,
+                object_name,
+                object_flags: 0,
+                serial_size: 0,
+                serial_offset: 0,
+            });
+        }
+
+        let Err(err) = runtime.linker_by_export_path("Shared") else {
+            panic!("picked one of the linkers exporting Shared");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "Shared is exported by Engine and Game; qualify it with its package"
+        );
+        assert!(runtime.linker_by_export_path("Missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn clear_releases_everything() {
+        let mut runtime = UnrealRuntime::default();
+        let core = Rc::downgrade(&linker(&mut runtime, "Core", Vec::new()));
+        let object = Rc::downgrade(&runtime.intrinsic_object("Core", "Object", UObjectKind::Class));
+
+        let stats = runtime.clear();
+
+        // Core.Object, Core.Class and the Core package
+        assert_eq!(
+            stats,
+            UnloadStats {
+                linkers: 1,
+                objects: 3
+            }
+        );
+        assert_eq!(core.strong_count(), 0);
+        assert_eq!(object.strong_count(), 0);
+        assert!(runtime.intrinsic_objects.is_empty());
+    }
 }
@@ -1,58 +1,128 @@
 use byteorder::*;
-use std::collections::HashMap;
-use std::io::{self, Seek};
-use std::io::{SeekFrom, Write};
-use winnow::BStr;
-
-use crate::{PKG_TAG, de::*};
-
-fn write_packed_int<W: Write>(writer: &mut W, value: i32) -> io::Result<()> {
-    let sign = if value < 0 { 0x80 } else { 0x00 };
-    let mut v: u32 = value.unsigned_abs(); // handles i32::MIN safely (becomes 2147483648)
-
-    // B0 carries 6 bits of payload, plus sign and "more" flag if needed.
-    let mut b0 = (v & 0x3f) as u8;
-    if v >= 0x40 {
-        b0 |= 0x40; // more bytes follow
-    }
-    b0 |= sign;
-    writer.write_u8(b0)?;
-
-    if (b0 & 0x40) != 0 {
-        // Emit remaining bits in 7-bit chunks, MSB=1 while more chunks remain.
-        v >>= 6;
-        loop {
-            let mut b = (v & 0x7f) as u8;
-            v >>= 7;
-            if v != 0 {
-                b |= 0x80; // continuation
-                writer.write_u8(b)?;
-            } else {
-                writer.write_u8(b)?; // final chunk (no continuation bit)
-                break;
-            }
-        }
-    }
+use std::io::{self, Read, Write};
+use std::ops::Range;
 
-    Ok(())
-}
+use tracing::debug;
+
+use crate::{
+    PKG_TAG, common::normalize_index, de::*, object::ObjectFlags, packed::write_packed_int,
+};
 
-fn write_var_string<W: Write>(writer: &mut W, value: &BStr) -> io::Result<()> {
+/// Serialized data for a single export, split into the chunks it was originally read in.
+/// Each chunk is paired with the offset it was read from in the source package.
+pub type ExportData = Vec<(u64, Vec<u8>)>;
+
+pub(crate) fn write_var_string<W: Write>(writer: &mut W, value: &str) -> io::Result<()> {
     if value.is_empty() {
         writer.write_u8(0)?;
         return Ok(());
     }
     write_packed_int(writer, (value.len() + 1) as i32)?;
-    writer.write_all(value)?;
+    writer.write_all(value.as_bytes())?;
     writer.write_u8(0x0)?;
 
     Ok(())
 }
 
-pub fn serialize_unreal_package<W: Write + Seek>(
-    mut writer: W,
-    package: &mut RawPackage<'_>,
+/// Counts the bytes written through it. Used to lay out a package before writing it.
+struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Number of bytes `write` produces.
+fn measure(write: impl FnOnce(&mut CountingWriter<io::Sink>) -> io::Result<()>) -> io::Result<u64> {
+    let mut counter = CountingWriter::new(io::sink());
+    write(&mut counter)?;
+
+    Ok(counter.count)
+}
+
+fn export_serial_size(data: &ExportData) -> usize {
+    data.iter().map(|(_offset, data)| data.len()).sum()
+}
+
+/// How [`serialize_unreal_package`] decides where each table and export is written.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// Lay the package out again from scratch: the tables are written back to back after the
+    /// summary, followed by each export's data in export table order. Used when the package
+    /// or its data has changed size.
+    #[default]
+    Compact,
+    /// Write every table and export at the offset already recorded for it in the package,
+    /// so that resaving an unmodified package reproduces the original file. Sections are
+    /// written in offset order, whatever order the tables list them in.
+    ///
+    /// The output is byte-identical to the input except for these normalizations:
+    ///
+    /// - Gaps between sections are padding whose contents aren't kept, so they're written
+    ///   as zeros. Anything after the last section is dropped.
+    /// - Compact indices are written in their shortest form.
+    /// - Names are written as UTF-8, so names decoded with a lossy or Latin-1
+    ///   [`StringEncoding`](crate::StringEncoding) are re-encoded.
+    ///
+    /// Export data must be the same size as the export's recorded serial size, and the
+    /// sections must not overlap, or the package can't be written and an error is returned.
+    PreserveLayout,
+}
+
+/// A contiguous part of the output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Section {
+    Summary,
+    Names,
+    Imports,
+    ExportTable,
+    Export(usize),
+}
+
+/// Writes `package` out with `export_data` as the serialized data of each export.
+/// `export_data` must contain one entry per export, in export table order.
+///
+/// The package is written front to back in a single pass, so `writer` does not need to be
+/// seekable. Every field is written in the byte order `E`, and offsets embedded in texture
+/// data are expected to be in that order as well. With [`LayoutMode::Compact`] the package
+/// is laid out before anything is written: the header's table offsets and each export's
+/// serial size and offset are updated in `package` to match the output. With
+/// [`LayoutMode::PreserveLayout`] they're left as they are and written as recorded.
+pub fn serialize_unreal_package<E: ByteOrder, W: Write>(
+    writer: W,
+    package: &mut RawPackage,
+    export_data: &[ExportData],
+    mode: LayoutMode,
 ) -> io::Result<()> {
+    assert_eq!(
+        package.exports.len(),
+        export_data.len(),
+        "export data must be provided for every export"
+    );
+
+    match mode {
+        LayoutMode::Compact => layout_package::<E>(package, export_data)?,
+        LayoutMode::PreserveLayout => check_preserved_layout::<E>(package, export_data)?,
+    }
+
+    let sections = sections::<E>(package)?;
     let RawPackage {
         header,
         names,
@@ -60,6 +130,184 @@ pub fn serialize_unreal_package<W: Write + Seek>(
         exports,
     } = package;
 
+    let mut writer = CountingWriter::new(writer);
+    for (range, section) in sections {
+        // Sections never overlap, so this only ever pads forwards
+        io::copy(
+            &mut io::repeat(0).take(range.start - writer.count),
+            &mut writer,
+        )?;
+
+        match section {
+            Section::Summary => write_summary::<E, _>(&mut writer, header)?,
+            Section::Names => write_names::<E, _>(&mut writer, names)?,
+            Section::Imports => write_imports::<E, _>(&mut writer, imports)?,
+            Section::ExportTable => write_export_table::<E, _>(&mut writer, exports)?,
+            Section::Export(i) => {
+                let export = &exports[i];
+                let class_name = if export.class_index < 0 {
+                    let idx = (-export.class_index) as usize - 1;
+                    names[imports[idx].object_name as usize].name.as_ref()
+                } else {
+                    "Class"
+                };
+
+                write_export_data::<E, _>(
+                    &mut writer,
+                    class_name,
+                    &export_data[i],
+                    export.serial_offset as u32,
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Where each non-empty section of `package` is written, ordered by offset.
+fn sections<E: ByteOrder>(package: &RawPackage) -> io::Result<Vec<(Range<u64>, Section)>> {
+    let header = &package.header;
+    let mut sections = vec![
+        (
+            0,
+            measure(|w| write_summary::<E, _>(w, header))?,
+            Section::Summary,
+        ),
+        (
+            header.name_offset as u64,
+            measure(|w| write_names::<E, _>(w, &package.names))?,
+            Section::Names,
+        ),
+        (
+            header.import_offset as u64,
+            measure(|w| write_imports::<E, _>(w, &package.imports))?,
+            Section::Imports,
+        ),
+        (
+            header.export_offset as u64,
+            measure(|w| write_export_table::<E, _>(w, &package.exports))?,
+            Section::ExportTable,
+        ),
+    ];
+    for (i, export) in package.exports.iter().enumerate() {
+        if export.serial_size > 0 {
+            sections.push((
+                export.serial_offset as u64,
+                export.serial_size as u64,
+                Section::Export(i),
+            ));
+        }
+    }
+
+    let mut sections = sections
+        .into_iter()
+        .filter(|(_, size, _)| *size > 0)
+        .map(|(start, size, section)| (start..start + size, section))
+        .collect::<Vec<_>>();
+    sections.sort_by_key(|(range, _)| range.start);
+
+    Ok(sections)
+}
+
+/// Checks that `package` can be written at the offsets it records.
+fn check_preserved_layout<E: ByteOrder>(
+    package: &RawPackage,
+    export_data: &[ExportData],
+) -> io::Result<()> {
+    for (i, (export, data)) in package.exports.iter().zip(export_data).enumerate() {
+        let size = export_serial_size(data);
+        if size != export.serial_size as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "export {i} has {size:#X} bytes of data but a serial size of {:#X}",
+                    export.serial_size
+                ),
+            ));
+        }
+        if export.serial_size > 0 && export.serial_offset < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "export {i} has a negative serial offset {}",
+                    export.serial_offset
+                ),
+            ));
+        }
+    }
+
+    let mut end = 0;
+    for (range, section) in sections::<E>(package)? {
+        if range.start < end {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{section:?} at {:#X} overlaps the previous section, which ends at {end:#X}",
+                    range.start
+                ),
+            ));
+        }
+        end = range.end;
+    }
+
+    Ok(())
+}
+
+/// Computes where every table and export will be written, updating the header's table
+/// offsets and each export's serial size and offset.
+fn layout_package<E: ByteOrder>(
+    package: &mut RawPackage,
+    export_data: &[ExportData],
+) -> io::Result<()> {
+    for (i, (export, data)) in package.exports.iter_mut().zip(export_data).enumerate() {
+        let new_serial_size = export_serial_size(data);
+        debug!(
+            "Export index: {i:#X}. Old size={:#X}, new size={new_serial_size:#X}",
+            export.serial_size
+        );
+        export.serial_size = new_serial_size as i32;
+        export.serial_offset = 0;
+    }
+
+    // The header's offsets are fixed-size, so its length doesn't depend on their values
+    let header = &mut package.header;
+    let summary_size = measure(|w| write_summary::<E, _>(w, header))?;
+    let names_size = measure(|w| write_names::<E, _>(w, &package.names))?;
+    let imports_size = measure(|w| write_imports::<E, _>(w, &package.imports))?;
+
+    header.name_offset = summary_size as u32;
+    header.import_offset = header.name_offset + names_size as u32;
+    header.export_offset = header.import_offset + imports_size as u32;
+
+    // Serial offsets are packed, so the export table's size depends on where the data
+    // lands, which in turn depends on the table's size. Offsets start at zero and only
+    // grow, so this settles after a couple of rounds.
+    loop {
+        let table_size = measure(|w| write_export_table::<E, _>(w, &package.exports))?;
+
+        let mut offset = header.export_offset as u64 + table_size;
+        let mut changed = false;
+        for export in package
+            .exports
+            .iter_mut()
+            .filter(|export| export.serial_size > 0)
+        {
+            if export.serial_offset as u64 != offset {
+                export.serial_offset = offset as i32;
+                changed = true;
+            }
+
+            offset += export.serial_size as u64;
+        }
+
+        if !changed {
+            return Ok(());
+        }
+    }
+}
+
+fn write_summary<E: ByteOrder, W: Write>(writer: &mut W, header: &PackageHeader) -> io::Result<()> {
     let PackageHeader {
         version,
         flags,
@@ -71,246 +319,753 @@ pub fn serialize_unreal_package<W: Write + Seek>(
         import_offset,
         unk,
         unknown_data,
-        guid_a,
-        guid_b,
-        guid_c,
-        guid_d,
+        guid,
         generations,
     } = header;
 
-    struct Correction {
-        offset: u64,
-        value: u32,
-        packed: bool,
-    }
-
-    let mut offset_corrections = Vec::new();
+    writer.write_u32::<E>(PKG_TAG)?;
+    writer.write_u32::<E>(*version)?;
+    writer.write_u32::<E>(*flags)?;
 
-    writer.write_u32::<LE>(PKG_TAG)?;
-    writer.write_u32::<LE>(*version)?;
-    writer.write_u32::<LE>(*flags)?;
+    writer.write_u32::<E>(*name_count)?;
+    writer.write_u32::<E>(*name_offset)?;
 
-    writer.write_u32::<LE>(*name_count)?;
-    let name_offset_offset = writer.stream_position()?;
-    writer.write_u32::<LE>(*name_offset)?;
+    writer.write_u32::<E>(*export_count)?;
+    writer.write_u32::<E>(*export_offset)?;
 
-    writer.write_u32::<LE>(*export_count)?;
-    let export_offset_offset = writer.stream_position()?;
-    writer.write_u32::<LE>(*export_offset)?;
+    writer.write_u32::<E>(*import_count)?;
+    writer.write_u32::<E>(*import_offset)?;
 
-    writer.write_u32::<LE>(*import_count)?;
-    let import_offset_offset = writer.stream_position()?;
-    writer.write_u32::<LE>(*import_offset)?;
-
-    writer.write_u32::<LE>(*unk)?;
-    write_packed_int(&mut writer, unknown_data.len() as i32)?;
+    writer.write_u32::<E>(*unk)?;
+    write_packed_int(writer, unknown_data.len() as i32)?;
 
     writer.write_all(unknown_data)?;
 
-    writer.write_u32::<LE>(*guid_a)?;
-    writer.write_u32::<LE>(*guid_b)?;
-    writer.write_u32::<LE>(*guid_c)?;
-    writer.write_u32::<LE>(*guid_d)?;
+    writer.write_u32::<E>(guid.a)?;
+    writer.write_u32::<E>(guid.b)?;
+    writer.write_u32::<E>(guid.c)?;
+    writer.write_u32::<E>(guid.d)?;
 
-    writer.write_u32::<LE>(generations.len() as u32)?;
+    writer.write_u32::<E>(generations.len() as u32)?;
 
     for GenerationInfo {
         export_count,
         name_count,
     } in generations
     {
-        writer.write_u32::<LE>(*export_count)?;
-        writer.write_u32::<LE>(*name_count)?;
+        writer.write_u32::<E>(*export_count)?;
+        writer.write_u32::<E>(*name_count)?;
     }
 
-    let names_offset = writer.stream_position()?;
-
-    offset_corrections.push(Correction {
-        offset: name_offset_offset,
-        value: names_offset as u32,
-        packed: false,
-    });
+    Ok(())
+}
 
-    // Write out the name table
-    for Name { name, flags } in names.iter() {
-        write_var_string(&mut writer, name)?;
-        writer.write_u32::<LE>(*flags)?;
+fn write_names<E: ByteOrder, W: Write>(writer: &mut W, names: &[Name]) -> io::Result<()> {
+    for Name { name, flags } in names {
+        write_var_string(writer, name)?;
+        writer.write_u32::<E>(*flags)?;
     }
 
-    let imports_position = writer.stream_position()?;
-    offset_corrections.push(Correction {
-        offset: import_offset_offset,
-        value: imports_position as u32,
-        packed: false,
-    });
+    Ok(())
+}
+
+fn write_imports<E: ByteOrder, W: Write>(writer: &mut W, imports: &[Import]) -> io::Result<()> {
     for Import {
         class_package,
         class_name,
         package_index,
         object_name,
-        object,
-    } in imports.iter()
+    } in imports
     {
-        write_packed_int(&mut writer, *class_package)?;
-        write_packed_int(&mut writer, *class_name)?;
-        writer.write_i32::<LE>(*package_index)?;
-        write_packed_int(&mut writer, *object_name)?;
+        write_packed_int(writer, *class_package)?;
+        write_packed_int(writer, *class_name)?;
+        writer.write_i32::<E>(*package_index)?;
+        write_packed_int(writer, *object_name)?;
     }
 
-    let exports_position = writer.stream_position()?;
-    offset_corrections.push(Correction {
-        offset: export_offset_offset,
-        value: exports_position as u32,
-        packed: false,
-    });
+    Ok(())
+}
 
-    for (
-        i,
-        ObjectExport {
-            class_index,
-            super_index,
-            package_index,
-            object_name,
-            object_flags,
-            serial_size,
-            serial_offset,
-            data,
-        },
-    ) in exports.iter_mut().enumerate()
+fn write_export_table<E: ByteOrder, W: Write>(
+    writer: &mut W,
+    exports: &[ObjectExport],
+) -> io::Result<()> {
+    for ObjectExport {
+        class_index,
+        super_index,
+        package_index,
+        object_name,
+        object_flags,
+        serial_size,
+        serial_offset,
+    } in exports
     {
-        write_packed_int(&mut writer, *class_index)?;
+        write_packed_int(writer, *class_index)?;
 
-        write_packed_int(&mut writer, *super_index)?;
+        write_packed_int(writer, *super_index)?;
 
-        writer.write_i32::<LE>(*package_index)?;
+        writer.write_i32::<E>(*package_index)?;
 
-        write_packed_int(&mut writer, *object_name)?;
+        write_packed_int(writer, *object_name)?;
 
-        writer.write_u32::<LE>(*object_flags)?;
+        writer.write_u32::<E>(*object_flags)?;
 
-        let new_serial_size = data.iter().fold(0, |accum, (_offset, data)| accum + data.len());
-        println!("Export index: {i:#X}. Old size={serial_size:#X}, new size={new_serial_size:#X}");
-        *serial_size = new_serial_size as i32;
-
-        write_packed_int(&mut writer, *serial_size)?;
+        write_packed_int(writer, *serial_size)?;
 
         if *serial_size > 0 {
-            // Write out a fix-sized placeholder
-            writer.write_all([0x0, 0x0, 0x0, 0x0, 0x0].as_slice())?;
+            write_packed_int(writer, *serial_offset)?;
         }
     }
 
-    for export in exports.iter_mut() {
-        let new_serial_size = export.data.iter().fold(0, |accum, (offset, data)| accum + data.len());
-        if new_serial_size == 0 {
-            continue;
-        }
-
-        let offset_before = export.serial_offset;
-        export.serial_offset = writer.stream_position()? as i32;
-        let mut normalized_offset = 0u32;
-        let data_start = writer.stream_position()? as u32;
-        for (data_idx, (offset, data)) in export.data.iter().enumerate() {
-            normalized_offset += data.len() as u32;
-
-            let class_name = if export.class_index < 0 {
-                let idx = (-export.class_index) as usize - 1;
-                names[imports[idx].object_name as usize].name
-            } else  {
-                BStr::new(b"Class".as_slice())
-            };
+    Ok(())
+}
 
-            if class_name == "Texture" {
-                if offset_before == 0x4431C0{
-                    println!("BLOCK START");
-                }
+/// Finds the absolute offsets embedded in a texture's data chunk which point at the end of
+/// the next chunk (i.e. mipmap skip offsets). Returns the offset each should be rewritten
+/// to, relative to the start of the export's data, along with the range of data preceding
+/// it.
+fn texture_offset_fields<E: ByteOrder>(
+    data: &[u8],
+    next_chunk: Option<&(u64, Vec<u8>)>,
+    normalized_offset: u32,
+) -> Vec<(u32, Range<usize>)> {
+    let mut ranges: Vec<(u32, Range<usize>)> = Vec::new();
+    if let Some((next_offset, next_data)) = next_chunk {
+        let normalized_next_offset = normalized_offset + next_data.len() as u32;
 
-                let mut ranges: Vec<(u32, std::ops::Range<usize>)> = Vec::new();
-                if let Some((next_offset, next_data)) = export.data.get(data_idx + 1) {
-                    let normalized_next_offset = normalized_offset + next_data.len() as u32;
-
-                    let next_offset = (*next_offset + next_data.len() as u64) as u32;
-                    let next_offset_bytes = next_offset.to_le_bytes();
-
-                    for (i, window) in data.windows(4).enumerate() {
-                        // if offset_before == 0x4431C0  {
-                        //     println!("{window:X?}, {next_offset_bytes:X?}");
-                        // }
-                        if window == next_offset_bytes {
-                            if let Some((last_end_off, last_range)) = ranges.last() {
-                                let last_range_end = last_range.end + 4;
-                                ranges.push((normalized_next_offset, (last_range_end)..(last_range_end + i)));
-                            } else {
-                                ranges.push((normalized_next_offset, 0..i));
-                            }
-                        }
-                    }
-                }
+        let next_offset = (*next_offset + next_data.len() as u64) as u32;
+        let mut next_offset_bytes = [0; 4];
+        E::write_u32(&mut next_offset_bytes, next_offset);
 
-                if ranges.is_empty() {
-                    writer.write_all(data)?;
+        for (i, window) in data.windows(4).enumerate() {
+            if window == next_offset_bytes {
+                if let Some((_last_end_off, last_range)) = ranges.last() {
+                    let last_range_end = last_range.end + 4;
+                    ranges.push((
+                        normalized_next_offset,
+                        (last_range_end)..(last_range_end + i),
+                    ));
                 } else {
-                    for (next_offset, range) in ranges.iter().cloned() {
-                        writer.write_all(&data[range])?;
-
-                        let position = writer.stream_position()?;
-                        offset_corrections.push(Correction { offset: position, value: data_start + next_offset, packed: false });
-
-                        // Write out zero so it skips zero bytes
-                        // TODO: I tried putting in the offset here but it broke things...
-                        // so leaving this as zeroes for now
-                        writer.write_all(&[0, 0, 0, 0])?;
-                    }
-                    // Write out the final bit of data
-                    let final_range_start = ranges.last().unwrap().1.end + 4;
-                    let final_range = final_range_start..data.len();
-                    writer.write_all(&data[final_range])?;
+                    ranges.push((normalized_next_offset, 0..i));
                 }
-            } else {
-                writer.write_all(data)?;
             }
         }
     }
 
-    writer.seek(SeekFrom::Start(exports_position))?;
+    ranges
+}
 
-    // Go update the exports table
-    for ObjectExport {
-        class_index,
-        super_index,
-        package_index,
-        object_name,
-        object_flags,
-        serial_size,
-        serial_offset,
-        data,
-    } in exports
-    {
-        write_packed_int(&mut writer, *class_index)?;
+/// Writes an export's data chunks. `data_start` is the absolute offset the data is written
+/// to, which offsets embedded in the data are rebased onto.
+fn write_export_data<E: ByteOrder, W: Write>(
+    writer: &mut W,
+    class_name: &str,
+    export_data: &ExportData,
+    data_start: u32,
+) -> io::Result<()> {
+    let mut normalized_offset = 0u32;
+    for (data_idx, (_offset, data)) in export_data.iter().enumerate() {
+        normalized_offset += data.len() as u32;
 
-        write_packed_int(&mut writer, *super_index)?;
+        if class_name != "Texture" {
+            writer.write_all(data)?;
+            continue;
+        }
 
-        writer.write_i32::<LE>(*package_index)?;
+        let ranges =
+            texture_offset_fields::<E>(data, export_data.get(data_idx + 1), normalized_offset);
+        let Some((_, last_range)) = ranges.last() else {
+            writer.write_all(data)?;
+            continue;
+        };
 
-        write_packed_int(&mut writer, *object_name)?;
+        for (next_offset, range) in ranges.iter().cloned() {
+            writer.write_all(&data[range])?;
+            writer.write_u32::<E>(data_start + next_offset)?;
+        }
 
-        writer.write_u32::<LE>(*object_flags)?;
+        // Write out the final bit of data
+        writer.write_all(&data[last_range.end + 4..])?;
+    }
 
-        write_packed_int(&mut writer, *serial_size)?;
+    Ok(())
+}
 
-        if *serial_size > 0 {
-            write_packed_int(&mut writer, *serial_offset)?;
+/// Options for [`strip_package`].
+#[derive(Debug, Clone)]
+pub struct StripOptions {
+    /// Exports with any of these flags are dropped
+    pub strip_flags: ObjectFlags,
+    /// Indices of further exports to drop
+    pub strip_exports: Vec<usize>,
+    /// Drop names which none of the package's tables refer to. Export data refers to names by
+    /// index as well, so only enable this if the data doesn't or is rewritten using the
+    /// returned [`IndexRemap`].
+    pub compact_names: bool,
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        StripOptions {
+            strip_flags: ObjectFlags::empty(),
+            strip_exports: Vec::new(),
+            compact_names: false,
         }
     }
+}
+
+/// Maps name and object indices from before [`strip_package`] to after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexRemap {
+    names: Vec<Option<i32>>,
+    exports: Vec<Option<i32>>,
+}
 
-    for correction in offset_corrections {
-        writer.seek(SeekFrom::Start(correction.offset))?;
-        if correction.packed {
-            write_packed_int(&mut writer, correction.value as i32)?;
+impl IndexRemap {
+    /// New index of the name at `index`, or `None` if it was dropped.
+    pub fn name(&self, index: i32) -> Option<i32> {
+        let index = usize::try_from(index).ok()?;
+        self.names.get(index).copied().flatten()
+    }
+
+    /// New raw object index for the raw object index `index`, or `None` if it refers to a
+    /// dropped export. Imports are never dropped so keep their index.
+    pub fn object(&self, index: i32) -> Option<i32> {
+        if index > 0 {
+            self.exports.get(normalize_index(index)).copied().flatten()
         } else {
-            writer.write_u32::<LE>(correction.value)?;
+            Some(index)
         }
     }
 
-    Ok(())
+    pub fn stripped_exports(&self) -> usize {
+        self.exports.iter().filter(|index| index.is_none()).count()
+    }
+
+    pub fn stripped_names(&self) -> usize {
+        self.names.iter().filter(|index| index.is_none()).count()
+    }
+}
+
+/// Assigns consecutive new indices to the entries which are kept, starting at `first`.
+fn remap_kept(keep: &[bool], first: i32) -> Vec<Option<i32>> {
+    let mut next = first;
+    keep.iter()
+        .map(|&keep| {
+            keep.then(|| {
+                next += 1;
+                next - 1
+            })
+        })
+        .collect()
+}
+
+/// Drops exports from `package` and optionally compacts its name table, rewriting the indices
+/// in every table to match. `export_data` holds each export's data in export table order, as
+/// for [`serialize_unreal_package`], and has the dropped exports' entries removed.
+///
+/// Exports nested inside a dropped export are dropped along with it. Dropping an export which
+/// a remaining export uses as its class or super is an error, and the package is left
+/// untouched.
+pub fn strip_package(
+    package: &mut RawPackage,
+    export_data: &mut Vec<ExportData>,
+    options: &StripOptions,
+) -> io::Result<IndexRemap> {
+    assert_eq!(
+        package.exports.len(),
+        export_data.len(),
+        "export data must be provided for every export"
+    );
+
+    let mut stripped = package
+        .exports
+        .iter()
+        .map(|export| {
+            ObjectFlags::from_bits_retain(export.object_flags).intersects(options.strip_flags)
+        })
+        .collect::<Vec<_>>();
+
+    for &index in &options.strip_exports {
+        let Some(stripped) = stripped.get_mut(index) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot strip export {index}: out of bounds"),
+            ));
+        };
+        *stripped = true;
+    }
+
+    // Outers usually come before the objects inside them but aren't required to, so repeat
+    // until nothing else is dropped
+    loop {
+        let mut changed = false;
+        for (i, export) in package.exports.iter().enumerate() {
+            if !stripped[i]
+                && export.package_index > 0
+                && stripped.get(normalize_index(export.package_index)) == Some(&true)
+            {
+                stripped[i] = true;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let kept_exports = stripped
+        .iter()
+        .map(|stripped| !stripped)
+        .collect::<Vec<_>>();
+    let mut remap = IndexRemap {
+        names: Vec::new(),
+        exports: remap_kept(&kept_exports, 1),
+    };
+
+    let kept = || {
+        package
+            .exports
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| kept_exports[*i])
+    };
+
+    for (i, export) in kept() {
+        for (field, index) in [("class", export.class_index), ("super", export.super_index)] {
+            if remap.object(index).is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "export {i} uses stripped export {} as its {field}",
+                        normalize_index(index)
+                    ),
+                ));
+            }
+        }
+    }
+
+    let mut used_names = vec![!options.compact_names; package.names.len()];
+    if options.compact_names {
+        let import_names = package
+            .imports
+            .iter()
+            .flat_map(|import| [import.class_package, import.class_name, import.object_name]);
+        let export_names = kept().map(|(_, export)| export.object_name);
+
+        for index in import_names.chain(export_names) {
+            let Some(used) = usize::try_from(index)
+                .ok()
+                .and_then(|index| used_names.get_mut(index))
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("name index {index} out of bounds"),
+                ));
+            };
+            *used = true;
+        }
+    }
+    remap.names = remap_kept(&used_names, 0);
+
+    // Everything has been checked, so start rewriting
+    let mut i = 0;
+    package.exports.retain(|_| {
+        i += 1;
+        kept_exports[i - 1]
+    });
+    let mut i = 0;
+    export_data.retain(|_| {
+        i += 1;
+        kept_exports[i - 1]
+    });
+    let mut i = 0;
+    package.names.retain(|_| {
+        i += 1;
+        used_names[i - 1]
+    });
+
+    let name = |index: i32| remap.name(index).expect("name was marked as used");
+    let object = |index: i32| remap.object(index).expect("export was checked");
+
+    for import in &mut package.imports {
+        import.class_package = name(import.class_package);
+        import.class_name = name(import.class_name);
+        import.object_name = name(import.object_name);
+    }
+
+    for export in &mut package.exports {
+        export.class_index = object(export.class_index);
+        export.super_index = object(export.super_index);
+        export.package_index = object(export.package_index);
+        export.object_name = name(export.object_name);
+    }
+
+    let header = &mut package.header;
+    header.export_count = package.exports.len() as u32;
+    header.name_count = package.names.len() as u32;
+    for generation in &mut header.generations {
+        generation.export_count = generation.export_count.min(header.export_count);
+        generation.name_count = generation.name_count.min(header.name_count);
+    }
+
+    debug!(
+        "Stripped {} exports and {} names",
+        remap.stripped_exports(),
+        remap.stripped_names()
+    );
+
+    Ok(remap)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::reader::PackageReader;
+
+    fn test_package() -> RawPackage {
+        let names = ["None", "Core", "Engine", "Texture", "MyTexture", "Other"]
+            .into_iter()
+            .map(|name| Name {
+                name: Rc::from(name),
+                flags: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let export = |class_index, object_name| ObjectExport {
+            class_index,
+            super_index: 0,
+            package_index: 0,
+            object_name,
+            object_flags: 0,
+            serial_size: 0x1234,
+            serial_offset: 0x5678,
+        };
+
+        RawPackage {
+            header: PackageHeader {
+                version: 100,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: 3,
+                export_offset: 0,
+                import_count: 2,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: vec![1, 2, 3],
+                guid: Default::default(),
+                generations: vec![GenerationInfo {
+                    export_count: 3,
+                    name_count: names.len() as u32,
+                }],
+            },
+            names,
+            imports: vec![
+                // Engine
+                Import {
+                    class_package: 1,
+                    class_name: 1,
+                    package_index: 0,
+                    object_name: 2,
+                },
+                // Engine.Texture
+                Import {
+                    class_package: 1,
+                    class_name: 1,
+                    package_index: -1,
+                    object_name: 3,
+                },
+            ],
+            exports: vec![export(-2, 4), export(0, 5), export(0, 5)],
+        }
+    }
+
+    /// Writes the test package in the byte order `E` and checks it reads back the same.
+    fn round_trip<E: ByteOrder>() -> Vec<u8> {
+        let mut package = test_package();
+        let export_data = vec![vec![(0, vec![1, 2, 3])], Vec::new(), Vec::new()];
+
+        let mut out = Vec::new();
+        serialize_unreal_package::<E, _>(&mut out, &mut package, &export_data, LayoutMode::Compact)
+            .unwrap();
+
+        let mut reader = PackageReader::new(Cursor::new(out.as_slice()));
+        let read = read_package::<E, _>(&mut reader).unwrap();
+        assert_eq!(read.header.version, 100);
+        assert_eq!(read.header.name_offset, package.header.name_offset);
+        assert_eq!(read.header.unknown_data, [1, 2, 3]);
+        assert_eq!(read.header.generations.len(), 1);
+        assert_eq!(read.names.len(), package.names.len());
+        assert_eq!(read.imports[1].package_index, -1);
+        assert_eq!(read.exports, package.exports);
+
+        out
+    }
+
+    #[test]
+    fn round_trips_header_extensions() {
+        use crate::de::HeaderExtension;
+        use crate::profile::FormatProfile;
+
+        let extension = HeaderExtension::SplinterCell {
+            tag: 0x0FF0_ADDE,
+            package_name: "Engine".to_owned(),
+        };
+        let mut package = test_package();
+        package.header.set_extension(&extension);
+
+        let mut out = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut out,
+            &mut package,
+            &[Vec::new(), Vec::new(), Vec::new()],
+            LayoutMode::Compact,
+        )
+        .unwrap();
+        let mut reader = PackageReader::new(Cursor::new(out.as_slice()));
+        let read = read_package::<LittleEndian, _>(&mut reader).unwrap();
+
+        let splinter_cell = FormatProfile::splinter_cell();
+        assert_eq!(read.header.extension(&splinter_cell).unwrap(), extension);
+        // Profiles which don't know the layout get the raw fields
+        assert_eq!(
+            read.header.extension(&FormatProfile::stock_ue2()).unwrap(),
+            HeaderExtension::Raw {
+                unk: 0x0FF0_ADDE,
+                data: b"Engine\0".to_vec(),
+            }
+        );
+
+        // Bytes which aren't a string can't be decoded as Splinter Cell's extension
+        package.header.unknown_data = vec![1, 2, 3];
+        let err = package.header.extension(&splinter_cell).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn round_trips_in_both_byte_orders() {
+        let le = round_trip::<LittleEndian>();
+        let be = round_trip::<BigEndian>();
+
+        // Only the byte order differs, so the layout is the same
+        assert_eq!(le.len(), be.len());
+        assert_eq!(le[..4], PKG_TAG.to_le_bytes());
+        assert_eq!(be[..4], PKG_TAG.to_be_bytes());
+    }
+
+    #[test]
+    fn streamed_package_round_trips() {
+        let mut package = test_package();
+        let export_data = vec![
+            vec![(0x1000, vec![0xAA; 8])],
+            Vec::new(),
+            vec![(0x2000, vec![0xBB; 3]), (0x2003, vec![0xCC; 2])],
+        ];
+
+        let mut out = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut out,
+            &mut package,
+            &export_data,
+            LayoutMode::Compact,
+        )
+        .unwrap();
+
+        let mut reader = PackageReader::new(Cursor::new(out.as_slice()));
+        let read = read_package::<LittleEndian, _>(&mut reader).unwrap();
+
+        assert_eq!(read.names.len(), package.names.len());
+        assert_eq!(read.imports.len(), 2);
+        assert_eq!(read.exports, package.exports);
+
+        let exports = &read.exports;
+        assert_eq!(exports[0].serial_size, 8);
+        assert_eq!(exports[1].serial_size, 0);
+        assert_eq!(exports[2].serial_size, 5);
+        assert_eq!(exports[2].serial_offset, exports[0].serial_offset + 8);
+        assert_eq!(out.len(), exports[2].serial_offset as usize + 5);
+
+        let data = |export: &ObjectExport| {
+            let start = export.serial_offset as usize;
+            &out[start..start + export.serial_size as usize]
+        };
+        // The texture's data has no embedded offsets so is written as-is
+        assert_eq!(data(&exports[0]), [0xAA; 8]);
+        assert_eq!(data(&exports[2]), [0xBB, 0xBB, 0xBB, 0xCC, 0xCC]);
+    }
+
+    #[test]
+    fn preserves_recorded_layout() {
+        let mut package = test_package();
+        let header = &mut package.header;
+        // Exports before the tables, out of table order, with padding between them
+        header.name_offset = 0x100;
+        header.import_offset = 0x180;
+        header.export_offset = 0x1A0;
+        package.exports[0].serial_size = 4;
+        package.exports[0].serial_offset = 0x90;
+        package.exports[1].serial_size = 0;
+        package.exports[1].serial_offset = 0;
+        package.exports[2].serial_size = 2;
+        package.exports[2].serial_offset = 0x80;
+        let export_data = vec![
+            vec![(0x90, vec![0xAA; 4])],
+            Vec::new(),
+            vec![(0x80, vec![0xBB; 2])],
+        ];
+
+        let mut out = Vec::new();
+        let mut written = package.clone();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut out,
+            &mut written,
+            &export_data,
+            LayoutMode::PreserveLayout,
+        )
+        .unwrap();
+        assert_eq!(written.exports, package.exports);
+        assert_eq!(out[0x80..0x82], [0xBB; 2]);
+        assert_eq!(out[0x82..0x90], [0; 0xE]);
+        assert_eq!(out[0x90..0x94], [0xAA; 4]);
+
+        let mut reader = PackageReader::new(Cursor::new(out.as_slice()));
+        let mut read = read_package::<LittleEndian, _>(&mut reader).unwrap();
+        assert_eq!(read.header.name_offset, 0x100);
+        assert_eq!(read.exports, package.exports);
+
+        // Writing what was read reproduces the same bytes
+        let mut resaved = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut resaved,
+            &mut read,
+            &export_data,
+            LayoutMode::PreserveLayout,
+        )
+        .unwrap();
+        assert_eq!(resaved, out);
+    }
+
+    #[test]
+    fn refuses_to_preserve_invalid_layout() {
+        let mut package = test_package();
+        let export_data = vec![vec![(0, vec![1, 2, 3])], Vec::new(), Vec::new()];
+        let mut out = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut out,
+            &mut package,
+            &export_data,
+            LayoutMode::Compact,
+        )
+        .unwrap();
+
+        // Data which no longer fits its recorded size
+        let grown = vec![vec![(0, vec![1, 2, 3, 4])], Vec::new(), Vec::new()];
+        let err = serialize_unreal_package::<LittleEndian, _>(
+            io::sink(),
+            &mut package.clone(),
+            &grown,
+            LayoutMode::PreserveLayout,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // Names overlapping the import table
+        package.header.import_offset = package.header.name_offset + 1;
+        let err = serialize_unreal_package::<LittleEndian, _>(
+            io::sink(),
+            &mut package,
+            &export_data,
+            LayoutMode::PreserveLayout,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn texture_offsets_are_rebased() {
+        // The first chunk ends with the original offset of the end of the next chunk
+        let mut first = vec![0x11, 0x22];
+        first.extend_from_slice(&0x4008u32.to_le_bytes());
+        let export_data = vec![(0x4000, first), (0x4006, vec![0x33, 0x44])];
+
+        let mut out = Vec::new();
+        write_export_data::<LittleEndian, _>(&mut out, "Texture", &export_data, 0x100).unwrap();
+
+        let mut expected = vec![0x11, 0x22];
+        expected.extend_from_slice(&0x108u32.to_le_bytes());
+        expected.extend_from_slice(&[0x33, 0x44]);
+        assert_eq!(out, expected);
+
+        // Big-endian offsets are found and rewritten in the same byte order
+        let mut first = vec![0x11, 0x22];
+        first.extend_from_slice(&0x4008u32.to_be_bytes());
+        let be_data = vec![(0x4000, first), (0x4006, vec![0x33, 0x44])];
+        let mut out = Vec::new();
+        write_export_data::<BigEndian, _>(&mut out, "Texture", &be_data, 0x100).unwrap();
+        assert_eq!(out[2..6], 0x108u32.to_be_bytes());
+
+        // Other classes are left alone
+        let mut out = Vec::new();
+        write_export_data::<LittleEndian, _>(&mut out, "Sound", &export_data, 0x100).unwrap();
+        assert_eq!(out[2..6], 0x4008u32.to_le_bytes());
+    }
+
+    #[test]
+    fn strips_exports_and_names() {
+        let mut package = test_package();
+        // MyTexture, Other.Other, and Other which is editor-only
+        package.exports[1].package_index = 3;
+        package.exports[2].object_flags = ObjectFlags::NOT_FOR_CLIENT.bits();
+        let mut export_data = vec![Vec::new(), Vec::new(), vec![(0, vec![1])]];
+
+        let options = StripOptions {
+            strip_flags: ObjectFlags::NOT_FOR_CLIENT,
+            strip_exports: Vec::new(),
+            compact_names: true,
+        };
+        let remap = strip_package(&mut package, &mut export_data, &options).unwrap();
+
+        // Only MyTexture remains, so "Other" is no longer referenced
+        assert_eq!(remap.stripped_exports(), 2);
+        assert_eq!(remap.stripped_names(), 2);
+        assert_eq!(remap.object(1), Some(1));
+        assert_eq!(remap.object(2), None);
+        assert_eq!(remap.object(-2), Some(-2));
+        assert_eq!(remap.name(4), Some(3));
+        assert_eq!(remap.name(5), None);
+
+        assert_eq!(export_data.len(), 1);
+        assert_eq!(package.header.export_count, 1);
+        assert_eq!(package.header.generations[0].name_count, 4);
+
+        let names = package
+            .names
+            .iter()
+            .map(|name| name.name.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Core", "Engine", "Texture", "MyTexture"]);
+        assert_eq!(package.exports[0].object_name, 3);
+        assert_eq!(package.imports[1].object_name, 2);
+    }
+
+    #[test]
+    fn refuses_to_strip_referenced_class() {
+        let mut package = test_package();
+        package.exports[0].class_index = 2;
+        let mut export_data = vec![Vec::new(); 3];
+
+        let options = StripOptions {
+            strip_exports: vec![1],
+            ..Default::default()
+        };
+        let err = strip_package(&mut package, &mut export_data, &options).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(package.exports.len(), 3);
+    }
 }
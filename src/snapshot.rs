@@ -0,0 +1,307 @@
+use std::{collections::HashMap, ops::Index, rc::Rc};
+
+use crate::{
+    object::{
+        ObjectFlags, RcUnrealObject, UObjectKind, UnrealObject,
+        builtins::{Const, Enum, Property, PropertyFlags, Struct, TextBuffer, TextData},
+    },
+    runtime::{RcUnrealObjPointer, UnrealRuntime},
+};
+
+/// Position of an object in a [`Snapshot`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObjectId(usize);
+
+impl ObjectId {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// What a [`FrozenObject`] keeps beyond its place in the object graph, depending on its kind.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrozenData {
+    None,
+    /// Structs, including functions, states and classes
+    Struct {
+        super_struct: Option<ObjectId>,
+        /// Fields declared directly in the struct, in declaration order
+        children: Vec<ObjectId>,
+        /// The script as it was serialized
+        script: Vec<u8>,
+    },
+    Const(String),
+    Enum(Vec<String>),
+    Property {
+        flags: PropertyFlags,
+        array_dim: u32,
+        category: Option<String>,
+    },
+    TextBuffer(TextData),
+}
+
+/// A copy of a loaded object which refers to other objects by their [`ObjectId`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrozenObject {
+    pub name: String,
+    /// e.g. `Engine.Actor.Spawn`
+    pub full_name: String,
+    pub kind: UObjectKind,
+    pub flags: ObjectFlags,
+    /// Name of the package the object was loaded from and its index in the package's export
+    /// table. `None` for intrinsic objects.
+    pub export: Option<(String, usize)>,
+    pub class: Option<ObjectId>,
+    pub outer: Option<ObjectId>,
+    pub data: FrozenData,
+}
+
+/// An immutable copy of a runtime's object graph, made with
+/// [`Package::freeze`](crate::Package::freeze).
+///
+/// Unlike loaded objects, a snapshot is `Send + Sync`, so it can be shared between threads
+/// once loading is done. Objects referred to by loaded objects but not loaded from a
+/// package, such as intrinsic classes, are included too.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    objects: Vec<FrozenObject>,
+    /// Keyed by lowercase full name
+    by_full_name: HashMap<String, ObjectId>,
+}
+
+impl Snapshot {
+    pub(crate) fn freeze(runtime: &UnrealRuntime) -> Self {
+        let mut freezer = Freezer::default();
+        for (package, index, obj) in runtime.iter_objects() {
+            let full_name = runtime
+                .linker(&package)
+                .map(|linker| {
+                    let linker = linker.borrow();
+                    linker.package.exports[index].full_name(&linker)
+                })
+                .unwrap_or_else(|| full_name(&obj));
+
+            let id = freezer.id(&obj);
+            freezer.exports.insert(id, ((package, index), full_name));
+        }
+
+        // Freezing an object can queue the objects it refers to
+        let mut objects = Vec::with_capacity(freezer.pending.len());
+        while objects.len() < freezer.pending.len() {
+            let id = ObjectId(objects.len());
+            let obj = Rc::clone(&freezer.pending[id.0]);
+            objects.push(freezer.freeze(id, &obj));
+        }
+
+        let by_full_name = objects
+            .iter()
+            .enumerate()
+            .map(|(i, obj)| (obj.full_name.to_ascii_lowercase(), ObjectId(i)))
+            .collect();
+
+        Snapshot {
+            objects,
+            by_full_name,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn get(&self, id: ObjectId) -> Option<&FrozenObject> {
+        self.objects.get(id.0)
+    }
+
+    /// The object named `full_name`, ignoring ASCII case.
+    pub fn find(&self, full_name: &str) -> Option<ObjectId> {
+        self.by_full_name
+            .get(&full_name.to_ascii_lowercase())
+            .copied()
+    }
+
+    /// Every object along with its id. Objects loaded from packages come first, in the order
+    /// the runtime loaded them.
+    pub fn objects(&self) -> impl Iterator<Item = (ObjectId, &FrozenObject)> {
+        self.objects
+            .iter()
+            .enumerate()
+            .map(|(i, obj)| (ObjectId(i), obj))
+    }
+}
+
+impl Index<ObjectId> for Snapshot {
+    type Output = FrozenObject;
+
+    fn index(&self, id: ObjectId) -> &FrozenObject {
+        &self.objects[id.0]
+    }
+}
+
+#[derive(Default)]
+struct Freezer {
+    ids: HashMap<RcUnrealObjPointer, ObjectId>,
+    /// Every object given an id, in id order
+    pending: Vec<RcUnrealObject>,
+    /// Where each exported object came from and its full name
+    exports: HashMap<ObjectId, ((String, usize), String)>,
+}
+
+impl Freezer {
+    /// The id of `obj`, queueing it to be frozen if it doesn't have one yet.
+    fn id(&mut self, obj: &RcUnrealObject) -> ObjectId {
+        *self
+            .ids
+            .entry(RcUnrealObjPointer::from_unreal_object(obj))
+            .or_insert_with(|| {
+                self.pending.push(Rc::clone(obj));
+                ObjectId(self.pending.len() - 1)
+            })
+    }
+
+    fn freeze(&mut self, id: ObjectId, obj: &RcUnrealObject) -> FrozenObject {
+        let (export, full_name) = match self.exports.remove(&id) {
+            Some((export, full_name)) => (Some(export), full_name),
+            None => (None, full_name(obj)),
+        };
+
+        let obj = obj.borrow();
+        let base = obj.base_object();
+        let class = base.class().map(|class| self.id(class));
+        let outer = base.outer_object().map(|outer| self.id(&outer));
+
+        FrozenObject {
+            name: base.name().to_owned(),
+            full_name,
+            kind: obj.kind(),
+            flags: base.flags(),
+            export,
+            class,
+            outer,
+            data: self.freeze_data(&*obj),
+        }
+    }
+
+    fn freeze_data(&mut self, obj: &dyn UnrealObject) -> FrozenData {
+        if let Some(ustruct) = downcast::<Struct>(obj, UObjectKind::Struct) {
+            return FrozenData::Struct {
+                super_struct: ustruct
+                    .parent_object
+                    .super_field()
+                    .map(|super_struct| self.id(&super_struct)),
+                children: ustruct.fields().map(|field| self.id(&field)).collect(),
+                script: ustruct.script_bytes().to_vec(),
+            };
+        }
+
+        if let Some(constant) = downcast::<Const>(obj, UObjectKind::Const) {
+            return FrozenData::Const(constant.value.clone());
+        }
+
+        if let Some(uenum) = downcast::<Enum>(obj, UObjectKind::Enum) {
+            return FrozenData::Enum(uenum.names().iter().map(|name| name.to_string()).collect());
+        }
+
+        if let Some(property) = downcast::<Property>(obj, UObjectKind::Property) {
+            let info = property.info();
+            return FrozenData::Property {
+                flags: info.flags,
+                array_dim: info.array_dim,
+                category: info.category,
+            };
+        }
+
+        if let Some(text_buffer) = downcast::<TextBuffer>(obj, UObjectKind::TextBuffer) {
+            return FrozenData::TextBuffer(text_buffer.text.clone());
+        }
+
+        FrozenData::None
+    }
+}
+
+fn downcast<T: 'static>(obj: &dyn UnrealObject, kind: UObjectKind) -> Option<&T> {
+    obj.parent_of_kind(kind)?.as_any().downcast_ref::<T>()
+}
+
+/// Full name of an object which isn't exported, built from its outers.
+fn full_name(obj: &RcUnrealObject) -> String {
+    obj.borrow().base_object().full_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        package::Package,
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn freezes_loaded_objects() {
+        assert_send_sync::<Snapshot>();
+
+        let mut builder = PackageBuilder::new();
+        let int_property = builder.core_class("IntProperty");
+        let data = DataBuilder::ue2_property(0, 0, 0).build();
+        let height = builder.export(int_property, 2, "Height", data);
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Jump");
+        let data = DataBuilder::field(0, 0)
+            // script_text, children, friendly_name
+            .packed(0)
+            .packed(height)
+            .packed(name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Jump", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        package.load_objects().unwrap();
+
+        let snapshot = package.freeze();
+        let jump = &snapshot[snapshot.find("test.jump").unwrap()];
+        assert_eq!(jump.kind, UObjectKind::Function);
+        assert_eq!(jump.export, Some(("Test".to_owned(), 1)));
+        assert_eq!(
+            snapshot[jump.class.unwrap()].full_name,
+            "Core.Function",
+            "intrinsic classes are frozen too"
+        );
+
+        let FrozenData::Struct {
+            children, script, ..
+        } = &jump.data
+        else {
+            panic!("expected a struct, got {:?}", jump.data);
+        };
+        assert!(script.is_empty());
+        assert_eq!(children.len(), 1);
+
+        let height = &snapshot[children[0]];
+        assert_eq!(height.full_name, "Test.Jump.Height");
+        assert_eq!(height.outer, snapshot.find("Test.Jump"));
+        assert!(matches!(
+            height.data,
+            FrozenData::Property { array_dim: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,335 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::Serialize;
+
+use crate::{
+    common::normalize_index,
+    de::{Import, Linker, Name, ObjectExport, RawPackage},
+    object::{UObjectKind, builtins::Struct},
+    ser::write_var_string,
+};
+
+/// Number of exports listed in [`PackageStats::largest_exports`].
+const LARGEST_EXPORTS: usize = 10;
+
+/// Export counts and sizes for a single class.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ClassStats {
+    pub count: usize,
+    pub total_bytes: u64,
+    pub largest_bytes: u64,
+    /// Number of exports in each size bucket, keyed by the bucket's upper bound. Buckets are
+    /// powers of two, so an export of 300 bytes is counted under 512.
+    pub size_histogram: BTreeMap<u64, usize>,
+}
+
+/// An export listed in [`PackageStats::largest_exports`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportSize {
+    /// Index into the export table
+    pub index: usize,
+    pub name: String,
+    pub class: String,
+    pub size: u64,
+}
+
+/// Counts and sizes summarizing a package's tables. See [`RawPackage::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageStats {
+    pub name_count: usize,
+    pub import_count: usize,
+    pub export_count: usize,
+    /// Size of the name table as serialized
+    pub name_table_bytes: u64,
+    /// Total serial size of every export
+    pub export_bytes: u64,
+    /// Keyed by class name. Script bytecode is counted as part of the `Function`, `State`,
+    /// and `Class` exports which hold it.
+    pub classes: BTreeMap<String, ClassStats>,
+    /// The largest exports, biggest first
+    pub largest_exports: Vec<ExportSize>,
+}
+
+/// How many objects a loaded package holds and roughly how much memory it takes up. See
+/// [`Package::linker_usage`](crate::Package::linker_usage).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkerUsage {
+    /// Name of the package
+    pub name: String,
+    pub loaded_objects: usize,
+    pub objects_by_kind: BTreeMap<UObjectKind, usize>,
+    /// Estimated size in bytes of the package's tables and loaded objects. See
+    /// [`Linker::approximate_memory_usage`].
+    pub approximate_bytes: usize,
+}
+
+impl Linker {
+    pub fn loaded_object_count(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Number of loaded objects of each concrete kind.
+    pub fn loaded_objects_by_kind(&self) -> BTreeMap<UObjectKind, usize> {
+        let mut counts = BTreeMap::new();
+        for obj in self.objects.values() {
+            if let Ok(obj) = obj.try_borrow() {
+                *counts.entry(obj.kind()).or_default() += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Estimates how much memory the package's tables and loaded objects take up. Objects
+    /// are counted by their own size plus their script, so memory they share with other
+    /// packages, such as interned names, is counted in each.
+    pub fn approximate_memory_usage(&self) -> usize {
+        let package = &self.package;
+        let names = package
+            .names
+            .iter()
+            .map(|name| size_of::<Name>() + name.name.len())
+            .sum::<usize>();
+        let tables = package.imports.len() * size_of::<Import>()
+            + package.exports.len() * size_of::<ObjectExport>();
+
+        let objects = self
+            .objects
+            .values()
+            .filter_map(|obj| obj.try_borrow().ok())
+            .map(|obj| {
+                let script = obj
+                    .parent_of_kind(UObjectKind::Struct)
+                    .and_then(|obj| obj.as_any().downcast_ref::<Struct>())
+                    .map_or(0, |ustruct| ustruct.script_bytes().len());
+
+                size_of_val(&*obj) + script
+            })
+            .sum::<usize>();
+
+        names + tables + objects
+    }
+
+    pub fn usage(&self) -> LinkerUsage {
+        LinkerUsage {
+            name: self.name.clone(),
+            loaded_objects: self.loaded_object_count(),
+            objects_by_kind: self.loaded_objects_by_kind(),
+            approximate_bytes: self.approximate_memory_usage(),
+        }
+    }
+}
+
+impl RawPackage {
+    /// Summarizes the package's tables: how many of each class it exports, how much data
+    /// they take up, and which exports are largest. Only the tables are read, so this works
+    /// without loading any objects.
+    pub fn stats(&self) -> PackageStats {
+        let name = |index: i32| {
+            usize::try_from(index)
+                .ok()
+                .and_then(|index| self.names.get(index))
+                .map_or("<invalid>", |name| name.name.as_ref())
+        };
+        let class_name = |class_index: i32| match class_index {
+            0 => "Class",
+            i if i < 0 => self
+                .imports
+                .get(normalize_index(i))
+                .map_or("<invalid>", |import| name(import.object_name)),
+            i => self
+                .exports
+                .get(normalize_index(i))
+                .map_or("<invalid>", |export| name(export.object_name)),
+        };
+
+        let mut name_table = Vec::new();
+        for entry in &self.names {
+            write_var_string(&mut name_table, &entry.name).expect("writing to a Vec can't fail");
+        }
+        // Each entry is followed by its flags
+        let name_table_bytes = (name_table.len() + self.names.len() * 4) as u64;
+
+        let mut stats = PackageStats {
+            name_count: self.names.len(),
+            import_count: self.imports.len(),
+            export_count: self.exports.len(),
+            name_table_bytes,
+            ..Default::default()
+        };
+
+        let mut sizes = Vec::with_capacity(self.exports.len());
+        for (index, export) in self.exports.iter().enumerate() {
+            let size = export.serial_size.max(0) as u64;
+            let class = class_name(export.class_index);
+
+            let class_stats = stats.classes.entry(class.to_owned()).or_default();
+            class_stats.count += 1;
+            class_stats.total_bytes += size;
+            class_stats.largest_bytes = class_stats.largest_bytes.max(size);
+            *class_stats
+                .size_histogram
+                .entry(size.next_power_of_two())
+                .or_default() += 1;
+
+            stats.export_bytes += size;
+            sizes.push((size, index));
+        }
+
+        // Ties keep export table order
+        sizes.sort_by(|(a_size, a_index), (b_size, b_index)| {
+            b_size.cmp(a_size).then(a_index.cmp(b_index))
+        });
+        stats.largest_exports = sizes
+            .into_iter()
+            .take(LARGEST_EXPORTS)
+            .map(|(size, index)| {
+                let export = &self.exports[index];
+                ExportSize {
+                    index,
+                    name: name(export.object_name).to_owned(),
+                    class: class_name(export.class_index).to_owned(),
+                    size,
+                }
+            })
+            .collect();
+
+        stats
+    }
+}
+
+impl fmt::Display for PackageStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} names ({:#X} bytes), {} imports, {} exports ({:#X} bytes)",
+            self.name_count,
+            self.name_table_bytes,
+            self.import_count,
+            self.export_count,
+            self.export_bytes
+        )?;
+
+        writeln!(f, "\nExports by class:")?;
+        let mut classes = self.classes.iter().collect::<Vec<_>>();
+        classes.sort_by(|(a_name, a), (b_name, b)| {
+            b.total_bytes.cmp(&a.total_bytes).then(a_name.cmp(b_name))
+        });
+        for (class, stats) in classes {
+            writeln!(
+                f,
+                "  {class:<24} {:>6} exports {:>10} bytes (largest {})",
+                stats.count, stats.total_bytes, stats.largest_bytes
+            )?;
+        }
+
+        writeln!(f, "\nLargest exports:")?;
+        for export in &self.largest_exports {
+            writeln!(
+                f,
+                "  {:>5} {:<32} {:<24} {:>10} bytes",
+                export.index, export.name, export.class, export.size
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::{
+        package::Package,
+        test_support::{DataBuilder, PackageBuilder},
+    };
+
+    #[test]
+    fn summarizes_exports_by_class() {
+        let mut builder = PackageBuilder::new();
+        let function = builder.core_class("Function");
+        let text_buffer = builder.core_class("TextBuffer");
+        let class = builder.export(0, 0, "Pawn", vec![0; 3]);
+        builder.export(function, class, "Tick", vec![0; 300]);
+        builder.export(function, class, "Touch", vec![0; 40]);
+        builder.export(
+            text_buffer,
+            class,
+            "ScriptText",
+            DataBuilder::object().build(),
+        );
+
+        let mut package = builder.raw_package();
+        // Serial sizes are only filled in when the package is built
+        for (export, size) in package.exports.iter_mut().zip([3, 300, 40, 1]) {
+            export.serial_size = size;
+        }
+
+        let stats = package.stats();
+        assert_eq!(stats.export_count, 4);
+        assert_eq!(stats.import_count, package.imports.len());
+        assert_eq!(stats.export_bytes, 344);
+
+        let functions = &stats.classes["Function"];
+        assert_eq!(functions.count, 2);
+        assert_eq!(functions.total_bytes, 340);
+        assert_eq!(functions.largest_bytes, 300);
+        assert_eq!(
+            functions.size_histogram,
+            BTreeMap::from([(64, 1), (512, 1)])
+        );
+        assert_eq!(stats.classes["Class"].count, 1);
+
+        let largest = stats
+            .largest_exports
+            .iter()
+            .map(|export| (export.name.as_str(), export.class.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            largest,
+            [
+                ("Tick", "Function"),
+                ("Touch", "Function"),
+                ("Pawn", "Class"),
+                ("ScriptText", "TextBuffer")
+            ]
+        );
+
+        // Short names have a one byte length prefix and a null terminator, then their flags
+        let names = package
+            .names
+            .iter()
+            .map(|name| name.name.len() as u64 + 2 + 4)
+            .sum::<u64>();
+        assert_eq!(stats.name_table_bytes, names);
+    }
+
+    #[test]
+    fn accounts_for_loaded_objects() {
+        let mut builder = PackageBuilder::new();
+        let constant = builder.core_class("Const");
+        for name in ["One", "Two"] {
+            let data = DataBuilder::field(0, 0).string("1").build();
+            builder.export(constant, 0, name, data);
+        }
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Test", Cursor::new(builder.build())).unwrap();
+        let empty = package.linker_usage().remove(0);
+        assert_eq!(empty.loaded_objects, 0);
+
+        package.load_objects().unwrap();
+        let usage = package.linker_usage().remove(0);
+        assert_eq!(usage.name, "Test");
+        assert_eq!(usage.loaded_objects, 2);
+        assert_eq!(
+            usage.objects_by_kind,
+            BTreeMap::from([(UObjectKind::Const, 2)])
+        );
+        assert!(usage.approximate_bytes > empty.approximate_bytes);
+    }
+}
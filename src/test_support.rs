@@ -0,0 +1,482 @@
+//! Builds small packages in memory so that deserialization can be tested without
+//! committing game data.
+
+use std::rc::Rc;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::{
+    de::{GenerationInfo, Import, Name, ObjectExport, PackageHeader, RawPackage},
+    packed::write_packed_int,
+    ser::{LayoutMode, serialize_unreal_package},
+};
+
+/// Raw version of stock UE2 packages
+pub const UE2_VERSION: u32 = 100;
+
+/// Builds a package's tables and export data.
+///
+/// Indices returned by the builder are raw object indices as they appear in serialized
+/// data: negative for imports and positive for exports.
+pub struct PackageBuilder {
+    version: u32,
+    names: Vec<String>,
+    imports: Vec<Import>,
+    exports: Vec<(ObjectExport, Vec<u8>)>,
+    /// Import of the `Core` package, added on first use
+    core: Option<i32>,
+}
+
+impl PackageBuilder {
+    pub fn new() -> Self {
+        PackageBuilder {
+            version: UE2_VERSION,
+            // Empty property lists are terminated by name 0
+            names: vec!["None".to_owned()],
+            imports: Vec::new(),
+            exports: Vec::new(),
+            core: None,
+        }
+    }
+
+    pub fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the index of `name` in the name table, adding it if needed.
+    pub fn name(&mut self, name: &str) -> i32 {
+        let index = match self.names.iter().position(|existing| existing == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_owned());
+                self.names.len() - 1
+            }
+        };
+
+        index as i32
+    }
+
+    /// Adds an import of `class_package.class_name` named `name` inside `outer`.
+    pub fn import(&mut self, class_package: &str, class_name: &str, outer: i32, name: &str) -> i32 {
+        let import = Import {
+            class_package: self.name(class_package),
+            class_name: self.name(class_name),
+            package_index: outer,
+            object_name: self.name(name),
+        };
+        self.imports.push(import);
+
+        -(self.imports.len() as i32)
+    }
+
+    /// Imports the builtin class `Core.<class_name>`.
+    pub fn core_class(&mut self, class_name: &str) -> i32 {
+        let core = match self.core {
+            Some(core) => core,
+            None => {
+                let core = self.import("Core", "Package", 0, "Core");
+                self.core = Some(core);
+                core
+            }
+        };
+
+        self.import("Core", "Class", core, class_name)
+    }
+
+    /// Adds an export of `class` named `name` inside `outer`, serialized as `data`.
+    pub fn export(&mut self, class: i32, outer: i32, name: &str, data: Vec<u8>) -> i32 {
+        let export = ObjectExport {
+            class_index: class,
+            super_index: 0,
+            package_index: outer,
+            object_name: self.name(name),
+            object_flags: 0,
+            serial_size: 0,
+            serial_offset: 0,
+        };
+        self.exports.push((export, data));
+
+        self.exports.len() as i32
+    }
+
+    /// Sets the struct or class that the export at the raw index `export` inherits from.
+    pub fn set_super(&mut self, export: i32, super_index: i32) {
+        self.exports[export as usize - 1].0.super_index = super_index;
+    }
+
+    /// The package's header and tables. Serial sizes and offsets are only filled in by
+    /// [`PackageBuilder::build`].
+    pub fn raw_package(&self) -> RawPackage {
+        let names = self
+            .names
+            .iter()
+            .map(|name| Name {
+                name: Rc::from(name.as_str()),
+                flags: 0,
+            })
+            .collect::<Vec<_>>();
+
+        RawPackage {
+            header: PackageHeader {
+                version: self.version,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: self.exports.len() as u32,
+                export_offset: 0,
+                import_count: self.imports.len() as u32,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: vec![GenerationInfo {
+                    export_count: self.exports.len() as u32,
+                    name_count: names.len() as u32,
+                }],
+            },
+            names,
+            imports: self.imports.clone(),
+            exports: self
+                .exports
+                .iter()
+                .map(|(export, _)| export.clone())
+                .collect(),
+        }
+    }
+
+    /// Serializes the package.
+    pub fn build(&self) -> Vec<u8> {
+        let mut package = self.raw_package();
+        let export_data = self
+            .exports
+            .iter()
+            .map(|(_, data)| vec![(0, data.clone())])
+            .collect::<Vec<_>>();
+
+        let mut out = Vec::new();
+        serialize_unreal_package::<LittleEndian, _>(
+            &mut out,
+            &mut package,
+            &export_data,
+            LayoutMode::Compact,
+        )
+        .expect("failed to serialize package");
+
+        out
+    }
+}
+
+/// Writes the serialized data of an export. Each constructor writes the fields of a builtin
+/// class's parents so that only the class's own fields need to be added.
+#[derive(Default)]
+pub struct DataBuilder(Vec<u8>);
+
+impl DataBuilder {
+    /// An `Object` with no tagged properties.
+    pub fn object() -> Self {
+        DataBuilder::default().packed(0)
+    }
+
+    /// A `Field` with the given super field and next field.
+    pub fn field(super_field: i32, next: i32) -> Self {
+        Self::object().packed(super_field).packed(next)
+    }
+
+    /// A `Struct` with no children and an empty script, as serialized by stock UE2.
+    pub fn ue2_struct(friendly_name: i32) -> Self {
+        Self::field(0, 0)
+            // script_text, children
+            .packed(0)
+            .packed(0)
+            .packed(friendly_name)
+            // line, text_pos, script_size
+            .u32(0)
+            .u32(0)
+            .u32(0)
+    }
+
+    /// A `Property` as serialized by stock UE2.
+    pub fn ue2_property(next: i32, flags: u32, category: i32) -> Self {
+        Self::field(0, next).u32(1).u32(flags).packed(category)
+    }
+
+    /// The class declaration following a class's `State` data, as serialized by stock UE2,
+    /// with no dependencies, package imports or hidden categories.
+    pub fn ue2_class_declaration(self, flags: u32, within: i32, config_name: i32) -> Self {
+        // class_flags, class_guid
+        let this = self.u32(flags).u64(0).u64(0);
+        // dependencies, package_imports
+        this.packed(0)
+            .packed(0)
+            .packed(within)
+            .packed(config_name)
+            // hide_categories
+            .packed(0)
+    }
+
+    pub fn u8(mut self, value: u8) -> Self {
+        self.0.push(value);
+        self
+    }
+
+    pub fn u16(mut self, value: u16) -> Self {
+        self.0.write_u16::<LittleEndian>(value).unwrap();
+        self
+    }
+
+    pub fn u32(mut self, value: u32) -> Self {
+        self.0.write_u32::<LittleEndian>(value).unwrap();
+        self
+    }
+
+    pub fn u64(mut self, value: u64) -> Self {
+        self.0.write_u64::<LittleEndian>(value).unwrap();
+        self
+    }
+
+    pub fn packed(mut self, value: i32) -> Self {
+        write_packed_int(&mut self.0, value).unwrap();
+        self
+    }
+
+    /// A null-terminated ANSI string.
+    pub fn string(self, value: &str) -> Self {
+        let mut builder = self.packed(value.len() as i32 + 1);
+        builder.0.extend_from_slice(value.as_bytes());
+        builder.u8(0)
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        de::read_package,
+        object::{
+            UObjectKind,
+            builtins::{Const, Enum, Function, FunctionFlags, PropertyFlags, State, TextBuffer},
+        },
+        package::Package,
+        reader::PackageReader,
+    };
+
+    /// One export of each builtin class which can be deserialized.
+    fn builtins_package() -> PackageBuilder {
+        let mut builder = PackageBuilder::new();
+
+        let text_buffer = builder.core_class("TextBuffer");
+        let data = DataBuilder::object().u32(1).u32(2).string("hello").build();
+        builder.export(text_buffer, 0, "ScriptText", data);
+
+        let constant = builder.core_class("Const");
+        let data = DataBuilder::field(0, 0).string("42").build();
+        builder.export(constant, 0, "MaxCount", data);
+
+        let enum_class = builder.core_class("Enum");
+        let values = [builder.name("RED"), builder.name("GREEN")];
+        let data = DataBuilder::field(0, 0)
+            .packed(values.len() as i32)
+            .packed(values[0])
+            .packed(values[1])
+            .build();
+        let colors = builder.export(enum_class, 0, "EColor", data);
+
+        let byte_property = builder.core_class("ByteProperty");
+        let category = builder.name("Display");
+        let data = DataBuilder::ue2_property(0, PropertyFlags::EDIT.bits(), category)
+            .packed(colors)
+            .build();
+        builder.export(byte_property, 0, "Color", data);
+
+        let int_property = builder.core_class("IntProperty");
+        let data = DataBuilder::ue2_property(0, PropertyFlags::NET.bits(), 0)
+            .u16(0x10)
+            .build();
+        builder.export(int_property, 0, "Health", data);
+
+        let struct_class = builder.core_class("Struct");
+        let name = builder.name("Vector");
+        let data = DataBuilder::ue2_struct(name).build();
+        builder.export(struct_class, 0, "Vector", data);
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Tick");
+        let data = DataBuilder::ue2_struct(name)
+            // inative, operator_precedence, function_flags
+            .u16(0)
+            .u8(0)
+            .u32(0x2)
+            .build();
+        builder.export(function, 0, "Tick", data);
+
+        let state = builder.core_class("State");
+        let name = builder.name("Idle");
+        let data = DataBuilder::ue2_struct(name)
+            // probe_mask, ignore_mask, label_table_offset, state_flags
+            .u64(u64::MAX)
+            .u64(0)
+            .u16(0xFFFF)
+            .u32(0)
+            .build();
+        builder.export(state, 0, "Idle", data);
+
+        builder
+    }
+
+    #[test]
+    fn tables_round_trip() {
+        let builder = builtins_package();
+        let data = builder.build();
+
+        let mut reader = PackageReader::new(Cursor::new(data.as_slice()));
+        let package = read_package::<LittleEndian, _>(&mut reader).unwrap();
+        let expected = builder.raw_package();
+
+        assert_eq!(package.header.version, expected.header.version);
+        assert_eq!(package.names, expected.names);
+        assert_eq!(package.imports, expected.imports);
+        assert_eq!(package.exports.len(), expected.exports.len());
+        for (export, expected) in package.exports.iter().zip(&expected.exports) {
+            assert_eq!(export.class_index, expected.class_index);
+            assert_eq!(export.object_name, expected.object_name);
+            assert!(export.serial_size > 0);
+        }
+    }
+
+    #[test]
+    fn loads_builtin_exports() {
+        let data = builtins_package().build();
+        let mut package = Package::<LittleEndian, _>::open("Builtins", Cursor::new(data)).unwrap();
+
+        let contents = package.load_objects().unwrap();
+        assert_eq!(contents.objects().len(), 8);
+
+        let object = |name| contents.object(name).unwrap();
+
+        let text_buffer = object("ScriptText");
+        let text_buffer = text_buffer.borrow();
+        let text_buffer = text_buffer.as_any().downcast_ref::<TextBuffer>().unwrap();
+        assert_eq!(
+            (
+                text_buffer.position,
+                text_buffer.top,
+                package.script_text(&object("ScriptText")).unwrap().unwrap()
+            ),
+            (1, 2, "hello".to_owned())
+        );
+
+        let constant = object("MaxCount");
+        let constant = constant.borrow();
+        assert_eq!(
+            constant.as_any().downcast_ref::<Const>().unwrap().value,
+            "42"
+        );
+
+        let colors = object("EColor");
+        let colors = colors.borrow();
+        let names = colors.as_any().downcast_ref::<Enum>().unwrap().names();
+        assert_eq!(
+            names.iter().map(|name| &**name).collect::<Vec<_>>(),
+            ["RED", "GREEN"]
+        );
+
+        let properties = contents.properties();
+        assert_eq!(properties.len(), 2);
+        assert_eq!(properties[0].name, "Color");
+        assert_eq!(properties[0].category.as_deref(), Some("Display"));
+        assert_eq!(properties[1].rep_offset, Some(0x10));
+
+        assert!(object("Vector").borrow().is_a(UObjectKind::Struct));
+
+        let function = object("Tick");
+        let function = function.borrow();
+        assert_eq!(
+            function
+                .as_any()
+                .downcast_ref::<Function>()
+                .unwrap()
+                .inative(),
+            0
+        );
+
+        let state = object("Idle");
+        assert!(state.borrow().as_any().downcast_ref::<State>().is_some());
+    }
+
+    #[test]
+    fn loads_licensee_layout() {
+        // Splinter Cell adds struct flags and narrows property array dimensions
+        let mut builder = PackageBuilder::new().version((0x1D << 16) | UE2_VERSION);
+
+        let struct_class = builder.core_class("Struct");
+        let name = builder.name("Rotator");
+        let data = DataBuilder::field(0, 0)
+            .packed(0)
+            .packed(0)
+            .packed(name)
+            // struct flags
+            .u32(0x1)
+            .u32(0)
+            .u32(0)
+            .u32(0)
+            .build();
+        builder.export(struct_class, 0, "Rotator", data);
+
+        let int_property = builder.core_class("IntProperty");
+        let data = DataBuilder::field(0, 0).u16(4).u32(0).packed(0).build();
+        builder.export(int_property, 0, "Values", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Licensee", Cursor::new(builder.build())).unwrap();
+        let contents = package.load_objects().unwrap();
+
+        assert_eq!(contents.format_profile().name, "Splinter Cell");
+        assert_eq!(contents.properties()[0].array_dim, 4);
+    }
+
+    #[test]
+    fn keeps_unknown_flag_bits() {
+        // Bits no stock flag uses, as set by licensee builds
+        let mut builder = PackageBuilder::new();
+
+        let int_property = builder.core_class("IntProperty");
+        let data =
+            DataBuilder::ue2_property(0, PropertyFlags::EDIT.bits() | 0x0008_0000, 0).build();
+        builder.export(int_property, 0, "Ammo", data);
+
+        let function = builder.core_class("Function");
+        let name = builder.name("Fire");
+        let data = DataBuilder::ue2_struct(name)
+            .u16(0)
+            .u8(0)
+            .u32(FunctionFlags::DEFINED.bits() | 0x8000_0000)
+            .build();
+        builder.export(function, 0, "Fire", data);
+
+        let mut package =
+            Package::<LittleEndian, _>::open("Licensee", Cursor::new(builder.build())).unwrap();
+        let contents = package.load_objects().unwrap();
+
+        let flags = contents.properties()[0].flags;
+        assert!(flags.contains(PropertyFlags::EDIT));
+        assert_eq!(flags.unknown_bits(), 0x0008_0000);
+
+        let function = contents.object("Fire").unwrap();
+        let function = function.borrow();
+        let flags = function
+            .as_any()
+            .downcast_ref::<Function>()
+            .unwrap()
+            .function_flags;
+        assert!(flags.contains(FunctionFlags::DEFINED));
+        assert_eq!(flags.unknown_bits(), 0x8000_0000);
+        assert!(format!("{flags:?}").contains("0x80000000"));
+    }
+}
@@ -0,0 +1,473 @@
+use std::fmt;
+
+use crate::{common::normalize_index, de::RawPackage, object::ObjectFlags};
+
+/// Flags which the engine keeps when an object is saved to a package. Anything else in an
+/// export's flags is runtime-only state and suggests the table is corrupt.
+const SAVED_OBJECT_FLAGS: ObjectFlags = ObjectFlags::NOT_FOR_CLIENT
+    .union(ObjectFlags::NOT_FOR_SERVER)
+    .union(ObjectFlags::NOT_FOR_EDIT)
+    .union(ObjectFlags::LOAD_FOR_CLIENT)
+    .union(ObjectFlags::LOAD_FOR_SERVER)
+    .union(ObjectFlags::LOAD_FOR_EDIT)
+    .union(ObjectFlags::PUBLIC)
+    .union(ObjectFlags::STANDALONE)
+    .union(ObjectFlags::NATIVE)
+    .union(ObjectFlags::SOURCE_MODIFIED)
+    .union(ObjectFlags::TRANSACTIONAL)
+    .union(ObjectFlags::HAS_STACK)
+    .union(ObjectFlags::PER_OBJECT_LOCALIZED);
+
+/// Which table a finding refers to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PackageTable {
+    Import,
+    Export,
+}
+
+impl fmt::Display for PackageTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageTable::Import => f.write_str("import"),
+            PackageTable::Export => f.write_str("export"),
+        }
+    }
+}
+
+/// A single problem found by [`RawPackage::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationFinding {
+    /// A table's length doesn't match the count recorded in the header.
+    CountMismatch {
+        table: &'static str,
+        header_count: u32,
+        actual: usize,
+    },
+    /// An entry refers to a name which isn't in the name table.
+    NameIndexOutOfBounds {
+        table: PackageTable,
+        entry: usize,
+        field: &'static str,
+        index: i32,
+    },
+    /// An entry refers to an import or export which doesn't exist.
+    ObjectIndexOutOfBounds {
+        table: PackageTable,
+        entry: usize,
+        field: &'static str,
+        index: i32,
+    },
+    /// An export's serial size is negative.
+    NegativeSerialSize { export: usize, size: i32 },
+    /// An export's data extends past the end of the file.
+    SerialRangeOutsideFile {
+        export: usize,
+        offset: i32,
+        size: i32,
+        file_size: u64,
+    },
+    /// Two exports claim overlapping ranges of the file.
+    SerialRangeOverlap { export: usize, other: usize },
+    /// An export has flags which are never saved to a package.
+    UnexpectedObjectFlags { export: usize, flags: u32 },
+    /// The package has no generation history.
+    NoGenerations,
+    /// A generation records more exports or names than the package contains.
+    GenerationOutOfRange {
+        generation: usize,
+        export_count: u32,
+        name_count: u32,
+    },
+}
+
+impl fmt::Display for ValidationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationFinding::CountMismatch {
+                table,
+                header_count,
+                actual,
+            } => write!(
+                f,
+                "header records {header_count} {table} entries but {actual} were read"
+            ),
+            ValidationFinding::NameIndexOutOfBounds {
+                table,
+                entry,
+                field,
+                index,
+            } => write!(
+                f,
+                "{table} {entry}: {field} name index {index} out of bounds"
+            ),
+            ValidationFinding::ObjectIndexOutOfBounds {
+                table,
+                entry,
+                field,
+                index,
+            } => write!(
+                f,
+                "{table} {entry}: {field} object index {index} out of bounds"
+            ),
+            ValidationFinding::NegativeSerialSize { export, size } => {
+                write!(f, "export {export}: negative serial size {size}")
+            }
+            ValidationFinding::SerialRangeOutsideFile {
+                export,
+                offset,
+                size,
+                file_size,
+            } => write!(
+                f,
+                "export {export}: serial range {offset:#X}+{size:#X} exceeds file size {file_size:#X}"
+            ),
+            ValidationFinding::SerialRangeOverlap { export, other } => {
+                write!(f, "export {export}: serial range overlaps export {other}")
+            }
+            ValidationFinding::UnexpectedObjectFlags { export, flags } => {
+                write!(f, "export {export}: unexpected object flags {flags:#010X}")
+            }
+            ValidationFinding::NoGenerations => f.write_str("package has no generations"),
+            ValidationFinding::GenerationOutOfRange {
+                generation,
+                export_count,
+                name_count,
+            } => write!(
+                f,
+                "generation {generation}: {export_count} exports / {name_count} names exceeds package contents"
+            ),
+        }
+    }
+}
+
+impl RawPackage {
+    /// Checks the package's tables for internal consistency. `file_size` is the size of the
+    /// package data the export table's serial offsets are relative to.
+    ///
+    /// An empty list means no problems were found.
+    pub fn validate(&self, file_size: u64) -> Vec<ValidationFinding> {
+        let mut findings = Vec::new();
+
+        self.validate_counts(&mut findings);
+        self.validate_imports(&mut findings);
+        self.validate_exports(file_size, &mut findings);
+        self.validate_generations(&mut findings);
+
+        findings
+    }
+
+    fn validate_counts(&self, findings: &mut Vec<ValidationFinding>) {
+        let tables = [
+            ("name", self.header.name_count, self.names.len()),
+            ("import", self.header.import_count, self.imports.len()),
+            ("export", self.header.export_count, self.exports.len()),
+        ];
+
+        for (table, header_count, actual) in tables {
+            if header_count as usize != actual {
+                findings.push(ValidationFinding::CountMismatch {
+                    table,
+                    header_count,
+                    actual,
+                });
+            }
+        }
+    }
+
+    fn validate_imports(&self, findings: &mut Vec<ValidationFinding>) {
+        for (entry, import) in self.imports.iter().enumerate() {
+            let names = [
+                ("class_package", import.class_package),
+                ("class_name", import.class_name),
+                ("object_name", import.object_name),
+            ];
+            for (field, index) in names {
+                if !self.is_valid_name(index) {
+                    findings.push(ValidationFinding::NameIndexOutOfBounds {
+                        table: PackageTable::Import,
+                        entry,
+                        field,
+                        index,
+                    });
+                }
+            }
+
+            if !self.is_valid_object(import.package_index) {
+                findings.push(ValidationFinding::ObjectIndexOutOfBounds {
+                    table: PackageTable::Import,
+                    entry,
+                    field: "package_index",
+                    index: import.package_index,
+                });
+            }
+        }
+    }
+
+    fn validate_exports(&self, file_size: u64, findings: &mut Vec<ValidationFinding>) {
+        let mut ranges = Vec::with_capacity(self.exports.len());
+
+        for (entry, export) in self.exports.iter().enumerate() {
+            if !self.is_valid_name(export.object_name) {
+                findings.push(ValidationFinding::NameIndexOutOfBounds {
+                    table: PackageTable::Export,
+                    entry,
+                    field: "object_name",
+                    index: export.object_name,
+                });
+            }
+
+            let objects = [
+                ("class_index", export.class_index),
+                ("super_index", export.super_index),
+                ("package_index", export.package_index),
+            ];
+            for (field, index) in objects {
+                if !self.is_valid_object(index) {
+                    findings.push(ValidationFinding::ObjectIndexOutOfBounds {
+                        table: PackageTable::Export,
+                        entry,
+                        field,
+                        index,
+                    });
+                }
+            }
+
+            let unexpected = export.object_flags & !SAVED_OBJECT_FLAGS.bits();
+            if unexpected != 0 {
+                findings.push(ValidationFinding::UnexpectedObjectFlags {
+                    export: entry,
+                    flags: unexpected,
+                });
+            }
+
+            if export.serial_size < 0 {
+                findings.push(ValidationFinding::NegativeSerialSize {
+                    export: entry,
+                    size: export.serial_size,
+                });
+                continue;
+            }
+
+            if export.serial_size == 0 {
+                continue;
+            }
+
+            let end = export.serial_offset as i64 + export.serial_size as i64;
+            if export.serial_offset < 0 || end as u64 > file_size {
+                findings.push(ValidationFinding::SerialRangeOutsideFile {
+                    export: entry,
+                    offset: export.serial_offset,
+                    size: export.serial_size,
+                    file_size,
+                });
+                continue;
+            }
+
+            ranges.push((export.serial_offset as i64, end, entry));
+        }
+
+        ranges.sort_unstable();
+        // The range reaching furthest so far, which a later range can overlap even if others
+        // sort between them
+        let mut furthest: Option<(i64, usize)> = None;
+        for (start, end, entry) in ranges {
+            if let Some((furthest_end, other)) = furthest
+                && start < furthest_end
+            {
+                findings.push(ValidationFinding::SerialRangeOverlap {
+                    export: entry,
+                    other,
+                });
+            }
+
+            if furthest.is_none_or(|(furthest_end, _)| end > furthest_end) {
+                furthest = Some((end, entry));
+            }
+        }
+    }
+
+    fn validate_generations(&self, findings: &mut Vec<ValidationFinding>) {
+        if self.header.generations.is_empty() {
+            findings.push(ValidationFinding::NoGenerations);
+            return;
+        }
+
+        for (generation, info) in self.header.generations.iter().enumerate() {
+            if info.export_count as usize > self.exports.len()
+                || info.name_count as usize > self.names.len()
+            {
+                findings.push(ValidationFinding::GenerationOutOfRange {
+                    generation,
+                    export_count: info.export_count,
+                    name_count: info.name_count,
+                });
+            }
+        }
+    }
+
+    fn is_valid_name(&self, index: i32) -> bool {
+        index >= 0 && (index as usize) < self.names.len()
+    }
+
+    fn is_valid_object(&self, index: i32) -> bool {
+        match index {
+            0 => true,
+            i if i < 0 => normalize_index(i) < self.imports.len(),
+            i => normalize_index(i) < self.exports.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::de::{GenerationInfo, Import, Name, ObjectExport, PackageHeader};
+
+    fn test_package() -> RawPackage {
+        let names = ["None", "Core", "Package", "Class", "MyClass"]
+            .into_iter()
+            .map(|name| Name {
+                name: Rc::from(name),
+                flags: 0,
+            })
+            .collect::<Vec<_>>();
+
+        let export = |offset| ObjectExport {
+            class_index: 0,
+            super_index: 0,
+            package_index: 0,
+            object_name: 4,
+            object_flags: (ObjectFlags::PUBLIC | ObjectFlags::STANDALONE).bits(),
+            serial_size: 0x10,
+            serial_offset: offset,
+        };
+
+        RawPackage {
+            header: PackageHeader {
+                version: 0,
+                flags: 0,
+                name_count: names.len() as u32,
+                name_offset: 0,
+                export_count: 2,
+                export_offset: 0,
+                import_count: 1,
+                import_offset: 0,
+                unk: 0,
+                unknown_data: Vec::new(),
+                guid: Default::default(),
+                generations: vec![GenerationInfo {
+                    export_count: 2,
+                    name_count: names.len() as u32,
+                }],
+            },
+            names,
+            imports: vec![Import {
+                class_package: 1,
+                class_name: 2,
+                package_index: 0,
+                object_name: 1,
+            }],
+            exports: vec![export(0x100), export(0x110)],
+        }
+    }
+
+    #[test]
+    fn valid_package_has_no_findings() {
+        assert_eq!(test_package().validate(0x200), Vec::new());
+    }
+
+    #[test]
+    fn reports_corrupt_entries() {
+        let mut package = test_package();
+        package.imports[0].object_name = 99;
+        package.exports[0].class_index = -5;
+        package.exports[1].serial_offset = 0x108;
+        package.exports[1].object_flags |= ObjectFlags::DESTROYED.bits();
+
+        let findings = package.validate(0x200);
+        assert_eq!(
+            findings,
+            vec![
+                ValidationFinding::NameIndexOutOfBounds {
+                    table: PackageTable::Import,
+                    entry: 0,
+                    field: "object_name",
+                    index: 99,
+                },
+                ValidationFinding::ObjectIndexOutOfBounds {
+                    table: PackageTable::Export,
+                    entry: 0,
+                    field: "class_index",
+                    index: -5,
+                },
+                ValidationFinding::UnexpectedObjectFlags {
+                    export: 1,
+                    flags: ObjectFlags::DESTROYED.bits(),
+                },
+                ValidationFinding::SerialRangeOverlap {
+                    export: 1,
+                    other: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_overlaps_with_earlier_ranges() {
+        let mut package = test_package();
+        // Export 0 spans both of the exports after it
+        package.exports[0].serial_size = 0x40;
+        let mut export = package.exports[1].clone();
+        export.serial_offset = 0x120;
+        package.exports.push(export);
+        package.header.export_count = 3;
+
+        let findings = package.validate(0x200);
+        assert_eq!(
+            findings,
+            vec![
+                ValidationFinding::SerialRangeOverlap {
+                    export: 1,
+                    other: 0,
+                },
+                ValidationFinding::SerialRangeOverlap {
+                    export: 2,
+                    other: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_minimum_object_index() {
+        let mut package = test_package();
+        package.exports[0].class_index = i32::MIN;
+
+        let findings = package.validate(0x200);
+        assert_eq!(
+            findings,
+            vec![ValidationFinding::ObjectIndexOutOfBounds {
+                table: PackageTable::Export,
+                entry: 0,
+                field: "class_index",
+                index: i32::MIN,
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_range_outside_file() {
+        let findings = test_package().validate(0x118);
+        assert_eq!(
+            findings,
+            vec![ValidationFinding::SerialRangeOutsideFile {
+                export: 1,
+                offset: 0x110,
+                size: 0x10,
+                file_size: 0x118,
+            }]
+        );
+    }
+}
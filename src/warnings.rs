@@ -0,0 +1,90 @@
+use std::fmt;
+
+use tracing::warn;
+
+/// The kind of condition a [`LoadWarning`] reports.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// Flags had bits set which aren't known to mean anything
+    UnknownFlags,
+    /// An export's data was a different size than its export table entry says. Only
+    /// reported in lenient mode; otherwise it's an error.
+    SerialSizeMismatch,
+    /// An export wasn't deserialized because a load limit was reached
+    SkippedExport,
+    /// A referenced package or export couldn't be found, so a placeholder was used instead
+    MissingObject,
+    /// A script contained a token which couldn't be decoded, so the rest was kept raw
+    PartialScript,
+    /// A function's parameters couldn't be laid out
+    ParameterLayout,
+}
+
+/// A condition noticed while loading which didn't stop the load but may mean the loaded
+/// objects are incomplete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadWarning {
+    pub kind: WarningKind,
+    /// Full name of the object being loaded, if the warning is about one
+    pub object: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.object {
+            Some(object) => write!(f, "{object}: {}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Called with each warning as it's recorded.
+pub type WarningCallback = Box<dyn FnMut(&LoadWarning)>;
+
+/// Warnings collected by the runtime, retrievable once a load finishes. A callback can also
+/// be set to see each warning as it's recorded.
+#[derive(Default)]
+pub struct Warnings {
+    records: Vec<LoadWarning>,
+    callback: Option<WarningCallback>,
+}
+
+impl Warnings {
+    /// Records `warning`, passing it to the callback first if one is set.
+    pub fn push(&mut self, warning: LoadWarning) {
+        warn!("{warning}");
+
+        if let Some(callback) = &mut self.callback {
+            callback(&warning);
+        }
+        self.records.push(warning);
+    }
+
+    /// Warnings recorded so far, oldest first.
+    pub fn records(&self) -> &[LoadWarning] {
+        &self.records
+    }
+
+    /// Removes and returns the warnings recorded so far.
+    pub fn take(&mut self) -> Vec<LoadWarning> {
+        std::mem::take(&mut self.records)
+    }
+
+    pub fn set_callback(&mut self, callback: Option<WarningCallback>) {
+        self.callback = callback;
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+}
+
+impl fmt::Debug for Warnings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Warnings")
+            .field("records", &self.records)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}